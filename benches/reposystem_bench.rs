@@ -7,9 +7,13 @@
 //! - Config parsing throughput
 //! - Graph export (DOT) performance
 //! - Repository ID generation
+//! - Parallel scanning and edge detection over a synthetic checkout tree
 
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rayon::prelude::*;
+use reposystem::detect::{DetectorRegistry, RepoIndex};
 use reposystem::graph::EcosystemGraph;
+use reposystem::scanner::{scan_path, worker_pool, ScanConfig};
 use reposystem::types::{Edge, EdgeMeta, Forge, ImportMeta, RelationType, Repo, Visibility, Channel};
 use chrono::Utc;
 
@@ -282,6 +286,68 @@ fn bench_forge_operations(c: &mut Criterion) {
     });
 }
 
+// =============================================================================
+// Parallel Scan Benchmarks
+// =============================================================================
+
+const SCAN_TREE_REPOS: usize = 300;
+
+/// Build a tree of `n` real git checkouts, each path-depending on its neighbour
+fn build_scan_tree(n: usize) -> tempfile::TempDir {
+    let root = tempfile::TempDir::new().unwrap();
+    for i in 0..n {
+        let dir = root.path().join(format!("org{}/repo{}", i % 10, i));
+        gix::init(&dir).unwrap();
+        let next = (i + 1) % n;
+        std::fs::write(
+            dir.join("Cargo.toml"),
+            format!(
+                "[package]\nname = \"repo{i}\"\n\n[dependencies]\nrepo{next} = {{ path = \"../../org{}/repo{next}\" }}\n",
+                next % 10
+            ),
+        )
+        .unwrap();
+    }
+    root
+}
+
+fn bench_parallel_scan(c: &mut Criterion) {
+    let tree = build_scan_tree(SCAN_TREE_REPOS);
+    let mut group = c.benchmark_group("scan_300_repos");
+    group.sample_size(10);
+
+    for (name, jobs) in [("sequential", 1), ("parallel", 0)] {
+        let config = ScanConfig {
+            deep: true,
+            jobs,
+            ..Default::default()
+        };
+        group.bench_function(name, |b| {
+            b.iter(|| scan_path(black_box(tree.path()), &config).unwrap())
+        });
+    }
+
+    let results = scan_path(tree.path(), &ScanConfig::default()).unwrap();
+    let repos: Vec<Repo> = results.into_iter().map(|r| r.repo).collect();
+    let index = RepoIndex::from_repos(&repos);
+    let registry = DetectorRegistry::builtin();
+    let config = ScanConfig::default();
+    for (name, jobs) in [("detect_sequential", 1), ("detect_parallel", 0)] {
+        let pool = worker_pool(jobs).unwrap();
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                pool.install(|| {
+                    repos
+                        .par_iter()
+                        .map(|r| registry.detect(r, r.local_path.as_deref().unwrap(), &index, &config))
+                        .collect::<Vec<_>>()
+                })
+            })
+        });
+    }
+    group.finish();
+}
+
 // =============================================================================
 // Criterion Group Setup
 // =============================================================================
@@ -293,7 +359,8 @@ fn bench_forge_operations(c: &mut Criterion) {
     bench_graph_edges,
     bench_graph_export,
     bench_graph_queries,
-    bench_forge_operations
+    bench_forge_operations,
+    bench_parallel_scan
 );
 
 criterion_main!(benches);
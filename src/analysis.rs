@@ -0,0 +1,1146 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Graph analytics over dependency edges - cycles, release layers, longest
+//! chain, fan-in/fan-out rankings, transitive impact ("blast radius"), weak
+//! links and paths between repos
+
+use crate::graph::EcosystemGraph;
+use crate::types::{Channel, Edge, Polarity, RelationType};
+use anyhow::Result;
+use petgraph::algo::tarjan_scc;
+use petgraph::graph::{DiGraph, NodeIndex};
+use petgraph::visit::EdgeRef;
+use petgraph::Direction;
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, BinaryHeap, HashMap, HashSet};
+
+/// Relations that mean "A cannot be released before B"
+pub const DEPENDENCY_RELS: [RelationType; 2] = [RelationType::Uses, RelationType::Extends];
+
+/// A repo with the number of distinct repos on one side of it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Degree {
+    /// Repo ID
+    pub id: String,
+    /// Distinct dependents (fan-in) or dependencies (fan-out)
+    pub count: usize,
+}
+
+/// Full analysis report, as printed by `reposystem analyze`
+#[derive(Debug, Clone, Serialize)]
+pub struct Analysis {
+    /// Repos considered
+    pub repos: usize,
+    /// Dependency edges considered (parallel edges counted once)
+    pub dependency_edges: usize,
+    /// Dependency cycles, each sorted by ID
+    pub cycles: Vec<Vec<String>>,
+    /// Release layers: layer 0 has no dependencies and is released first
+    pub layers: Vec<Vec<String>>,
+    /// Longest dependency chain, from the first repo to release to the last
+    pub longest_chain: Vec<String>,
+    /// Repos ranked by number of dependents
+    pub fan_in: Vec<Degree>,
+    /// Repos ranked by number of dependencies
+    pub fan_out: Vec<Degree>,
+}
+
+/// The repo graph restricted to [`DEPENDENCY_RELS`] edges
+///
+/// An edge `A -> B` means A depends on B. Parallel edges (e.g. `uses` over two
+/// channels) collapse into one; seams are left out.
+pub struct DependencyGraph {
+    graph: DiGraph<String, ()>,
+    /// Strongly connected components, dependencies before dependents
+    components: Vec<Vec<NodeIndex>>,
+}
+
+impl DependencyGraph {
+    /// Build the dependency view of an ecosystem graph
+    #[must_use]
+    pub fn new(ecosystem: &EcosystemGraph) -> Self {
+        let mut graph = DiGraph::new();
+        let mut indices = HashMap::new();
+        let mut ids: Vec<&str> = ecosystem.repos().iter().map(|r| r.id.as_str()).collect();
+        ids.sort_unstable();
+        for id in ids {
+            indices.insert(id, graph.add_node(id.to_string()));
+        }
+
+        let mut seen = BTreeSet::new();
+        for edge in ecosystem.edges() {
+            if !DEPENDENCY_RELS.contains(&edge.rel) {
+                continue;
+            }
+            if let (Some(&from), Some(&to)) = (indices.get(edge.from.as_str()), indices.get(edge.to.as_str())) {
+                if seen.insert((from, to)) {
+                    graph.add_edge(from, to, ());
+                }
+            }
+        }
+
+        // tarjan_scc yields components in reverse topological order, i.e.
+        // every component comes after the components it depends on.
+        let mut components = tarjan_scc(&graph);
+        for component in &mut components {
+            component.sort_by(|a, b| graph[*a].cmp(&graph[*b]));
+        }
+        Self { graph, components }
+    }
+
+    /// Number of repos in the view
+    #[must_use]
+    pub fn node_count(&self) -> usize {
+        self.graph.node_count()
+    }
+
+    /// Number of distinct dependency edges
+    #[must_use]
+    pub fn edge_count(&self) -> usize {
+        self.graph.edge_count()
+    }
+
+    /// Groups of repos that depend on each other in a cycle (including a repo
+    /// that depends on itself), sorted
+    #[must_use]
+    pub fn cycles(&self) -> Vec<Vec<String>> {
+        let mut cycles: Vec<Vec<String>> = self
+            .components
+            .iter()
+            .filter(|c| c.len() > 1 || self.graph.contains_edge(c[0], c[0]))
+            .map(|c| self.ids(c))
+            .collect();
+        cycles.sort();
+        cycles
+    }
+
+    /// Layer of every repo: 0 for repos without dependencies, otherwise one
+    /// more than the highest layer among its dependencies. Repos in a cycle
+    /// share a layer.
+    fn layer_of(&self) -> HashMap<NodeIndex, usize> {
+        let mut layer_of = HashMap::new();
+        for component in &self.components {
+            let layer = component
+                .iter()
+                .flat_map(|&n| self.graph.neighbors_directed(n, Direction::Outgoing))
+                .filter(|dep| !component.contains(dep))
+                .map(|dep| layer_of[&dep] + 1)
+                .max()
+                .unwrap_or(0);
+            for &n in component {
+                layer_of.insert(n, layer);
+            }
+        }
+        layer_of
+    }
+
+    /// Release layers: every repo's dependencies are in earlier layers (or
+    /// its own layer, for cycles). Each layer is sorted by ID.
+    #[must_use]
+    pub fn layers(&self) -> Vec<Vec<String>> {
+        let layer_of = self.layer_of();
+        let mut layers: Vec<Vec<String>> = Vec::new();
+        for (node, layer) in layer_of {
+            if layers.len() <= layer {
+                layers.resize(layer + 1, Vec::new());
+            }
+            layers[layer].push(self.graph[node].clone());
+        }
+        for layer in &mut layers {
+            layer.sort();
+        }
+        layers
+    }
+
+    /// The longest dependency chain, from the deepest dependency up to the
+    /// repo that can only be released last. A cycle on the chain contributes
+    /// all of its members. Ties go to the smallest ID.
+    #[must_use]
+    pub fn longest_chain(&self) -> Vec<String> {
+        let layer_of = self.layer_of();
+        let by_id = |a: &NodeIndex, b: &NodeIndex| self.graph[*a].cmp(&self.graph[*b]);
+        let Some(top) = layer_of
+            .iter()
+            .max_by(|(a, la), (b, lb)| la.cmp(lb).then_with(|| by_id(b, a)))
+            .map(|(n, _)| *n)
+        else {
+            return Vec::new();
+        };
+
+        let component_of = |n: NodeIndex| self.components.iter().find(|c| c.contains(&n));
+        let mut chain = Vec::new();
+        let mut current = top;
+        loop {
+            let component = component_of(current).map_or_else(|| vec![current], Clone::clone);
+            let layer = layer_of[&current];
+            chain.push(self.ids(&component));
+            if layer == 0 {
+                break;
+            }
+            let next = component
+                .iter()
+                .flat_map(|&n| self.graph.neighbors_directed(n, Direction::Outgoing))
+                .filter(|dep| layer_of[dep] + 1 == layer)
+                .min_by(by_id);
+            match next {
+                Some(next) => current = next,
+                None => break,
+            }
+        }
+        chain.into_iter().rev().flatten().collect()
+    }
+
+    /// Repos ranked by distinct dependents, most first, then by ID
+    #[must_use]
+    pub fn fan_in(&self) -> Vec<Degree> {
+        self.ranked(Direction::Incoming)
+    }
+
+    /// Repos ranked by distinct dependencies, most first, then by ID
+    #[must_use]
+    pub fn fan_out(&self) -> Vec<Degree> {
+        self.ranked(Direction::Outgoing)
+    }
+
+    fn ranked(&self, direction: Direction) -> Vec<Degree> {
+        let mut degrees: Vec<Degree> = self
+            .graph
+            .node_indices()
+            .map(|n| Degree {
+                id: self.graph[n].clone(),
+                count: self.graph.neighbors_directed(n, direction).filter(|m| *m != n).count(),
+            })
+            .filter(|d| d.count > 0)
+            .collect();
+        degrees.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.id.cmp(&b.id)));
+        degrees
+    }
+
+    /// Run every analysis
+    #[must_use]
+    pub fn analyze(&self) -> Analysis {
+        Analysis {
+            repos: self.node_count(),
+            dependency_edges: self.edge_count(),
+            cycles: self.cycles(),
+            layers: self.layers(),
+            longest_chain: self.longest_chain(),
+            fan_in: self.fan_in(),
+            fan_out: self.fan_out(),
+        }
+    }
+
+    fn ids(&self, nodes: &[NodeIndex]) -> Vec<String> {
+        nodes.iter().map(|n| self.graph[*n].clone()).collect()
+    }
+}
+
+/// Analyze the dependency structure of an ecosystem graph
+#[must_use]
+pub fn analyze(ecosystem: &EcosystemGraph) -> Analysis {
+    DependencyGraph::new(ecosystem).analyze()
+}
+
+/// Which edges an impact walk follows
+#[derive(Debug, Clone, Default)]
+pub struct ImpactFilter {
+    /// Only follow these relations (empty = all)
+    pub rels: Vec<RelationType>,
+    /// Only follow these channels (empty = all)
+    pub channels: Vec<Channel>,
+    /// Stop after this many hops (0 = unlimited)
+    pub max_depth: usize,
+    /// Leave out repos reached with a lower path confidence
+    pub min_confidence: f64,
+}
+
+impl ImpactFilter {
+    fn follows(&self, edge: &Edge) -> bool {
+        (self.rels.is_empty() || self.rels.contains(&edge.rel))
+            && (self.channels.is_empty() || self.channels.contains(&edge.channel))
+    }
+}
+
+/// A risk annotation on an affected repo or on the edge it was reached by
+#[derive(Debug, Clone, Serialize)]
+pub struct RiskNote {
+    /// Aspect ID (`aspect:security`, …)
+    pub aspect_id: String,
+    /// Annotation weight (1-3)
+    pub weight: u8,
+    /// Why the annotation was made
+    pub reason: String,
+}
+
+/// A repo (or seam) affected when the impact target breaks
+#[derive(Debug, Clone, Serialize)]
+pub struct Affected {
+    /// Affected node ID
+    pub id: String,
+    /// Hops from the target along the strongest path
+    pub depth: usize,
+    /// The node it depends on along that path
+    pub parent: String,
+    /// The edge from this node to `parent`
+    pub edge_id: String,
+    /// Relation of that edge
+    pub rel: RelationType,
+    /// Channel of that edge
+    pub channel: Channel,
+    /// Product of edge confidences along the strongest path
+    pub confidence: f64,
+    /// Risk annotations on the node or on `edge_id`
+    pub risks: Vec<RiskNote>,
+    /// Slot bindings the node consumes, as `slot -> provider`
+    pub bindings: Vec<String>,
+}
+
+/// Everything transitively depending on a repo, seam or edge
+#[derive(Debug, Clone, Serialize)]
+pub struct Impact {
+    /// The node or edge whose failure was assumed
+    pub target: String,
+    /// Affected nodes, nearest first (then by ID)
+    pub affected: Vec<Affected>,
+}
+
+impl Impact {
+    /// Nodes reached in one hop
+    pub fn direct(&self) -> impl Iterator<Item = &Affected> {
+        self.affected.iter().filter(|a| a.depth == 1)
+    }
+
+    /// Nodes whose strongest path runs straight through `id`
+    pub fn children<'a>(&'a self, id: &'a str) -> impl Iterator<Item = &'a Affected> {
+        self.affected.iter().filter(move |a| a.parent == id)
+    }
+}
+
+/// Confidence of an edge: its strongest evidence, or 1.0 for an edge with
+/// none (an unqualified assertion)
+#[must_use]
+pub fn edge_confidence(edge: &Edge) -> f64 {
+    edge.evidence
+        .iter()
+        .map(|e| e.confidence.clamp(0.0, 1.0))
+        .reduce(f64::max)
+        .unwrap_or(1.0)
+}
+
+/// A frontier entry of the impact walk, ordered strongest path first
+struct Step<'a> {
+    confidence: f64,
+    depth: usize,
+    id: &'a str,
+    edge: &'a Edge,
+}
+
+impl Ord for Step<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.confidence
+            .total_cmp(&other.confidence)
+            .then_with(|| other.depth.cmp(&self.depth))
+            .then_with(|| other.id.cmp(self.id))
+            .then_with(|| other.edge.id.cmp(&self.edge.id))
+    }
+}
+
+impl PartialOrd for Step<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Step<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Step<'_> {}
+
+/// Walk reverse edges from `target` (a repo, seam or edge ID) to find every
+/// node that depends on it, directly or transitively.
+///
+/// Each node is reached along its strongest path: the one with the highest
+/// product of [`edge_confidence`]s, then the fewest hops. For an edge target
+/// the walk starts at the edge's source.
+///
+/// # Errors
+/// Returns an error if `target` is not a node or edge of the graph.
+pub fn impact(ecosystem: &EcosystemGraph, target: &str, filter: &ImpactFilter) -> Result<Impact> {
+    let (graph, indices) = edge_view(ecosystem, |e| filter.follows(e));
+
+    let mut frontier = BinaryHeap::new();
+    let mut visited: HashSet<&str> = HashSet::new();
+    if let Some(&root) = indices.get(target) {
+        visited.insert(target);
+        push_dependents(&graph, root, 1.0, 0, &mut frontier);
+    } else if let Some(edge) = ecosystem.edges().iter().find(|e| e.id == target) {
+        // The dependency end itself keeps working; only its dependents break
+        visited.insert(edge.to.as_str());
+        frontier.push(Step {
+            confidence: edge_confidence(edge),
+            depth: 1,
+            id: &edge.from,
+            edge,
+        });
+    } else {
+        anyhow::bail!("Not a repo, seam or edge: {target}");
+    }
+
+    let mut affected = Vec::new();
+    while let Some(step) = frontier.pop() {
+        if step.confidence < filter.min_confidence || !visited.insert(step.id) {
+            continue;
+        }
+        if filter.max_depth == 0 || step.depth < filter.max_depth {
+            push_dependents(&graph, indices[step.id], step.confidence, step.depth, &mut frontier);
+        }
+        affected.push(describe(ecosystem, &step));
+    }
+    affected.sort_by(|a, b| a.depth.cmp(&b.depth).then_with(|| a.id.cmp(&b.id)));
+
+    Ok(Impact {
+        target: target.to_string(),
+        affected,
+    })
+}
+
+/// Repos and seams as a petgraph carrying the ecosystem edges accepted by
+/// `keep`, with an ID → index lookup
+fn edge_view(
+    ecosystem: &EcosystemGraph,
+    keep: impl Fn(&Edge) -> bool,
+) -> (DiGraph<&str, &Edge>, HashMap<&str, NodeIndex>) {
+    let mut graph = DiGraph::new();
+    let mut indices = HashMap::new();
+    let ids = ecosystem.repos().iter().map(|r| r.id.as_str());
+    for id in ids.chain(ecosystem.seams().iter().map(|s| s.id.as_str())) {
+        indices.insert(id, graph.add_node(id));
+    }
+    let mut edges: Vec<&Edge> = ecosystem.edges().iter().filter(|e| keep(e)).collect();
+    edges.sort_by(|a, b| a.id.cmp(&b.id));
+    for edge in edges {
+        if let (Some(&from), Some(&to)) = (indices.get(edge.from.as_str()), indices.get(edge.to.as_str())) {
+            graph.add_edge(from, to, edge);
+        }
+    }
+    (graph, indices)
+}
+
+/// Queue every node with an edge into `node`
+fn push_dependents<'a>(
+    graph: &DiGraph<&'a str, &'a Edge>,
+    node: NodeIndex,
+    confidence: f64,
+    depth: usize,
+    frontier: &mut BinaryHeap<Step<'a>>,
+) {
+    for edge in graph.edges_directed(node, Direction::Incoming) {
+        let edge: &Edge = edge.weight();
+        frontier.push(Step {
+            confidence: confidence * edge_confidence(edge),
+            depth: depth + 1,
+            id: &edge.from,
+            edge,
+        });
+    }
+}
+
+/// Attach risk annotations and slot bindings to a reached node
+fn describe(ecosystem: &EcosystemGraph, step: &Step) -> Affected {
+    let risks = ecosystem
+        .aspects
+        .annotations
+        .iter()
+        .filter(|a| a.polarity == Polarity::Risk && (a.target == step.id || a.target == step.edge.id))
+        .map(|a| RiskNote {
+            aspect_id: a.aspect_id.clone(),
+            weight: a.weight,
+            reason: a.reason.clone(),
+        })
+        .collect();
+    let bindings = ecosystem
+        .slots
+        .bindings
+        .iter()
+        .filter(|b| b.consumer_id == step.id)
+        .map(|b| format!("{} -> {}", b.slot_id, b.provider_id))
+        .collect();
+    Affected {
+        id: step.id.to_string(),
+        depth: step.depth,
+        parent: step.edge.to.clone(),
+        edge_id: step.edge.id.clone(),
+        rel: step.edge.rel,
+        channel: step.edge.channel,
+        confidence: step.confidence,
+        risks,
+        bindings,
+    }
+}
+
+/// A risky or fragile repo, seam or edge
+#[derive(Debug, Clone, Serialize)]
+pub struct WeakLink {
+    /// The edge or repo involved
+    pub target_id: String,
+    /// Human-readable target name
+    pub target_name: String,
+    /// Why this is considered weak
+    pub reason: String,
+    /// Severity level (1-3)
+    pub severity: u8,
+    /// Related aspect name
+    pub aspect: String,
+}
+
+/// Find weak links, most severe first: risk annotations, single points of
+/// failure and edges without evidence.
+///
+/// A repo or seam is a single point of failure when it has three or more
+/// incoming edges (five or more for severity 3), of any relation, and no
+/// reliability strength annotation. Only those nodes get an [`impact`] walk,
+/// for the transitive count in the reason.
+///
+/// `aspect` (an aspect ID such as `aspect:security`) restricts the risk
+/// annotations considered; the structural checks always run.
+///
+/// # Errors
+/// Returns an error if an impact walk fails.
+pub fn weak_links(ecosystem: &EcosystemGraph, aspect: Option<&str>) -> Result<Vec<WeakLink>> {
+    let name_of = |id: &str| ecosystem.get_repo(id).map_or_else(|| id.to_string(), |r| r.name.clone());
+    let edge_name = |e: &Edge| format!("{} -> {}", name_of(&e.from), name_of(&e.to));
+
+    let mut weak_links: Vec<WeakLink> = Vec::new();
+
+    // 1. Repos/edges with risk annotations
+    for annotation in &ecosystem.aspects.annotations {
+        if annotation.polarity != Polarity::Risk || aspect.is_some_and(|a| a != annotation.aspect_id) {
+            continue;
+        }
+
+        let target_name = if annotation.target.starts_with("edge:") {
+            ecosystem
+                .store
+                .edges
+                .iter()
+                .find(|e| e.id == annotation.target)
+                .map_or_else(|| annotation.target.clone(), edge_name)
+        } else {
+            name_of(&annotation.target)
+        };
+
+        let aspect_name = ecosystem
+            .aspects
+            .aspects
+            .iter()
+            .find(|a| a.id == annotation.aspect_id)
+            .map_or_else(|| annotation.aspect_id.replace("aspect:", ""), |a| a.name.clone());
+
+        weak_links.push(WeakLink {
+            target_id: annotation.target.clone(),
+            target_name,
+            reason: annotation.reason.clone(),
+            severity: annotation.weight,
+            aspect: aspect_name,
+        });
+    }
+
+    // 2. Single points of failure (nodes many repos depend on, with no redundancy)
+    let mut incoming: HashMap<&str, usize> = HashMap::new();
+    for edge in &ecosystem.store.edges {
+        *incoming.entry(edge.to.as_str()).or_default() += 1;
+    }
+    let node_ids = ecosystem.repos().iter().map(|r| r.id.as_str());
+    let node_ids: Vec<&str> = node_ids.chain(ecosystem.seams().iter().map(|s| s.id.as_str())).collect();
+    for node_id in node_ids {
+        let count = incoming.get(node_id).copied().unwrap_or(0);
+        if count < 3 {
+            continue;
+        }
+        // Only flag if there is no reliability strength annotation
+        let has_reliability_strength = ecosystem.aspects.annotations.iter().any(|a| {
+            a.target == node_id && a.aspect_id == "aspect:reliability" && a.polarity == Polarity::Strength
+        });
+        if has_reliability_strength
+            || weak_links.iter().any(|w| w.target_id == node_id && w.aspect == "Reliability")
+        {
+            continue;
+        }
+        weak_links.push(WeakLink {
+            target_id: node_id.to_string(),
+            target_name: name_of(node_id),
+            reason: format!(
+                "Single point of failure: {count} repos depend on this ({} transitively)",
+                impact(ecosystem, node_id, &ImpactFilter::default())?.affected.len()
+            ),
+            severity: if count >= 5 { 3 } else { 2 },
+            aspect: "Reliability".into(),
+        });
+    }
+
+    // 3. Edges without evidence
+    for edge in &ecosystem.store.edges {
+        if edge.evidence.is_empty() {
+            weak_links.push(WeakLink {
+                target_id: edge.id.clone(),
+                target_name: edge_name(edge),
+                reason: "Edge has no evidence".into(),
+                severity: 1,
+                aspect: "Maintainability".into(),
+            });
+        }
+    }
+
+    weak_links.sort_by_key(|w| std::cmp::Reverse(w.severity));
+    Ok(weak_links)
+}
+
+/// One edge along a path
+#[derive(Debug, Clone, Serialize)]
+pub struct Hop {
+    /// Node the hop starts at
+    pub from: String,
+    /// Node the hop ends at
+    pub to: String,
+    /// Edge walked
+    pub edge_id: String,
+    /// Relation of the edge
+    pub rel: RelationType,
+    /// Channel of the edge
+    pub channel: Channel,
+    /// The edge was walked against its direction (`to` depends on `from`)
+    pub reversed: bool,
+    /// `to` is an external seam
+    pub seam: bool,
+}
+
+/// A simple path between two nodes
+#[derive(Debug, Clone, Serialize)]
+pub struct RepoPath {
+    /// Hops in order
+    pub hops: Vec<Hop>,
+    /// Some hop enters an external seam
+    pub via_seam: bool,
+}
+
+impl RepoPath {
+    fn new(hops: Vec<Hop>) -> Self {
+        let via_seam = hops.iter().any(|h| h.seam);
+        Self { hops, via_seam }
+    }
+
+    /// Node IDs along the path, both ends included
+    #[must_use]
+    pub fn nodes(&self) -> Vec<&str> {
+        let mut nodes: Vec<&str> = self.hops.first().map(|h| h.from.as_str()).into_iter().collect();
+        nodes.extend(self.hops.iter().map(|h| h.to.as_str()));
+        nodes
+    }
+}
+
+/// How to search for paths
+#[derive(Debug, Clone)]
+pub struct PathQuery {
+    /// Longest path to enumerate, in hops
+    pub max_hops: usize,
+    /// Stop after this many paths
+    pub limit: usize,
+    /// Also walk edges backwards, to find repos coupled through a shared
+    /// dependency or seam
+    pub undirected: bool,
+}
+
+impl Default for PathQuery {
+    fn default() -> Self {
+        Self {
+            max_hops: 6,
+            limit: 100,
+            undirected: false,
+        }
+    }
+}
+
+/// All simple paths between two nodes, plus the shortest
+#[derive(Debug, Clone, Serialize)]
+pub struct PathReport {
+    /// Start node
+    pub from: String,
+    /// End node
+    pub to: String,
+    /// Fewest-hop path, found regardless of `max_hops`
+    pub shortest: Option<RepoPath>,
+    /// Simple paths of at most `max_hops`, shortest first
+    pub paths: Vec<RepoPath>,
+    /// More paths exist than `limit` allowed
+    pub truncated: bool,
+}
+
+/// Find how `from` is connected to `to`.
+///
+/// Edges are followed from dependent to dependency (`A uses B` leads from A
+/// to B) unless the query is undirected. Nodes may be repos or seams.
+///
+/// # Errors
+/// Returns an error if either node does not exist.
+pub fn paths_between(ecosystem: &EcosystemGraph, from: &str, to: &str, query: &PathQuery) -> Result<PathReport> {
+    let (graph, indices) = edge_view(ecosystem, |_| true);
+    let node = |id: &str| {
+        indices
+            .get(id)
+            .copied()
+            .ok_or_else(|| anyhow::anyhow!("Not a repo or seam: {id}"))
+    };
+    let (start, goal) = (node(from)?, node(to)?);
+    let seams: HashSet<&str> = ecosystem.seams().iter().map(|s| s.id.as_str()).collect();
+    let walk = PathWalk {
+        graph: &graph,
+        seams: &seams,
+        undirected: query.undirected,
+    };
+
+    let mut paths = Vec::new();
+    let mut truncated = false;
+    if start != goal {
+        let mut on_path = vec![start];
+        let mut hops = Vec::new();
+        walk.simple_paths(goal, query, &mut on_path, &mut hops, &mut paths, &mut truncated);
+    }
+    paths.sort_by(|a, b| a.hops.len().cmp(&b.hops.len()).then_with(|| a.nodes().cmp(&b.nodes())));
+
+    Ok(PathReport {
+        from: from.to_string(),
+        to: to.to_string(),
+        shortest: walk.shortest(start, goal),
+        paths,
+        truncated,
+    })
+}
+
+/// Traversal state shared by the path searches
+struct PathWalk<'g, 'a> {
+    graph: &'g DiGraph<&'a str, &'a Edge>,
+    seams: &'g HashSet<&'a str>,
+    undirected: bool,
+}
+
+impl PathWalk<'_, '_> {
+    /// Hops leaving `node`, forward edges first, each group in edge-ID order
+    fn hops_from(&self, node: NodeIndex) -> Vec<(NodeIndex, Hop)> {
+        let mut hops = Vec::new();
+        let mut directions = vec![(Direction::Outgoing, false)];
+        if self.undirected {
+            directions.push((Direction::Incoming, true));
+        }
+        for (direction, reversed) in directions {
+            let mut edges: Vec<_> = self.graph.edges_directed(node, direction).collect();
+            edges.sort_by(|a, b| a.weight().id.cmp(&b.weight().id));
+            for edge in edges {
+                let next = if reversed { edge.source() } else { edge.target() };
+                let next_id = self.graph[next];
+                let e: &Edge = edge.weight();
+                hops.push((
+                    next,
+                    Hop {
+                        from: self.graph[node].to_string(),
+                        to: next_id.to_string(),
+                        edge_id: e.id.clone(),
+                        rel: e.rel,
+                        channel: e.channel,
+                        reversed,
+                        seam: self.seams.contains(next_id),
+                    },
+                ));
+            }
+        }
+        hops
+    }
+
+    /// Depth-first enumeration of simple paths from the end of `on_path`
+    fn simple_paths(
+        &self,
+        goal: NodeIndex,
+        query: &PathQuery,
+        on_path: &mut Vec<NodeIndex>,
+        hops: &mut Vec<Hop>,
+        paths: &mut Vec<RepoPath>,
+        truncated: &mut bool,
+    ) {
+        if hops.len() >= query.max_hops {
+            return;
+        }
+        let Some(&node) = on_path.last() else {
+            return;
+        };
+        for (next, hop) in self.hops_from(node) {
+            if *truncated || on_path.contains(&next) {
+                continue;
+            }
+            hops.push(hop);
+            if next == goal {
+                if paths.len() == query.limit {
+                    *truncated = true;
+                } else {
+                    paths.push(RepoPath::new(hops.clone()));
+                }
+            } else {
+                on_path.push(next);
+                self.simple_paths(goal, query, on_path, hops, paths, truncated);
+                on_path.pop();
+            }
+            hops.pop();
+        }
+    }
+
+    /// Breadth-first fewest-hop path
+    fn shortest(&self, start: NodeIndex, goal: NodeIndex) -> Option<RepoPath> {
+        if start == goal {
+            return None;
+        }
+        let mut came_from: HashMap<NodeIndex, (NodeIndex, Hop)> = HashMap::new();
+        let mut queue = std::collections::VecDeque::from([start]);
+        while let Some(node) = queue.pop_front() {
+            for (next, hop) in self.hops_from(node) {
+                if next == start || came_from.contains_key(&next) {
+                    continue;
+                }
+                came_from.insert(next, (node, hop));
+                if next == goal {
+                    let mut hops = Vec::new();
+                    let mut at = goal;
+                    while let Some((prev, hop)) = came_from.remove(&at) {
+                        hops.push(hop);
+                        at = prev;
+                    }
+                    hops.reverse();
+                    return Some(RepoPath::new(hops));
+                }
+                queue.push_back(next);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Channel, Edge, EdgeMeta, Forge, ImportMeta, Repo, Visibility};
+    use chrono::Utc;
+
+    fn repo(name: &str) -> Repo {
+        Repo {
+            kind: "Repo".into(),
+            id: Repo::forge_id(Forge::GitHub, "test", name),
+            forge: Forge::GitHub,
+            owner: "test".into(),
+            name: name.into(),
+            default_branch: "main".into(),
+            visibility: Visibility::Public,
+            tags: vec![],
+            estate: crate::types::default_estate(),
+            metadata: Default::default(),
+            imports: ImportMeta {
+                source: "test".into(),
+                path_hint: None,
+                imported_at: Utc::now(),
+            },
+            local_path: None,
+        }
+    }
+
+    fn id(name: &str) -> String {
+        Repo::forge_id(Forge::GitHub, "test", name)
+    }
+
+    /// Build a graph from `from -> to` dependency pairs (by name)
+    fn graph(names: &[&str], deps: &[(&str, &str, RelationType)]) -> EcosystemGraph {
+        let mut graph = EcosystemGraph::new();
+        for name in names {
+            graph.add_repo(repo(name));
+        }
+        for (from, to, rel) in deps {
+            let (from, to) = (id(from), id(to));
+            graph
+                .add_edge(Edge {
+                    kind: "Edge".into(),
+                    id: Edge::generate_id(&from, &to, *rel, Channel::Api, None),
+                    from,
+                    to,
+                    rel: *rel,
+                    channel: Channel::Api,
+                    label: None,
+                    evidence: vec![],
+                    meta: EdgeMeta {
+                        created_by: "test".into(),
+                        created_at: Utc::now(),
+                    },
+                })
+                .unwrap();
+        }
+        graph
+    }
+
+    #[test]
+    fn test_layers_chain_and_fan() {
+        use RelationType::{Extends, Mirrors, Uses};
+        // app -> web -> core, app -> core, cli -> core, web extends ui; mirrors ignored
+        let g = graph(
+            &["app", "web", "core", "cli", "ui", "fork"],
+            &[
+                ("app", "web", Uses),
+                ("app", "core", Uses),
+                ("web", "core", Uses),
+                ("cli", "core", Uses),
+                ("web", "ui", Extends),
+                ("fork", "app", Mirrors),
+            ],
+        );
+        let analysis = analyze(&g);
+
+        assert!(analysis.cycles.is_empty());
+        assert_eq!(analysis.dependency_edges, 5);
+        assert_eq!(
+            analysis.layers,
+            vec![
+                vec![id("core"), id("fork"), id("ui")],
+                vec![id("cli"), id("web")],
+                vec![id("app")],
+            ]
+        );
+        assert_eq!(analysis.longest_chain, vec![id("core"), id("web"), id("app")]);
+        assert_eq!(analysis.fan_in[0], Degree { id: id("core"), count: 3 });
+        assert_eq!(analysis.fan_out[0], Degree { id: id("app"), count: 2 });
+        assert_eq!(analysis.fan_out[1], Degree { id: id("web"), count: 2 });
+    }
+
+    #[test]
+    fn test_impact_follows_strongest_path() {
+        use crate::types::{AnnotationSource, AspectAnnotation, BindingMode, Evidence, SlotBinding};
+        use RelationType::{Mirrors, Uses};
+
+        let mut g = graph(
+            &["core", "web", "app", "cli", "docs"],
+            &[("web", "core", Uses), ("app", "web", Uses), ("app", "core", Uses), ("cli", "core", Mirrors)],
+        );
+        let set_confidence = |g: &mut EcosystemGraph, from: &str, to: &str, confidence: f64| {
+            let edge = g.store.edges.iter_mut().find(|e| e.from == id(from) && e.to == id(to)).unwrap();
+            edge.evidence = vec![Evidence {
+                evidence_type: "inferred".into(),
+                reference: "test".into(),
+                excerpt: None,
+                confidence,
+            }];
+        };
+        set_confidence(&mut g, "web", "core", 0.5);
+        set_confidence(&mut g, "app", "core", 0.9);
+        g.aspects.annotations.push(AspectAnnotation {
+            kind: "AspectAnnotation".into(),
+            id: AspectAnnotation::generate_id(&id("app"), "aspect:security"),
+            target: id("app"),
+            aspect_id: "aspect:security".into(),
+            weight: 3,
+            polarity: Polarity::Risk,
+            reason: "holds secrets".into(),
+            evidence: vec![],
+            source: AnnotationSource {
+                mode: "manual".into(),
+                who: "test".into(),
+                when: Utc::now(),
+                rule_id: None,
+            },
+        });
+        g.slots.bindings.push(SlotBinding {
+            kind: "SlotBinding".into(),
+            id: SlotBinding::generate_id(&id("app"), "slot:core.auth"),
+            consumer_id: id("app"),
+            slot_id: "slot:core.auth".into(),
+            provider_id: "provider:core.auth:core".into(),
+            mode: BindingMode::Manual,
+            created_at: Utc::now(),
+            created_by: "test".into(),
+        });
+
+        let all = impact(&g, &id("core"), &ImpactFilter::default()).unwrap();
+        let ids: Vec<&str> = all.affected.iter().map(|a| a.id.as_str()).collect();
+        assert_eq!(ids, vec![id("app"), id("cli"), id("web")]);
+        // app is reached directly (0.9) rather than through web (0.5)
+        let app = &all.affected[0];
+        assert_eq!((app.depth, app.parent.as_str()), (1, id("core").as_str()));
+        assert!((app.confidence - 0.9).abs() < f64::EPSILON);
+        assert_eq!(app.risks[0].aspect_id, "aspect:security");
+        assert_eq!(app.bindings, vec!["slot:core.auth -> provider:core.auth:core"]);
+        assert_eq!(all.direct().count(), 3);
+
+        let uses_only = ImpactFilter {
+            rels: vec![Uses],
+            min_confidence: 0.6,
+            ..ImpactFilter::default()
+        };
+        let filtered = impact(&g, &id("core"), &uses_only).unwrap();
+        let ids: Vec<&str> = filtered.affected.iter().map(|a| a.id.as_str()).collect();
+        assert_eq!(ids, vec![id("app")]);
+
+        // Breaking only web -> core affects web and, through it, app
+        let edge = g.edges().iter().find(|e| e.from == id("web") && e.to == id("core")).unwrap();
+        let by_edge = impact(&g, &edge.id, &ImpactFilter::default()).unwrap();
+        let ids: Vec<(&str, usize)> = by_edge.affected.iter().map(|a| (a.id.as_str(), a.depth)).collect();
+        assert_eq!(ids, vec![(id("web").as_str(), 1), (id("app").as_str(), 2)]);
+        assert!((by_edge.affected[1].confidence - 0.5).abs() < f64::EPSILON);
+
+        assert!(impact(&g, "repo:gh:test/missing", &ImpactFilter::default()).is_err());
+        assert!(impact(&g, &id("docs"), &ImpactFilter::default()).unwrap().affected.is_empty());
+    }
+
+    #[test]
+    fn test_paths_between() {
+        use crate::types::{default_estate, ExternalSeam, SeamDomain};
+        use RelationType::{Extends, RefersTo, Uses};
+
+        let mut g = graph(
+            &["a", "b", "c", "d"],
+            &[("a", "b", Uses), ("b", "c", Uses), ("a", "c", Extends), ("d", "c", Uses)],
+        );
+        g.add_seam(ExternalSeam {
+            kind: "ExternalSeam".into(),
+            id: "seam:aerie:net".into(),
+            domain: SeamDomain::Network,
+            system: "aerie".into(),
+            name: "net".into(),
+            uri: None,
+            description: None,
+            estate: default_estate(),
+        });
+        for from in ["a", "d"] {
+            let from = id(from);
+            g.add_edge(Edge {
+                kind: "Edge".into(),
+                id: Edge::generate_id(&from, "seam:aerie:net", RefersTo, Channel::Runtime, None),
+                from,
+                to: "seam:aerie:net".into(),
+                rel: RefersTo,
+                channel: Channel::Runtime,
+                label: None,
+                evidence: vec![],
+                meta: EdgeMeta {
+                    created_by: "test".into(),
+                    created_at: Utc::now(),
+                },
+            })
+            .unwrap();
+        }
+
+        let report = paths_between(&g, &id("a"), &id("c"), &PathQuery::default()).unwrap();
+        assert_eq!(report.shortest.as_ref().unwrap().nodes(), vec![id("a"), id("c")]);
+        assert_eq!(report.paths.len(), 2);
+        assert_eq!(report.paths[1].nodes(), vec![id("a"), id("b"), id("c")]);
+        assert_eq!(report.paths[1].hops[1].channel, Channel::Api);
+        assert!(!report.truncated);
+
+        let short = PathQuery { max_hops: 1, ..PathQuery::default() };
+        assert_eq!(paths_between(&g, &id("a"), &id("c"), &short).unwrap().paths.len(), 1);
+        let one = PathQuery { limit: 1, ..PathQuery::default() };
+        assert!(paths_between(&g, &id("a"), &id("c"), &one).unwrap().truncated);
+
+        // a and d only meet through shared dependencies (c, or the seam)
+        let directed = paths_between(&g, &id("a"), &id("d"), &PathQuery::default()).unwrap();
+        assert!(directed.shortest.is_none() && directed.paths.is_empty());
+        let undirected = PathQuery { undirected: true, ..PathQuery::default() };
+        let report = paths_between(&g, &id("a"), &id("d"), &undirected).unwrap();
+        assert_eq!(report.shortest.as_ref().unwrap().hops.len(), 2);
+        let via_seam: Vec<_> = report.paths.iter().filter(|p| p.via_seam).collect();
+        assert_eq!(via_seam.len(), 1);
+        assert_eq!(via_seam[0].nodes(), vec![id("a").as_str(), "seam:aerie:net", id("d").as_str()]);
+        assert!(via_seam[0].hops[1].reversed);
+
+        assert!(paths_between(&g, &id("a"), "repo:gh:test/missing", &PathQuery::default()).is_err());
+    }
+
+    #[test]
+    fn test_cycles_share_a_layer() {
+        use RelationType::Uses;
+        let g = graph(
+            &["a", "b", "c", "base", "solo"],
+            &[("a", "b", Uses), ("b", "a", Uses), ("b", "base", Uses), ("c", "a", Uses), ("solo", "solo", Uses)],
+        );
+        let deps = DependencyGraph::new(&g);
+
+        assert_eq!(deps.cycles(), vec![vec![id("a"), id("b")], vec![id("solo")]]);
+        assert_eq!(
+            deps.layers(),
+            vec![vec![id("base"), id("solo")], vec![id("a"), id("b")], vec![id("c")]]
+        );
+        assert_eq!(deps.longest_chain(), vec![id("base"), id("a"), id("b"), id("c")]);
+        // Self-dependencies don't count towards fan-in/fan-out
+        assert!(deps.fan_in().iter().all(|d| d.id != id("solo")));
+    }
+
+    #[test]
+    fn test_weak_links() {
+        use RelationType::Uses;
+        // Three direct dependents make core a single point of failure
+        let mut g = graph(&["a", "b", "c", "core"], &[("a", "core", Uses), ("b", "core", Uses), ("c", "core", Uses)]);
+        let risk = |aspect: &str, weight| crate::types::AspectAnnotation {
+            kind: "AspectAnnotation".into(),
+            id: crate::types::AspectAnnotation::generate_id(&id("a"), aspect),
+            target: id("a"),
+            aspect_id: aspect.into(),
+            weight,
+            polarity: Polarity::Risk,
+            reason: "old deps".into(),
+            evidence: vec![],
+            source: crate::types::AnnotationSource {
+                mode: "manual".into(),
+                who: "test".into(),
+                when: Utc::now(),
+                rule_id: None,
+            },
+        };
+        g.aspects.annotations.push(risk("aspect:security", 3));
+        g.aspects.annotations.push(risk("aspect:privacy", 1));
+
+        let links = weak_links(&g, Some("aspect:security")).unwrap();
+        let summary: Vec<_> = links.iter().map(|w| (w.target_name.as_str(), w.aspect.as_str(), w.severity)).collect();
+        assert_eq!(&summary[..2], [("a", "Security", 3), ("core", "Reliability", 2)]);
+        // Every edge lacks evidence; the privacy annotation is filtered out
+        assert_eq!(links.iter().filter(|w| w.aspect == "Maintainability").count(), 3);
+        assert_eq!(links.len(), 5);
+        assert!(links[2].target_name.ends_with(" -> core"));
+
+        assert_eq!(weak_links(&g, None).unwrap().len(), 6);
+    }
+
+    #[test]
+    fn test_weak_links_single_point_threshold() {
+        use RelationType::{Extends, Mirrors, Uses};
+        let spof = |g: &EcosystemGraph| -> Vec<(String, String, u8)> {
+            weak_links(g, None)
+                .unwrap()
+                .into_iter()
+                .filter(|w| w.aspect == "Reliability")
+                .map(|w| (w.target_name, w.reason, w.severity))
+                .collect()
+        };
+
+        // Incoming edges of any relation count, even two from the same repo;
+        // the transitive count comes from the impact walk
+        let g = graph(
+            &["a", "b", "top", "core", "lib"],
+            &[("a", "core", Uses), ("a", "core", Mirrors), ("b", "core", Extends), ("top", "a", Uses), ("a", "lib", Uses), ("b", "lib", Uses)],
+        );
+        assert_eq!(
+            spof(&g),
+            [("core".to_string(), "Single point of failure: 3 repos depend on this (3 transitively)".to_string(), 2)]
+        );
+
+        // Five incoming edges raise the severity
+        let g = graph(
+            &["a", "b", "c", "d", "e", "core"],
+            &[("a", "core", Uses), ("b", "core", Uses), ("c", "core", Uses), ("d", "core", Uses), ("e", "core", Uses)],
+        );
+        assert_eq!(spof(&g)[0].2, 3);
+    }
+}
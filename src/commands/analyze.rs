@@ -0,0 +1,69 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Analyze command - cycles, release layers, longest chain and fan-in/fan-out
+
+use crate::analysis::{analyze, Degree};
+use crate::commands::data_dir;
+use crate::graph::EcosystemGraph;
+use anyhow::{Context, Result};
+
+/// Run the analyze command
+///
+/// # Errors
+/// Returns an error if the graph cannot be loaded.
+pub fn run(top: usize, json: bool) -> Result<()> {
+    let data_dir = data_dir()?;
+    let graph = EcosystemGraph::load(&data_dir)
+        .with_context(|| format!("Failed to load graph from {}", data_dir.display()))?;
+    let analysis = analyze(&graph);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&analysis)?);
+        return Ok(());
+    }
+
+    let name = |id: &String| graph.get_repo(id).map_or_else(|| id.clone(), |r| r.name.clone());
+    let names = |ids: &[String]| ids.iter().map(name).collect::<Vec<_>>();
+
+    println!(
+        "Analyzed {} repositories, {} dependency edges (uses/extends)",
+        analysis.repos, analysis.dependency_edges
+    );
+    println!();
+
+    if analysis.cycles.is_empty() {
+        println!("No dependency cycles.");
+    } else {
+        println!("Dependency cycles ({}):", analysis.cycles.len());
+        for cycle in &analysis.cycles {
+            println!("  {}", names(cycle).join(" <-> "));
+        }
+    }
+    println!();
+
+    println!("Release layers (release layer 0 first):");
+    for (i, layer) in analysis.layers.iter().enumerate() {
+        println!("  {i}: {}", names(layer).join(", "));
+    }
+    println!();
+
+    println!("Longest dependency chain ({} repos):", analysis.longest_chain.len());
+    println!("  {}", names(&analysis.longest_chain).join(" <- "));
+    println!();
+
+    print_ranking("Fan-in (most depended on):", &analysis.fan_in, top, &name);
+    print_ranking("Fan-out (most dependencies):", &analysis.fan_out, top, &name);
+    Ok(())
+}
+
+/// Print the top entries of a fan-in/fan-out ranking
+fn print_ranking(title: &str, ranking: &[Degree], top: usize, name: &dyn Fn(&String) -> String) {
+    println!("{title}");
+    if ranking.is_empty() {
+        println!("  (none)");
+    }
+    for degree in ranking.iter().take(top) {
+        println!("  {:>4}  {}", degree.count, name(&degree.id));
+    }
+    println!();
+}
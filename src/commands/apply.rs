@@ -3,16 +3,24 @@
 //
 //! Apply and rollback plan execution
 
+use crate::commands::plan::paint_diff_line;
 use crate::graph::EcosystemGraph;
+use crate::patch::{self, Patch};
+use crate::snapshot;
 use crate::types::{
-    ApplyResult, AuditEntry, BindingMode, OpResult, Plan, PlanOp, PlanStatus, SlotBinding,
+    ApplyResult, AuditEntry, BindingMode, FileChangeType, OpResult, Plan, PlanOp, PlanStatus, RiskLevel, SlotBinding,
+    StageOutcome, StageResult,
 };
 use anyhow::{anyhow, bail, Context, Result};
-use chrono::Utc;
-use std::path::Path;
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::io::{BufRead, Write};
+use std::path::{Component, Path, PathBuf};
 
 /// Arguments for apply commands
 #[derive(Debug, Default)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct ApplyArgs {
     /// Dry-run mode - show what would happen without executing
     pub dry_run: bool,
@@ -20,6 +28,12 @@ pub struct ApplyArgs {
     pub auto_rollback: bool,
     /// Skip health check after successful apply
     pub skip_health_check: bool,
+    /// Apply one repo at a time, confirming each stage
+    pub staged: bool,
+    /// File of stage answers (`y`, `n` or `q`, one per line) instead of prompts
+    pub answers: Option<PathBuf>,
+    /// Colour the diffs shown for each stage
+    pub color: bool,
 }
 
 /// Run apply command
@@ -57,6 +71,12 @@ fn run_apply(data_dir: &Path, plan_name: Option<String>, args: ApplyArgs) -> Res
     if plan.status == PlanStatus::Applied {
         return Err(anyhow!("Plan has already been applied"));
     }
+    if args.staged {
+        return run_staged_apply(data_dir, graph, &plan, &args);
+    }
+    if plan.status == PlanStatus::PartiallyApplied {
+        bail!("Plan is partially applied; resume it with 'apply --staged'");
+    }
 
     if args.dry_run {
         println!("Dry-run: Would apply plan '{}'", plan.name);
@@ -65,109 +85,58 @@ fn run_apply(data_dir: &Path, plan_name: Option<String>, args: ApplyArgs) -> Res
         for (i, op) in plan.operations.iter().enumerate() {
             println!("  {}. {}", i + 1, op.description());
         }
+        print_file_check(&graph, &plan.operations, &plan_branch(&plan.id));
         println!();
         println!("Overall risk: {:?}", plan.overall_risk);
         return Ok(());
     }
 
+    prepare_apply(data_dir, &mut graph, &plan.id, &plan.operations, &format!("apply {}", plan.id))?;
+
     println!("Applying plan: {}", plan.name);
     println!("{}", "-".repeat(60));
-
+    let branch = plan_branch(&plan.id);
     let started_at = Utc::now();
-    let applied_by = std::env::var("USER").unwrap_or_else(|_| "unknown".into());
-    let mut op_results: Vec<OpResult> = Vec::new();
+    let (op_results, failure_index) = execute_plan_ops(&mut graph, &plan, &branch, args.auto_rollback);
     let mut notes: Vec<String> = Vec::new();
-    let mut failed = false;
-    let mut failure_index: Option<usize> = None;
-
-    // Execute operations
-    for (i, op) in plan.operations.iter().enumerate() {
-        println!("  [{}/{}] {}", i + 1, plan.operations.len(), op.description());
-
-        let result = execute_operation(&mut graph, op);
-        let success = result.is_ok();
-        let error = result.err().map(|e| e.to_string());
-
-        op_results.push(OpResult {
-            op_index: i,
-            success,
-            error: error.clone(),
-            executed_at: Utc::now(),
-        });
-
-        if success {
-            println!("       OK");
-        } else {
-            println!("       FAILED: {}", error.as_deref().unwrap_or("unknown error"));
-            failed = true;
-            failure_index = Some(i);
-
-            if args.auto_rollback {
-                notes.push(format!("Auto-rollback triggered after operation {} failed", i + 1));
-                break;
-            }
-        }
-    }
-
-    let finished_at = Utc::now();
 
     // Determine result
-    let (result, auto_rollback_triggered, rollback_plan_id) = if failed {
-        if let (true, Some(failure_idx)) = (args.auto_rollback, failure_index) {
-            // Execute rollback
-            let rollback_result = execute_rollback(&mut graph, &plan, failure_idx);
-            match rollback_result {
-                Ok(rollback_id) => (ApplyResult::RolledBack, true, Some(rollback_id)),
+    let (result, auto_rollback_triggered, rollback_plan_id) = match failure_index {
+        None => (ApplyResult::Success, false, None),
+        Some(_) if !args.auto_rollback => (ApplyResult::PartialFailure, false, None),
+        Some(failure_idx) => {
+            notes.push(format!("Auto-rollback triggered after operation {} failed", failure_idx + 1));
+            // Reverse only the operations that succeeded; the failed one changed nothing
+            let mut done: Vec<usize> = (0..failure_idx).collect();
+            match execute_rollback(&mut graph, &plan, &mut done, &branch) {
+                Ok(rollback_id) => {
+                    notes.extend(restore_base_refs(&mut graph, &plan.id, None));
+                    (ApplyResult::RolledBack, true, Some(rollback_id))
+                }
                 Err(e) => {
                     notes.push(format!("Rollback failed: {}", e));
                     (ApplyResult::Failure, true, None)
                 }
             }
-        } else {
-            (ApplyResult::PartialFailure, false, None)
         }
-    } else {
-        (ApplyResult::Success, false, None)
     };
 
-    // Health check
-    let health_check_passed = if !args.skip_health_check && result == ApplyResult::Success {
-        let health_result = run_health_check(&graph);
-        if !health_result {
-            notes.push("Health check failed - manual review recommended".into());
-        }
-        Some(health_result)
-    } else {
-        None
-    };
-
-    // Create audit entry
-    let audit_entry = AuditEntry {
-        kind: "AuditEntry".into(),
-        id: format!("audit:{}:{}", plan.id, started_at.timestamp()),
-        plan_id: plan.id.clone(),
-        result,
-        op_results,
-        started_at,
-        finished_at,
-        applied_by,
+    let health_check_passed = health_check(&graph, &args, result, &mut notes);
+    graph.audit.entries.push(AuditEntry {
         auto_rollback_triggered,
         rollback_plan_id: rollback_plan_id.clone(),
         health_check_passed,
         notes,
-    };
-
-    graph.audit.entries.push(audit_entry);
+        ..audit_entry("audit", &plan.id, started_at, result, op_results)
+    });
 
     // Update plan status
     if let Some(p) = graph.plans.plans.iter_mut().find(|p| p.id == plan.id) {
-        p.status = match result {
-            ApplyResult::Success => PlanStatus::Applied,
-            ApplyResult::RolledBack => PlanStatus::Draft,
-            _ => PlanStatus::Draft,
-        };
         if result == ApplyResult::Success {
+            p.status = PlanStatus::Applied;
             p.applied_at = Some(Utc::now());
+        } else {
+            p.status = PlanStatus::Draft;
         }
     }
 
@@ -180,11 +149,7 @@ fn run_apply(data_dir: &Path, plan_name: Option<String>, args: ApplyArgs) -> Res
     match result {
         ApplyResult::Success => {
             println!("Plan applied successfully!");
-            if health_check_passed == Some(true) {
-                println!("Health check: PASSED");
-            } else if health_check_passed == Some(false) {
-                println!("Health check: FAILED - review recommended");
-            }
+            print_health_check(health_check_passed);
         }
         ApplyResult::PartialFailure => {
             println!("Plan partially applied with failures");
@@ -199,11 +164,417 @@ fn run_apply(data_dir: &Path, plan_name: Option<String>, args: ApplyArgs) -> Res
                 println!("Rollback plan ID: {}", rb_id);
             }
         }
+        ApplyResult::Incomplete => unreachable!("only a staged apply stops early"),
     }
 
     Ok(())
 }
 
+/// Run a plan's operations in order, stopping at the first failure when
+/// `stop_on_failure` is set; returns their results and the first failed one
+fn execute_plan_ops(
+    graph: &mut EcosystemGraph,
+    plan: &Plan,
+    branch: &str,
+    stop_on_failure: bool,
+) -> (Vec<OpResult>, Option<usize>) {
+    let mut op_results: Vec<OpResult> = Vec::new();
+    let mut failure_index: Option<usize> = None;
+
+    for (i, op) in plan.operations.iter().enumerate() {
+        println!("  [{}/{}] {}", i + 1, plan.operations.len(), op.description());
+
+        let error = execute_operation(graph, op, branch).err().map(|e| e.to_string());
+        op_results.push(OpResult { op_index: i, success: error.is_none(), error: error.clone(), executed_at: Utc::now() });
+        if let Some(error) = error {
+            println!("       FAILED: {error}");
+            failure_index.get_or_insert(i);
+            if stop_on_failure {
+                break;
+            }
+        } else {
+            println!("       OK");
+        }
+    }
+    (op_results, failure_index)
+}
+
+/// Check that every file change in `ops` applies, then snapshot the stores
+/// and record the checkouts' base refs; nothing is changed on failure
+fn prepare_apply(data_dir: &Path, graph: &mut EcosystemGraph, plan_id: &str, ops: &[PlanOp], reason: &str) -> Result<()> {
+    let patch_failures = check_file_changes(graph, ops);
+    if !patch_failures.is_empty() {
+        print_file_check(graph, ops, &plan_branch(plan_id));
+        bail!("{} file change(s) do not apply; nothing was changed", patch_failures.len());
+    }
+    snapshot_stores(data_dir, graph, reason)?;
+    record_base_refs(graph, plan_id, ops)
+}
+
+/// An audit entry for a run on `plan_id` that started at `started_at` and
+/// finishes now, with no rollback, health check, notes or stages
+fn audit_entry(
+    prefix: &str,
+    plan_id: &str,
+    started_at: DateTime<Utc>,
+    result: ApplyResult,
+    op_results: Vec<OpResult>,
+) -> AuditEntry {
+    AuditEntry {
+        kind: "AuditEntry".into(),
+        id: format!("{prefix}:{plan_id}:{}", started_at.timestamp()),
+        plan_id: plan_id.to_string(),
+        result,
+        op_results,
+        started_at,
+        finished_at: Utc::now(),
+        applied_by: std::env::var("USER").unwrap_or_else(|_| "unknown".into()),
+        auto_rollback_triggered: false,
+        rollback_plan_id: None,
+        health_check_passed: None,
+        notes: Vec::new(),
+        stages: Vec::new(),
+    }
+}
+
+/// Run the health check after a successful apply, unless skipped, noting a
+/// failure in `notes`
+fn health_check(graph: &EcosystemGraph, args: &ApplyArgs, result: ApplyResult, notes: &mut Vec<String>) -> Option<bool> {
+    (!args.skip_health_check && result == ApplyResult::Success).then(|| {
+        let passed = run_health_check(graph);
+        if !passed {
+            notes.push("Health check failed - manual review recommended".into());
+        }
+        passed
+    })
+}
+
+/// Print the health check result, if one was run
+fn print_health_check(passed: Option<bool>) {
+    match passed {
+        Some(true) => println!("Health check: PASSED"),
+        Some(false) => println!("Health check: FAILED - review recommended"),
+        None => {}
+    }
+}
+
+/// One stage of a staged apply: the operations touching one repo
+struct Stage {
+    repo_id: String,
+    op_indexes: Vec<usize>,
+}
+
+/// Group a plan's operations by the repo they touch, in plan order
+fn plan_stages(plan: &Plan) -> Vec<Stage> {
+    let mut stages: Vec<Stage> = Vec::new();
+    for (i, op) in plan.operations.iter().enumerate() {
+        let repo_id = match op {
+            PlanOp::SwitchBinding { consumer_id, .. }
+            | PlanOp::CreateBinding { consumer_id, .. }
+            | PlanOp::RemoveBinding { consumer_id, .. } => consumer_id,
+            PlanOp::FileChange { repo_id, .. } => repo_id,
+        };
+        match stages.iter_mut().find(|s| &s.repo_id == repo_id) {
+            Some(stage) => stage.op_indexes.push(i),
+            None => stages.push(Stage { repo_id: repo_id.clone(), op_indexes: vec![i] }),
+        }
+    }
+    stages
+}
+
+/// An answer to a stage prompt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Answer {
+    Yes,
+    No,
+    Quit,
+}
+
+impl Answer {
+    fn parse(text: &str) -> Option<Self> {
+        match text.trim().to_lowercase().as_str() {
+            "y" | "yes" => Some(Self::Yes),
+            "n" | "no" | "s" | "skip" => Some(Self::No),
+            "q" | "quit" => Some(Self::Quit),
+            _ => None,
+        }
+    }
+}
+
+/// Where stage answers come from: stdin, or an answers file for CI
+enum Answers {
+    Stdin,
+    File(std::vec::IntoIter<String>),
+}
+
+impl Answers {
+    /// Answers from `path` (blank lines and `#` comments ignored), or stdin
+    fn open(path: Option<&Path>) -> Result<Self> {
+        let Some(path) = path else {
+            return Ok(Self::Stdin);
+        };
+        let text = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let lines: Vec<String> = text
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .map(String::from)
+            .collect();
+        if let Some(bad) = lines.iter().find(|l| Answer::parse(l).is_none()) {
+            bail!("Invalid answer in {}: {bad} (use y, n or q)", path.display());
+        }
+        Ok(Self::File(lines.into_iter()))
+    }
+
+    /// Ask a question; running out of answers stops the apply
+    fn ask(&mut self, prompt: &str) -> Result<Answer> {
+        match self {
+            Self::File(lines) => {
+                let line = lines.next();
+                println!("{prompt}{}", line.as_deref().unwrap_or("(no answer left)"));
+                Ok(line.as_deref().and_then(Answer::parse).unwrap_or(Answer::Quit))
+            }
+            Self::Stdin => loop {
+                print!("{prompt}");
+                std::io::stdout().flush()?;
+                let mut line = String::new();
+                if std::io::stdin().lock().read_line(&mut line)? == 0 {
+                    println!();
+                    return Ok(Answer::Quit);
+                }
+                match Answer::parse(&line) {
+                    Some(answer) => return Ok(answer),
+                    None => println!("Please answer y (apply), n (skip for now) or q (stop)"),
+                }
+            },
+        }
+    }
+}
+
+/// Apply a plan one repo at a time
+///
+/// Each stage shows its operations, risk and file diffs, then waits for an
+/// answer: `y` applies it, `n` leaves it for a later run, `q` stops. A stage
+/// stops at its first failed operation, and so does the apply (reversing the
+/// stage's done operations with `--auto-rollback`). Operations done by an
+/// earlier staged run are not repeated, so running it again resumes a
+/// `PartiallyApplied` plan.
+fn run_staged_apply(data_dir: &Path, mut graph: EcosystemGraph, plan: &Plan, args: &ApplyArgs) -> Result<()> {
+    let mut applied: BTreeSet<usize> = plan.applied_ops.iter().copied().collect();
+    let pending_ops: Vec<PlanOp> = (0..plan.operations.len())
+        .filter(|i| !applied.contains(i))
+        .map(|i| plan.operations[i].clone())
+        .collect();
+    let branch = plan_branch(&plan.id);
+
+    if args.dry_run {
+        let stages = plan_stages(plan);
+        println!("Dry-run: Would apply plan '{}' in {} stage(s)", plan.name, stages.len());
+        for (n, stage) in stages.iter().enumerate() {
+            let done = stage.op_indexes.iter().all(|i| applied.contains(i));
+            println!();
+            let header = format!("Stage {}/{}: {}", n + 1, stages.len(), repo_name(&graph, &stage.repo_id));
+            if done {
+                println!("{header} - already applied");
+            } else {
+                print_stage(plan, &header, &stage.op_indexes, false);
+            }
+        }
+        print_file_check(&graph, &pending_ops, &branch);
+        return Ok(());
+    }
+
+    // Nothing is applied unless every pending file change applies cleanly
+    prepare_apply(data_dir, &mut graph, &plan.id, &pending_ops, &format!("apply {} (staged)", plan.id))?;
+
+    println!("Applying plan in stages: {}", plan.name);
+    println!("{}", "-".repeat(60));
+    let started_at = Utc::now();
+    let mut run = run_stages(&mut graph, plan, args, &mut applied)?;
+
+    let complete = applied.len() == plan.operations.len();
+    let result = match run.stopped_on {
+        Some(StageOutcome::RolledBack) => ApplyResult::RolledBack,
+        Some(_) => ApplyResult::PartialFailure,
+        None if complete => ApplyResult::Success,
+        None => ApplyResult::Incomplete,
+    };
+    let health_check_passed = health_check(&graph, args, result, &mut run.notes);
+    graph.audit.entries.push(AuditEntry {
+        auto_rollback_triggered: run.stopped_on == Some(StageOutcome::RolledBack),
+        health_check_passed,
+        notes: run.notes,
+        stages: run.stages,
+        ..audit_entry("audit", &plan.id, started_at, result, run.op_results)
+    });
+
+    if let Some(p) = graph.plans.plans.iter_mut().find(|p| p.id == plan.id) {
+        if complete {
+            p.status = PlanStatus::Applied;
+            p.applied_at = Some(Utc::now());
+            p.applied_ops.clear();
+        } else if !applied.is_empty() {
+            p.status = PlanStatus::PartiallyApplied;
+            p.applied_ops = applied.iter().copied().collect();
+        }
+    }
+    graph.save(data_dir).context("Failed to save graph")?;
+
+    println!();
+    println!("{}", "=".repeat(60));
+    match result {
+        ApplyResult::Success => {
+            println!("Plan applied successfully!");
+            print_health_check(health_check_passed);
+        }
+        ApplyResult::RolledBack => println!("Stage failed and was rolled back; earlier stages stay applied"),
+        _ => {
+            println!("{} of {} operations applied", applied.len(), plan.operations.len());
+            if !applied.is_empty() {
+                println!("Resume with: reposystem apply apply {} --staged", plan.id);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// What a staged run did, for its audit entry
+#[derive(Default)]
+struct StagedRun {
+    op_results: Vec<OpResult>,
+    stages: Vec<StageResult>,
+    notes: Vec<String>,
+    /// Outcome of the stage that stopped the run (failed or rolled back)
+    stopped_on: Option<StageOutcome>,
+}
+
+/// Ask about and run each stage with operations left to do, adding the done
+/// operations to `applied`
+fn run_stages(
+    graph: &mut EcosystemGraph,
+    plan: &Plan,
+    args: &ApplyArgs,
+    applied: &mut BTreeSet<usize>,
+) -> Result<StagedRun> {
+    let stages = plan_stages(plan);
+    let mut answers = Answers::open(args.answers.as_deref())?;
+    let branch = plan_branch(&plan.id);
+    let mut run = StagedRun::default();
+
+    for (n, stage) in stages.iter().enumerate() {
+        let repo = repo_name(graph, &stage.repo_id);
+        let todo: Vec<usize> = stage.op_indexes.iter().copied().filter(|i| !applied.contains(i)).collect();
+        println!();
+        if todo.is_empty() {
+            println!("Stage {}/{}: {repo} - already applied", n + 1, stages.len());
+            continue;
+        }
+        let risk = print_stage(plan, &format!("Stage {}/{}: {repo}", n + 1, stages.len()), &todo, args.color);
+
+        let mut stage_result = StageResult {
+            repo_id: stage.repo_id.clone(),
+            op_indexes: todo.clone(),
+            risk,
+            outcome: StageOutcome::Skipped,
+            decided_at: Utc::now(),
+        };
+        match answers.ask(&format!("Apply stage {}/{} ({repo})? [y/n/q] ", n + 1, stages.len()))? {
+            Answer::Quit => {
+                run.notes.push(format!("Stopped before stage {} ({repo})", n + 1));
+                break;
+            }
+            Answer::No => {
+                println!("  Skipped");
+                run.stages.push(stage_result);
+                continue;
+            }
+            Answer::Yes => {}
+        }
+
+        let (outcome, mut done) = run_stage(graph, plan, &todo, &branch, &mut run.op_results);
+        stage_result.outcome = outcome;
+        if outcome == StageOutcome::Failed && args.auto_rollback {
+            run.notes.push(format!("Auto-rollback of stage {} ({repo}) triggered", n + 1));
+            match execute_rollback(graph, plan, &mut done, &branch) {
+                Ok(_) => {
+                    stage_result.outcome = StageOutcome::RolledBack;
+                    run.notes.extend(restore_base_refs(graph, &plan.id, Some(&stage.repo_id)));
+                }
+                Err(e) => {
+                    // Only the operations the rollback did not reach stay applied
+                    run.notes.push(format!("Rollback failed: {e}"));
+                    applied.extend(done);
+                }
+            }
+        } else {
+            applied.extend(done);
+        }
+        stage_result.decided_at = Utc::now();
+        let outcome = stage_result.outcome;
+        run.stages.push(stage_result);
+        if outcome != StageOutcome::Applied {
+            run.stopped_on = Some(outcome);
+            break;
+        }
+    }
+    Ok(run)
+}
+
+/// Snapshot the stores before a run changes them
+fn snapshot_stores(data_dir: &Path, graph: &EcosystemGraph, reason: &str) -> Result<()> {
+    let info = snapshot::take(data_dir, graph, reason)
+        .context("Failed to snapshot the stores; nothing was changed")?;
+    println!("Snapshot: {} (restore with: reposystem snapshot restore {})", info.id, info.id);
+    Ok(())
+}
+
+/// A repo's name, or its ID if it is not in the graph
+fn repo_name(graph: &EcosystemGraph, repo_id: &str) -> String {
+    graph.get_repo(repo_id).map_or_else(|| repo_id.to_string(), |r| r.name.clone())
+}
+
+/// Print a stage's operations with their file diffs; returns its risk
+fn print_stage(plan: &Plan, header: &str, op_indexes: &[usize], color: bool) -> RiskLevel {
+    let ops: Vec<PlanOp> = op_indexes.iter().map(|&i| plan.operations[i].clone()).collect();
+    let risk = Plan::calculate_overall_risk(&ops);
+    println!("{header} ({} op(s), risk {risk:?})", ops.len());
+    for (&i, op) in op_indexes.iter().zip(&ops) {
+        println!("  {}. {}", i + 1, op.description());
+        if let PlanOp::FileChange { diff: Some(diff), .. } = op {
+            for line in diff.lines() {
+                println!("       {}", paint_diff_line(line, color));
+            }
+        }
+    }
+    risk
+}
+
+/// Run a confirmed stage's operations, stopping at the first failure.
+/// Returns the outcome and the indexes of the operations done.
+fn run_stage(
+    graph: &mut EcosystemGraph,
+    plan: &Plan,
+    op_indexes: &[usize],
+    branch: &str,
+    op_results: &mut Vec<OpResult>,
+) -> (StageOutcome, Vec<usize>) {
+    let mut done = Vec::new();
+    for &i in op_indexes {
+        let op = &plan.operations[i];
+        println!("  [{}/{}] {}", i + 1, plan.operations.len(), op.description());
+        let error = execute_operation(graph, op, branch).err().map(|e| e.to_string());
+        op_results.push(OpResult { op_index: i, success: error.is_none(), error: error.clone(), executed_at: Utc::now() });
+        if let Some(error) = error {
+            println!("       FAILED: {error}");
+            return (StageOutcome::Failed, done);
+        }
+        println!("       OK");
+        done.push(i);
+    }
+    (StageOutcome::Applied, done)
+}
+
 /// Undo/rollback a plan
 fn run_undo(data_dir: &Path, plan_name: Option<String>, args: ApplyArgs) -> Result<()> {
     let plan_id = plan_name.ok_or_else(|| anyhow!("Plan name/ID is required"))?;
@@ -220,17 +591,20 @@ fn run_undo(data_dir: &Path, plan_name: Option<String>, args: ApplyArgs) -> Resu
         .cloned()
         .ok_or_else(|| anyhow!("Plan not found: {}", plan_id))?;
 
-    if plan.status != PlanStatus::Applied {
-        return Err(anyhow!("Plan has not been applied, cannot undo"));
-    }
+    // A partially applied plan only reverses the operations done so far
+    let done: Vec<usize> = match plan.status {
+        PlanStatus::Applied => (0..plan.operations.len()).collect(),
+        PlanStatus::PartiallyApplied => plan.applied_ops.clone(),
+        _ => return Err(anyhow!("Plan has not been applied, cannot undo")),
+    };
 
     // Generate rollback operations (reverse order)
-    let rollback_ops: Vec<PlanOp> = plan
-        .operations
+    let rollback_ops: Vec<PlanOp> = done
         .iter()
         .rev()
-        .filter_map(|op| reverse_operation(op))
+        .map(|&i| reverse_operation(&plan.operations[i]))
         .collect();
+    let branch = plan_branch(&plan.id);
 
     if args.dry_run {
         println!("Dry-run: Would undo plan '{}'", plan.name);
@@ -239,69 +613,59 @@ fn run_undo(data_dir: &Path, plan_name: Option<String>, args: ApplyArgs) -> Resu
         for (i, op) in rollback_ops.iter().enumerate() {
             println!("  {}. {}", i + 1, op.description());
         }
+        print_file_check(&graph, &rollback_ops, &branch);
         return Ok(());
     }
 
+    let patch_failures = check_file_changes(&graph, &rollback_ops);
+    if !patch_failures.is_empty() {
+        print_file_check(&graph, &rollback_ops, &branch);
+        bail!("{} file change(s) cannot be reversed; nothing was changed", patch_failures.len());
+    }
+    snapshot_stores(data_dir, &graph, &format!("undo {}", plan.id))?;
+
     println!("Undoing plan: {}", plan.name);
     println!("{}", "-".repeat(60));
 
     let started_at = Utc::now();
-    let applied_by = std::env::var("USER").unwrap_or_else(|_| "unknown".into());
     let mut op_results: Vec<OpResult> = Vec::new();
     let mut failed = false;
 
     for (i, op) in rollback_ops.iter().enumerate() {
         println!("  [{}/{}] {}", i + 1, rollback_ops.len(), op.description());
 
-        let result = execute_operation(&mut graph, op);
-        let success = result.is_ok();
-        let error = result.err().map(|e| e.to_string());
-
-        op_results.push(OpResult {
-            op_index: i,
-            success,
-            error: error.clone(),
-            executed_at: Utc::now(),
-        });
-
-        if success {
-            println!("       OK");
-        } else {
-            println!("       FAILED: {}", error.as_deref().unwrap_or("unknown error"));
+        let error = execute_operation(&mut graph, op, &branch).err().map(|e| e.to_string());
+        op_results.push(OpResult { op_index: i, success: error.is_none(), error: error.clone(), executed_at: Utc::now() });
+        if let Some(error) = error {
+            println!("       FAILED: {error}");
             failed = true;
+        } else {
+            println!("       OK");
         }
     }
 
-    let finished_at = Utc::now();
     let result = if failed {
         ApplyResult::PartialFailure
     } else {
         ApplyResult::Success
     };
+    let mut notes = vec!["Manual undo operation".to_string()];
+    if !failed {
+        notes.extend(restore_base_refs(&mut graph, &plan.id, None));
+    }
 
     // Create audit entry for the undo
-    let audit_entry = AuditEntry {
-        kind: "AuditEntry".into(),
-        id: format!("audit:undo:{}:{}", plan.id, started_at.timestamp()),
-        plan_id: plan.id.clone(),
-        result,
-        op_results,
-        started_at,
-        finished_at,
-        applied_by,
-        auto_rollback_triggered: false,
-        rollback_plan_id: None,
-        health_check_passed: None,
-        notes: vec!["Manual undo operation".into()],
-    };
-
-    graph.audit.entries.push(audit_entry);
+    graph.audit.entries.push(AuditEntry {
+        notes,
+        ..audit_entry("audit:undo", &plan.id, started_at, result, op_results)
+    });
 
     // Update plan status back to draft
     if !failed {
         if let Some(p) = graph.plans.plans.iter_mut().find(|p| p.id == plan.id) {
             p.status = PlanStatus::Draft;
             p.applied_at = None;
+            p.applied_ops.clear();
         }
     }
 
@@ -384,7 +748,10 @@ fn run_audit_log(data_dir: &Path, plan_name: Option<String>) -> Result<()> {
 }
 
 /// Execute a single operation
-fn execute_operation(graph: &mut EcosystemGraph, op: &PlanOp) -> Result<()> {
+///
+/// File changes are written to the repo's working tree and committed on
+/// `branch`, switching the checkout to it first.
+fn execute_operation(graph: &mut EcosystemGraph, op: &PlanOp, branch: &str) -> Result<()> {
     match op {
         PlanOp::SwitchBinding {
             binding_id,
@@ -514,18 +881,384 @@ fn execute_operation(graph: &mut EcosystemGraph, op: &PlanOp) -> Result<()> {
             }
             Ok(())
         }
-        PlanOp::FileChange { repo_id, file_path, change_type, .. } => {
-            // File changes are recorded but not executed automatically
-            // This is a safety measure - actual file operations should be done manually
-            println!("       [FILE] {:?} {} in {}", change_type, file_path, repo_id);
-            println!("       Note: File changes require manual execution");
+        PlanOp::FileChange { repo_id, file_path, change_type, diff, .. } => {
+            execute_file_change(graph, repo_id, file_path, *change_type, diff.as_deref(), branch)
+        }
+    }
+}
+
+/// Write a file change to the repo's checkout and commit it on `branch`
+fn execute_file_change(
+    graph: &EcosystemGraph,
+    repo_id: &str,
+    file_path: &str,
+    change_type: FileChangeType,
+    diff: Option<&str>,
+    branch: &str,
+) -> Result<()> {
+    let root = repo_checkout(graph, repo_id)?;
+    let target = worktree_file(&root, file_path)?;
+    let patch = file_patch(file_path, change_type, diff)?;
+    let patched = patch.apply(read_file(&target)?.as_deref())?;
+
+    switch_to_branch(&root, branch)?;
+    println!("       [FILE] {change_type:?} {file_path} on branch {branch}");
+    match patched {
+        Some(text) => {
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&target, text).with_context(|| format!("Failed to write {}", target.display()))?;
+        }
+        None => fs::remove_file(&target).with_context(|| format!("Failed to delete {}", target.display()))?,
+    }
+    let commit = commit_file(&root, file_path, &format!("{change_type:?} {file_path}\n\nApplied by reposystem on {branch}"))?;
+    println!("       [FILE] Committed {}", commit.to_hex_with_len(7));
+    Ok(())
+}
+
+/// Branch a plan's file changes are applied on: `reposystem/<plan>`
+fn plan_branch(plan_id: &str) -> String {
+    let name: String = plan_id
+        .trim_start_matches("plan:")
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '.' { c } else { '-' })
+        .collect();
+    format!("reposystem/{name}")
+}
+
+/// The local checkout of a repo, from its `path_hint`
+fn repo_checkout(graph: &EcosystemGraph, repo_id: &str) -> Result<PathBuf> {
+    let repo = graph.get_repo(repo_id).ok_or_else(|| anyhow!("Repo not found: {repo_id}"))?;
+    repo.imports
+        .path_hint
+        .clone()
+        .filter(|p| p.is_dir())
+        .ok_or_else(|| anyhow!("{} has no local checkout (path_hint)", repo.name))
+}
+
+/// `file_path` inside the checkout, refusing absolute paths and `..`
+fn worktree_file(root: &Path, file_path: &str) -> Result<PathBuf> {
+    let relative = Path::new(file_path);
+    if !relative.components().all(|c| matches!(c, Component::Normal(_))) {
+        bail!("File path must stay inside the repository: {file_path}");
+    }
+    Ok(root.join(relative))
+}
+
+/// A file's contents, or `None` if it does not exist
+fn read_file(path: &Path) -> Result<Option<String>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    fs::read_to_string(path).map(Some).with_context(|| format!("Failed to read {}", path.display()))
+}
+
+/// Parse a file change's diff, checking it is for the op's file and kind of change
+fn file_patch(file_path: &str, change_type: FileChangeType, diff: Option<&str>) -> Result<Patch> {
+    let diff = diff.ok_or_else(|| anyhow!("File change to {file_path} has no diff to apply"))?;
+    let patch = Patch::parse(diff)?;
+    if patch.path() != file_path {
+        bail!("Diff is for {}, not {file_path}", patch.path());
+    }
+    let kind = match (&patch.old_path, &patch.new_path) {
+        (None, _) => FileChangeType::Create,
+        (_, None) => FileChangeType::Delete,
+        _ => FileChangeType::Modify,
+    };
+    if kind != change_type {
+        bail!("Diff is a {kind:?} of {file_path}, but the op says {change_type:?}");
+    }
+    Ok(patch)
+}
+
+/// Check every file change against the working trees without writing
+/// anything (like `git apply --check`), in order, so a later change to the
+/// same file sees the earlier ones. Each checkout must be a git repository
+/// without uncommitted changes, since changes are committed on a branch.
+/// Returns (op index, error) per failure.
+fn check_file_changes(graph: &EcosystemGraph, ops: &[PlanOp]) -> Vec<(usize, String)> {
+    let mut files: HashMap<PathBuf, Option<String>> = HashMap::new();
+    let mut clean: HashSet<PathBuf> = HashSet::new();
+    let mut failures = Vec::new();
+    for (i, op) in ops.iter().enumerate() {
+        let PlanOp::FileChange { repo_id, file_path, change_type, diff, .. } = op else {
+            continue;
+        };
+        let result = (|| -> Result<()> {
+            let root = repo_checkout(graph, repo_id)?;
+            if !clean.contains(&root) {
+                let repo = gix::open(&root).with_context(|| format!("No git repository at {}", root.display()))?;
+                ensure_clean(&repo)?;
+                clean.insert(root.clone());
+            }
+            let target = worktree_file(&root, file_path)?;
+            let patch = file_patch(file_path, *change_type, diff.as_deref())?;
+            let current = match files.get(&target) {
+                Some(contents) => contents.clone(),
+                None => read_file(&target)?,
+            };
+            files.insert(target, patch.apply(current.as_deref())?);
             Ok(())
+        })();
+        if let Err(e) = result {
+            failures.push((i, format!("{e:#}")));
+        }
+    }
+    failures
+}
+
+/// Print the patch check for each file change of a plan
+fn print_file_check(graph: &EcosystemGraph, ops: &[PlanOp], branch: &str) {
+    if !ops.iter().any(|op| matches!(op, PlanOp::FileChange { .. })) {
+        return;
+    }
+    let failures: HashMap<usize, String> = check_file_changes(graph, ops).into_iter().collect();
+    println!();
+    println!("File changes (checked against the working tree, applied on {branch}):");
+    for (i, op) in ops.iter().enumerate() {
+        if matches!(op, PlanOp::FileChange { .. }) {
+            match failures.get(&i) {
+                Some(error) => println!("  {}. FAILED: {error}", i + 1),
+                None => println!("  {}. applies cleanly", i + 1),
+            }
+        }
+    }
+}
+
+/// Point the checkout's HEAD at `branch`, creating it at the current commit.
+/// The working tree is left as it is, so an existing branch must be at the
+/// same commit.
+fn switch_to_branch(root: &Path, branch: &str) -> Result<()> {
+    use gix::refs::transaction::PreviousValue;
+    use gix::refs::Target;
+
+    let mut repo = gix::open(root).with_context(|| format!("No git repository at {}", root.display()))?;
+    // The reflog needs a committer; fall back to a generic one if git has none
+    repo.committer_or_set_generic_fallback()?;
+    let full_name = format!("refs/heads/{branch}");
+    if repo.head_name()?.is_some_and(|name| name.as_bstr() == full_name.as_str()) {
+        return Ok(());
+    }
+    let head = repo.head_id().with_context(|| format!("{} has no commits to branch from", root.display()))?.detach();
+    match repo.try_find_reference(full_name.as_str())? {
+        Some(mut existing) => {
+            if existing.peel_to_id()?.detach() != head {
+                bail!("Branch {branch} already exists at another commit in {}; check it out first", root.display());
+            }
+        }
+        None => {
+            repo.reference(full_name.as_str(), head, PreviousValue::MustNotExist, format!("branch: Created for {branch}"))?;
+        }
+    }
+    point_head(&repo, Target::Symbolic(full_name.as_str().try_into()?), format!("checkout: moving to {branch}"))
+}
+
+/// Point HEAD at `target` without touching the working tree or index
+fn point_head(repo: &gix::Repository, target: gix::refs::Target, message: String) -> Result<()> {
+    use gix::refs::transaction::{Change, LogChange, PreviousValue, RefEdit, RefLog};
+
+    repo.edit_reference(RefEdit {
+        change: Change::Update {
+            log: LogChange {
+                mode: RefLog::AndReference,
+                force_create_reflog: false,
+                message: message.into(),
+            },
+            expected: PreviousValue::Any,
+            new: target,
+        },
+        name: "HEAD".try_into()?,
+        deref: false,
+    })?;
+    Ok(())
+}
+
+/// Refuse a checkout whose index or tracked files differ from HEAD: file
+/// changes are committed on a branch started from HEAD, and switching to it
+/// would carry other edits along. Untracked files are left alone.
+fn ensure_clean(repo: &gix::Repository) -> Result<()> {
+    use gix::index::entry::{stat, Mode, Stat};
+
+    let root = repo.workdir().context("Repository has no working tree")?;
+    let index = repo.open_index()?;
+    let dirty = |what: String| anyhow!("{} has uncommitted changes ({what}); commit or stash them first", root.display());
+
+    // Staged: the index must list exactly HEAD's files
+    let head = repo.index_from_tree(&repo.head_tree_id()?)?;
+    let listing = |file: &gix::index::File| -> Vec<_> {
+        file.entries().iter().map(|e| (e.path(file).to_owned(), e.id, e.mode, e.stage())).collect()
+    };
+    let (staged, committed) = (listing(&index), listing(&head));
+    if staged != committed {
+        let path = staged.iter().zip(&committed).find(|(a, b)| a != b).map_or_else(
+            || staged.get(committed.len()).or(committed.get(staged.len())).map(|e| e.0.clone()),
+            |(a, _)| Some(a.0.clone()),
+        );
+        return Err(dirty(format!("{} is staged", path.unwrap_or_default())));
+    }
+
+    // Unstaged: each tracked file must still hash to its index entry, unless
+    // its stat data shows it untouched since the index was written
+    let options = stat::Options::default();
+    for entry in index.entries() {
+        if entry.mode != Mode::FILE && entry.mode != Mode::FILE_EXECUTABLE {
+            continue;
+        }
+        let path = entry.path(&index);
+        let file = root.join(gix::path::from_bstr(path));
+        let Ok(metadata) = gix::index::fs::Metadata::from_path_no_follow(&file) else {
+            return Err(dirty(format!("{path} is deleted")));
+        };
+        if Stat::from_fs(&metadata)?.matches(&entry.stat, options) && !entry.stat.is_racy(index.timestamp(), options) {
+            continue;
+        }
+        let data = fs::read(&file).with_context(|| format!("Failed to read {}", file.display()))?;
+        if gix::objs::compute_hash(repo.object_hash(), gix::objs::Kind::Blob, &data)? != entry.id {
+            return Err(dirty(format!("{path} is modified")));
+        }
+    }
+    Ok(())
+}
+
+/// Commit `file_path` as it now is in the working tree on the checked-out
+/// branch, updating the index to match so the checkout stays clean
+fn commit_file(root: &Path, file_path: &str, message: &str) -> Result<gix::ObjectId> {
+    use gix::index::entry::{Flags, Mode, Stage, Stat};
+    use gix::object::tree::EntryKind;
+
+    let mut repo = gix::open(root).with_context(|| format!("No git repository at {}", root.display()))?;
+    repo.committer_or_set_generic_fallback()?;
+    let head = repo.head_commit()?;
+    let head_tree = head.tree()?;
+    let target = root.join(file_path);
+    let blob = match fs::read(&target) {
+        Ok(bytes) => Some(repo.write_blob(bytes)?.detach()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", target.display())),
+    };
+
+    let mut editor = repo.edit_tree(head_tree.id)?;
+    match blob {
+        Some(id) => {
+            let kind = head_tree.lookup_entry_by_path(file_path)?.map_or(EntryKind::Blob, |e| e.mode().kind());
+            editor.upsert(file_path, kind, id)?;
+        }
+        None => {
+            editor.remove(file_path)?;
+        }
+    }
+    let tree = editor.write()?.detach();
+    let committer = repo.committer().context("No committer configured")??;
+    let author = repo.author().transpose()?.unwrap_or(committer);
+    let commit = repo.commit_as(committer, author, "HEAD", message, tree, [head.id])?.detach();
+
+    let mut index = repo.open_index()?;
+    let path: &gix::bstr::BStr = file_path.into();
+    match blob {
+        Some(id) => {
+            let stat = Stat::from_fs(&gix::index::fs::Metadata::from_path_no_follow(&target)?)?;
+            if let Some(entry) = index.entry_mut_by_path_and_stage(path, Stage::Unconflicted) {
+                entry.id = id;
+                entry.stat = stat;
+            } else {
+                index.dangerously_push_entry(stat, id, Flags::empty(), Mode::FILE, path);
+                index.sort_entries();
+            }
         }
+        None => index.remove_entries(|_, entry_path, _| entry_path == path),
     }
+    // The cached trees describe the old index
+    index.remove_tree();
+    index.write(gix::index::write::Options::default())?;
+    Ok(commit)
+}
+
+/// Remember the ref each checkout touched by `ops` is on before the plan
+/// switches it to the plan branch. A ref recorded by an earlier staged run
+/// is kept.
+fn record_base_refs(graph: &mut EcosystemGraph, plan_id: &str, ops: &[PlanOp]) -> Result<()> {
+    let branch = format!("refs/heads/{}", plan_branch(plan_id));
+    let mut bases = Vec::new();
+    for op in ops {
+        let PlanOp::FileChange { repo_id, .. } = op else {
+            continue;
+        };
+        let root = repo_checkout(graph, repo_id)?;
+        let repo = gix::open(&root).with_context(|| format!("No git repository at {}", root.display()))?;
+        let base = match repo.head_name()? {
+            Some(name) if name.as_bstr() == branch.as_str() => continue,
+            Some(name) => name.as_bstr().to_string(),
+            None => repo.head_id()?.to_string(),
+        };
+        bases.push((repo_id.clone(), base));
+    }
+    if let Some(plan) = graph.plans.plans.iter_mut().find(|p| p.id == plan_id) {
+        for (repo_id, base) in bases {
+            plan.base_refs.entry(repo_id).or_insert(base);
+        }
+    }
+    Ok(())
+}
+
+/// Switch checkouts (all, or just `only`) back from the plan branch to the
+/// refs they were on before the plan, and forget those refs. Returns notes
+/// for checkouts left on the branch.
+fn restore_base_refs(graph: &mut EcosystemGraph, plan_id: &str, only: Option<&str>) -> Vec<String> {
+    let Some(plan) = graph.plans.plans.iter_mut().find(|p| p.id == plan_id) else {
+        return Vec::new();
+    };
+    let bases: Vec<(String, String)> = match only {
+        Some(repo_id) => plan.base_refs.remove_entry(repo_id).into_iter().collect(),
+        None => std::mem::take(&mut plan.base_refs).into_iter().collect(),
+    };
+
+    let branch = plan_branch(plan_id);
+    let mut notes = Vec::new();
+    for (repo_id, base) in bases {
+        let repo = repo_name(graph, &repo_id);
+        match restore_base_ref(graph, &repo_id, &base, &branch) {
+            Ok(true) => println!("  Switched {repo} back to {base}"),
+            Ok(false) => {}
+            Err(e) => {
+                println!("  {repo} left on {branch}: {e:#}");
+                notes.push(format!("{repo} left on {branch}: {e:#}"));
+            }
+        }
+    }
+    notes
+}
+
+/// Point a checkout on the plan branch back at `base` and delete the
+/// branch, provided the branch's files match `base` again. Returns false if
+/// the checkout is not on the plan branch.
+fn restore_base_ref(graph: &EcosystemGraph, repo_id: &str, base: &str, branch: &str) -> Result<bool> {
+    use gix::refs::Target;
+
+    let root = repo_checkout(graph, repo_id)?;
+    let mut repo = gix::open(&root).with_context(|| format!("No git repository at {}", root.display()))?;
+    repo.committer_or_set_generic_fallback()?;
+    let full_name = format!("refs/heads/{branch}");
+    if !repo.head_name()?.is_some_and(|name| name.as_bstr() == full_name.as_str()) {
+        return Ok(false);
+    }
+
+    let (target, base_id) = if base.starts_with("refs/") {
+        (Target::Symbolic(base.try_into()?), repo.find_reference(base)?.peel_to_id()?.detach())
+    } else {
+        let id = gix::ObjectId::from_hex(base.as_bytes())?;
+        (Target::Object(id), id)
+    };
+    if repo.find_commit(base_id)?.tree_id()? != repo.head_tree_id()? {
+        bail!("its files differ from {base}");
+    }
+    point_head(&repo, target, format!("checkout: moving from {branch} to {base}"))?;
+    repo.find_reference(full_name.as_str())?.delete()?;
+    Ok(true)
 }
 
 /// Generate a reverse operation for rollback
-fn reverse_operation(op: &PlanOp) -> Option<PlanOp> {
+fn reverse_operation(op: &PlanOp) -> PlanOp {
     match op {
         PlanOp::SwitchBinding {
             binding_id,
@@ -535,7 +1268,7 @@ fn reverse_operation(op: &PlanOp) -> Option<PlanOp> {
             to_provider_id,
             risk,
             reason,
-        } => Some(PlanOp::SwitchBinding {
+        } => PlanOp::SwitchBinding {
             binding_id: binding_id.clone(),
             consumer_id: consumer_id.clone(),
             slot_id: slot_id.clone(),
@@ -543,14 +1276,14 @@ fn reverse_operation(op: &PlanOp) -> Option<PlanOp> {
             to_provider_id: from_provider_id.clone(),  // Swap
             risk: *risk,
             reason: format!("Rollback: {}", reason),
-        }),
+        },
         PlanOp::CreateBinding {
             consumer_id,
             slot_id,
             provider_id,
             risk,
             reason,
-        } => Some(PlanOp::RemoveBinding {
+        } => PlanOp::RemoveBinding {
             binding_id: format!(
                 "binding:{}:{}:{}",
                 slot_id.split(':').last().unwrap_or("slot"),
@@ -562,7 +1295,7 @@ fn reverse_operation(op: &PlanOp) -> Option<PlanOp> {
             provider_id: provider_id.clone(),
             risk: *risk,
             reason: format!("Rollback: {}", reason),
-        }),
+        },
         PlanOp::RemoveBinding {
             binding_id: _,
             consumer_id,
@@ -570,39 +1303,49 @@ fn reverse_operation(op: &PlanOp) -> Option<PlanOp> {
             provider_id,
             risk,
             reason,
-        } => Some(PlanOp::CreateBinding {
+        } => PlanOp::CreateBinding {
             consumer_id: consumer_id.clone(),
             slot_id: slot_id.clone(),
             provider_id: provider_id.clone(),
             risk: *risk,
             reason: format!("Rollback: {}", reason),
-        }),
-        PlanOp::FileChange { .. } => {
-            // File changes can't be automatically reversed
-            None
-        }
+        },
+        PlanOp::FileChange {
+            repo_id,
+            file_path,
+            change_type,
+            diff,
+            risk,
+        } => PlanOp::FileChange {
+            repo_id: repo_id.clone(),
+            file_path: file_path.clone(),
+            change_type: change_type.inverse(),
+            diff: diff.as_deref().and_then(patch::reverse_diff),
+            risk: *risk,
+        },
     }
 }
 
-/// Execute rollback for a failed plan
-fn execute_rollback(graph: &mut EcosystemGraph, plan: &Plan, up_to_index: usize) -> Result<String> {
-    let rollback_ops: Vec<PlanOp> = plan.operations[..=up_to_index]
-        .iter()
-        .rev()
-        .filter_map(|op| reverse_operation(op))
-        .collect();
-
+/// Execute rollback for a failed plan, reversing the given operations newest first.
+/// Pass only operations that succeeded: reversing one that failed would undo
+/// a change that was never made. Each reversed operation is removed from
+/// `op_indexes`, so after a failed rollback it holds those still applied.
+fn execute_rollback(graph: &mut EcosystemGraph, plan: &Plan, op_indexes: &mut Vec<usize>, branch: &str) -> Result<String> {
     let rollback_id = format!("rollback:{}:{}", plan.id, Utc::now().timestamp());
+    let total = op_indexes.len();
 
     println!();
     println!("  Executing automatic rollback...");
 
-    for (i, op) in rollback_ops.iter().enumerate() {
-        println!("  [rollback {}/{}] {}", i + 1, rollback_ops.len(), op.description());
-        if let Err(e) = execute_operation(graph, &op) {
-            println!("       ROLLBACK FAILED: {}", e);
-            return Err(anyhow!("Rollback failed at step {}: {}", i + 1, e));
+    while let Some(&i) = op_indexes.last() {
+        let step = total - op_indexes.len() + 1;
+        let op = reverse_operation(&plan.operations[i]);
+        println!("  [rollback {step}/{total}] {}", op.description());
+        if let Err(e) = execute_operation(graph, &op, branch) {
+            println!("       ROLLBACK FAILED: {e}");
+            return Err(anyhow!("Rollback failed at step {step}: {e}"));
         }
+        op_indexes.pop();
         println!("       OK");
     }
 
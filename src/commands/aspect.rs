@@ -3,13 +3,15 @@
 //! Aspect tagging commands - annotate repos and edges with aspect weights
 
 use crate::graph::EcosystemGraph;
-use crate::types::{AnnotationSource, AspectAnnotation, Evidence, Polarity};
+use crate::types::{AnnotationSource, AspectAnnotation, ChangeOp, Evidence, Polarity};
 use anyhow::{Context, Result};
 use chrono::Utc;
-use sha2::{Digest, Sha256};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Run aspect command
+///
+/// With `args.in_scenario`, `tag` and `remove` are recorded in that
+/// scenario's change set and the baseline is left alone.
 pub fn run(action: &str, target: Option<String>, aspect: Option<String>, args: AspectArgs) -> Result<()> {
     let data_dir = get_data_dir()?;
     let mut graph = EcosystemGraph::load(&data_dir)
@@ -22,57 +24,13 @@ pub fn run(action: &str, target: Option<String>, aspect: Option<String>, args: A
 
             // Resolve target (repo or edge)
             let target_id = resolve_target(&graph, &target)?;
+            let annotation = manual_annotation(&graph, &target_id, &aspect_name, &args)?;
+            let (aspect_id, weight, polarity) = (annotation.aspect_id.clone(), annotation.weight, annotation.polarity);
 
-            // Validate aspect exists
-            let aspect_id = format!("aspect:{}", aspect_name.to_lowercase());
-            if !graph.aspects.aspects.iter().any(|a| a.id == aspect_id) {
-                let valid: Vec<_> = graph.aspects.aspects.iter().map(|a| &a.name).collect();
-                anyhow::bail!("Unknown aspect: {}. Valid: {:?}", aspect_name, valid);
+            if let Some(scenario) = &args.in_scenario {
+                return record_annotation(&mut graph, &data_dir, scenario, annotation, &aspect_name);
             }
 
-            // Parse weight (0-3)
-            let weight = args.weight.unwrap_or(1).min(3);
-
-            // Parse polarity
-            let polarity = match args.polarity.as_deref().unwrap_or("neutral") {
-                "risk" | "weakness" | "concern" => Polarity::Risk,
-                "strength" | "positive" => Polarity::Strength,
-                "neutral" | "observation" => Polarity::Neutral,
-                other => anyhow::bail!("Unknown polarity: {}. Valid: risk, strength, neutral", other),
-            };
-
-            // Generate annotation ID
-            let annotation_id = generate_annotation_id(&target_id, &aspect_id);
-
-            // Build evidence
-            let evidence = if let Some(ref ev) = args.evidence {
-                vec![Evidence {
-                    evidence_type: "manual".into(),
-                    reference: ev.clone(),
-                    excerpt: None,
-                    confidence: 1.0,
-                }]
-            } else {
-                vec![]
-            };
-
-            let annotation = AspectAnnotation {
-                kind: "AspectAnnotation".into(),
-                id: annotation_id.clone(),
-                target: target_id.clone(),
-                aspect_id: aspect_id.clone(),
-                weight,
-                polarity,
-                reason: args.reason.unwrap_or_else(|| "Manual annotation".into()),
-                evidence,
-                source: AnnotationSource {
-                    mode: "manual".into(),
-                    who: "user".into(),
-                    when: Utc::now(),
-                    rule_id: None,
-                },
-            };
-
             // Check if annotation already exists, update if so
             if let Some(existing) = graph.aspects.annotations.iter_mut().find(|a| a.target == target_id && a.aspect_id == aspect_id) {
                 *existing = annotation;
@@ -95,6 +53,10 @@ pub fn run(action: &str, target: Option<String>, aspect: Option<String>, args: A
             let target_id = resolve_target(&graph, &target)?;
             let aspect_id = format!("aspect:{}", aspect_name.to_lowercase());
 
+            if let Some(scenario) = args.in_scenario {
+                return record_annotation_removal(&mut graph, &data_dir, &scenario, &target_id, &aspect_name);
+            }
+
             let before = graph.aspects.annotations.len();
             graph.aspects.annotations.retain(|a| !(a.target == target_id && a.aspect_id == aspect_id));
 
@@ -149,36 +111,7 @@ pub fn run(action: &str, target: Option<String>, aspect: Option<String>, args: A
         "filter" => {
             // Filter and show repos/edges by aspect
             let aspect_name = aspect.unwrap_or_else(|| "security".into());
-            let aspect_id = format!("aspect:{}", aspect_name.to_lowercase());
-
-            let annotations: Vec<_> = graph
-                .aspects
-                .annotations
-                .iter()
-                .filter(|a| a.aspect_id == aspect_id)
-                .collect();
-
-            if annotations.is_empty() {
-                println!("No {} annotations found", aspect_name);
-                return Ok(());
-            }
-
-            println!("{} view ({} annotations):", aspect_name, annotations.len());
-            for ann in annotations {
-                let target_name = if ann.target.starts_with("repo:") {
-                    graph.get_repo(&ann.target).map(|r| r.name.clone()).unwrap_or_else(|| ann.target.clone())
-                } else {
-                    ann.target.clone()
-                };
-
-                let icon = match ann.polarity {
-                    Polarity::Risk => "⚠",
-                    Polarity::Strength => "✓",
-                    Polarity::Neutral => "○",
-                };
-
-                println!("  {} {} [weight: {}] - {}", icon, target_name, ann.weight, ann.reason);
-            }
+            print_aspect_view(&graph, &aspect_name);
         }
 
         other => {
@@ -189,6 +122,138 @@ pub fn run(action: &str, target: Option<String>, aspect: Option<String>, args: A
     Ok(())
 }
 
+/// Print every repo and edge annotated with an aspect
+fn print_aspect_view(graph: &EcosystemGraph, aspect_name: &str) {
+    let aspect_id = format!("aspect:{}", aspect_name.to_lowercase());
+
+    let annotations: Vec<_> = graph
+        .aspects
+        .annotations
+        .iter()
+        .filter(|a| a.aspect_id == aspect_id)
+        .collect();
+
+    if annotations.is_empty() {
+        println!("No {} annotations found", aspect_name);
+        return;
+    }
+
+    println!("{} view ({} annotations):", aspect_name, annotations.len());
+    for ann in annotations {
+        let target_name = if ann.target.starts_with("repo:") {
+            graph.get_repo(&ann.target).map(|r| r.name.clone()).unwrap_or_else(|| ann.target.clone())
+        } else {
+            ann.target.clone()
+        };
+
+        let icon = match ann.polarity {
+            Polarity::Risk => "⚠",
+            Polarity::Strength => "✓",
+            Polarity::Neutral => "○",
+        };
+
+        println!("  {} {} [weight: {}] - {}", icon, target_name, ann.weight, ann.reason);
+    }
+}
+
+/// Build a manual annotation of `target_id` from the tag arguments
+fn manual_annotation(
+    graph: &EcosystemGraph,
+    target_id: &str,
+    aspect_name: &str,
+    args: &AspectArgs,
+) -> Result<AspectAnnotation> {
+    // Validate aspect exists
+    let aspect_id = format!("aspect:{}", aspect_name.to_lowercase());
+    if !graph.aspects.aspects.iter().any(|a| a.id == aspect_id) {
+        let valid: Vec<_> = graph.aspects.aspects.iter().map(|a| &a.name).collect();
+        anyhow::bail!("Unknown aspect: {}. Valid: {:?}", aspect_name, valid);
+    }
+
+    // Parse weight (0-3)
+    let weight = args.weight.unwrap_or(1).min(3);
+
+    // Parse polarity
+    let polarity = match args.polarity.as_deref().unwrap_or("neutral") {
+        "risk" | "weakness" | "concern" => Polarity::Risk,
+        "strength" | "positive" => Polarity::Strength,
+        "neutral" | "observation" => Polarity::Neutral,
+        other => anyhow::bail!("Unknown polarity: {}. Valid: risk, strength, neutral", other),
+    };
+
+    // Build evidence
+    let evidence = if let Some(ref ev) = args.evidence {
+        vec![Evidence {
+            evidence_type: "manual".into(),
+            reference: ev.clone(),
+            excerpt: None,
+            confidence: 1.0,
+        }]
+    } else {
+        vec![]
+    };
+
+    Ok(AspectAnnotation {
+        kind: "AspectAnnotation".into(),
+        id: AspectAnnotation::generate_id(target_id, &aspect_id),
+        target: target_id.to_string(),
+        aspect_id,
+        weight,
+        polarity,
+        reason: args.reason.clone().unwrap_or_else(|| "Manual annotation".into()),
+        evidence,
+        source: AnnotationSource {
+            mode: "manual".into(),
+            who: "user".into(),
+            when: Utc::now(),
+            rule_id: None,
+        },
+    })
+}
+
+/// Record an annotation in a scenario
+fn record_annotation(
+    graph: &mut EcosystemGraph,
+    data_dir: &Path,
+    scenario: &str,
+    annotation: AspectAnnotation,
+    aspect_name: &str,
+) -> Result<()> {
+    let (target_id, weight, polarity) = (annotation.target.clone(), annotation.weight, annotation.polarity);
+    graph.record_change(scenario, ChangeOp::AddAnnotation { annotation })?;
+    graph.save(data_dir)?;
+    println!("Recorded in scenario {scenario}: {aspect_name} annotation on {target_id}");
+    println!("  weight: {weight}/3");
+    println!("  polarity: {polarity:?}");
+    Ok(())
+}
+
+/// Record the removal of `target_id`'s annotation for an aspect in a scenario
+fn record_annotation_removal(
+    graph: &mut EcosystemGraph,
+    data_dir: &Path,
+    scenario: &str,
+    target_id: &str,
+    aspect_name: &str,
+) -> Result<()> {
+    let aspect_id = format!("aspect:{}", aspect_name.to_lowercase());
+    let annotation_id = AspectAnnotation::generate_id(target_id, &aspect_id);
+    let exists = graph
+        .with_scenario(scenario)?
+        .aspects
+        .annotations
+        .iter()
+        .any(|a| a.id == annotation_id);
+    if !exists {
+        println!("No {aspect_name} annotation found on {target_id} in scenario {scenario}");
+        return Ok(());
+    }
+    graph.record_change(scenario, ChangeOp::RemoveAnnotation { annotation_id })?;
+    graph.save(data_dir)?;
+    println!("Recorded in scenario {scenario}: remove {aspect_name} annotation from {target_id}");
+    Ok(())
+}
+
 /// Additional arguments for aspect commands
 #[derive(Default)]
 pub struct AspectArgs {
@@ -200,6 +265,8 @@ pub struct AspectArgs {
     pub reason: Option<String>,
     /// Evidence reference (file path, URL, etc.)
     pub evidence: Option<String>,
+    /// Record the change in this scenario instead of the baseline
+    pub in_scenario: Option<String>,
 }
 
 /// Resolve a target name to ID (repo or edge)
@@ -229,15 +296,6 @@ fn resolve_target(graph: &EcosystemGraph, target: &str) -> Result<String> {
     }
 }
 
-/// Generate a deterministic annotation ID
-fn generate_annotation_id(target: &str, aspect_id: &str) -> String {
-    let mut hasher = Sha256::new();
-    hasher.update(target.as_bytes());
-    hasher.update(aspect_id.as_bytes());
-    let hash = hex::encode(hasher.finalize());
-    format!("aa:{}", &hash[..8])
-}
-
 /// Get the data directory
 fn get_data_dir() -> Result<PathBuf> {
     if let Ok(dir) = std::env::var("REPOSYSTEM_DATA_DIR") {
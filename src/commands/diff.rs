@@ -0,0 +1,185 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Diff command - semantic changes between two graph snapshots
+//!
+//! A snapshot is one of:
+//! - `current`: the live graph
+//! - a data directory (`graph.json`, `aspects.json`, `slots.json`, …)
+//! - a file: an estate export (JSON, YAML or TOML) or a bare `graph.json`
+//! - a git revision (`HEAD~1`, `main`): the data directory as committed
+//! - `<rev>:<path>`: a file as committed, relative to the repository root
+//!   (or to the current directory with a leading `./`)
+
+use crate::commands::data_dir;
+use crate::diff::GraphDiff;
+use crate::envelope::{self, EnvelopeFormat};
+use crate::graph::EcosystemGraph;
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A loaded snapshot
+struct Snapshot {
+    graph: EcosystemGraph,
+    /// False for a bare `graph.json`, which has no aspect or slot data
+    full: bool,
+}
+
+/// Run the diff command
+///
+/// `format` is `text`, `json` or `dot`; `json` (the global `--json`) forces
+/// JSON.
+///
+/// # Errors
+/// Returns an error if a snapshot cannot be found or parsed, or the format
+/// is unknown.
+pub fn run(old: &str, new: &str, format: &str, json: bool) -> Result<()> {
+    let format = if json { "json" } else { format };
+    if !matches!(format, "text" | "json" | "dot") {
+        bail!("Unknown diff format: {format}. Supported: text, json, dot");
+    }
+
+    let data_dir = data_dir()?;
+    let before = load(old, &data_dir)?;
+    let after = load(new, &data_dir)?;
+    let diff = if before.full && after.full {
+        GraphDiff::new(&before.graph, &after.graph)
+    } else {
+        GraphDiff::graph_only(&before.graph, &after.graph)
+    };
+
+    match format {
+        "json" => println!("{}", serde_json::to_string_pretty(&diff)?),
+        "dot" => print!("{}", diff.to_dot(&before.graph, &after.graph)),
+        _ => print_text(&diff, old, new),
+    }
+    Ok(())
+}
+
+fn print_text(diff: &GraphDiff, old: &str, new: &str) {
+    println!("Comparing {old} -> {new}");
+    if diff.is_empty() {
+        println!("No changes.");
+    }
+    print_sections(diff);
+}
+
+/// Print each changed section with its added (+), removed (-) and changed
+/// (~) items; shared with `scenario compare`
+pub(crate) fn print_sections(diff: &GraphDiff) {
+    for (name, section) in diff.sections() {
+        let Some(section) = section else {
+            println!("{name}: not compared (only one snapshot has aspect and slot data)");
+            continue;
+        };
+        if section.is_empty() {
+            continue;
+        }
+        println!(
+            "{name}: {} added, {} removed, {} changed",
+            section.added.len(),
+            section.removed.len(),
+            section.changed.len()
+        );
+        let marked = [("+", &section.added), ("-", &section.removed), ("~", &section.changed)];
+        for (mark, items) in marked {
+            for item in items {
+                println!("  {mark} {} ({})", item.label, item.id);
+                for field in &item.fields {
+                    println!("      {field}");
+                }
+            }
+        }
+    }
+}
+
+/// Resolve a snapshot spec: `current`, a path, a git revision or `<rev>:<path>`
+fn load(spec: &str, data_dir: &Path) -> Result<Snapshot> {
+    if spec == "current" {
+        let graph = EcosystemGraph::load(data_dir)
+            .with_context(|| format!("Failed to load graph from {}", data_dir.display()))?;
+        return Ok(Snapshot { graph, full: true });
+    }
+
+    let path = Path::new(spec);
+    if path.is_dir() {
+        let graph = EcosystemGraph::from_json_files(|name| {
+            let file = path.join(name);
+            if !file.exists() {
+                return Ok(None);
+            }
+            fs::read_to_string(&file).map(Some).with_context(|| format!("Failed to read {}", file.display()))
+        })?;
+        return Ok(Snapshot { graph, full: true });
+    }
+    if path.is_file() {
+        let text = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        return parse_file(&text, path);
+    }
+
+    match spec.split_once(':') {
+        Some((rev, file)) if !rev.is_empty() && !file.is_empty() => {
+            let cwd = std::env::current_dir()?;
+            let repo = open_repo(&cwd)?;
+            let file = if file.starts_with("./") || file.starts_with("../") {
+                repo_relative(&repo, &cwd.join(file))?
+            } else {
+                PathBuf::from(file)
+            };
+            match read_at(&repo, rev, &file)? {
+                Some(text) => parse_file(&text, &file),
+                None => bail!("{} does not exist at {rev}", file.display()),
+            }
+        }
+        _ => {
+            let repo = open_repo(data_dir).with_context(|| {
+                format!("'{spec}' is not a file or directory, and the data directory is not in a git repository")
+            })?;
+            let dir = repo_relative(&repo, data_dir)?;
+            let graph = EcosystemGraph::from_json_files(|name| read_at(&repo, spec, &dir.join(name)))?;
+            Ok(Snapshot { graph, full: true })
+        }
+    }
+}
+
+/// Parse a snapshot file: an estate export, or a bare graph store
+fn parse_file(text: &str, path: &Path) -> Result<Snapshot> {
+    let format = EnvelopeFormat::from_path(path).unwrap_or(EnvelopeFormat::Json);
+    let value = envelope::decode(text, format).with_context(|| format!("Failed to parse {}", path.display()))?;
+    if value.get("schema").is_some() {
+        let graph = EcosystemGraph::from_estate_export(text, format)?;
+        return Ok(Snapshot { graph, full: true });
+    }
+    let store = serde_json::to_string(&value)?;
+    let graph = EcosystemGraph::from_json_files(|name| Ok((name == "graph.json").then(|| store.clone())))
+        .with_context(|| format!("{} is neither an estate export nor a graph.json", path.display()))?;
+    Ok(Snapshot { graph, full: false })
+}
+
+fn open_repo(dir: &Path) -> Result<gix::Repository> {
+    gix::discover(dir).with_context(|| format!("No git repository at {}", dir.display()))
+}
+
+/// `path` relative to the repository's working tree
+fn repo_relative(repo: &gix::Repository, path: &Path) -> Result<PathBuf> {
+    let root = repo.workdir().context("Git repository has no working tree")?;
+    let (root, path) = (root.canonicalize()?, path.canonicalize().unwrap_or_else(|_| path.to_path_buf()));
+    match path.strip_prefix(&root) {
+        Ok(rel) => Ok(rel.to_path_buf()),
+        Err(_) => bail!("{} is outside the git repository at {}", path.display(), root.display()),
+    }
+}
+
+/// Contents of `path` at revision `rev`, or `None` if it did not exist
+fn read_at(repo: &gix::Repository, rev: &str, path: &Path) -> Result<Option<String>> {
+    let tree = repo
+        .rev_parse_single(rev)
+        .with_context(|| format!("'{rev}' is not a file, directory or git revision"))?
+        .object()?
+        .peel_to_tree()?;
+    let Some(entry) = tree.lookup_entry_by_path(path)? else {
+        return Ok(None);
+    };
+    let data = entry.object()?.detach().data;
+    String::from_utf8(data).map(Some).with_context(|| format!("{} at {rev} is not UTF-8", path.display()))
+}
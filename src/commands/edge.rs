@@ -3,21 +3,17 @@
 //! Edge management commands - create and remove relationships between repos
 
 use crate::graph::EcosystemGraph;
-use crate::types::{Channel, Edge, EdgeMeta, Evidence, RelationType};
+use crate::types::{ChangeOp, Channel, Edge, EdgeMeta, Evidence, RelationType};
 use anyhow::{Context, Result};
 use chrono::Utc;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Run edge command
-pub fn run(
-    action: &str,
-    from: Option<String>,
-    to: Option<String>,
-    rel: Option<String>,
-    channel: Option<String>,
-    label: Option<String>,
-    evidence: Option<String>,
-) -> Result<()> {
+///
+/// With `args.in_scenario`, `add` and `remove` are recorded in that
+/// scenario's change set and the baseline is left alone.
+pub fn run(action: &str, from: Option<String>, to: Option<String>, args: EdgeArgs) -> Result<()> {
+    let EdgeArgs { rel, channel, label, evidence, in_scenario } = args;
     let data_dir = get_data_dir()?;
     let mut graph = EcosystemGraph::load(&data_dir)
         .with_context(|| format!("Failed to load graph from {}", data_dir.display()))?;
@@ -55,22 +51,13 @@ pub fn run(
             // Generate edge ID
             let edge_id = Edge::generate_id(&from_id, &to_id, rel_type, chan, label.as_deref());
 
-            // Build evidence if provided
-            let evidence_vec = if let Some(ev) = evidence {
-                vec![Evidence {
-                    evidence_type: "manual".into(),
-                    reference: ev,
-                    excerpt: None,
-                    confidence: 1.0,
-                }]
-            } else {
-                vec![Evidence {
-                    evidence_type: "manual".into(),
-                    reference: "user-created".into(),
-                    excerpt: None,
-                    confidence: 1.0,
-                }]
-            };
+            // Manual evidence, pointing at the given reference if any
+            let evidence_vec = vec![Evidence {
+                evidence_type: "manual".into(),
+                reference: evidence.unwrap_or_else(|| "user-created".into()),
+                excerpt: None,
+                confidence: 1.0,
+            }];
 
             let edge = Edge {
                 kind: "Edge".into(),
@@ -87,6 +74,14 @@ pub fn run(
                 },
             };
 
+            if let Some(scenario) = in_scenario {
+                graph.record_change(&scenario, ChangeOp::AddEdge { edge })?;
+                graph.save(&data_dir)?;
+                println!("Recorded in scenario {scenario}: add edge {from_id} -> {to_id}");
+                println!("  id: {edge_id}");
+                return Ok(());
+            }
+
             graph.add_edge(edge)?;
             graph.save(&data_dir)?;
 
@@ -104,6 +99,10 @@ pub fn run(
             let from_id = resolve_repo_id(&graph, &from)?;
             let to_id = resolve_repo_id(&graph, &to)?;
 
+            if let Some(scenario) = in_scenario {
+                return record_removal(&mut graph, &data_dir, &scenario, &from_id, &to_id);
+            }
+
             // Find and remove matching edges
             let initial_count = graph.store.edges.len();
             graph.store.edges.retain(|e| !(e.from == from_id && e.to == to_id));
@@ -140,6 +139,42 @@ pub fn run(
     Ok(())
 }
 
+/// Record the removal of every edge `from_id -> to_id` in a scenario
+fn record_removal(graph: &mut EcosystemGraph, data_dir: &Path, scenario: &str, from_id: &str, to_id: &str) -> Result<()> {
+    let edge_ids: Vec<String> = graph
+        .with_scenario(scenario)?
+        .edges_from(from_id)
+        .into_iter()
+        .filter(|e| e.to == to_id)
+        .map(|e| e.id.clone())
+        .collect();
+    if edge_ids.is_empty() {
+        println!("No edges found from {from_id} -> {to_id} in scenario {scenario}");
+        return Ok(());
+    }
+    for edge_id in &edge_ids {
+        graph.record_change(scenario, ChangeOp::RemoveEdge { edge_id: edge_id.clone() })?;
+    }
+    graph.save(data_dir)?;
+    println!("Recorded in scenario {scenario}: remove {} edge(s) from {from_id} -> {to_id}", edge_ids.len());
+    Ok(())
+}
+
+/// Additional arguments for edge commands
+#[derive(Default)]
+pub struct EdgeArgs {
+    /// Relationship type: uses, provides, extends, mirrors, replaces
+    pub rel: Option<String>,
+    /// Channel type: api, artifact, config, runtime, human, unknown
+    pub channel: Option<String>,
+    /// Human-readable label
+    pub label: Option<String>,
+    /// Evidence reference (file path, URL, etc.)
+    pub evidence: Option<String>,
+    /// Record the change in this scenario instead of the baseline
+    pub in_scenario: Option<String>,
+}
+
 /// Resolve a repo name or ID to a full ID
 fn resolve_repo_id(graph: &EcosystemGraph, name_or_id: &str) -> Result<String> {
     // If it looks like a full ID, use it directly
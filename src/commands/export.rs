@@ -2,8 +2,12 @@
 // SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
 //! Export command - exports the ecosystem graph to various formats
 
+use crate::diagram::AspectOverlay;
+use crate::envelope::EnvelopeFormat;
 use crate::graph::EcosystemGraph;
+use crate::query::Query;
 use anyhow::{Context, Result};
+use std::collections::HashSet;
 use std::fs;
 use std::io::Write;
 use std::path::PathBuf;
@@ -14,15 +18,25 @@
 pub enum ExportFormat {
     /// Graphviz DOT format
     Dot,
+    /// Mermaid flowchart (renders natively in forge READMEs and `AsciiDoc`)
+    Mermaid,
+    /// `PlantUML` diagram
+    PlantUml,
+    /// `GraphML` (`yEd`, `NetworkX`)
+    GraphMl,
+    /// GEXF (Gephi)
+    Gexf,
     /// JSON format (GraphStore only)
     Json,
     /// Unified estate-export envelope (all stores + estates + seams).
     /// This is what the front-ends (web/GUI/TUI) consume.
     EstateJson,
-    /// YAML format (future)
+    /// Estate-export envelope as YAML
     Yaml,
-    /// TOML format (future)
+    /// Estate-export envelope as TOML
     Toml,
+    /// Single offline HTML page with an interactive viewer
+    Html,
 }
 
 impl ExportFormat {
@@ -30,10 +44,15 @@ impl ExportFormat {
     pub fn from_str(s: &str) -> Option<Self> {
         match s.to_lowercase().as_str() {
             "dot" | "graphviz" => Some(Self::Dot),
+            "mermaid" | "mmd" => Some(Self::Mermaid),
+            "plantuml" | "puml" => Some(Self::PlantUml),
+            "graphml" => Some(Self::GraphMl),
+            "gexf" => Some(Self::Gexf),
             "json" => Some(Self::Json),
             "estate-json" | "estate" => Some(Self::EstateJson),
             "yaml" | "yml" => Some(Self::Yaml),
             "toml" => Some(Self::Toml),
+            "html" | "htm" => Some(Self::Html),
             _ => None,
         }
     }
@@ -42,51 +61,107 @@ pub fn from_str(s: &str) -> Option<Self> {
     pub fn extension(&self) -> &'static str {
         match self {
             Self::Dot => "dot",
+            Self::Mermaid => "mmd",
+            Self::PlantUml => "puml",
+            Self::GraphMl => "graphml",
+            Self::Gexf => "gexf",
             Self::Json | Self::EstateJson => "json",
             Self::Yaml => "yaml",
             Self::Toml => "toml",
+            Self::Html => "html",
         }
     }
 }
 
+/// The repos annotated with `aspect`, plus the endpoints of annotated edges,
+/// with every edge among them
+fn aspect_subgraph(graph: &EcosystemGraph, aspect: &str) -> Result<EcosystemGraph> {
+    let aspect = aspect.replace('"', "");
+    let mut repos: HashSet<String> = HashSet::new();
+    for subject in ["repos", "edges"] {
+        let filter = Query::parse(&format!("{subject} where has_annotation(aspect = \"{aspect}\")"))?;
+        repos.extend(filter.subgraph(graph).store.repos.into_iter().map(|r| r.id));
+    }
+    let edges = graph.store.edges.iter().map(|e| e.id.clone()).collect();
+    let mut sub = graph.clone();
+    sub.retain(&repos, &edges, None);
+    Ok(sub)
+}
+
 /// Run the export command
-pub fn run(format: &str, output: Option<PathBuf>, aspect: Option<String>) -> Result<()> {
+///
+/// `aspect` keeps repos carrying an annotation for that aspect, or at
+/// either end of an edge that does; `query` keeps whatever the query selects (see [`crate::query`]). Both narrow the
+/// graph before it is rendered, so every format sees the same subset. For
+/// DOT and Mermaid, `overlay` colours the nodes by an aspect's weights,
+/// badges its weak links and adds a legend (see [`AspectOverlay`]). With
+/// `scenario`, the scenario's what-if graph is exported instead of the
+/// baseline.
+pub fn run(
+    format: &str,
+    output: Option<PathBuf>,
+    aspect: Option<String>,
+    overlay: Option<&str>,
+    query: Option<&str>,
+    scenario: Option<&str>,
+) -> Result<()> {
     info!("Exporting to {}", format);
 
     let export_format = ExportFormat::from_str(format).ok_or_else(|| {
         anyhow::anyhow!(
-            "Unknown export format: {}. Supported: dot, json, estate-json",
+            "Unknown export format: {}. Supported: dot, mermaid, plantuml, graphml, gexf, json, estate-json, yaml, toml, html",
             format
         )
     })?;
 
     // Load the graph
     let data_dir = get_data_dir()?;
-    let graph = EcosystemGraph::load(&data_dir)
+    let mut graph = EcosystemGraph::load(&data_dir)
         .with_context(|| format!("Failed to load graph from {}", data_dir.display()))?;
 
+    if let Some(name) = scenario {
+        info!("Applying scenario: {}", name);
+        graph = graph.with_scenario(name)?;
+    }
+
     if graph.is_empty() {
         eprintln!("Warning: Graph is empty. Run 'reposystem scan' first.");
     }
 
+    // The overlay is computed before filtering, so weak links reflect the
+    // whole graph
+    let overlay = match (overlay, export_format) {
+        (Some(aspect_name), ExportFormat::Dot | ExportFormat::Mermaid) => {
+            Some(AspectOverlay::new(&graph, aspect_name)?)
+        }
+        (Some(_), _) => anyhow::bail!("--overlay is only supported for dot and mermaid exports"),
+        (None, _) => None,
+    };
+
     // Apply aspect filter if specified
-    if let Some(ref aspect_name) = aspect {
+    if let Some(aspect_name) = &aspect {
         info!("Filtering by aspect: {}", aspect_name);
-        // TODO: Implement aspect filtering
-        eprintln!("Note: Aspect filtering not yet implemented");
+        graph = aspect_subgraph(&graph, aspect_name)?;
+    }
+
+    // Apply query filter if specified
+    if let Some(text) = query {
+        info!("Filtering by query: {}", text);
+        graph = Query::parse(text)?.subgraph(&graph);
     }
 
     // Generate output
     let content = match export_format {
-        ExportFormat::Dot => graph.to_dot(),
+        ExportFormat::Dot => graph.to_dot_with(overlay.as_ref()),
+        ExportFormat::Mermaid => graph.to_mermaid_with(overlay.as_ref()),
+        ExportFormat::PlantUml => graph.to_plantuml(),
+        ExportFormat::GraphMl => graph.to_graphml(),
+        ExportFormat::Gexf => graph.to_gexf(),
         ExportFormat::Json => graph.to_json()?,
         ExportFormat::EstateJson => graph.to_estate_export()?,
-        ExportFormat::Yaml => {
-            anyhow::bail!("YAML export not yet implemented");
-        }
-        ExportFormat::Toml => {
-            anyhow::bail!("TOML export not yet implemented");
-        }
+        ExportFormat::Yaml => graph.to_estate_export_as(EnvelopeFormat::Yaml)?,
+        ExportFormat::Toml => graph.to_estate_export_as(EnvelopeFormat::Toml)?,
+        ExportFormat::Html => graph.to_html()?,
     };
 
     // Write output
@@ -3,12 +3,15 @@
 //! Group management commands - create clusters of related repositories
 
 use crate::graph::EcosystemGraph;
-use crate::types::Group;
+use crate::types::{ChangeOp, Group};
 use anyhow::{Context, Result};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Run group command
-pub fn run(action: &str, name: Option<String>, repos: Vec<String>) -> Result<()> {
+///
+/// With `in_scenario`, `add`, `remove` and `set` are recorded in that
+/// scenario's change set and the baseline is left alone.
+pub fn run(action: &str, name: Option<String>, repos: Vec<String>, in_scenario: Option<&str>) -> Result<()> {
     let data_dir = get_data_dir()?;
     let mut graph = EcosystemGraph::load(&data_dir)
         .with_context(|| format!("Failed to load graph from {}", data_dir.display()))?;
@@ -19,10 +22,7 @@ pub fn run(action: &str, name: Option<String>, repos: Vec<String>) -> Result<()>
             let group_id = format!("group:{}", slug(&name));
 
             // Resolve repo names to IDs
-            let member_ids: Vec<String> = repos
-                .iter()
-                .map(|r| resolve_repo_id(&graph, r))
-                .collect::<Result<Vec<_>>>()?;
+            let member_ids = resolve_repo_ids(&graph, &repos)?;
 
             let group = Group {
                 kind: "Group".into(),
@@ -43,20 +43,21 @@ pub fn run(action: &str, name: Option<String>, repos: Vec<String>) -> Result<()>
 
         "add" => {
             let name = name.ok_or_else(|| anyhow::anyhow!("Group name is required"))?;
-            let group_id = format!("group:{}", slug(&name));
 
             // Resolve repo IDs first (before mutable borrow)
-            let repo_ids: Vec<String> = repos
-                .iter()
-                .map(|r| resolve_repo_id(&graph, r))
-                .collect::<Result<Vec<_>>>()?;
-
-            let group = graph
-                .store
-                .groups
-                .iter_mut()
-                .find(|g| g.id == group_id || g.name == name)
-                .ok_or_else(|| anyhow::anyhow!("Group not found: {}", name))?;
+            let repo_ids = resolve_repo_ids(&graph, &repos)?;
+
+            if let Some(scenario) = in_scenario {
+                return record_membership(&mut graph, &data_dir, scenario, &name, |members| {
+                    for repo_id in repo_ids {
+                        if !members.contains(&repo_id) {
+                            members.push(repo_id);
+                        }
+                    }
+                });
+            }
+
+            let group = find_group_mut(&mut graph, &name)?;
 
             for repo_id in repo_ids {
                 if !group.members.contains(&repo_id) {
@@ -72,20 +73,17 @@ pub fn run(action: &str, name: Option<String>, repos: Vec<String>) -> Result<()>
 
         "remove" | "rm" => {
             let name = name.ok_or_else(|| anyhow::anyhow!("Group name is required"))?;
-            let group_id = format!("group:{}", slug(&name));
 
             // Resolve repo IDs first (before mutable borrow)
-            let repo_ids: Vec<String> = repos
-                .iter()
-                .map(|r| resolve_repo_id(&graph, r))
-                .collect::<Result<Vec<_>>>()?;
-
-            let group = graph
-                .store
-                .groups
-                .iter_mut()
-                .find(|g| g.id == group_id || g.name == name)
-                .ok_or_else(|| anyhow::anyhow!("Group not found: {}", name))?;
+            let repo_ids = resolve_repo_ids(&graph, &repos)?;
+
+            if let Some(scenario) = in_scenario {
+                return record_membership(&mut graph, &data_dir, scenario, &name, |members| {
+                    members.retain(|m| !repo_ids.contains(m));
+                });
+            }
+
+            let group = find_group_mut(&mut graph, &name)?;
 
             for repo_id in repo_ids {
                 let before = group.members.len();
@@ -100,6 +98,21 @@ pub fn run(action: &str, name: Option<String>, repos: Vec<String>) -> Result<()>
             graph.save(&data_dir)?;
         }
 
+        "set" => {
+            let name = name.ok_or_else(|| anyhow::anyhow!("Group name is required"))?;
+            let repo_ids = resolve_repo_ids(&graph, &repos)?;
+
+            if let Some(scenario) = in_scenario {
+                return record_membership(&mut graph, &data_dir, scenario, &name, |members| *members = repo_ids);
+            }
+
+            let group = find_group_mut(&mut graph, &name)?;
+            group.members = repo_ids;
+            println!("Set {} to {} member(s)", group.name, group.members.len());
+
+            graph.save(&data_dir)?;
+        }
+
         "delete" => {
             let name = name.ok_or_else(|| anyhow::anyhow!("Group name is required"))?;
             let group_id = format!("group:{}", slug(&name));
@@ -115,54 +128,108 @@ pub fn run(action: &str, name: Option<String>, repos: Vec<String>) -> Result<()>
             }
         }
 
-        "list" | "ls" => {
-            if graph.store.groups.is_empty() {
-                println!("No groups defined. Use 'reposystem group create <name>' to create one.");
-                return Ok(());
-            }
-
-            println!("Groups ({}):", graph.store.groups.len());
-            for group in &graph.store.groups {
-                println!("  {} ({} members)", group.name, group.members.len());
-                for member in &group.members {
-                    let repo_name = graph.get_repo(member).map(|r| r.name.as_str()).unwrap_or(member);
-                    println!("    - {}", repo_name);
-                }
-            }
-        }
+        "list" | "ls" => list_groups(&graph),
 
         "show" => {
             let name = name.ok_or_else(|| anyhow::anyhow!("Group name is required"))?;
-            let group_id = format!("group:{}", slug(&name));
-
-            let group = graph
-                .store
-                .groups
-                .iter()
-                .find(|g| g.id == group_id || g.name == name)
-                .ok_or_else(|| anyhow::anyhow!("Group not found: {}", name))?;
-
-            println!("Group: {}", group.name);
-            println!("  id: {}", group.id);
-            if let Some(desc) = &group.description {
-                println!("  description: {}", desc);
-            }
-            println!("  members ({}):", group.members.len());
-            for member in &group.members {
-                let repo = graph.get_repo(member);
-                if let Some(r) = repo {
-                    println!("    {} [{}]", r.name, r.id);
-                } else {
-                    println!("    {} (not found)", member);
-                }
-            }
+            show_group(&graph, &name)?;
         }
 
         other => {
-            anyhow::bail!("Unknown action: {}. Valid: create, add, remove, delete, list, show", other);
+            anyhow::bail!("Unknown action: {}. Valid: create, add, remove, set, delete, list, show", other);
+        }
+    }
+
+    Ok(())
+}
+
+/// Print every group with its members
+fn list_groups(graph: &EcosystemGraph) {
+    if graph.store.groups.is_empty() {
+        println!("No groups defined. Use 'reposystem group create <name>' to create one.");
+        return;
+    }
+
+    println!("Groups ({}):", graph.store.groups.len());
+    for group in &graph.store.groups {
+        println!("  {} ({} members)", group.name, group.members.len());
+        for member in &group.members {
+            let repo_name = graph.get_repo(member).map(|r| r.name.as_str()).unwrap_or(member);
+            println!("    - {}", repo_name);
+        }
+    }
+}
+
+/// Print a group and its members
+fn show_group(graph: &EcosystemGraph, name: &str) -> Result<()> {
+    let group_id = format!("group:{}", slug(name));
+
+    let group = graph
+        .store
+        .groups
+        .iter()
+        .find(|g| g.id == group_id || g.name == name)
+        .ok_or_else(|| anyhow::anyhow!("Group not found: {}", name))?;
+
+    println!("Group: {}", group.name);
+    println!("  id: {}", group.id);
+    if let Some(desc) = &group.description {
+        println!("  description: {}", desc);
+    }
+    println!("  members ({}):", group.members.len());
+    for member in &group.members {
+        let repo = graph.get_repo(member);
+        if let Some(r) = repo {
+            println!("    {} [{}]", r.name, r.id);
+        } else {
+            println!("    {} (not found)", member);
         }
     }
+    Ok(())
+}
+
+/// Find a group by ID or name
+fn find_group_mut<'a>(graph: &'a mut EcosystemGraph, name: &str) -> Result<&'a mut Group> {
+    let group_id = format!("group:{}", slug(name));
+    graph
+        .store
+        .groups
+        .iter_mut()
+        .find(|g| g.id == group_id || g.name == name)
+        .ok_or_else(|| anyhow::anyhow!("Group not found: {name}"))
+}
+
+/// Resolve repo names or IDs to full IDs
+fn resolve_repo_ids(graph: &EcosystemGraph, repos: &[String]) -> Result<Vec<String>> {
+    repos.iter().map(|r| resolve_repo_id(graph, r)).collect()
+}
 
+/// Record a membership change in a scenario: `update` edits the members the
+/// group has in that scenario, and the result is stored as a
+/// `SetGroupMembership` op
+fn record_membership(
+    graph: &mut EcosystemGraph,
+    data_dir: &Path,
+    scenario: &str,
+    name: &str,
+    update: impl FnOnce(&mut Vec<String>),
+) -> Result<()> {
+    let group_id = format!("group:{}", slug(name));
+    let group = graph
+        .with_scenario(scenario)?
+        .store
+        .groups
+        .into_iter()
+        .find(|g| g.id == group_id || g.name == name)
+        .ok_or_else(|| anyhow::anyhow!("Group not found: {name}"))?;
+    let mut members = group.members.clone();
+    update(&mut members);
+
+    let count = members.len();
+    let op = ChangeOp::SetGroupMembership { group_id: group.id, members, previous: Some(group.members) };
+    graph.record_change(scenario, op)?;
+    graph.save(data_dir)?;
+    println!("Recorded in scenario {scenario}: {} now has {count} member(s)", group.name);
     Ok(())
 }
 
@@ -0,0 +1,138 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Impact command - what breaks, transitively, if a repo, seam or edge breaks
+
+use crate::analysis::{impact, Affected, Impact, ImpactFilter};
+use crate::commands::data_dir;
+use crate::graph::EcosystemGraph;
+use crate::types::{Channel, RelationType};
+use anyhow::{Context, Result};
+
+/// Arguments for the impact command.
+pub struct ImpactArgs {
+    /// Repo (name or ID), seam ID or edge ID assumed to break.
+    pub target: String,
+    /// Only follow these relations (empty = all).
+    pub rels: Vec<String>,
+    /// Only follow these channels (empty = all).
+    pub channels: Vec<String>,
+    /// Stop after this many hops (0 = unlimited).
+    pub depth: usize,
+    /// Leave out repos reached with a lower path confidence.
+    pub min_confidence: f64,
+}
+
+/// Run the impact command
+///
+/// # Errors
+/// Returns an error if the graph cannot be loaded, a filter value is unknown
+/// or the target cannot be resolved.
+pub fn run(args: ImpactArgs, json: bool) -> Result<()> {
+    let ImpactArgs {
+        target,
+        rels,
+        channels,
+        depth,
+        min_confidence,
+    } = args;
+    let data_dir = data_dir()?;
+    let graph = EcosystemGraph::load(&data_dir)
+        .with_context(|| format!("Failed to load graph from {}", data_dir.display()))?;
+
+    let filter = ImpactFilter {
+        rels: rels
+            .iter()
+            .map(|r| {
+                RelationType::from_code(r).ok_or_else(|| {
+                    anyhow::anyhow!("Unknown relation type: {r}. Valid: uses, provides, extends, mirrors, replaces, refersto")
+                })
+            })
+            .collect::<Result<_>>()?,
+        channels: channels
+            .iter()
+            .map(|c| {
+                Channel::from_code(c).ok_or_else(|| {
+                    anyhow::anyhow!("Unknown channel: {c}. Valid: api, artifact, config, runtime, human, unknown")
+                })
+            })
+            .collect::<Result<_>>()?,
+        max_depth: depth,
+        min_confidence,
+    };
+
+    let target = resolve_target(&graph, &target)?;
+    let blast = impact(&graph, &target, &filter)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&blast)?);
+        return Ok(());
+    }
+
+    let name = |id: &str| graph.get_repo(id).map_or_else(|| id.to_string(), |r| r.name.clone());
+    if blast.affected.is_empty() {
+        println!("Nothing depends on {} ({target}).", name(&target));
+        return Ok(());
+    }
+    println!(
+        "Impact of {} ({target}): {} affected, {} direct",
+        name(&target),
+        blast.affected.len(),
+        blast.direct().count()
+    );
+    println!();
+
+    // An edge target's tree hangs off the edge's dependency end
+    let root = blast
+        .affected
+        .iter()
+        .find(|a| a.depth == 1)
+        .map_or(target.as_str(), |a| a.parent.as_str());
+    println!("{}", name(root));
+    print_tree(&blast, root, "", &name);
+    Ok(())
+}
+
+/// Print the dependents of `id` below it, one branch per line
+fn print_tree(blast: &Impact, id: &str, prefix: &str, name: &dyn Fn(&str) -> String) {
+    let children: Vec<&Affected> = blast.children(id).collect();
+    for (i, child) in children.iter().enumerate() {
+        let last = i + 1 == children.len();
+        let (branch, indent) = if last { ("└── ", "    ") } else { ("├── ", "│   ") };
+        println!(
+            "{prefix}{branch}{} [{}/{}] confidence {:.2}",
+            name(&child.id),
+            child.rel.code(),
+            child.channel.code(),
+            child.confidence
+        );
+        let detail = format!("{prefix}{indent}");
+        for risk in &child.risks {
+            let aspect = risk.aspect_id.trim_start_matches("aspect:");
+            println!("{detail}  ! {aspect} ({}): {}", risk.weight, risk.reason);
+        }
+        for binding in &child.bindings {
+            println!("{detail}  slot {binding}");
+        }
+        print_tree(blast, &child.id, &detail, name);
+    }
+}
+
+/// Resolve a target name to an ID (repo by name or ID, seam or edge ID)
+fn resolve_target(graph: &EcosystemGraph, target: &str) -> Result<String> {
+    if target.starts_with("repo:") || target.starts_with("seam:") || target.starts_with("edge:") {
+        return Ok(target.to_string());
+    }
+
+    let matches: Vec<_> = graph.repos().iter().filter(|r| r.name == target).collect();
+    match matches.len() {
+        0 => anyhow::bail!("No repo found: {target}"),
+        1 => Ok(matches[0].id.clone()),
+        _ => {
+            eprintln!("Multiple repos match '{target}':");
+            for r in &matches {
+                eprintln!("  {} ({})", r.name, r.id);
+            }
+            anyhow::bail!("Ambiguous name. Use full ID.");
+        }
+    }
+}
@@ -3,6 +3,8 @@
 //
 //! Import command — populate the ecosystem graph from the estate manifest.
 
+use crate::envelope::EnvelopeFormat;
+use crate::graph::EcosystemGraph;
 use crate::importers::manifest::{self, ManifestImport};
 use anyhow::{Context, Result};
 use std::path::PathBuf;
@@ -18,6 +20,8 @@ pub struct ImportArgs {
     pub estate: String,
     /// Estate display name.
     pub estate_name: Option<String>,
+    /// Estate-export file to read (`estate` source); format from its extension.
+    pub file: Option<PathBuf>,
 }
 
 /// Run the import command.
@@ -27,7 +31,8 @@ pub struct ImportArgs {
 pub fn run(source: &str, args: ImportArgs) -> Result<()> {
     match source {
         "manifest" | "repos" | "toml" => run_manifest(args),
-        other => anyhow::bail!("Unknown import source: {other}. Supported: manifest"),
+        "estate" | "export" => run_estate(args),
+        other => anyhow::bail!("Unknown import source: {other}. Supported: manifest, estate"),
     }
 }
 
@@ -56,3 +61,43 @@ fn run_manifest(args: ImportArgs) -> Result<()> {
     println!("Graph saved to {}", data_dir.display());
     Ok(())
 }
+
+/// Replace the stores with an estate-export envelope (JSON, YAML or TOML),
+/// keeping the local audit log and cached plan diffs.
+fn run_estate(args: ImportArgs) -> Result<()> {
+    let path = args
+        .file
+        .ok_or_else(|| anyhow::anyhow!("--file is required for estate import"))?;
+    let format = EnvelopeFormat::from_path(&path).ok_or_else(|| {
+        anyhow::anyhow!(
+            "Cannot tell the format of {}. Use a .json, .yaml/.yml or .toml file",
+            path.display()
+        )
+    })?;
+
+    info!("Importing estate export from {}", path.display());
+    let text = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut graph = EcosystemGraph::from_estate_export(&text, format)
+        .with_context(|| format!("Failed to import {}", path.display()))?;
+
+    let data_dir = crate::commands::data_dir()?;
+    if let Ok(existing) = EcosystemGraph::load(&data_dir) {
+        graph.audit = existing.audit;
+        graph.plans.diffs = existing.plans.diffs;
+    }
+    graph
+        .save(&data_dir)
+        .with_context(|| format!("Failed to save graph to {}", data_dir.display()))?;
+
+    println!(
+        "Imported {} repos, {} edges, {} groups, {} annotations from {}",
+        graph.store.repos.len(),
+        graph.store.edges.len(),
+        graph.store.groups.len(),
+        graph.aspects.annotations.len(),
+        path.display()
+    );
+    println!("Graph saved to {}", data_dir.display());
+    Ok(())
+}
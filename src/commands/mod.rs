@@ -3,18 +3,25 @@
 //
 //! Command implementations
 
+pub mod analyze;
 pub mod apply;
 pub mod aspect;
 pub mod completions;
 pub mod config;
+pub mod diff;
 pub mod edge;
 pub mod export;
 pub mod group;
+pub mod impact;
 pub mod import;
+pub mod path;
 pub mod plan;
+pub mod query;
+pub mod repo;
 pub mod scan;
 pub mod scenario;
 pub mod slot;
+pub mod snapshot;
 pub mod view;
 pub mod weak_links;
 
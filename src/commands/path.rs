@@ -0,0 +1,102 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Path command - explain how two repos are connected
+
+use crate::analysis::{paths_between, PathQuery, RepoPath};
+use crate::commands::data_dir;
+use crate::graph::EcosystemGraph;
+use anyhow::{Context, Result};
+
+/// Run the path command
+///
+/// # Errors
+/// Returns an error if the graph cannot be loaded or either end cannot be
+/// resolved.
+pub fn run(from: &str, to: &str, query: &PathQuery, json: bool) -> Result<()> {
+    let data_dir = data_dir()?;
+    let graph = EcosystemGraph::load(&data_dir)
+        .with_context(|| format!("Failed to load graph from {}", data_dir.display()))?;
+
+    let from = resolve_node(&graph, from)?;
+    let to = resolve_node(&graph, to)?;
+    let report = paths_between(&graph, &from, &to, query)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    let name = |id: &str| {
+        graph
+            .get_repo(id)
+            .map(|r| r.name.clone())
+            .or_else(|| graph.seams().iter().find(|s| s.id == id).map(|s| s.name.clone()))
+            .unwrap_or_else(|| id.to_string())
+    };
+
+    let Some(shortest) = &report.shortest else {
+        println!("No path from {} to {}.", name(&from), name(&to));
+        if !query.undirected {
+            println!("  (try --undirected to include shared dependencies)");
+        }
+        return Ok(());
+    };
+
+    println!(
+        "Shortest path from {} to {} ({} hops):",
+        name(&from),
+        name(&to),
+        shortest.hops.len()
+    );
+    println!("  {}", format_path(shortest, &name));
+    println!();
+
+    println!(
+        "All simple paths up to {} hops ({}{}):",
+        query.max_hops,
+        report.paths.len(),
+        if report.truncated { ", truncated" } else { "" }
+    );
+    for (i, path) in report.paths.iter().enumerate() {
+        println!("  {:>3}. {}", i + 1, format_path(path, &name));
+    }
+    if report.paths.iter().any(|p| p.via_seam) {
+        println!();
+        println!("  [seam] = passes through an external seam");
+    }
+    Ok(())
+}
+
+/// Render a path as `a --uses/api--> b <--extends/unknown-- c`
+fn format_path(path: &RepoPath, name: &dyn Fn(&str) -> String) -> String {
+    let mut out = path.hops.first().map(|h| name(&h.from)).unwrap_or_default();
+    for hop in &path.hops {
+        let label = format!("{}/{}", hop.rel.code(), hop.channel.code());
+        let (left, right) = if hop.reversed { ("<--", "--") } else { ("--", "-->") };
+        out.push_str(&[" ", left, &label, right, " ", &name(&hop.to)].concat());
+    }
+    if path.via_seam {
+        out.push_str("  [seam]");
+    }
+    out
+}
+
+/// Resolve a repo name or a repo/seam ID
+fn resolve_node(graph: &EcosystemGraph, name_or_id: &str) -> Result<String> {
+    if name_or_id.starts_with("repo:") || name_or_id.starts_with("seam:") {
+        return Ok(name_or_id.to_string());
+    }
+
+    let matches: Vec<_> = graph.repos().iter().filter(|r| r.name == name_or_id).collect();
+    match matches.len() {
+        0 => anyhow::bail!("No repo found: {name_or_id}"),
+        1 => Ok(matches[0].id.clone()),
+        _ => {
+            eprintln!("Multiple repos match '{name_or_id}':");
+            for r in &matches {
+                eprintln!("  {} ({})", r.name, r.id);
+            }
+            anyhow::bail!("Ambiguous name. Use full ID.");
+        }
+    }
+}
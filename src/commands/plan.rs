@@ -4,11 +4,16 @@
 
 use anyhow::{bail, Context, Result};
 use chrono::Utc;
-use std::path::Path;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write as _;
+use owo_colors::OwoColorize;
+use std::path::{Path, PathBuf};
 
 use crate::graph::EcosystemGraph;
+use crate::patch::Patch;
+use crate::rewrite;
 use crate::types::{
-    Plan, PlanDiff, PlanOp, PlanStatus, RiskLevel, SlotBinding,
+    Plan, PlanDiff, PlanOp, PlanStatus, RelationType, RiskLevel, SlotBinding,
 };
 
 /// Arguments for plan creation
@@ -19,6 +24,12 @@ pub struct PlanArgs {
     pub name: Option<String>,
     /// Plan description
     pub description: Option<String>,
+    /// Also propose bindings for repos with no binding for a slot
+    pub include_suggestions: bool,
+    /// Where `diff` writes the plan's patch bundle
+    pub output: Option<PathBuf>,
+    /// Colour the diff output
+    pub color: bool,
 }
 
 /// Run the plan command
@@ -31,7 +42,7 @@ pub fn run(action: &str, name: Option<String>, args: PlanArgs) -> Result<()> {
         "create" => create_plan(&data_dir, args),
         "list" => list_plans(&data_dir),
         "show" => show_plan(&data_dir, name),
-        "diff" => show_diff(&data_dir, name),
+        "diff" => show_diff(&data_dir, name, args.output.as_deref(), args.color),
         "rollback" => generate_rollback(&data_dir, name),
         "delete" => delete_plan(&data_dir, name),
         _ => bail!("Unknown action: {}. Use create, list, show, diff, rollback, or delete", action),
@@ -55,13 +66,8 @@ fn create_plan(data_dir: &Path, args: PlanArgs) -> Result<()> {
     let scenario_id = scenario.id.clone();
     let scenario_name = scenario.name.clone();
 
-    // Get the changeset for this scenario
-    let changeset = graph.store.changesets
-        .iter()
-        .find(|c| c.scenario_id == scenario_id);
-
-    // Generate plan operations from the scenario's changeset and current bindings
-    let operations = generate_plan_operations(&graph, &scenario_id, changeset)?;
+    // Generate plan operations from the scenario's changes and current bindings
+    let operations = generate_plan_operations(&graph, &scenario_id, args.include_suggestions)?;
 
     // Calculate overall risk
     let overall_risk = Plan::calculate_overall_risk(&operations);
@@ -80,11 +86,13 @@ fn create_plan(data_dir: &Path, args: PlanArgs) -> Result<()> {
         created_at: Utc::now(),
         created_by: std::env::var("USER").unwrap_or_else(|_| "unknown".into()),
         applied_at: None,
+        applied_ops: Vec::new(),
+        base_refs: BTreeMap::new(),
         rollback_plan_id: None,
     };
 
     // Generate diff for the plan
-    let diff = generate_plan_diff(&plan);
+    let diff = generate_plan_diff(&graph, &plan);
 
     // Add to store
     graph.plans.plans.push(plan.clone());
@@ -104,41 +112,43 @@ fn create_plan(data_dir: &Path, args: PlanArgs) -> Result<()> {
 }
 
 /// Generate plan operations from a scenario
+///
+/// The scenario is materialised (base chain included) and its slot bindings
+/// are compared with the baseline's: a `uses` edge added to a provider's repo
+/// creates or switches a binding, and a removed one removes it. Bindings the
+/// scenario leaves alone are still checked for a higher priority provider.
+/// With `include_suggestions`, repos with no binding for a slot that has a
+/// compatible provider get a `CreateBinding` as well. Binding creates and
+/// switches are followed by the `FileChange` ops their slots' templates imply.
 fn generate_plan_operations(
     graph: &EcosystemGraph,
-    _scenario_id: &str,
-    changeset: Option<&crate::types::ChangeSet>,
+    scenario_id: &str,
+    include_suggestions: bool,
 ) -> Result<Vec<PlanOp>> {
-    let mut operations = Vec::new();
-
-    // If there's a changeset, analyze it for binding-related changes
-    if let Some(cs) = changeset {
-        for op in &cs.ops {
-            match op {
-                crate::types::ChangeOp::AddEdge { edge } => {
-                    // Adding an edge might indicate a new binding relationship
-                    // For now, we note it as informational
-                    tracing::debug!("Scenario adds edge: {} -> {}", edge.from, edge.to);
-                }
-                crate::types::ChangeOp::RemoveEdge { edge_id } => {
-                    tracing::debug!("Scenario removes edge: {}", edge_id);
-                }
-                _ => {}
-            }
+    let effective = graph.with_scenario(scenario_id)?;
+    let mut operations = scenario_binding_operations(graph, &effective);
+    let planned: HashSet<(String, String)> = operations
+        .iter()
+        .filter_map(|op| match op {
+            PlanOp::SwitchBinding { consumer_id, slot_id, .. }
+            | PlanOp::CreateBinding { consumer_id, slot_id, .. }
+            | PlanOp::RemoveBinding { consumer_id, slot_id, .. } => Some((consumer_id.clone(), slot_id.clone())),
+            PlanOp::FileChange { .. } => None,
+        })
+        .collect();
+
+    // For the bindings the scenario keeps, check if a better provider exists
+    for binding in &effective.slots.bindings {
+        if planned.contains(&(binding.consumer_id.clone(), binding.slot_id.clone())) {
+            continue;
         }
-    }
-
-    // Compare current bindings to desired state
-    // For each slot used by consumers, check if the binding should change
-    for binding in &graph.slots.bindings {
-        // Check if this binding's provider is marked for replacement in the scenario
-        let current_provider = graph.slots.providers
+        let current_provider = effective.slots.providers
             .iter()
             .find(|p| p.id == binding.provider_id);
 
         if let Some(provider) = current_provider {
             // Look for alternative providers for the same slot
-            let alternatives: Vec<_> = graph.slots.providers
+            let alternatives: Vec<_> = effective.slots.providers
                 .iter()
                 .filter(|p| p.slot_id == binding.slot_id && p.id != binding.provider_id)
                 .collect();
@@ -147,9 +157,9 @@ fn generate_plan_operations(
             for alt in &alternatives {
                 if alt.priority > provider.priority && !alt.is_fallback {
                     // Check compatibility
-                    let compat = graph.slots.check_compatibility(&binding.slot_id, &alt.id);
+                    let compat = effective.slots.check_compatibility(&binding.slot_id, &alt.id);
                     if compat.compatible {
-                        let risk = assess_binding_switch_risk(graph, binding, provider, alt);
+                        let risk = assess_binding_switch_risk(&effective, binding, provider, alt);
                         operations.push(PlanOp::SwitchBinding {
                             binding_id: binding.id.clone(),
                             consumer_id: binding.consumer_id.clone(),
@@ -168,43 +178,129 @@ fn generate_plan_operations(
         }
     }
 
-    // Also check for consumers that need bindings
+    if include_suggestions {
+        operations.extend(suggested_bindings(&effective));
+    }
+
+    // Slot binding templates say which consumer files each binding rewrites
+    let file_changes = rewrite::template_file_changes(&effective, &operations)
+        .context("Failed to apply slot binding templates")?;
+    operations.extend(file_changes);
+
+    Ok(operations)
+}
+
+/// Binding changes implied by the scenario: its effective bindings against
+/// the baseline's, per consumer and slot
+fn scenario_binding_operations(baseline: &EcosystemGraph, effective: &EcosystemGraph) -> Vec<PlanOp> {
+    let mut operations = Vec::new();
+    let provider = |graph: &EcosystemGraph, id: &str| graph.slots.providers.iter().find(|p| p.id == id).cloned();
+    // The uses edge from the consumer to the repo implementing a provider
+    let edge_to = |graph: &EcosystemGraph, consumer: &str, provider_id: &str| {
+        let repo = provider(graph, provider_id)?.repo_id?;
+        graph
+            .store
+            .edges
+            .iter()
+            .find(|e| e.from == consumer && e.to == repo && e.rel == RelationType::Uses)
+            .map(|e| format!("{} -> {}", e.from, e.to))
+    };
+
+    for binding in &effective.slots.bindings {
+        let current = baseline.slots.get_binding(&binding.consumer_id, &binding.slot_id);
+        if current.is_some_and(|c| c.provider_id == binding.provider_id) {
+            continue;
+        }
+        let via = edge_to(effective, &binding.consumer_id, &binding.provider_id)
+            .map_or_else(|| "Scenario binds this provider".to_string(), |e| format!("Scenario adds uses edge {e}"));
+        let compat = effective.slots.check_compatibility(&binding.slot_id, &binding.provider_id);
+        let from = current.and_then(|c| provider(baseline, &c.provider_id));
+        match (current, from, provider(effective, &binding.provider_id)) {
+            (Some(current), Some(from), Some(to)) => operations.push(PlanOp::SwitchBinding {
+                binding_id: current.id.clone(),
+                consumer_id: binding.consumer_id.clone(),
+                slot_id: binding.slot_id.clone(),
+                from_provider_id: current.provider_id.clone(),
+                to_provider_id: binding.provider_id.clone(),
+                risk: if compat.compatible {
+                    assess_binding_switch_risk(baseline, current, &from, &to)
+                } else {
+                    RiskLevel::Critical
+                },
+                reason: format!("{via}: {} replaces {}", to.name, from.name),
+            }),
+            _ => operations.push(PlanOp::CreateBinding {
+                consumer_id: binding.consumer_id.clone(),
+                slot_id: binding.slot_id.clone(),
+                provider_id: binding.provider_id.clone(),
+                risk: if compat.compatible { RiskLevel::Low } else { RiskLevel::High },
+                reason: if compat.compatible { via } else { format!("{via} ({})", compat.reason) },
+            }),
+        }
+    }
+
+    for binding in &baseline.slots.bindings {
+        if effective.slots.get_binding(&binding.consumer_id, &binding.slot_id).is_some() {
+            continue;
+        }
+        let reason = edge_to(baseline, &binding.consumer_id, &binding.provider_id)
+            .map_or_else(|| "Scenario drops this binding".to_string(), |e| format!("Scenario removes uses edge {e}"));
+        operations.push(PlanOp::RemoveBinding {
+            binding_id: binding.id.clone(),
+            consumer_id: binding.consumer_id.clone(),
+            slot_id: binding.slot_id.clone(),
+            provider_id: binding.provider_id.clone(),
+            risk: RiskLevel::Medium,
+            reason,
+        });
+    }
+
+    operations
+}
+
+/// Repos with no binding for a slot that has a compatible provider, bound to
+/// the highest priority non-fallback one (`--include-suggestions`); repos
+/// implementing the slot are skipped
+fn suggested_bindings(graph: &EcosystemGraph) -> Vec<PlanOp> {
+    let mut operations = Vec::new();
     for repo in &graph.store.repos {
-        // Check each slot to see if this repo might need it
         for slot in &graph.slots.slots {
-            let existing_binding = graph.slots.get_binding(&repo.id, &slot.id);
-            if existing_binding.is_none() {
-                // Check if there's a compatible provider available
-                let compatible_providers: Vec<_> = graph.slots.providers
-                    .iter()
-                    .filter(|p| {
-                        p.slot_id == slot.id &&
-                        graph.slots.check_compatibility(&slot.id, &p.id).compatible
-                    })
-                    .collect();
-
-                if !compatible_providers.is_empty() {
-                    // Pick the highest priority non-fallback provider
-                    let best = compatible_providers
-                        .iter()
-                        .filter(|p| !p.is_fallback)
-                        .max_by_key(|p| p.priority)
-                        .or_else(|| compatible_providers.first());
-
-                    if let Some(provider) = best {
-                        // Only suggest if this seems relevant based on scenario
-                        // For now, we're conservative and only include explicit switches
-                        tracing::debug!(
-                            "Could bind {} to {} via {}",
-                            repo.id, slot.id, provider.id
-                        );
-                    }
-                }
+            // A repo implementing the slot does not consume it
+            let implements = graph.slots.providers
+                .iter()
+                .any(|p| p.slot_id == slot.id && p.repo_id.as_deref() == Some(repo.id.as_str()));
+            if implements || graph.slots.get_binding(&repo.id, &slot.id).is_some() {
+                continue;
+            }
+            let compatible_providers: Vec<_> = graph.slots.providers
+                .iter()
+                .filter(|p| {
+                    p.slot_id == slot.id &&
+                    graph.slots.check_compatibility(&slot.id, &p.id).compatible
+                })
+                .collect();
+
+            let best = compatible_providers
+                .iter()
+                .filter(|p| !p.is_fallback)
+                .max_by_key(|p| p.priority)
+                .or_else(|| compatible_providers.first());
+
+            if let Some(provider) = best {
+                operations.push(PlanOp::CreateBinding {
+                    consumer_id: repo.id.clone(),
+                    slot_id: slot.id.clone(),
+                    provider_id: provider.id.clone(),
+                    risk: RiskLevel::Low,
+                    reason: format!(
+                        "Suggestion: {} has no {} binding; {} (priority {}) is compatible",
+                        repo.name, slot.name, provider.name, provider.priority
+                    ),
+                });
             }
         }
     }
-
-    Ok(operations)
+    operations
 }
 
 /// Assess risk level for a binding switch
@@ -250,22 +346,26 @@ fn assess_binding_switch_risk(
     }
 }
 
-/// Generate a diff summary for a plan
-fn generate_plan_diff(plan: &Plan) -> PlanDiff {
+/// Generate a diff for a plan against the current checkouts
+fn generate_plan_diff(graph: &EcosystemGraph, plan: &Plan) -> PlanDiff {
     let mut bindings_changed = 0;
     let mut bindings_created = 0;
     let mut bindings_removed = 0;
-    let mut files_affected = 0;
-    let file_diffs = Vec::new();
+    let file_diffs = rewrite::plan_file_diffs(graph, plan);
 
     for op in &plan.operations {
         match op {
             PlanOp::SwitchBinding { .. } => bindings_changed += 1,
             PlanOp::CreateBinding { .. } => bindings_created += 1,
             PlanOp::RemoveBinding { .. } => bindings_removed += 1,
-            PlanOp::FileChange { .. } => files_affected += 1,
+            PlanOp::FileChange { .. } => {}
         }
     }
+    let files_affected = file_diffs
+        .iter()
+        .map(|fd| (&fd.repo_id, &fd.file_path))
+        .collect::<HashSet<_>>()
+        .len();
 
     PlanDiff {
         plan_id: plan.id.clone(),
@@ -296,6 +396,7 @@ fn list_plans(data_dir: &Path) -> Result<()> {
             PlanStatus::Draft => "📝",
             PlanStatus::Ready => "✅",
             PlanStatus::Applied => "🚀",
+            PlanStatus::PartiallyApplied => "⏸️",
             PlanStatus::RolledBack => "↩️",
             PlanStatus::Cancelled => "❌",
         };
@@ -370,8 +471,9 @@ fn show_plan(data_dir: &Path, name: Option<String>) -> Result<()> {
     Ok(())
 }
 
-/// Show dry-run diff for a plan
-fn show_diff(data_dir: &Path, name: Option<String>) -> Result<()> {
+/// Show dry-run diff for a plan, optionally writing its file diffs as a
+/// patch bundle
+fn show_diff(data_dir: &Path, name: Option<String>, output: Option<&Path>, color: bool) -> Result<()> {
     let graph = EcosystemGraph::load(data_dir)
         .context("Failed to load ecosystem graph")?;
 
@@ -382,34 +484,37 @@ fn show_diff(data_dir: &Path, name: Option<String>) -> Result<()> {
         .find(|p| p.id == plan_id || p.name == plan_id)
         .ok_or_else(|| anyhow::anyhow!("Plan not found: {}", plan_id))?;
 
-    let diff = graph.plans.get_diff(&plan.id);
+    // Recomputed so the patches match what is checked out now
+    let d = generate_plan_diff(&graph, plan);
+
+    if let Some(path) = output {
+        std::fs::write(path, patch_bundle(&graph, &d))
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        println!("Wrote {} file patch(es) to {}", d.file_diffs.len(), path.display());
+        return Ok(());
+    }
 
     println!("Dry-Run Diff for: {}", plan.name);
     println!("{}", "=".repeat(60));
     println!();
 
-    if let Some(d) = diff {
-        println!("Summary:");
-        println!("  Bindings changed: {}", d.bindings_changed);
-        println!("  Bindings created: {}", d.bindings_created);
-        println!("  Bindings removed: {}", d.bindings_removed);
-        println!("  Files affected: {}", d.files_affected);
-        println!();
+    println!("Summary:");
+    println!("  Bindings changed: {}", d.bindings_changed);
+    println!("  Bindings created: {}", d.bindings_created);
+    println!("  Bindings removed: {}", d.bindings_removed);
+    println!("  Files affected: {}", d.files_affected);
+    println!();
 
-        if !d.file_diffs.is_empty() {
-            println!("File Changes:");
-            for fd in &d.file_diffs {
-                println!("  {} ({:?})", fd.file_path, fd.change_type);
-                println!("    +{} -{}", fd.lines_added, fd.lines_removed);
-                if !fd.diff.is_empty() {
-                    for line in fd.diff.lines().take(10) {
-                        println!("    {}", line);
-                    }
-                    if fd.diff.lines().count() > 10 {
-                        println!("    ... ({} more lines)", fd.diff.lines().count() - 10);
-                    }
-                }
+    if !d.file_diffs.is_empty() {
+        println!("File Changes:");
+        for fd in &d.file_diffs {
+            let repo = graph.get_repo(&fd.repo_id).map_or(fd.repo_id.as_str(), |r| r.name.as_str());
+            println!("  {}: {} ({:?}) +{} -{}",
+                repo, fd.file_path, fd.change_type, fd.lines_added, fd.lines_removed);
+            for line in fd.diff.lines() {
+                println!("    {}", paint_diff_line(line, color));
             }
+            println!();
         }
     }
 
@@ -450,6 +555,47 @@ fn show_diff(data_dir: &Path, name: Option<String>) -> Result<()> {
     Ok(())
 }
 
+/// Colour a unified diff line: headers bold, hunk headers cyan, additions
+/// green, removals red
+pub(crate) fn paint_diff_line(line: &str, color: bool) -> String {
+    if !color {
+        return line.to_string();
+    }
+    if line.starts_with("---") || line.starts_with("+++") || line.starts_with("diff ") {
+        line.bold().to_string()
+    } else if line.starts_with("@@") {
+        line.cyan().to_string()
+    } else if line.starts_with('+') {
+        line.green().to_string()
+    } else if line.starts_with('-') {
+        line.red().to_string()
+    } else {
+        line.to_string()
+    }
+}
+
+/// A plan's file diffs as one patch, with each path under its repo's name, so
+/// `git apply` or `patch -p1` can apply it from a directory of checkouts
+fn patch_bundle(graph: &EcosystemGraph, diff: &PlanDiff) -> String {
+    let mut bundle = String::new();
+    for fd in &diff.file_diffs {
+        let repo = graph.get_repo(&fd.repo_id).map_or(fd.repo_id.as_str(), |r| r.name.as_str());
+        let file = format!("{repo}/{}", fd.file_path);
+        let _ = writeln!(bundle, "diff --git a/{file} b/{file}");
+        if let Ok(mut patch) = Patch::parse(&fd.diff) {
+            patch.old_path = patch.old_path.map(|_| file.clone());
+            patch.new_path = patch.new_path.map(|_| file.clone());
+            bundle.push_str(&patch.to_string());
+        } else {
+            bundle.push_str(&fd.diff);
+            if !fd.diff.ends_with('\n') {
+                bundle.push('\n');
+            }
+        }
+    }
+    bundle
+}
+
 /// Generate a rollback plan
 fn generate_rollback(data_dir: &Path, name: Option<String>) -> Result<()> {
     let mut graph = EcosystemGraph::load(data_dir)
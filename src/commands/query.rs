@@ -0,0 +1,70 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Query command - select repos, edges or annotations with a filter expression
+
+use crate::commands::data_dir;
+use crate::graph::EcosystemGraph;
+use crate::query::{Query, QueryResult};
+use anyhow::{Context, Result};
+
+/// Run the query command
+///
+/// # Errors
+/// Returns an error if the query does not parse or the graph cannot be loaded.
+pub fn run(query: &str, ids_only: bool, json: bool) -> Result<()> {
+    let query = Query::parse(query)?;
+
+    let data_dir = data_dir()?;
+    let graph = EcosystemGraph::load(&data_dir)
+        .with_context(|| format!("Failed to load graph from {}", data_dir.display()))?;
+    let result = query.select(&graph);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&result)?);
+        return Ok(());
+    }
+
+    if ids_only {
+        for id in result.ids() {
+            println!("{id}");
+        }
+        return Ok(());
+    }
+
+    let name = |id: &str| graph.get_repo(id).map_or_else(|| id.to_string(), |r| r.name.clone());
+
+    match &result {
+        QueryResult::Repos(repos) => {
+            for repo in repos {
+                println!("{} ({})", repo.name, repo.id);
+            }
+        }
+        QueryResult::Edges(edges) => {
+            for edge in edges {
+                println!(
+                    "{} -> {}  [{}/{}]  {}",
+                    name(&edge.from),
+                    name(&edge.to),
+                    edge.rel.code(),
+                    edge.channel.code(),
+                    edge.id
+                );
+            }
+        }
+        QueryResult::Annotations(anns) => {
+            for ann in anns {
+                println!(
+                    "{}  {} {:?} {}/3  {}",
+                    name(&ann.target),
+                    ann.aspect_id.trim_start_matches("aspect:"),
+                    ann.polarity,
+                    ann.weight,
+                    ann.reason
+                );
+            }
+        }
+    }
+
+    eprintln!("{} {} matched", result.len(), query.target);
+    Ok(())
+}
@@ -0,0 +1,106 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Repo commands - remove, re-key or merge a repository with referential cleanup
+
+use crate::commands::data_dir;
+use crate::graph::{EcosystemGraph, RepoCascade};
+use anyhow::{Context, Result};
+
+/// Run repo command
+///
+/// # Errors
+/// Fails if a repo cannot be resolved, the new ID is invalid or taken,
+/// or the graph cannot be loaded or saved.
+pub fn run(action: &str, repo: Option<String>, new_id: Option<String>, json: bool) -> Result<()> {
+    let data_dir = data_dir()?;
+    let mut graph = EcosystemGraph::load(&data_dir)
+        .with_context(|| format!("Failed to load graph from {}", data_dir.display()))?;
+
+    let repo = repo.ok_or_else(|| anyhow::anyhow!("Repository is required"))?;
+    let repo_id = resolve_repo_id(&graph, &repo)?;
+
+    let (verb, cascade) = match action {
+        "rm" | "remove" | "delete" => ("Removed", graph.remove_repo(&repo_id)?),
+        "mv" | "rename" | "rekey" => {
+            let new_id = new_id.ok_or_else(|| anyhow::anyhow!("New repo ID is required"))?;
+            if !new_id.starts_with("repo:") {
+                anyhow::bail!("New ID must start with 'repo:' (e.g. repo:gl:owner/name)");
+            }
+            ("Moved", graph.rekey_repo(&repo_id, &new_id)?)
+        }
+        "merge" => {
+            let into = new_id.ok_or_else(|| anyhow::anyhow!("Repository to merge into is required"))?;
+            let into_id = resolve_repo_id(&graph, &into)?;
+            ("Merged", graph.merge_repo(&repo_id, &into_id)?)
+        }
+        other => anyhow::bail!("Unknown action: {other}. Valid: rm, mv, merge"),
+    };
+
+    graph.save(&data_dir)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&cascade)?);
+    } else {
+        print_cascade(verb, &cascade);
+    }
+    Ok(())
+}
+
+/// Print everything a cascade touched
+fn print_cascade(verb: &str, cascade: &RepoCascade) {
+    match &cascade.new_id {
+        Some(new_id) => println!("{verb} {} -> {new_id}", cascade.repo_id),
+        None => println!("{verb} {}", cascade.repo_id),
+    }
+
+    for (label, ids) in [
+        ("components", &cascade.components),
+        ("edges", &cascade.edges),
+        ("groups", &cascade.groups),
+        ("annotations", &cascade.annotations),
+        ("providers", &cascade.providers),
+        ("bindings", &cascade.bindings),
+        ("scenarios", &cascade.scenarios),
+        ("removed edges", &cascade.removed_edges),
+        ("removed annotations", &cascade.removed_annotations),
+    ] {
+        if ids.is_empty() {
+            continue;
+        }
+        println!("  {label} ({}):", ids.len());
+        for id in ids {
+            println!("    {id}");
+        }
+    }
+
+    println!("{} references updated", cascade.total());
+}
+
+/// Resolve a repo name or ID to a full ID
+fn resolve_repo_id(graph: &EcosystemGraph, name_or_id: &str) -> Result<String> {
+    if name_or_id.starts_with("repo:") {
+        if graph.get_repo(name_or_id).is_some() {
+            return Ok(name_or_id.to_string());
+        }
+        anyhow::bail!("Repo not found: {name_or_id}");
+    }
+
+    // Removal is destructive, so only exact name matches are accepted
+    let matches: Vec<_> = graph
+        .repos()
+        .iter()
+        .filter(|r| r.name == name_or_id)
+        .collect();
+
+    match matches.len() {
+        0 => anyhow::bail!("No repo found matching: {name_or_id}"),
+        1 => Ok(matches[0].id.clone()),
+        _ => {
+            eprintln!("Multiple repos match '{name_or_id}':");
+            for r in &matches {
+                eprintln!("  {} ({})", r.name, r.id);
+            }
+            anyhow::bail!("Ambiguous repo name. Use full ID.");
+        }
+    }
+}
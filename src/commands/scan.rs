@@ -2,37 +2,106 @@
 // SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
 //! Scan command - discovers git repositories and builds the ecosystem graph
 
-use crate::graph::EcosystemGraph;
-use crate::scanner::{ScanConfig, scan_path};
-use crate::types::Forge;
+use crate::detect::{DetectorRegistry, RepoIndex};
+use crate::graph::{EcosystemGraph, EdgeSync};
+use crate::scan_cache::{CacheEntry, RepoFingerprint, ScanCache};
+use crate::progress::Progress;
+use crate::scanner::{
+    discover_repos, find_transfers, scan_repos, worker_pool, ScanConfig, ScanResult, Transfer,
+    SCAN_SOURCE,
+};
+use crate::types::{Edge, Forge, Repo};
 use anyhow::{Context, Result};
-use std::path::PathBuf;
-use tracing::info;
+use chrono::Utc;
+use rayon::prelude::*;
+use rayon::ThreadPool;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Arguments for the scan command.
+#[allow(clippy::struct_excessive_bools)]
+pub struct ScanArgs {
+    /// Path to scan.
+    pub path: PathBuf,
+    /// Deep scan: extract metadata and detect dependency edges.
+    pub deep: bool,
+    /// Shallow scan (depth-limited).
+    pub shallow: bool,
+    /// Extract metadata (implies a deep scan).
+    pub metadata: bool,
+    /// Report Cargo/npm workspaces after scanning.
+    pub detect_workspaces: bool,
+    /// Only run these edge detectors.
+    pub detectors: Option<Vec<String>>,
+    /// Skip these edge detectors.
+    pub skip_detectors: Vec<String>,
+    /// Rescan every repo, ignoring the scan cache.
+    pub full: bool,
+    /// Remove repos whose checkout has disappeared from the graph.
+    pub prune: bool,
+    /// Fold local-only repos into the forge repo they turned into.
+    pub merge_transfers: bool,
+    /// Worker threads (0 = one per CPU).
+    pub jobs: usize,
+    /// Suppress the progress bar.
+    pub quiet: bool,
+}
 
 /// Run the scan command
-pub fn run(
-    path: PathBuf,
-    deep: bool,
-    shallow: bool,
-    metadata: bool,
-    detect_workspaces: bool,
-    json: bool,
-) -> Result<()> {
+pub fn run(args: ScanArgs, json: bool) -> Result<()> {
+    let ScanArgs {
+        path,
+        deep,
+        shallow,
+        metadata,
+        detect_workspaces,
+        detectors,
+        skip_detectors,
+        full,
+        prune,
+        merge_transfers,
+        jobs,
+        quiet,
+    } = args;
     info!("Scanning: {:?}", path);
 
+    let registry = DetectorRegistry::builtin();
+    let known = registry.names();
+    check_detector_names(&known, detectors.iter().flatten().chain(&skip_detectors))?;
+
     // Build scan config from flags
     let config = ScanConfig {
         max_depth: if shallow { 2 } else { 0 },
         follow_symlinks: false,
         deep: deep || metadata,
+        detectors,
+        disabled_detectors: skip_detectors,
+        jobs,
         ..Default::default()
     };
 
-    // Perform the scan
-    let results = scan_path(&path, &config)
+    let enabled_detectors: Vec<String> = known
+        .iter()
+        .filter(|name| config.deep && config.detector_enabled(name))
+        .map(|name| (*name).to_string())
+        .collect();
+
+    let root = path
+        .canonicalize()
+        .with_context(|| format!("Failed to canonicalize path: {}", path.display()))?;
+    let repo_paths = discover_repos(&root, &config)
         .with_context(|| format!("Failed to scan {}", path.display()))?;
 
-    if results.is_empty() {
+    // Merge into the existing graph so hand-made edges, groups and annotations
+    // survive a re-scan and detected edges can resolve against known repos.
+    let data_dir = get_data_dir()?;
+    let mut graph = EcosystemGraph::load(&data_dir)
+        .with_context(|| format!("Failed to load graph from {}", data_dir.display()))?;
+    let mut cache = ScanCache::load(&data_dir);
+
+    let mut vanished = find_vanished(&graph, &root);
+
+    if repo_paths.is_empty() && vanished.is_empty() {
         if json {
             println!("{}", EcosystemGraph::new().to_json()?);
         } else {
@@ -41,44 +110,60 @@ pub fn run(
         return Ok(());
     }
 
-    // Build the ecosystem graph
-    let mut graph = EcosystemGraph::new();
+    // Unchanged checkouts are taken from the graph; everything else is rescanned
+    let pool = worker_pool(config.jobs)?;
+    let checkouts = fingerprint_all(&pool, repo_paths);
+    let (unchanged, stale) = if full {
+        (Vec::new(), checkouts)
+    } else {
+        partition_by_cache(checkouts, &cache, &graph, &config, &enabled_detectors)
+    };
+    let mut scanned = rescan(&pool, stale, &config, quiet);
+    let mut repo_set_changed = merge_scanned(&mut graph, &mut scanned);
 
-    for result in &results {
-        graph.add_repo(result.repo.clone());
+    // Local-only repos that gained a forge remote (or moved) are reported as
+    // transfers rather than vanished, and merged on request.
+    let seen: Vec<&Repo> = scanned.iter().map(|(r, _)| &r.repo).chain(&unchanged).collect();
+    let transfers = find_transfers(&graph, &seen);
+    vanished.retain(|r| !transfers.iter().any(|t| t.local_id == r.id));
+    if merge_transfers {
+        repo_set_changed |= merge_all(&mut graph, &mut cache, &transfers)?;
+    }
 
-        // Report any warnings
-        for warning in &result.warnings {
-            eprintln!("  Warning for {}: {}", result.repo.name, warning);
+    if prune {
+        for repo in &vanished {
+            graph.remove_repo(&repo.id)?;
+            cache.forget_repo(&repo.id);
+            repo_set_changed = true;
         }
     }
+    cache
+        .entries
+        .retain(|p, _| !p.starts_with(&root) || p.join(".git").is_dir());
 
-    // Human-readable summary (suppressed in --json mode so stdout stays pure JSON).
-    if !json {
-        println!("Found {} repositories:", graph.node_count());
-        println!();
-
-        for repo in graph.repos() {
-            let forge_info = if repo.forge == Forge::Local {
-                "local".to_string()
-            } else {
-                format!("{}/{}", repo.owner, repo.name)
-            };
-
-            println!("  {} [{}:{}]", repo.name, repo.forge.code(), forge_info);
-
-            if !repo.tags.is_empty() {
-                println!("    tags: {}", repo.tags.join(", "));
-            }
+    // Deep scans infer dependency edges from manifests. Unchanged repos only
+    // need re-detecting when the set of repos their manifests resolve against
+    // has changed.
+    let mut edge_sync = EdgeSync::default();
+    if config.deep {
+        let mut to_detect: Vec<&Repo> = scanned.iter().map(|(r, _)| &r.repo).collect();
+        if repo_set_changed {
+            to_detect.extend(&unchanged);
         }
+        edge_sync = detect_edges(&pool, &mut graph, &registry, &config, &to_detect, quiet);
+    }
 
-        println!();
+    record_scans(&mut cache, &scanned, &config, &enabled_detectors);
+
+    // Human-readable summary (suppressed in --json mode so stdout stays pure JSON).
+    if !json {
+        print_summary(&scanned, &unchanged, &vanished, prune, config.deep.then_some(edge_sync));
+        print_transfers(&transfers, merge_transfers);
     }
 
-    // Determine data directory
-    let data_dir = get_data_dir()?;
     graph.save(&data_dir)
         .with_context(|| format!("Failed to save graph to {}", data_dir.display()))?;
+    cache.save(&data_dir)?;
 
     if json {
         println!("{}", graph.to_json()?);
@@ -94,6 +179,273 @@ pub fn run(
     Ok(())
 }
 
+/// Reject detector names that are not registered
+fn check_detector_names<'a>(known: &[&str], names: impl Iterator<Item = &'a String>) -> Result<()> {
+    for name in names {
+        if !known.contains(&name.as_str()) {
+            anyhow::bail!("Unknown edge detector: {name}. Available: {}", known.join(", "));
+        }
+    }
+    Ok(())
+}
+
+/// Fingerprint every checkout on the worker pool, keeping input order
+fn fingerprint_all(pool: &ThreadPool, repo_paths: Vec<PathBuf>) -> Vec<(PathBuf, RepoFingerprint)> {
+    pool.install(|| {
+        repo_paths
+            .into_par_iter()
+            .map(|path| {
+                let fingerprint = RepoFingerprint::of(&path);
+                (path, fingerprint)
+            })
+            .collect()
+    })
+}
+
+/// Split discovered checkouts into repos whose cache entry is still fresh
+/// (taken from the graph) and checkouts that need rescanning.
+fn partition_by_cache(
+    checkouts: Vec<(PathBuf, RepoFingerprint)>,
+    cache: &ScanCache,
+    graph: &EcosystemGraph,
+    config: &ScanConfig,
+    enabled_detectors: &[String],
+) -> (Vec<Repo>, Vec<(PathBuf, RepoFingerprint)>) {
+    let mut unchanged = Vec::new();
+    let mut stale = Vec::new();
+    for (repo_path, fingerprint) in checkouts {
+        let cached = cache
+            .fresh(&repo_path, &fingerprint, config.deep, enabled_detectors)
+            .and_then(|entry| graph.get_repo(&entry.repo_id));
+        if let Some(repo) = cached {
+            let mut repo = repo.clone();
+            repo.local_path = Some(repo_path);
+            unchanged.push(repo);
+        } else {
+            stale.push((repo_path, fingerprint));
+        }
+    }
+    (unchanged, stale)
+}
+
+/// Scan each checkout on the worker pool. Fingerprints are taken before the
+/// scan so the cache records the state that was actually read. Output follows
+/// input order.
+fn rescan(
+    pool: &ThreadPool,
+    checkouts: Vec<(PathBuf, RepoFingerprint)>,
+    config: &ScanConfig,
+    quiet: bool,
+) -> Vec<(ScanResult, RepoFingerprint)> {
+    let (repo_paths, fingerprints): (Vec<PathBuf>, Vec<RepoFingerprint>) =
+        checkouts.into_iter().unzip();
+
+    let progress = Progress::new("Scanning", repo_paths.len(), quiet);
+    let results = scan_repos(pool, repo_paths, config, &progress);
+    progress.finish();
+
+    let mut scanned = Vec::new();
+    for ((repo_path, result), fingerprint) in results.into_iter().zip(fingerprints) {
+        match result {
+            Ok(result) => scanned.push((result, fingerprint)),
+            Err(err) => warn!("Failed to scan repository {}: {}", repo_path.display(), err),
+        }
+    }
+    scanned
+}
+
+/// Upsert rescanned repos into the graph, keeping each repo's original import
+/// time. Returns whether any repo was new to the graph.
+fn merge_scanned(graph: &mut EcosystemGraph, scanned: &mut [(ScanResult, RepoFingerprint)]) -> bool {
+    let mut added = false;
+    for (result, _) in scanned {
+        match graph.get_repo(&result.repo.id) {
+            Some(existing) => {
+                // Keep hand-made metadata (and recorded aliases); scanned keys win
+                let mut metadata = existing.metadata.clone();
+                metadata.extend(std::mem::take(&mut result.repo.metadata));
+                result.repo.metadata = metadata;
+                result.repo.imports.imported_at = existing.imports.imported_at;
+            }
+            None => added = true,
+        }
+        graph.add_repo(result.repo.clone());
+
+        // Report any warnings
+        for warning in &result.warnings {
+            eprintln!("  Warning for {}: {}", result.repo.name, warning);
+        }
+    }
+    added
+}
+
+/// Run the enabled edge detectors over `repos` on the worker pool, then sync
+/// the results into the graph in input order.
+fn detect_edges(
+    pool: &ThreadPool,
+    graph: &mut EcosystemGraph,
+    registry: &DetectorRegistry,
+    config: &ScanConfig,
+    repos: &[&Repo],
+    quiet: bool,
+) -> EdgeSync {
+    let index = RepoIndex::from_graph(graph);
+    let progress = Progress::new("Detecting edges", repos.len(), quiet);
+    let detected: Vec<Vec<Edge>> = pool.install(|| {
+        repos
+            .par_iter()
+            .map(|repo| {
+                let edges = repo
+                    .local_path
+                    .as_deref()
+                    .map(|path| registry.detect(repo, path, &index, config))
+                    .unwrap_or_default();
+                progress.tick();
+                edges
+            })
+            .collect()
+    });
+    progress.finish();
+
+    let sources = registry.sources(config);
+    let mut total = EdgeSync::default();
+    for (repo, edges) in repos.iter().zip(detected) {
+        if repo.local_path.is_none() {
+            continue;
+        }
+        let sync = graph.sync_edges_from(&repo.id, &sources, edges);
+        total.added += sync.added;
+        total.updated += sync.updated;
+        total.removed += sync.removed;
+    }
+    total
+}
+
+/// Fold each transferred local repo into its forge repo. Returns whether any
+/// repo was merged.
+fn merge_all(graph: &mut EcosystemGraph, cache: &mut ScanCache, transfers: &[Transfer]) -> Result<bool> {
+    for transfer in transfers {
+        graph.merge_repo(&transfer.local_id, &transfer.forge_id)?;
+        cache.forget_repo(&transfer.local_id);
+    }
+    Ok(!transfers.is_empty())
+}
+
+/// Record freshly scanned checkouts in the cache
+fn record_scans(
+    cache: &mut ScanCache,
+    scanned: &[(ScanResult, RepoFingerprint)],
+    config: &ScanConfig,
+    enabled_detectors: &[String],
+) {
+    let now = Utc::now();
+    for (result, fingerprint) in scanned {
+        let Some(repo_path) = result.repo.local_path.clone() else {
+            continue;
+        };
+        cache.record(
+            repo_path,
+            CacheEntry {
+                repo_id: result.repo.id.clone(),
+                fingerprint: fingerprint.clone(),
+                deep: config.deep,
+                detectors: enabled_detectors.to_vec(),
+                scanned_at: now,
+            },
+        );
+    }
+}
+
+/// Repos previously scanned under `root` whose checkout is gone
+fn find_vanished(graph: &EcosystemGraph, root: &Path) -> Vec<Repo> {
+    graph
+        .repos()
+        .iter()
+        .filter(|r| r.imports.source == SCAN_SOURCE)
+        .filter(|r| {
+            r.imports
+                .path_hint
+                .as_ref()
+                .is_some_and(|p| p.starts_with(root) && !p.join(".git").is_dir())
+        })
+        .cloned()
+        .collect()
+}
+
+/// Print the human-readable scan summary
+fn print_summary(
+    scanned: &[(ScanResult, RepoFingerprint)],
+    unchanged: &[Repo],
+    vanished: &[Repo],
+    prune: bool,
+    edge_sync: Option<EdgeSync>,
+) {
+    println!(
+        "Found {} repositories ({} scanned, {} unchanged):",
+        scanned.len() + unchanged.len(),
+        scanned.len(),
+        unchanged.len()
+    );
+    println!();
+
+    for repo in scanned.iter().map(|(r, _)| &r.repo) {
+        let forge_info = if repo.forge == Forge::Local {
+            "local".to_string()
+        } else {
+            format!("{}/{}", repo.owner, repo.name)
+        };
+
+        println!("  {} [{}:{}]", repo.name, repo.forge.code(), forge_info);
+
+        if !repo.tags.is_empty() {
+            println!("    tags: {}", repo.tags.join(", "));
+        }
+    }
+
+    println!();
+
+    if !vanished.is_empty() {
+        let verb = if prune { "Pruned" } else { "Vanished" };
+        println!("{verb} {} repositories no longer on disk:", vanished.len());
+        for repo in vanished {
+            let hint = repo.imports.path_hint.as_deref().unwrap_or(Path::new("?"));
+            println!("  {} ({})", repo.id, hint.display());
+        }
+        if !prune {
+            println!("  (re-run with --prune to remove them from the graph)");
+        }
+        println!();
+    }
+
+    if let Some(sync) = edge_sync {
+        println!(
+            "Detected edges: {} new, {} updated, {} removed",
+            sync.added, sync.updated, sync.removed
+        );
+    }
+}
+
+/// Print local-only repos that reappeared under a forge ID
+fn print_transfers(transfers: &[Transfer], merged: bool) {
+    if transfers.is_empty() {
+        return;
+    }
+    let verb = if merged { "Merged" } else { "Transferred" };
+    println!("{verb} {} local repositories now known by a forge ID:", transfers.len());
+    for transfer in transfers {
+        println!(
+            "  {} -> {} (same {})",
+            transfer.local_id,
+            transfer.forge_id,
+            transfer.matched_by.replace('-', " ")
+        );
+    }
+    if !merged {
+        println!("  (re-run with --merge-transfers to move their edges, annotations and groups)");
+    }
+    println!();
+}
+
 /// Get the data directory for storing the graph
 fn get_data_dir() -> Result<PathBuf> {
     // Check environment variable first
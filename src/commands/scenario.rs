@@ -1,15 +1,102 @@
 // SPDX-License-Identifier: MPL-2.0
 // SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
-//! Scenario management commands - create, compare, and switch between scenarios
+//! Scenario management commands - create, compare, edit, promote and merge scenarios
 
+use crate::commands::diff::print_sections;
+use crate::commands::{aspect, edge, group};
 use crate::graph::EcosystemGraph;
+use crate::scenario::{self as scenarios, Outcome, ScenarioComparison};
 use crate::types::{ChangeSet, Scenario};
 use anyhow::{Context, Result};
 use chrono::Utc;
-use std::path::PathBuf;
+use clap::{Parser, Subcommand};
+use std::path::{Path, PathBuf};
+
+/// `scenario edit <name> <op> [flags]`
+#[derive(Parser)]
+#[command(name = "scenario edit <name>", no_binary_name = true)]
+struct EditCommand {
+    #[command(subcommand)]
+    op: EditOp,
+}
+
+/// A change to record in a scenario
+#[derive(Subcommand)]
+enum EditOp {
+    /// Add an edge
+    AddEdge {
+        /// Source repository (name or ID)
+        #[arg(long)]
+        from: String,
+        /// Target repository (name or ID)
+        #[arg(long)]
+        to: String,
+        /// Relationship type: uses, provides, extends, mirrors, replaces
+        #[arg(long, default_value = "uses")]
+        rel: String,
+        /// Channel type: api, artifact, config, runtime, human, unknown
+        #[arg(long)]
+        channel: Option<String>,
+        /// Human-readable label
+        #[arg(long)]
+        label: Option<String>,
+        /// Evidence reference
+        #[arg(long)]
+        evidence: Option<String>,
+    },
+    /// Remove the edges between two repositories
+    RemoveEdge {
+        /// Source repository (name or ID)
+        #[arg(long)]
+        from: String,
+        /// Target repository (name or ID)
+        #[arg(long)]
+        to: String,
+    },
+    /// Add or replace an aspect annotation
+    Annotate {
+        /// Target repository or edge (name or ID)
+        #[arg(long)]
+        target: String,
+        /// Aspect name
+        #[arg(long)]
+        aspect: String,
+        /// Weight (0-3)
+        #[arg(long)]
+        weight: Option<u8>,
+        /// Polarity: risk, strength, neutral
+        #[arg(long)]
+        polarity: Option<String>,
+        /// Reason for annotation
+        #[arg(long)]
+        reason: Option<String>,
+        /// Evidence reference
+        #[arg(long)]
+        evidence: Option<String>,
+    },
+    /// Remove an aspect annotation
+    Unannotate {
+        /// Target repository or edge (name or ID)
+        #[arg(long)]
+        target: String,
+        /// Aspect name
+        #[arg(long)]
+        aspect: String,
+    },
+    /// Set a group's members
+    SetGroup {
+        /// Group name
+        group: String,
+        /// The new member list
+        repos: Vec<String>,
+    },
+}
 
 /// Run scenario command
-pub fn run(action: &str, name: Option<String>, base: Option<String>) -> Result<()> {
+///
+/// `args` holds what follows the name: the change for `edit`, the scenario
+/// to merge in for `merge`.
+pub fn run(action: &str, name: Option<String>, base: Option<String>, args: &[String], json: bool) -> Result<()> {
     let data_dir = get_data_dir()?;
     let mut graph = EcosystemGraph::load(&data_dir)
         .with_context(|| format!("Failed to load graph from {}", data_dir.display()))?;
@@ -17,47 +104,7 @@ pub fn run(action: &str, name: Option<String>, base: Option<String>) -> Result<(
     match action {
         "create" | "new" => {
             let name = name.ok_or_else(|| anyhow::anyhow!("Scenario name is required"))?;
-            let scenario_id = format!("scenario:{}", slug(&name));
-
-            // Check if scenario already exists
-            if graph.store.scenarios.iter().any(|s| s.id == scenario_id) {
-                anyhow::bail!("Scenario already exists: {}", name);
-            }
-
-            // Validate base scenario if provided
-            if let Some(ref base_name) = base {
-                let base_id = format!("scenario:{}", slug(base_name));
-                if !graph.store.scenarios.iter().any(|s| s.id == base_id) {
-                    anyhow::bail!("Base scenario not found: {}", base_name);
-                }
-            }
-
-            let base_id = base.as_ref().map(|b| format!("scenario:{}", slug(b)));
-
-            let scenario = Scenario {
-                kind: "Scenario".into(),
-                id: scenario_id.clone(),
-                name: name.to_string(),
-                base: base_id,
-                description: None,
-                created_at: Utc::now(),
-            };
-
-            // Create empty changeset for this scenario
-            let changeset = ChangeSet {
-                kind: "ChangeSet".into(),
-                scenario_id: scenario_id.clone(),
-                ops: vec![],
-            };
-
-            graph.store.scenarios.push(scenario);
-            graph.store.changesets.push(changeset);
-            graph.save(&data_dir)?;
-
-            println!("Created scenario: {} ({})", name, scenario_id);
-            if let Some(b) = base {
-                println!("  base: {}", b);
-            }
+            create_scenario(&mut graph, &data_dir, &name, base)?;
         }
 
         "delete" | "rm" => {
@@ -93,141 +140,59 @@ pub fn run(action: &str, name: Option<String>, base: Option<String>) -> Result<(
             }
         }
 
-        "list" | "ls" => {
-            if graph.store.scenarios.is_empty() {
-                println!("No scenarios defined. Use 'reposystem scenario create <name>' to create one.");
-                return Ok(());
-            }
-
-            println!("Scenarios ({}):", graph.store.scenarios.len());
-            for scenario in &graph.store.scenarios {
-                let ops_count = graph
-                    .store
-                    .changesets
-                    .iter()
-                    .find(|c| c.scenario_id == scenario.id)
-                    .map(|c| c.ops.len())
-                    .unwrap_or(0);
-
-                let base_info = scenario
-                    .base
-                    .as_ref()
-                    .map(|b| format!(" (base: {})", b.replace("scenario:", "")))
-                    .unwrap_or_default();
-
-                println!("  {}{} - {} ops", scenario.name, base_info, ops_count);
-            }
-        }
+        "list" | "ls" => list_scenarios(&graph),
 
         "show" => {
             let name = name.ok_or_else(|| anyhow::anyhow!("Scenario name is required"))?;
-            let scenario_id = format!("scenario:{}", slug(&name));
-
-            let scenario = graph
-                .store
-                .scenarios
-                .iter()
-                .find(|s| s.id == scenario_id || s.name == name)
-                .ok_or_else(|| anyhow::anyhow!("Scenario not found: {}", name))?;
-
-            let changeset = graph
-                .store
-                .changesets
-                .iter()
-                .find(|c| c.scenario_id == scenario.id);
+            show_scenario(&graph, &name)?;
+        }
 
-            println!("Scenario: {}", scenario.name);
-            println!("  id: {}", scenario.id);
-            if let Some(base) = &scenario.base {
-                println!("  base: {}", base);
-            }
-            if let Some(desc) = &scenario.description {
-                println!("  description: {}", desc);
-            }
-            println!("  created: {}", scenario.created_at.format("%Y-%m-%d %H:%M:%S"));
-
-            if let Some(cs) = changeset {
-                println!("  operations ({}):", cs.ops.len());
-                for op in &cs.ops {
-                    match op {
-                        crate::types::ChangeOp::AddEdge { edge } => {
-                            println!("    + edge: {} -> {}", edge.from, edge.to);
-                        }
-                        crate::types::ChangeOp::RemoveEdge { edge_id } => {
-                            println!("    - edge: {}", edge_id);
-                        }
-                        crate::types::ChangeOp::AddAnnotation { annotation } => {
-                            println!("    + annotation: {} on {}", annotation.aspect_id, annotation.target);
-                        }
-                        crate::types::ChangeOp::RemoveAnnotation { annotation_id } => {
-                            println!("    - annotation: {}", annotation_id);
-                        }
-                        crate::types::ChangeOp::SetGroupMembership { group_id, members } => {
-                            println!("    ~ group {}: {} members", group_id, members.len());
-                        }
-                    }
-                }
-            }
+        "edit" => {
+            let name = name.ok_or_else(|| anyhow::anyhow!("Scenario name is required for edit"))?;
+            edit_scenario(&graph, &name, args)?;
         }
 
         "compare" => {
-            // Compare two scenarios (name is scenario A, base is scenario B)
+            // Compare the effective graphs of two scenarios (name is A, base
+            // is B, default the baseline)
             let name = name.ok_or_else(|| anyhow::anyhow!("Scenario name is required for compare"))?;
-            let scenario_a_id = format!("scenario:{}", slug(&name));
-            let scenario_b_id = base
-                .map(|b| format!("scenario:{}", slug(&b)))
-                .unwrap_or_else(|| "baseline".into());
+            let b = base.as_deref().unwrap_or("baseline");
+            let comparison = ScenarioComparison::new(&graph, &scenario_ref(&graph, &name), &scenario_ref(&graph, b))?;
 
-            let scenario_a = graph
-                .store
-                .scenarios
-                .iter()
-                .find(|s| s.id == scenario_a_id || s.name == name);
-
-            let changeset_a = scenario_a.and_then(|s| {
-                graph
-                    .store
-                    .changesets
-                    .iter()
-                    .find(|c| c.scenario_id == s.id)
-            });
-
-            let changeset_b = if scenario_b_id == "baseline" {
-                None
+            if json {
+                println!("{}", serde_json::to_string_pretty(&comparison)?);
             } else {
-                graph
-                    .store
-                    .scenarios
-                    .iter()
-                    .find(|s| s.id == scenario_b_id)
-                    .and_then(|s| {
-                        graph
-                            .store
-                            .changesets
-                            .iter()
-                            .find(|c| c.scenario_id == s.id)
-                    })
-            };
-
-            let ops_a = changeset_a.map(|c| c.ops.len()).unwrap_or(0);
-            let ops_b = changeset_b.map(|c| c.ops.len()).unwrap_or(0);
+                print_comparison(&comparison, &name, b);
+            }
+        }
 
-            println!("Comparing scenarios:");
-            println!("  A: {} ({} ops)", name, ops_a);
-            println!("  B: {} ({} ops)", scenario_b_id.replace("scenario:", ""), ops_b);
-            println!();
+        "promote" => {
+            let name = name.ok_or_else(|| anyhow::anyhow!("Scenario name is required for promote"))?;
+            let applied_by = std::env::var("USER").unwrap_or_else(|_| "unknown".into());
+            let scenario = scenario_ref(&graph, &name);
+            let outcome = scenarios::promote(&mut graph, &scenario, &applied_by)?;
+            if outcome.conflicts.is_empty() {
+                graph.save(&data_dir)?;
+            }
+            report(&outcome, json, &format!("Promoted scenario {name} to the baseline"), "the baseline is unchanged")?;
+        }
 
-            if ops_a == 0 && ops_b == 0 {
-                println!("Both scenarios have no operations (same as baseline).");
-            } else {
-                println!("Operations in A but not in baseline: {}", ops_a);
-                println!("Operations in B but not in baseline: {}", ops_b);
+        "merge" => {
+            let into = name.ok_or_else(|| anyhow::anyhow!("Usage: scenario merge <into> <from>"))?;
+            let [from] = args else {
+                anyhow::bail!("Usage: scenario merge <into> <from>");
+            };
+            let (a, b) = (scenario_ref(&graph, &into), scenario_ref(&graph, from));
+            let outcome = scenarios::merge(&mut graph, &a, &b)?;
+            if outcome.conflicts.is_empty() {
+                graph.save(&data_dir)?;
             }
+            report(&outcome, json, &format!("Merged scenario {from} into {into}"), &format!("{into} is unchanged"))?;
         }
 
         other => {
             anyhow::bail!(
-                "Unknown action: {}. Valid: create, delete, list, show, compare",
+                "Unknown action: {}. Valid: create, delete, list, show, compare, edit, promote, merge",
                 other
             );
         }
@@ -236,6 +201,248 @@ pub fn run(action: &str, name: Option<String>, base: Option<String>) -> Result<(
     Ok(())
 }
 
+/// Create an empty scenario, optionally on top of a base scenario
+fn create_scenario(graph: &mut EcosystemGraph, data_dir: &Path, name: &str, base: Option<String>) -> Result<()> {
+    let scenario_id = format!("scenario:{}", slug(name));
+
+    // Check if scenario already exists
+    if graph.store.scenarios.iter().any(|s| s.id == scenario_id) {
+        anyhow::bail!("Scenario already exists: {}", name);
+    }
+
+    // Validate base scenario if provided
+    if let Some(ref base_name) = base {
+        let base_id = format!("scenario:{}", slug(base_name));
+        if !graph.store.scenarios.iter().any(|s| s.id == base_id) {
+            anyhow::bail!("Base scenario not found: {}", base_name);
+        }
+    }
+
+    let base_id = base.as_ref().map(|b| format!("scenario:{}", slug(b)));
+
+    let scenario = Scenario {
+        kind: "Scenario".into(),
+        id: scenario_id.clone(),
+        name: name.to_string(),
+        base: base_id,
+        description: None,
+        created_at: Utc::now(),
+    };
+
+    // Create empty changeset for this scenario
+    let changeset = ChangeSet {
+        kind: "ChangeSet".into(),
+        scenario_id: scenario_id.clone(),
+        ops: vec![],
+    };
+
+    graph.store.scenarios.push(scenario);
+    graph.store.changesets.push(changeset);
+    graph.save(data_dir)?;
+
+    println!("Created scenario: {} ({})", name, scenario_id);
+    if let Some(b) = base {
+        println!("  base: {}", b);
+    }
+    Ok(())
+}
+
+/// Print every scenario with its base and number of ops
+fn list_scenarios(graph: &EcosystemGraph) {
+    if graph.store.scenarios.is_empty() {
+        println!("No scenarios defined. Use 'reposystem scenario create <name>' to create one.");
+        return;
+    }
+
+    println!("Scenarios ({}):", graph.store.scenarios.len());
+    for scenario in &graph.store.scenarios {
+        let ops_count = graph
+            .store
+            .changesets
+            .iter()
+            .find(|c| c.scenario_id == scenario.id)
+            .map(|c| c.ops.len())
+            .unwrap_or(0);
+
+        let base_info = scenario
+            .base
+            .as_ref()
+            .map(|b| format!(" (base: {})", b.replace("scenario:", "")))
+            .unwrap_or_default();
+
+        println!("  {}{} - {} ops", scenario.name, base_info, ops_count);
+    }
+}
+
+/// Print a scenario and the ops in its change set
+fn show_scenario(graph: &EcosystemGraph, name: &str) -> Result<()> {
+    let scenario_id = format!("scenario:{}", slug(name));
+
+    let scenario = graph
+        .store
+        .scenarios
+        .iter()
+        .find(|s| s.id == scenario_id || s.name == name)
+        .ok_or_else(|| anyhow::anyhow!("Scenario not found: {}", name))?;
+
+    let changeset = graph
+        .store
+        .changesets
+        .iter()
+        .find(|c| c.scenario_id == scenario.id);
+
+    println!("Scenario: {}", scenario.name);
+    println!("  id: {}", scenario.id);
+    if let Some(base) = &scenario.base {
+        println!("  base: {}", base);
+    }
+    if let Some(desc) = &scenario.description {
+        println!("  description: {}", desc);
+    }
+    println!("  created: {}", scenario.created_at.format("%Y-%m-%d %H:%M:%S"));
+
+    if let Some(cs) = changeset {
+        println!("  operations ({}):", cs.ops.len());
+        for op in &cs.ops {
+            match op {
+                crate::types::ChangeOp::AddEdge { edge } => {
+                    println!("    + edge: {} -> {}", edge.from, edge.to);
+                }
+                crate::types::ChangeOp::RemoveEdge { edge_id } => {
+                    println!("    - edge: {}", edge_id);
+                }
+                crate::types::ChangeOp::AddAnnotation { annotation } => {
+                    println!("    + annotation: {} on {}", annotation.aspect_id, annotation.target);
+                }
+                crate::types::ChangeOp::RemoveAnnotation { annotation_id } => {
+                    println!("    - annotation: {}", annotation_id);
+                }
+                crate::types::ChangeOp::SetGroupMembership { group_id, members, .. } => {
+                    println!("    ~ group {}: {} members", group_id, members.len());
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Parse the change given to `edit` and record it in a scenario
+fn edit_scenario(graph: &EcosystemGraph, name: &str, args: &[String]) -> Result<()> {
+    let scenario = graph
+        .find_scenario(&scenario_ref(graph, name))
+        .ok_or_else(|| anyhow::anyhow!("Scenario not found: {name}"))?
+        .name
+        .clone();
+    let command = match EditCommand::try_parse_from(args) {
+        Ok(command) => command,
+        Err(e) if e.kind() == clap::error::ErrorKind::DisplayHelp => {
+            print!("{e}");
+            return Ok(());
+        }
+        Err(e) => anyhow::bail!("{}", e.to_string().trim_start_matches("error: ").trim_end()),
+    };
+    run_edit(command.op, scenario)
+}
+
+/// Record an edit through the edge, aspect and group commands
+fn run_edit(op: EditOp, scenario: String) -> Result<()> {
+    let in_scenario = Some(scenario);
+    match op {
+        EditOp::AddEdge { from, to, rel, channel, label, evidence } => {
+            let args = edge::EdgeArgs { rel: Some(rel), channel, label, evidence, in_scenario };
+            edge::run("add", Some(from), Some(to), args)
+        }
+        EditOp::RemoveEdge { from, to } => {
+            edge::run("remove", Some(from), Some(to), edge::EdgeArgs { in_scenario, ..Default::default() })
+        }
+        EditOp::Annotate { target, aspect, weight, polarity, reason, evidence } => {
+            let args = aspect::AspectArgs { weight, polarity, reason, evidence, in_scenario };
+            aspect::run("tag", Some(target), Some(aspect), args)
+        }
+        EditOp::Unannotate { target, aspect } => {
+            let args = aspect::AspectArgs { in_scenario, ..Default::default() };
+            aspect::run("remove", Some(target), Some(aspect), args)
+        }
+        EditOp::SetGroup { group, repos } => group::run("set", Some(group), repos, in_scenario.as_deref()),
+    }
+}
+
+/// Print a promotion or merge outcome; conflicts are an error
+fn report(outcome: &Outcome, json: bool, done: &str, unchanged: &str) -> Result<()> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(outcome)?);
+    } else if outcome.conflicts.is_empty() {
+        println!("{done} ({} ops)", outcome.applied);
+        if let Some(audit_id) = &outcome.audit_id {
+            println!("  audit: {audit_id}");
+        }
+    } else {
+        println!("Conflicts ({}):", outcome.conflicts.len());
+        for conflict in &outcome.conflicts {
+            println!("  #{} {}: {}", conflict.index, conflict.op, conflict.reason);
+        }
+    }
+    if !outcome.conflicts.is_empty() {
+        anyhow::bail!("{} conflicting op(s); {unchanged}", outcome.conflicts.len());
+    }
+    Ok(())
+}
+
+/// A scenario ID or name as given, or the ID its slug would have;
+/// `baseline` is kept for compare
+fn scenario_ref(graph: &EcosystemGraph, name: &str) -> String {
+    if name == "baseline" || graph.find_scenario(name).is_some() {
+        name.to_string()
+    } else {
+        format!("scenario:{}", slug(name))
+    }
+}
+
+/// Print a comparison: graph changes, then weak links, then aspect weights
+fn print_comparison(comparison: &ScenarioComparison, a: &str, b: &str) {
+    println!("Comparing scenario {a} with {b} (+ only in {a}, - only in {b})");
+    if comparison.is_empty() {
+        println!("No differences: both have the same effective graph.");
+        return;
+    }
+    print_sections(&comparison.diff);
+
+    let links = &comparison.weak_links;
+    if !links.is_empty() {
+        println!("weak links: {} -> {}", links.before, links.after);
+        let severity = |n: u8| "!".repeat(usize::from(n));
+        for (mark, list) in [("+", &links.added), ("-", &links.removed)] {
+            for link in list {
+                println!("  {mark} [{}] {} {}: {}", link.aspect, link.target_name, severity(link.severity), link.reason);
+            }
+        }
+        for (old, new) in &links.changed {
+            println!(
+                "  ~ [{}] {} {} -> {}: {}",
+                new.aspect,
+                new.target_name,
+                severity(old.severity),
+                severity(new.severity),
+                new.reason
+            );
+        }
+    }
+
+    if !comparison.risk.is_empty() {
+        println!("aspect weights ({b} -> {a}):");
+        for score in &comparison.risk {
+            println!(
+                "  {}: risk {} -> {}, strength {} -> {}",
+                score.aspect_id.trim_start_matches("aspect:"),
+                score.risk_before,
+                score.risk_after,
+                score.strength_before,
+                score.strength_after
+            );
+        }
+    }
+}
+
 /// Convert a name to a slug for IDs
 fn slug(name: &str) -> String {
     name.to_lowercase()
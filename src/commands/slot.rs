@@ -3,7 +3,8 @@
 //! Slot and provider management commands
 
 use crate::graph::EcosystemGraph;
-use crate::types::{BindingMode, Provider, ProviderType, Slot, SlotBinding};
+use crate::rewrite;
+use crate::types::{BindingMode, BindingTemplate, Provider, ProviderType, RewriteRule, Slot, SlotBinding};
 use anyhow::{Context, Result};
 use chrono::Utc;
 use std::path::PathBuf;
@@ -18,6 +19,33 @@ pub struct SlotArgs {
     pub description: Option<String>,
     /// Required capabilities (comma-separated)
     pub capabilities: Option<String>,
+    /// Binding template file glob
+    pub glob: Option<String>,
+    /// Binding template TOML key path
+    pub toml_key: Option<String>,
+    /// Binding template JSON pointer
+    pub json_pointer: Option<String>,
+    /// Binding template regex
+    pub regex: Option<String>,
+    /// Binding template value or replacement
+    pub value: Option<String>,
+}
+
+impl SlotArgs {
+    /// The binding template described by the template flags
+    fn template(&self) -> Result<BindingTemplate> {
+        let glob = self.glob.clone().ok_or_else(|| anyhow::anyhow!("--glob is required"))?;
+        let value = self.value.clone().ok_or_else(|| anyhow::anyhow!("--value is required"))?;
+        let rule = match (&self.toml_key, &self.json_pointer, &self.regex) {
+            (Some(path), None, None) => RewriteRule::TomlKey { path: path.clone(), value },
+            (None, Some(pointer), None) => RewriteRule::JsonPointer { pointer: pointer.clone(), value },
+            (None, None, Some(pattern)) => RewriteRule::Regex { pattern: pattern.clone(), replacement: value },
+            _ => anyhow::bail!("One of --toml-key, --json-pointer or --regex is required"),
+        };
+        let template = BindingTemplate { glob, rule };
+        rewrite::validate_template(&template)?;
+        Ok(template)
+    }
 }
 
 /// Arguments for provider commands
@@ -80,6 +108,7 @@ pub fn run_slot(action: &str, name: Option<String>, args: SlotArgs) -> Result<()
                 description: args.description.unwrap_or_else(|| format!("{} slot", name)),
                 interface_version: args.version,
                 required_capabilities: capabilities,
+                templates: Vec::new(),
             };
 
             graph.slots.slots.push(slot);
@@ -146,65 +175,133 @@ pub fn run_slot(action: &str, name: Option<String>, args: SlotArgs) -> Result<()
 
         "show" => {
             let name = name.ok_or_else(|| anyhow::anyhow!("Slot name or ID is required"))?;
+            show_slot(&graph, &name)?;
+        }
 
-            let slot = graph.slots.slots.iter()
-                .find(|s| s.id == name || s.name == name || s.id.ends_with(&format!(".{}", name)))
-                .ok_or_else(|| anyhow::anyhow!("Slot not found: {}", name))?;
+        "add-template" => {
+            let name = name.ok_or_else(|| anyhow::anyhow!("Slot name or ID is required"))?;
+            add_template(&mut graph, &name, &args)?;
+            graph.save(&data_dir)?;
+        }
 
-            println!("Slot: {}", slot.name);
-            println!("  id: {}", slot.id);
-            println!("  category: {}", slot.category);
-            println!("  description: {}", slot.description);
-            if let Some(v) = &slot.interface_version {
-                println!("  interface version: {}", v);
-            }
-            if !slot.required_capabilities.is_empty() {
-                println!("  required capabilities: {:?}", slot.required_capabilities);
-            }
+        "remove-template" => {
+            let name = name.ok_or_else(|| anyhow::anyhow!("Slot name or ID is required"))?;
+            let glob = args.glob.ok_or_else(|| anyhow::anyhow!("--glob is required"))?;
+            remove_template(&mut graph, &name, &glob)?;
+            graph.save(&data_dir)?;
+        }
 
-            let providers = graph.slots.providers_for_slot(&slot.id);
-            if providers.is_empty() {
-                println!("  providers: (none)");
-            } else {
-                println!("  providers ({}):", providers.len());
-                for p in providers {
-                    let type_str = match p.provider_type {
-                        ProviderType::Local => "local",
-                        ProviderType::Ecosystem => "ecosystem",
-                        ProviderType::External => "external",
-                        ProviderType::Stub => "stub",
-                    };
-                    let fallback = if p.is_fallback { " [fallback]" } else { "" };
-                    println!("    {} ({}, priority: {}){}", p.name, type_str, p.priority, fallback);
-                }
-            }
+        other => {
+            anyhow::bail!(
+                "Unknown slot action: {other}. Valid: create, delete, list, show, add-template, remove-template"
+            );
+        }
+    }
 
-            let bindings: Vec<_> = graph.slots.bindings.iter()
-                .filter(|b| b.slot_id == slot.id)
-                .collect();
-            if !bindings.is_empty() {
-                println!("  bindings ({}):", bindings.len());
-                for b in bindings {
-                    let consumer_name = graph.get_repo(&b.consumer_id)
-                        .map(|r| r.name.as_str())
-                        .unwrap_or(&b.consumer_id);
-                    let provider = graph.slots.providers.iter()
-                        .find(|p| p.id == b.provider_id)
-                        .map(|p| p.name.as_str())
-                        .unwrap_or(&b.provider_id);
-                    println!("    {} -> {}", consumer_name, provider);
-                }
-            }
+    Ok(())
+}
+
+/// Print a slot with its providers, templates and bindings
+fn show_slot(graph: &EcosystemGraph, name: &str) -> Result<()> {
+    let slot = graph.slots.slots.iter()
+        .find(|s| s.id == name || s.name == name || s.id.ends_with(&format!(".{}", name)))
+        .ok_or_else(|| anyhow::anyhow!("Slot not found: {}", name))?;
+
+    println!("Slot: {}", slot.name);
+    println!("  id: {}", slot.id);
+    println!("  category: {}", slot.category);
+    println!("  description: {}", slot.description);
+    if let Some(v) = &slot.interface_version {
+        println!("  interface version: {}", v);
+    }
+    if !slot.required_capabilities.is_empty() {
+        println!("  required capabilities: {:?}", slot.required_capabilities);
+    }
+
+    let providers = graph.slots.providers_for_slot(&slot.id);
+    if providers.is_empty() {
+        println!("  providers: (none)");
+    } else {
+        println!("  providers ({}):", providers.len());
+        for p in providers {
+            let type_str = match p.provider_type {
+                ProviderType::Local => "local",
+                ProviderType::Ecosystem => "ecosystem",
+                ProviderType::External => "external",
+                ProviderType::Stub => "stub",
+            };
+            let fallback = if p.is_fallback { " [fallback]" } else { "" };
+            println!("    {} ({}, priority: {}){}", p.name, type_str, p.priority, fallback);
         }
+    }
 
-        other => {
-            anyhow::bail!("Unknown slot action: {}. Valid: create, delete, list, show", other);
+    print_templates(graph, slot)
+}
+
+/// Print a slot's binding templates, and its bindings with the files each
+/// template matches in the consumer
+fn print_templates(graph: &EcosystemGraph, slot: &Slot) -> Result<()> {
+    if !slot.templates.is_empty() {
+        println!("  binding templates ({}):", slot.templates.len());
+        for t in &slot.templates {
+            println!("    {}: {}", t.glob, t.rule.description());
         }
     }
 
+    let bindings: Vec<_> = graph.slots.bindings.iter()
+        .filter(|b| b.slot_id == slot.id)
+        .collect();
+    if !bindings.is_empty() {
+        println!("  bindings ({}):", bindings.len());
+        for b in bindings {
+            let consumer_name = graph.get_repo(&b.consumer_id)
+                .map(|r| r.name.as_str())
+                .unwrap_or(&b.consumer_id);
+            let provider = graph.slots.providers.iter()
+                .find(|p| p.id == b.provider_id)
+                .map(|p| p.name.as_str())
+                .unwrap_or(&b.provider_id);
+            println!("    {} -> {}", consumer_name, provider);
+            for (file, template) in rewrite::binding_files(graph, &slot.templates, b)? {
+                println!("      {file} ({})", template.rule.description());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Add the template described by the template flags to a slot
+fn add_template(graph: &mut EcosystemGraph, name: &str, args: &SlotArgs) -> Result<()> {
+    let template = args.template()?;
+    let slot = find_slot_mut(graph, name)?;
+    if slot.templates.contains(&template) {
+        anyhow::bail!("Slot {} already has this template", slot.id);
+    }
+    println!("Added template to {}: {}: {}", slot.id, template.glob, template.rule.description());
+    slot.templates.push(template);
     Ok(())
 }
 
+/// Remove a slot's templates for `glob`
+fn remove_template(graph: &mut EcosystemGraph, name: &str, glob: &str) -> Result<()> {
+    let slot = find_slot_mut(graph, name)?;
+    let before = slot.templates.len();
+    slot.templates.retain(|t| t.glob != glob);
+    let removed = before - slot.templates.len();
+    if removed == 0 {
+        anyhow::bail!("Slot {} has no template for {glob}", slot.id);
+    }
+    println!("Removed {removed} template(s) for {glob} from {}", slot.id);
+    Ok(())
+}
+
+/// Find a slot by ID, name, or `<category>.<name>`
+fn find_slot_mut<'a>(graph: &'a mut EcosystemGraph, name: &str) -> Result<&'a mut Slot> {
+    graph.slots.slots.iter_mut()
+        .find(|s| s.id == name || s.name == name || s.id.ends_with(&format!(".{name}")))
+        .ok_or_else(|| anyhow::anyhow!("Slot not found: {name}"))
+}
+
 /// Run provider command
 pub fn run_provider(action: &str, name: Option<String>, args: ProviderArgs) -> Result<()> {
     let data_dir = get_data_dir()?;
@@ -0,0 +1,124 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//
+//! Snapshot commands - list, restore and prune store snapshots
+
+use crate::graph::EcosystemGraph;
+use crate::snapshot::{self, Retention};
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::Utc;
+use std::path::{Path, PathBuf};
+
+/// Arguments for snapshot commands
+#[derive(Debug, Default)]
+pub struct SnapshotArgs {
+    /// Keep at most this many snapshots (prune, retention)
+    pub keep: Option<usize>,
+    /// Remove snapshots older than this many days (prune, retention)
+    pub max_age_days: Option<u32>,
+    /// Print snapshots as JSON (list)
+    pub json: bool,
+}
+
+/// Run snapshot command
+///
+/// # Errors
+/// Fails on an unknown action, or if the snapshots or stores cannot be
+/// read or written.
+pub fn run(action: &str, id: Option<String>, args: &SnapshotArgs) -> Result<()> {
+    let data_dir = std::env::var("REPOSYSTEM_DATA_DIR").map_or_else(|_| PathBuf::from(".reposystem"), PathBuf::from);
+
+    match action {
+        "list" | "ls" => list_snapshots(&data_dir, args.json),
+        "restore" => restore_snapshot(&data_dir, id),
+        "prune" => prune_snapshots(&data_dir, args),
+        "retention" | "policy" => set_retention(&data_dir, args),
+        _ => bail!("Unknown snapshot action: {action}. Use list, restore, prune, or retention"),
+    }
+}
+
+/// List snapshots, newest first
+fn list_snapshots(data_dir: &Path, json: bool) -> Result<()> {
+    let snapshots = snapshot::list(data_dir)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&snapshots)?);
+        return Ok(());
+    }
+    if snapshots.is_empty() {
+        println!("No snapshots");
+        return Ok(());
+    }
+
+    println!("{:<28} {:<20} REASON", "ID", "TAKEN");
+    println!("{}", "-".repeat(70));
+    for s in &snapshots {
+        println!("{:<28} {:<20} {}", s.id, s.created_at.format("%Y-%m-%d %H:%M:%S"), s.reason);
+    }
+    println!();
+    println!("Total: {} snapshot(s)", snapshots.len());
+    Ok(())
+}
+
+/// Replace the stores with a snapshot's, snapshotting the current ones first
+fn restore_snapshot(data_dir: &Path, id: Option<String>) -> Result<()> {
+    let id = id.ok_or_else(|| anyhow!("Snapshot ID (or 'latest') is required"))?;
+    let target = snapshot::find(data_dir, &id)?;
+    let restored = snapshot::load(data_dir, &target)?;
+
+    let current = EcosystemGraph::load(data_dir).context("Failed to load ecosystem graph")?;
+    let backup = snapshot::take(data_dir, &current, &format!("restore {}", target.id))
+        .context("Failed to snapshot the current stores; nothing was restored")?;
+    restored.save(data_dir).context("Failed to save graph")?;
+
+    println!("Restored snapshot {} (taken before: {})", target.id, target.reason);
+    println!(
+        "  {} repo(s), {} plan(s), {} audit entries",
+        restored.store.repos.len(),
+        restored.plans.plans.len(),
+        restored.audit.entries.len()
+    );
+    println!("Previous stores saved as snapshot {}", backup.id);
+    Ok(())
+}
+
+/// Remove snapshots outside the retention policy, or the limits given
+fn prune_snapshots(data_dir: &Path, args: &SnapshotArgs) -> Result<()> {
+    let mut retention = Retention::load(data_dir)?;
+    if args.keep.is_some() || args.max_age_days.is_some() {
+        retention = Retention {
+            keep: args.keep,
+            max_age_days: args.max_age_days,
+        };
+    }
+    if retention.keep == Some(0) {
+        bail!("--keep must be at least 1");
+    }
+
+    let removed = snapshot::prune(data_dir, &retention, Utc::now())?;
+    for s in &removed {
+        println!("Removed snapshot {} ({})", s.id, s.reason);
+    }
+    println!("Pruned {} snapshot(s)", removed.len());
+    Ok(())
+}
+
+/// Show the retention policy, or set it from the limits given
+fn set_retention(data_dir: &Path, args: &SnapshotArgs) -> Result<()> {
+    let mut retention = Retention::load(data_dir)?;
+    if args.keep.is_some() || args.max_age_days.is_some() {
+        retention.keep = args.keep.or(retention.keep);
+        retention.max_age_days = args.max_age_days.or(retention.max_age_days);
+        retention.save(data_dir)?;
+        println!("Retention policy updated");
+    }
+
+    match retention.keep {
+        Some(keep) => println!("  Keep: newest {keep} snapshot(s)"),
+        None => println!("  Keep: no count limit"),
+    }
+    match retention.max_age_days {
+        Some(days) => println!("  Max age: {days} day(s)"),
+        None => println!("  Max age: no age limit"),
+    }
+    Ok(())
+}
@@ -3,6 +3,7 @@
 //! Interactive TUI view for exploring the ecosystem graph
 
 use crate::graph::EcosystemGraph;
+use crate::query::{Query, Target};
 use anyhow::{Context, Result};
 use crossterm::{
     event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind},
@@ -51,6 +52,15 @@ fn from_index(i: usize) -> Self {
             _ => Tab::Aspects,
         }
     }
+
+    /// The tab that lists what a query selects
+    fn for_target(target: Target) -> Self {
+        match target {
+            Target::Repos => Tab::Repos,
+            Target::Edges => Tab::Edges,
+            Target::Annotations => Tab::Aspects,
+        }
+    }
 }
 
 /// Application state
@@ -59,28 +69,110 @@ struct App {
     current_tab: Tab,
     list_state: ListState,
     should_quit: bool,
+    /// Active filter and the text it was parsed from
+    filter: Option<(String, Query)>,
+    /// Filter being typed after pressing `/`
+    input: Option<String>,
+    /// Last filter error, shown in the help bar
+    error: Option<String>,
+    /// Store positions listed in the current tab
+    visible: Vec<usize>,
+    /// Scenario whose what-if graph is shown, if any
+    scenario: Option<String>,
 }
 
 impl App {
     fn new(graph: EcosystemGraph) -> Self {
         let mut list_state = ListState::default();
         list_state.select(Some(0));
-        Self {
+        let mut app = Self {
             graph,
             current_tab: Tab::Repos,
             list_state,
             should_quit: false,
+            filter: None,
+            input: None,
+            error: None,
+            visible: Vec::new(),
+            scenario: None,
+        };
+        app.refresh();
+        app
+    }
+
+    /// Apply a filter typed in the viewer; an empty filter clears it
+    fn set_filter(&mut self, text: &str) {
+        let text = text.trim();
+        if text.is_empty() {
+            self.filter = None;
+            self.error = None;
+        } else {
+            match Query::parse(text) {
+                Ok(query) => {
+                    self.current_tab = Tab::for_target(query.target);
+                    self.filter = Some((text.to_string(), query));
+                    self.error = None;
+                }
+                Err(e) => self.error = Some(e.to_string()),
+            }
+        }
+        self.list_state.select(Some(0));
+        self.refresh();
+    }
+
+    /// Recompute which store entries the current tab lists
+    fn refresh(&mut self) {
+        let graph = &self.graph;
+        let query = self
+            .filter
+            .as_ref()
+            .map(|(_, q)| q)
+            .filter(|q| Tab::for_target(q.target) == self.current_tab);
+        self.visible = match self.current_tab {
+            Tab::Repos => (0..graph.store.repos.len())
+                .filter(|&i| query.map_or(true, |q| q.matches_repo(graph, &graph.store.repos[i])))
+                .collect(),
+            Tab::Edges => (0..graph.store.edges.len())
+                .filter(|&i| query.map_or(true, |q| q.matches_edge(graph, &graph.store.edges[i])))
+                .collect(),
+            Tab::Groups => (0..graph.store.groups.len()).collect(),
+            Tab::Aspects => (0..graph.aspects.annotations.len())
+                .filter(|&i| {
+                    query.map_or(true, |q| {
+                        q.matches_annotation(graph, &graph.aspects.annotations[i])
+                    })
+                })
+                .collect(),
+        };
+    }
+
+    /// Store position of the selected entry
+    fn selected(&self) -> Option<usize> {
+        self.list_state
+            .selected()
+            .and_then(|i| self.visible.get(i).copied())
+    }
+
+    /// Total entries in the current tab, ignoring the filter
+    fn total(&self) -> usize {
+        match self.current_tab {
+            Tab::Repos => self.graph.store.repos.len(),
+            Tab::Edges => self.graph.store.edges.len(),
+            Tab::Groups => self.graph.store.groups.len(),
+            Tab::Aspects => self.graph.aspects.annotations.len(),
         }
     }
 
     fn next_tab(&mut self) {
         self.current_tab = Tab::from_index(self.current_tab.index() + 1);
         self.list_state.select(Some(0));
+        self.refresh();
     }
 
     fn prev_tab(&mut self) {
         self.current_tab = Tab::from_index(self.current_tab.index().wrapping_sub(1).min(3));
         self.list_state.select(Some(0));
+        self.refresh();
     }
 
     fn next_item(&mut self) {
@@ -108,20 +200,27 @@ fn prev_item(&mut self) {
     }
 
     fn current_list_len(&self) -> usize {
-        match self.current_tab {
-            Tab::Repos => self.graph.store.repos.len(),
-            Tab::Edges => self.graph.store.edges.len(),
-            Tab::Groups => self.graph.store.groups.len(),
-            Tab::Aspects => self.graph.aspects.annotations.len(),
-        }
+        self.visible.len()
     }
 }
 
-/// Launch the interactive TUI viewer
-pub fn run() -> Result<()> {
+/// Launch the interactive TUI viewer, optionally with a query filter applied
+/// and on a scenario's what-if graph
+///
+/// # Errors
+/// Returns an error if the query does not parse, the graph or scenario
+/// cannot be loaded or the terminal cannot be set up.
+pub fn run(query: Option<&str>, scenario: Option<&str>) -> Result<()> {
+    if let Some(text) = query {
+        Query::parse(text)?;
+    }
+
     let data_dir = get_data_dir()?;
-    let graph = EcosystemGraph::load(&data_dir)
+    let mut graph = EcosystemGraph::load(&data_dir)
         .with_context(|| format!("Failed to load graph from {}", data_dir.display()))?;
+    if let Some(name) = scenario {
+        graph = graph.with_scenario(name)?;
+    }
 
     if graph.store.repos.is_empty() {
         println!("No repositories in graph. Run 'reposystem scan' first.");
@@ -137,6 +236,10 @@ pub fn run() -> Result<()> {
 
     // Create app and run
     let mut app = App::new(graph);
+    app.scenario = scenario.map(str::to_string);
+    if let Some(text) = query {
+        app.set_filter(text);
+    }
     let res = run_app(&mut terminal, &mut app);
 
     // Restore terminal
@@ -163,9 +266,29 @@ fn run_app<B: ratatui::backend::Backend>(
         terminal.draw(|f| ui(f, app)).map_err(|e| io::Error::new(io::ErrorKind::Other, format!("{e}")))?;
 
         if let Event::Key(key) = event::read()? {
-            if key.kind == KeyEventKind::Press {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            if let Some(input) = app.input.as_mut() {
+                match key.code {
+                    KeyCode::Enter => {
+                        let text = std::mem::take(input);
+                        app.input = None;
+                        app.set_filter(&text);
+                    }
+                    KeyCode::Esc => app.input = None,
+                    KeyCode::Backspace => {
+                        input.pop();
+                    }
+                    KeyCode::Char(c) => input.push(c),
+                    _ => {}
+                }
+            } else {
                 match key.code {
                     KeyCode::Char('q') | KeyCode::Esc => app.should_quit = true,
+                    KeyCode::Char('/') => {
+                        app.input = Some(app.filter.as_ref().map(|(t, _)| t.clone()).unwrap_or_default());
+                    }
                     KeyCode::Tab | KeyCode::Right => app.next_tab(),
                     KeyCode::BackTab | KeyCode::Left => app.prev_tab(),
                     KeyCode::Down | KeyCode::Char('j') => app.next_item(),
@@ -197,7 +320,10 @@ fn ui(f: &mut Frame, app: &App) {
         .map(|t| Line::from(Span::styled(*t, Style::default().fg(Color::White))))
         .collect();
     let tabs = Tabs::new(titles)
-        .block(Block::default().borders(Borders::ALL).title("Reposystem"))
+        .block(Block::default().borders(Borders::ALL).title(match &app.scenario {
+            Some(name) => format!("Reposystem — scenario: {name}"),
+            None => "Reposystem".to_string(),
+        }))
         .select(app.current_tab.index())
         .style(Style::default().fg(Color::White))
         .highlight_style(
@@ -216,26 +342,36 @@ fn ui(f: &mut Frame, app: &App) {
     render_list(f, app, content_chunks[0]);
     render_detail(f, app, content_chunks[1]);
 
-    // Help
-    let help = Paragraph::new(Line::from(vec![
-        Span::styled("←/→", Style::default().fg(Color::Yellow)),
-        Span::raw(" tabs  "),
-        Span::styled("↑/↓", Style::default().fg(Color::Yellow)),
-        Span::raw(" navigate  "),
-        Span::styled("q", Style::default().fg(Color::Yellow)),
-        Span::raw(" quit"),
-    ]))
-    .block(Block::default().borders(Borders::ALL));
+    // Help (or the filter prompt while typing one)
+    let help_line = if let Some(input) = &app.input {
+        Line::from(vec![
+            Span::styled("/", Style::default().fg(Color::Yellow)),
+            Span::raw(format!("{input}_")),
+        ])
+    } else if let Some(error) = &app.error {
+        Line::from(Span::styled(error.as_str(), Style::default().fg(Color::Red)))
+    } else {
+        Line::from(vec![
+            Span::styled("←/→", Style::default().fg(Color::Yellow)),
+            Span::raw(" tabs  "),
+            Span::styled("↑/↓", Style::default().fg(Color::Yellow)),
+            Span::raw(" navigate  "),
+            Span::styled("/", Style::default().fg(Color::Yellow)),
+            Span::raw(" filter  "),
+            Span::styled("q", Style::default().fg(Color::Yellow)),
+            Span::raw(" quit"),
+        ])
+    };
+    let help = Paragraph::new(help_line).block(Block::default().borders(Borders::ALL));
     f.render_widget(help, chunks[2]);
 }
 
 fn render_list(f: &mut Frame, app: &App, area: Rect) {
     let items: Vec<ListItem> = match app.current_tab {
         Tab::Repos => app
-            .graph
-            .store
-            .repos
+            .visible
             .iter()
+            .map(|&i| &app.graph.store.repos[i])
             .map(|r| {
                 ListItem::new(Line::from(vec![
                     Span::styled(&r.name, Style::default().fg(Color::Cyan)),
@@ -243,10 +379,9 @@ fn render_list(f: &mut Frame, app: &App, area: Rect) {
             })
             .collect(),
         Tab::Edges => app
-            .graph
-            .store
-            .edges
+            .visible
             .iter()
+            .map(|&i| &app.graph.store.edges[i])
             .map(|e| {
                 let from = app
                     .graph
@@ -266,10 +401,9 @@ fn render_list(f: &mut Frame, app: &App, area: Rect) {
             })
             .collect(),
         Tab::Groups => app
-            .graph
-            .store
-            .groups
+            .visible
             .iter()
+            .map(|&i| &app.graph.store.groups[i])
             .map(|g| {
                 ListItem::new(Line::from(vec![
                     Span::styled(&g.name, Style::default().fg(Color::Magenta)),
@@ -278,10 +412,9 @@ fn render_list(f: &mut Frame, app: &App, area: Rect) {
             })
             .collect(),
         Tab::Aspects => app
-            .graph
-            .aspects
-            .annotations
+            .visible
             .iter()
+            .map(|&i| &app.graph.aspects.annotations[i])
             .map(|a| {
                 let target = if a.target.starts_with("repo:") {
                     app.graph
@@ -301,11 +434,17 @@ fn render_list(f: &mut Frame, app: &App, area: Rect) {
             .collect(),
     };
 
-    let title = match app.current_tab {
-        Tab::Repos => format!("Repositories ({})", app.graph.store.repos.len()),
-        Tab::Edges => format!("Edges ({})", app.graph.store.edges.len()),
-        Tab::Groups => format!("Groups ({})", app.graph.store.groups.len()),
-        Tab::Aspects => format!("Annotations ({})", app.graph.aspects.annotations.len()),
+    let label = match app.current_tab {
+        Tab::Repos => "Repositories",
+        Tab::Edges => "Edges",
+        Tab::Groups => "Groups",
+        Tab::Aspects => "Annotations",
+    };
+    let title = match &app.filter {
+        Some((text, query)) if Tab::for_target(query.target) == app.current_tab => {
+            format!("{label} ({}/{}) [{text}]", app.visible.len(), app.total())
+        }
+        _ => format!("{label} ({})", app.total()),
     };
 
     let list = List::new(items)
@@ -321,11 +460,11 @@ fn render_list(f: &mut Frame, app: &App, area: Rect) {
 }
 
 fn render_detail(f: &mut Frame, app: &App, area: Rect) {
-    let selected = app.list_state.selected().unwrap_or(0);
+    let selected = app.selected();
 
     let detail_text: Vec<Line> = match app.current_tab {
         Tab::Repos => {
-            if let Some(repo) = app.graph.store.repos.get(selected) {
+            if let Some(repo) = selected.and_then(|i| app.graph.store.repos.get(i)) {
                 vec![
                     Line::from(vec![
                         Span::styled("Name: ", Style::default().fg(Color::Yellow)),
@@ -366,7 +505,7 @@ fn render_detail(f: &mut Frame, app: &App, area: Rect) {
             }
         }
         Tab::Edges => {
-            if let Some(edge) = app.graph.store.edges.get(selected) {
+            if let Some(edge) = selected.and_then(|i| app.graph.store.edges.get(i)) {
                 let from = app
                     .graph
                     .get_repo(&edge.from)
@@ -408,7 +547,7 @@ fn render_detail(f: &mut Frame, app: &App, area: Rect) {
             }
         }
         Tab::Groups => {
-            if let Some(group) = app.graph.store.groups.get(selected) {
+            if let Some(group) = selected.and_then(|i| app.graph.store.groups.get(i)) {
                 let mut lines = vec![
                     Line::from(vec![
                         Span::styled("Name: ", Style::default().fg(Color::Yellow)),
@@ -438,7 +577,7 @@ fn render_detail(f: &mut Frame, app: &App, area: Rect) {
             }
         }
         Tab::Aspects => {
-            if let Some(ann) = app.graph.aspects.annotations.get(selected) {
+            if let Some(ann) = selected.and_then(|i| app.graph.aspects.annotations.get(i)) {
                 let target = if ann.target.starts_with("repo:") {
                     app.graph
                         .get_repo(&ann.target)
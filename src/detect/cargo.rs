@@ -0,0 +1,70 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! `Cargo.toml` path and git dependencies
+
+use super::{edges_from_refs, read_manifest, DependencyRef, EdgeDetector, RepoIndex};
+use crate::types::{Edge, Repo};
+use std::path::Path;
+
+const FILE: &str = "Cargo.toml";
+const DEP_TABLES: &[&str] = &["dependencies", "dev-dependencies", "build-dependencies"];
+
+/// Path and git dependencies in `Cargo.toml`
+pub struct CargoDetector;
+
+impl EdgeDetector for CargoDetector {
+    fn name(&self) -> &'static str {
+        "cargo"
+    }
+
+    fn detect(&self, repo: &Repo, path: &Path, index: &RepoIndex) -> Vec<Edge> {
+        edges_from_refs(&repo.id, detect(path, index))
+    }
+}
+
+fn detect(root: &Path, index: &RepoIndex) -> Vec<DependencyRef> {
+    let Some(content) = read_manifest(root, FILE) else {
+        return vec![];
+    };
+    let manifest: toml::Table = match toml::from_str(&content) {
+        Ok(t) => t,
+        Err(err) => {
+            tracing::warn!("Failed to parse {}: {}", root.join(FILE).display(), err);
+            return vec![];
+        }
+    };
+
+    // [dependencies], [workspace.dependencies], [target.'cfg(..)'.dependencies]
+    let mut tables: Vec<&toml::Table> = DEP_TABLES
+        .iter()
+        .filter_map(|t| manifest.get(*t).and_then(toml::Value::as_table))
+        .collect();
+    if let Some(ws) = manifest.get("workspace").and_then(toml::Value::as_table) {
+        tables.extend(ws.get("dependencies").and_then(toml::Value::as_table));
+    }
+    if let Some(targets) = manifest.get("target").and_then(toml::Value::as_table) {
+        for target in targets.values().filter_map(toml::Value::as_table) {
+            tables.extend(
+                DEP_TABLES
+                    .iter()
+                    .filter_map(|t| target.get(*t).and_then(toml::Value::as_table)),
+            );
+        }
+    }
+
+    let mut hits = Vec::new();
+    for spec in tables.iter().flat_map(|t| t.values()).filter_map(toml::Value::as_table) {
+        if let Some(path) = spec.get("path").and_then(toml::Value::as_str) {
+            if let Some(target) = index.resolve_path(root, path) {
+                hits.push(DependencyRef::new("cargo", FILE, &content, path, target, 0.95));
+                continue;
+            }
+        }
+        if let Some(git) = spec.get("git").and_then(toml::Value::as_str) {
+            if let Some(target) = index.resolve_url(git) {
+                hits.push(DependencyRef::new("cargo", FILE, &content, git, target, 0.9));
+            }
+        }
+    }
+    hits
+}
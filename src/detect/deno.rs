@@ -0,0 +1,65 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! `deno.json` / `deno.jsonc` import-map entries
+
+use super::{edges_from_refs, read_manifest, DependencyRef, EdgeDetector, RepoIndex};
+use crate::types::{Edge, Repo};
+use std::path::Path;
+
+const FILES: &[&str] = &["deno.json", "deno.jsonc"];
+
+/// Import-map entries in `deno.json` / `deno.jsonc`
+pub struct DenoDetector;
+
+impl EdgeDetector for DenoDetector {
+    fn name(&self) -> &'static str {
+        "deno"
+    }
+
+    fn detect(&self, repo: &Repo, path: &Path, index: &RepoIndex) -> Vec<Edge> {
+        edges_from_refs(&repo.id, detect(path, index))
+    }
+}
+
+fn detect(root: &Path, index: &RepoIndex) -> Vec<DependencyRef> {
+    let mut hits = Vec::new();
+    for file in FILES {
+        let Some(content) = read_manifest(root, file) else {
+            continue;
+        };
+        let manifest: serde_json::Value = match serde_json::from_str(&strip_line_comments(&content)) {
+            Ok(v) => v,
+            Err(err) => {
+                tracing::warn!("Failed to parse {}: {}", root.join(file).display(), err);
+                continue;
+            }
+        };
+        let Some(imports) = manifest.get("imports").and_then(serde_json::Value::as_object) else {
+            continue;
+        };
+
+        for spec in imports.values().filter_map(serde_json::Value::as_str) {
+            if spec.starts_with("jsr:") || spec.starts_with("npm:") || spec.starts_with("node:") {
+                continue;
+            }
+            let target = if spec.starts_with("./") || spec.starts_with("../") {
+                index.resolve_path(root, spec)
+            } else {
+                index.resolve_url(spec)
+            };
+            if let Some(target) = target {
+                hits.push(DependencyRef::new("deno", file, &content, spec, target, 0.85));
+            }
+        }
+    }
+    hits
+}
+
+/// Drop whole-line `//` comments so `deno.jsonc` parses as JSON.
+fn strip_line_comments(content: &str) -> String {
+    content
+        .lines()
+        .map(|l| if l.trim_start().starts_with("//") { "" } else { l })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,62 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! `.gitmodules` submodule URLs
+
+use super::{edges_from_refs, read_manifest, DependencyRef, EdgeDetector, RepoIndex};
+use crate::types::{Edge, Forge, Repo};
+use std::path::Path;
+
+const FILE: &str = ".gitmodules";
+
+/// Submodules whose URL resolves to a repo in the graph
+pub struct GitmodulesDetector;
+
+impl EdgeDetector for GitmodulesDetector {
+    fn name(&self) -> &'static str {
+        "git-submodule"
+    }
+
+    fn detect(&self, repo: &Repo, path: &Path, index: &RepoIndex) -> Vec<Edge> {
+        edges_from_refs(&repo.id, detect(repo, path, index))
+    }
+}
+
+fn detect(repo: &Repo, root: &Path, index: &RepoIndex) -> Vec<DependencyRef> {
+    let Some(content) = read_manifest(root, FILE) else {
+        return vec![];
+    };
+
+    let mut hits = Vec::new();
+    for line in content.lines() {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        if key.trim() != "url" {
+            continue;
+        }
+        let url = value.trim();
+
+        let target = if url.starts_with("../") {
+            resolve_relative(repo, url, index)
+        } else {
+            index.resolve_url(url)
+        };
+        if let Some(target) = target {
+            hits.push(DependencyRef::new("git-submodule", FILE, &content, url, target, 0.9));
+        }
+    }
+    hits
+}
+
+/// Relative submodule URLs (`../sibling.git`) are relative to the
+/// superproject's own remote, i.e. a sibling under the same forge owner.
+fn resolve_relative<'a>(repo: &Repo, url: &str, index: &'a RepoIndex) -> Option<&'a str> {
+    if repo.forge == Forge::Local {
+        return None;
+    }
+    let name = url.strip_prefix("../")?.trim_end_matches(".git");
+    if name.contains('/') {
+        return None;
+    }
+    index.resolve_forge(repo.forge, &repo.owner, name)
+}
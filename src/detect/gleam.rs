@@ -0,0 +1,55 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! `gleam.toml` path and git dependencies
+
+use super::{edges_from_refs, read_manifest, DependencyRef, EdgeDetector, RepoIndex};
+use crate::types::{Edge, Repo};
+use std::path::Path;
+
+const FILE: &str = "gleam.toml";
+
+/// Path and git dependencies in `gleam.toml`
+pub struct GleamDetector;
+
+impl EdgeDetector for GleamDetector {
+    fn name(&self) -> &'static str {
+        "gleam"
+    }
+
+    fn detect(&self, repo: &Repo, path: &Path, index: &RepoIndex) -> Vec<Edge> {
+        edges_from_refs(&repo.id, detect(path, index))
+    }
+}
+
+fn detect(root: &Path, index: &RepoIndex) -> Vec<DependencyRef> {
+    let Some(content) = read_manifest(root, FILE) else {
+        return vec![];
+    };
+    let manifest: toml::Table = match toml::from_str(&content) {
+        Ok(t) => t,
+        Err(err) => {
+            tracing::warn!("Failed to parse {}: {}", root.join(FILE).display(), err);
+            return vec![];
+        }
+    };
+
+    let mut hits = Vec::new();
+    let specs = ["dependencies", "dev-dependencies"]
+        .iter()
+        .filter_map(|t| manifest.get(*t).and_then(toml::Value::as_table))
+        .flat_map(|t| t.values())
+        .filter_map(toml::Value::as_table);
+
+    for spec in specs {
+        if let Some(path) = spec.get("path").and_then(toml::Value::as_str) {
+            if let Some(target) = index.resolve_path(root, path) {
+                hits.push(DependencyRef::new("gleam", FILE, &content, path, target, 0.95));
+            }
+        } else if let Some(git) = spec.get("git").and_then(toml::Value::as_str) {
+            if let Some(target) = index.resolve_url(git) {
+                hits.push(DependencyRef::new("gleam", FILE, &content, git, target, 0.9));
+            }
+        }
+    }
+    hits
+}
@@ -0,0 +1,80 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! `go.mod` requires and local `replace` directives
+
+use super::{edges_from_refs, read_manifest, DependencyRef, EdgeDetector, RepoIndex};
+use crate::types::{Edge, Repo};
+use std::path::Path;
+
+const FILE: &str = "go.mod";
+
+/// `require` and local `replace` directives in `go.mod`
+pub struct GoDetector;
+
+impl EdgeDetector for GoDetector {
+    fn name(&self) -> &'static str {
+        "go"
+    }
+
+    fn detect(&self, repo: &Repo, path: &Path, index: &RepoIndex) -> Vec<Edge> {
+        edges_from_refs(&repo.id, detect(path, index))
+    }
+}
+
+fn detect(root: &Path, index: &RepoIndex) -> Vec<DependencyRef> {
+    let Some(content) = read_manifest(root, FILE) else {
+        return vec![];
+    };
+
+    let mut hits = Vec::new();
+    let mut block: Option<&str> = None;
+
+    for raw in content.lines() {
+        let line = raw.split("//").next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line == ")" {
+            block = None;
+            continue;
+        }
+
+        let (directive, rest) = if let Some(b) = block {
+            (b, line)
+        } else if let Some((d, rest)) = line.split_once(char::is_whitespace) {
+            let rest = rest.trim();
+            if rest == "(" {
+                block = Some(d);
+                continue;
+            }
+            (d, rest)
+        } else {
+            continue;
+        };
+
+        match directive {
+            "require" => {
+                let module = rest.split_whitespace().next().unwrap_or("");
+                if let Some(target) = index.resolve_url(module) {
+                    hits.push(DependencyRef::new("go", FILE, &content, module, target, 0.8));
+                }
+            }
+            "replace" => {
+                let Some((_, replacement)) = rest.split_once("=>") else {
+                    continue;
+                };
+                let replacement = replacement.split_whitespace().next().unwrap_or("");
+                let target = if replacement.starts_with("./") || replacement.starts_with("../") {
+                    index.resolve_path(root, replacement)
+                } else {
+                    index.resolve_url(replacement)
+                };
+                if let Some(target) = target {
+                    hits.push(DependencyRef::new("go", FILE, &content, replacement, target, 0.95));
+                }
+            }
+            _ => {}
+        }
+    }
+    hits
+}
@@ -0,0 +1,55 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! `mix.exs` `path:`, `git:` and `github:` dependencies
+
+use super::{edges_from_refs, read_manifest, DependencyRef, EdgeDetector, RepoIndex};
+use crate::types::{Edge, Repo};
+use std::path::Path;
+
+const FILE: &str = "mix.exs";
+
+/// `path:`, `git:` and `github:` dependencies in `mix.exs`
+pub struct MixDetector;
+
+impl EdgeDetector for MixDetector {
+    fn name(&self) -> &'static str {
+        "mix"
+    }
+
+    fn detect(&self, repo: &Repo, path: &Path, index: &RepoIndex) -> Vec<Edge> {
+        edges_from_refs(&repo.id, detect(path, index))
+    }
+}
+
+fn detect(root: &Path, index: &RepoIndex) -> Vec<DependencyRef> {
+    let Some(content) = read_manifest(root, FILE) else {
+        return vec![];
+    };
+
+    let mut hits = Vec::new();
+    for line in content.lines() {
+        if let Some(path) = quoted_after(line, "path:") {
+            if let Some(target) = index.resolve_path(root, path) {
+                hits.push(DependencyRef::new("mix", FILE, &content, path, target, 0.95));
+            }
+        }
+        if let Some(url) = quoted_after(line, "git:") {
+            if let Some(target) = index.resolve_url(url) {
+                hits.push(DependencyRef::new("mix", FILE, &content, url, target, 0.9));
+            }
+        }
+        if let Some(slug) = quoted_after(line, "github:") {
+            if let Some(target) = index.resolve_url(&format!("github:{slug}")) {
+                hits.push(DependencyRef::new("mix", FILE, &content, slug, target, 0.9));
+            }
+        }
+    }
+    hits
+}
+
+/// Extract the string literal following `key` on a line, e.g. `path: "../x"`.
+fn quoted_after<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    let rest = &line[line.find(key)? + key.len()..];
+    let rest = rest.trim_start().strip_prefix('"')?;
+    rest.split('"').next()
+}
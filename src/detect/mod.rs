@@ -0,0 +1,610 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Dependency-edge inference from manifest files.
+//!
+//! A deep scan reads each discovered repo's manifests (`Cargo.toml`,
+//! `package.json`, `deno.json`, `go.mod`, `mix.exs`, `gleam.toml`,
+//! `.gitmodules`), resolves local-path and forge-URL dependencies against the
+//! repos already in the graph, and emits `uses` edges stamped
+//! `created_by = "auto-detect:<detector>"` with `file:<line>` evidence. Dependencies that
+//! do not resolve to a known repo are ignored — the graph only records
+//! relationships *inside* the estate.
+//!
+//! Each manifest format is an [`EdgeDetector`]. The built-in detectors are
+//! registered by [`DetectorRegistry::builtin`]; in-house formats can be added
+//! with [`DetectorRegistry::register`] without touching the scanner, and
+//! [`ScanConfig`](crate::scanner::ScanConfig) enables or disables detectors by
+//! name.
+
+mod cargo;
+mod deno;
+mod gitmodules;
+mod go;
+mod gleam;
+mod mix;
+mod npm;
+
+pub use cargo::CargoDetector;
+pub use deno::DenoDetector;
+pub use gitmodules::GitmodulesDetector;
+pub use gleam::GleamDetector;
+pub use go::GoDetector;
+pub use mix::MixDetector;
+pub use npm::NpmDetector;
+
+use crate::graph::EcosystemGraph;
+use crate::scanner::{parse_owner_name, ScanConfig};
+use crate::types::{Channel, Edge, EdgeMeta, Evidence, Forge, RelationType, Repo};
+use chrono::Utc;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// `EdgeMeta.created_by` value for edges inferred from manifests. The
+/// registry appends the detector name (see [`detected_by`]); edges from
+/// older scans carry the bare value.
+pub const AUTO_DETECT: &str = "auto-detect";
+
+/// `EdgeMeta.created_by` value for edges found by the detector `name`.
+#[must_use]
+pub fn detected_by(name: &str) -> String {
+    format!("{AUTO_DETECT}:{name}")
+}
+
+/// Lookup table resolving dependency references to repo IDs in the graph.
+#[derive(Debug, Default, Clone)]
+pub struct RepoIndex {
+    /// Canonical local checkout path -> repo ID
+    by_path: HashMap<PathBuf, String>,
+    /// Lower-cased forge ID -> repo ID
+    by_forge_id: HashMap<String, String>,
+}
+
+impl RepoIndex {
+    /// Build an index over every repo currently in the graph.
+    #[must_use]
+    pub fn from_graph(graph: &EcosystemGraph) -> Self {
+        Self::from_repos(graph.repos())
+    }
+
+    /// Build an index over a set of repos.
+    #[must_use]
+    pub fn from_repos<'a>(repos: impl IntoIterator<Item = &'a Repo>) -> Self {
+        let mut index = Self::default();
+        for repo in repos {
+            index.insert(repo);
+        }
+        index
+    }
+
+    /// Add a repo to the index.
+    pub fn insert(&mut self, repo: &Repo) {
+        for path in [repo.local_path.as_ref(), repo.imports.path_hint.as_ref()]
+            .into_iter()
+            .flatten()
+        {
+            let canonical = path.canonicalize().unwrap_or_else(|_| path.clone());
+            self.by_path.insert(canonical, repo.id.clone());
+        }
+        if repo.forge != Forge::Local {
+            self.by_forge_id.insert(repo.id.to_lowercase(), repo.id.clone());
+        }
+    }
+
+    /// Resolve a filesystem path (relative to `base`) to the repo containing it.
+    ///
+    /// Walks up from the target so that `../other/crates/foo` resolves to the
+    /// repo checked out at `../other`.
+    #[must_use]
+    pub fn resolve_path(&self, base: &Path, relative: &str) -> Option<&str> {
+        let joined = base.join(relative);
+        let target = joined.canonicalize().ok()?;
+        target
+            .ancestors()
+            .find_map(|p| self.by_path.get(p))
+            .map(String::as_str)
+    }
+
+    /// Resolve a forge-hosted repo by its coordinates.
+    #[must_use]
+    pub fn resolve_forge(&self, forge: Forge, owner: &str, name: &str) -> Option<&str> {
+        let id = Repo::forge_id(forge, owner, name).to_lowercase();
+        self.by_forge_id.get(&id).map(String::as_str)
+    }
+
+    /// Resolve a git/forge URL (https, ssh, `git+…`, `github:owner/name`,
+    /// scheme-less `github.com/owner/name`) to a repo ID.
+    #[must_use]
+    pub fn resolve_url(&self, url: &str) -> Option<&str> {
+        let url = normalize_url(url)?;
+        let forge = Forge::from_url(&url)?;
+        let (owner, name) = parse_owner_name(&url)?;
+        self.resolve_forge(forge, &owner, &name)
+    }
+}
+
+/// Normalise the many spellings of a git URL into something
+/// [`parse_owner_name`] understands.
+fn normalize_url(url: &str) -> Option<String> {
+    let url = url.trim();
+    let url = url.strip_prefix("git+").unwrap_or(url);
+    let url = url.split(['#', '?']).next().unwrap_or(url);
+
+    for (prefix, host) in [
+        ("github:", "github.com"),
+        ("gitlab:", "gitlab.com"),
+        ("bitbucket:", "bitbucket.org"),
+    ] {
+        if let Some(rest) = url.strip_prefix(prefix) {
+            return Some(format!("https://{host}/{rest}"));
+        }
+    }
+
+    if let Some(rest) = url.strip_prefix("https://raw.githubusercontent.com/") {
+        return Some(format!("https://github.com/{rest}"));
+    }
+    if let Some(rest) = url.strip_prefix("ssh://") {
+        let rest = rest.split_once('@').map_or(rest, |(_, host)| host);
+        return Some(format!("https://{}", rest.replacen(':', "/", 1)));
+    }
+    if url.starts_with("git@") || url.starts_with("http://") || url.starts_with("https://") {
+        return Some(url.to_string());
+    }
+    if url.contains("://") {
+        return None;
+    }
+    // Scheme-less module paths (go.mod): github.com/owner/name/...
+    Forge::from_url(url).map(|_| format!("https://{url}"))
+}
+
+/// A single resolved dependency reference found in a manifest.
+#[derive(Debug, Clone)]
+pub struct DependencyRef {
+    /// Ecosystem label (cargo, npm, …) — becomes the edge label
+    pub ecosystem: &'static str,
+    /// Resolved target repo ID
+    pub target: String,
+    /// Manifest file path relative to the repo root
+    pub file: String,
+    /// 1-based line number of the reference
+    pub line: usize,
+    /// The manifest line, trimmed
+    pub excerpt: String,
+    /// Confidence in the resolution
+    pub confidence: f64,
+}
+
+impl DependencyRef {
+    /// Build a reference, locating `needle` in `content` for the evidence line.
+    #[must_use]
+    pub fn new(
+        ecosystem: &'static str,
+        file: &str,
+        content: &str,
+        needle: &str,
+        target: &str,
+        confidence: f64,
+    ) -> Self {
+        let (line, excerpt) = locate(content, needle);
+        Self {
+            ecosystem,
+            target: target.to_string(),
+            file: file.to_string(),
+            line,
+            excerpt,
+            confidence,
+        }
+    }
+}
+
+/// Find the first line mentioning `needle`; falls back to line 1.
+fn locate(content: &str, needle: &str) -> (usize, String) {
+    content
+        .lines()
+        .enumerate()
+        .find(|(_, line)| line.contains(needle))
+        .map_or((1, String::new()), |(i, line)| (i + 1, line.trim().to_string()))
+}
+
+/// Read a manifest from the repo root, returning `None` if absent or unreadable.
+#[must_use]
+pub fn read_manifest(root: &Path, file: &str) -> Option<String> {
+    let path = root.join(file);
+    if !path.is_file() {
+        return None;
+    }
+    match std::fs::read_to_string(&path) {
+        Ok(content) => Some(content),
+        Err(err) => {
+            tracing::warn!("Failed to read {}: {}", path.display(), err);
+            None
+        }
+    }
+}
+
+/// A finder for one kind of relationship evidence (usually one manifest format).
+///
+/// Detectors must be cheap to share across threads; they receive the repo being
+/// scanned, its checkout path, and an index of every repo in the graph to
+/// resolve references against.
+pub trait EdgeDetector: Send + Sync {
+    /// Stable name used to enable/disable the detector (e.g. `cargo`)
+    fn name(&self) -> &'static str;
+
+    /// Detect edges originating at `repo`, checked out at `path`.
+    fn detect(&self, repo: &Repo, path: &Path, index: &RepoIndex) -> Vec<Edge>;
+}
+
+/// An ordered collection of edge detectors.
+#[derive(Default)]
+pub struct DetectorRegistry {
+    detectors: Vec<Box<dyn EdgeDetector>>,
+}
+
+impl DetectorRegistry {
+    /// An empty registry.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A registry holding every built-in detector.
+    #[must_use]
+    pub fn builtin() -> Self {
+        let mut registry = Self::new();
+        registry.register(Box::new(CargoDetector));
+        registry.register(Box::new(NpmDetector));
+        registry.register(Box::new(DenoDetector));
+        registry.register(Box::new(GoDetector));
+        registry.register(Box::new(MixDetector));
+        registry.register(Box::new(GleamDetector));
+        registry.register(Box::new(GitmodulesDetector));
+        registry
+    }
+
+    /// Add a detector. A detector with the same name replaces the existing one.
+    pub fn register(&mut self, detector: Box<dyn EdgeDetector>) {
+        if let Some(existing) = self.detectors.iter_mut().find(|d| d.name() == detector.name()) {
+            *existing = detector;
+        } else {
+            self.detectors.push(detector);
+        }
+    }
+
+    /// Names of all registered detectors, in registration order.
+    #[must_use]
+    pub fn names(&self) -> Vec<&'static str> {
+        self.detectors.iter().map(|d| d.name()).collect()
+    }
+
+    /// Run every detector enabled by `config` and merge their edges, each
+    /// stamped with the [`detected_by`] value of the detector that found it.
+    ///
+    /// Output is sorted by edge ID so repeated scans are stable.
+    #[must_use]
+    pub fn detect(&self, repo: &Repo, path: &Path, index: &RepoIndex, config: &ScanConfig) -> Vec<Edge> {
+        let mut edges: Vec<Edge> = self
+            .detectors
+            .iter()
+            .filter(|d| config.detector_enabled(d.name()))
+            .flat_map(|d| {
+                d.detect(repo, path, index).into_iter().map(|mut e| {
+                    e.meta.created_by = detected_by(d.name());
+                    e
+                })
+            })
+            .filter(|e| e.from == repo.id && e.to != repo.id)
+            .collect();
+        edges.sort_by(|a, b| a.id.cmp(&b.id));
+        edges.dedup_by(|a, b| a.id == b.id);
+        edges
+    }
+
+    /// The `created_by` values of the edges a scan with `config` re-detects:
+    /// one per enabled detector, plus the bare [`AUTO_DETECT`] of older scans
+    /// when every detector is enabled.
+    #[must_use]
+    pub fn sources(&self, config: &ScanConfig) -> Vec<String> {
+        let enabled: Vec<&str> = self.names().into_iter().filter(|n| config.detector_enabled(n)).collect();
+        let mut sources: Vec<String> = enabled.iter().map(|n| detected_by(n)).collect();
+        if enabled.len() == self.detectors.len() {
+            sources.push(AUTO_DETECT.to_string());
+        }
+        sources
+    }
+}
+
+/// Detect dependency edges for a repo with every built-in detector enabled.
+#[must_use]
+pub fn detect_edges(repo: &Repo, path: &Path, index: &RepoIndex) -> Vec<Edge> {
+    DetectorRegistry::builtin().detect(repo, path, index, &ScanConfig::default())
+}
+
+/// Turn dependency references into `uses` edges stamped [`AUTO_DETECT`].
+///
+/// One edge is produced per (target repo, ecosystem); multiple references in
+/// the same ecosystem become multiple evidence entries on that edge.
+#[must_use]
+pub fn edges_from_refs(from: &str, refs: Vec<DependencyRef>) -> Vec<Edge> {
+    let mut grouped: BTreeMap<(String, &'static str), Vec<DependencyRef>> = BTreeMap::new();
+    for dep in refs {
+        if dep.target == from {
+            continue;
+        }
+        grouped
+            .entry((dep.target.clone(), dep.ecosystem))
+            .or_default()
+            .push(dep);
+    }
+
+    let now = Utc::now();
+    grouped
+        .into_iter()
+        .map(|((to, ecosystem), refs)| {
+            let mut evidence: Vec<Evidence> = Vec::new();
+            for dep in refs {
+                let reference = format!("{}:{}", dep.file, dep.line);
+                if evidence.iter().any(|e| e.reference == reference) {
+                    continue;
+                }
+                evidence.push(Evidence {
+                    evidence_type: "file".into(),
+                    reference,
+                    excerpt: (!dep.excerpt.is_empty()).then_some(dep.excerpt),
+                    confidence: dep.confidence,
+                });
+            }
+            Edge {
+                kind: "Edge".into(),
+                id: Edge::generate_id(from, &to, RelationType::Uses, Channel::Artifact, Some(ecosystem)),
+                from: from.to_string(),
+                to,
+                rel: RelationType::Uses,
+                channel: Channel::Artifact,
+                label: Some(ecosystem.to_string()),
+                evidence,
+                meta: EdgeMeta {
+                    created_by: AUTO_DETECT.into(),
+                    created_at: now,
+                },
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ImportMeta, Visibility};
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn make_repo(forge: Forge, owner: &str, name: &str, path: Option<&Path>) -> Repo {
+        let id = match path {
+            Some(p) if forge == Forge::Local => Repo::local_id(p),
+            _ => Repo::forge_id(forge, owner, name),
+        };
+        Repo {
+            kind: "Repo".into(),
+            id,
+            forge,
+            owner: owner.into(),
+            name: name.into(),
+            default_branch: "main".into(),
+            visibility: Visibility::Public,
+            tags: vec![],
+            estate: crate::types::default_estate(),
+            metadata: HashMap::default(),
+            imports: ImportMeta {
+                source: "test".into(),
+                path_hint: path.map(Path::to_path_buf),
+                imported_at: Utc::now(),
+            },
+            local_path: path.map(Path::to_path_buf),
+        }
+    }
+
+    #[test]
+    fn test_normalize_url_variants() {
+        let index = RepoIndex::from_repos(&[make_repo(Forge::GitHub, "acme", "core", None)]);
+        for url in [
+            "https://github.com/acme/core.git",
+            "git@github.com:acme/core.git",
+            "git+https://github.com/acme/core#v1",
+            "github:acme/core",
+            "ssh://git@github.com/acme/core.git",
+            "github.com/acme/core/v2",
+            "https://raw.githubusercontent.com/acme/core/main/mod.ts",
+            "https://github.com/Acme/Core",
+        ] {
+            assert_eq!(index.resolve_url(url), Some("repo:gh:acme/core"), "{url}");
+        }
+        assert_eq!(index.resolve_url("https://example.com/acme/core"), None);
+    }
+
+    #[test]
+    fn test_detects_cargo_and_gitmodules_edges() {
+        let temp = TempDir::new().unwrap();
+        let app = temp.path().join("app");
+        let lib = temp.path().join("lib");
+        fs::create_dir_all(&app).unwrap();
+        fs::create_dir_all(lib.join("crates/inner")).unwrap();
+
+        fs::write(
+            app.join("Cargo.toml"),
+            "[package]\nname = \"app\"\n\n[dependencies]\ninner = { path = \"../lib/crates/inner\" }\ncore = { git = \"https://github.com/acme/core\" }\nserde = \"1\"\n",
+        )
+        .unwrap();
+        fs::write(
+            app.join(".gitmodules"),
+            "[submodule \"vendor/core\"]\n\tpath = vendor/core\n\turl = git@github.com:acme/core.git\n",
+        )
+        .unwrap();
+
+        let app_repo = make_repo(Forge::Local, "local", "app", Some(&app));
+        let lib_repo = make_repo(Forge::Local, "local", "lib", Some(&lib));
+        let core_repo = make_repo(Forge::GitHub, "acme", "core", None);
+        let index = RepoIndex::from_repos([&app_repo, &lib_repo, &core_repo]);
+
+        let edges = detect_edges(&app_repo, &app, &index);
+        assert_eq!(edges.len(), 3);
+
+        let to_lib = edges.iter().find(|e| e.to == lib_repo.id).unwrap();
+        assert_eq!(to_lib.label.as_deref(), Some("cargo"));
+        assert_eq!(to_lib.meta.created_by, "auto-detect:cargo");
+        assert_eq!(to_lib.evidence[0].evidence_type, "file");
+        assert_eq!(to_lib.evidence[0].reference, "Cargo.toml:5");
+
+        let labels: Vec<_> = edges
+            .iter()
+            .filter(|e| e.to == core_repo.id)
+            .filter_map(|e| e.label.as_deref())
+            .collect();
+        assert!(labels.contains(&"cargo"));
+        assert!(labels.contains(&"git-submodule"));
+
+        // Deterministic across runs
+        let again = detect_edges(&app_repo, &app, &index);
+        let ids: Vec<_> = edges.iter().map(|e| &e.id).collect();
+        let ids_again: Vec<_> = again.iter().map(|e| &e.id).collect();
+        assert_eq!(ids, ids_again);
+    }
+
+    #[test]
+    fn test_detects_js_go_and_beam_edges() {
+        let temp = TempDir::new().unwrap();
+        let app = temp.path().join("app");
+        let shared = temp.path().join("shared");
+        fs::create_dir_all(&app).unwrap();
+        fs::create_dir_all(&shared).unwrap();
+
+        fs::write(
+            app.join("package.json"),
+            r#"{"dependencies": {"shared": "file:../shared", "left-pad": "^1.0.0"}, "devDependencies": {"core": "github:acme/core"}}"#,
+        )
+        .unwrap();
+        fs::write(
+            app.join("deno.json"),
+            "{\n  // import map\n  \"imports\": {\"core/\": \"https://raw.githubusercontent.com/acme/core/main/\"}\n}\n",
+        )
+        .unwrap();
+        fs::write(
+            app.join("go.mod"),
+            "module github.com/acme/app\n\nrequire (\n\tgithub.com/acme/core v1.2.0\n\tgolang.org/x/text v0.3.0\n)\n\nreplace github.com/acme/shared => ../shared\n",
+        )
+        .unwrap();
+        fs::write(
+            app.join("mix.exs"),
+            "defp deps do\n  [\n    {:shared, path: \"../shared\"},\n    {:core, github: \"acme/core\"}\n  ]\nend\n",
+        )
+        .unwrap();
+        fs::write(
+            app.join("gleam.toml"),
+            "name = \"app\"\n\n[dependencies]\nshared = { path = \"../shared\" }\n",
+        )
+        .unwrap();
+
+        let app_repo = make_repo(Forge::Local, "local", "app", Some(&app));
+        let shared_repo = make_repo(Forge::Local, "local", "shared", Some(&shared));
+        let core_repo = make_repo(Forge::GitHub, "acme", "core", None);
+        let index = RepoIndex::from_repos([&app_repo, &shared_repo, &core_repo]);
+
+        let edges = detect_edges(&app_repo, &app, &index);
+        let pairs: Vec<(&str, &str)> = edges
+            .iter()
+            .map(|e| (e.to.as_str(), e.label.as_deref().unwrap_or("")))
+            .collect();
+
+        for expected in [
+            (shared_repo.id.as_str(), "npm"),
+            ("repo:gh:acme/core", "npm"),
+            ("repo:gh:acme/core", "deno"),
+            ("repo:gh:acme/core", "go"),
+            (shared_repo.id.as_str(), "go"),
+            (shared_repo.id.as_str(), "mix"),
+            ("repo:gh:acme/core", "mix"),
+            (shared_repo.id.as_str(), "gleam"),
+        ] {
+            assert!(pairs.contains(&expected), "missing {expected:?} in {pairs:?}");
+        }
+        assert_eq!(edges.len(), 8);
+    }
+
+    /// In-house format: one `uses <owner>/<name>` line per dependency.
+    struct UsesFileDetector;
+
+    impl EdgeDetector for UsesFileDetector {
+        fn name(&self) -> &'static str {
+            "uses-file"
+        }
+
+        fn detect(&self, repo: &Repo, path: &Path, index: &RepoIndex) -> Vec<Edge> {
+            let Some(content) = read_manifest(path, "USES") else {
+                return vec![];
+            };
+            let refs = content
+                .lines()
+                .filter_map(|l| l.strip_prefix("uses "))
+                .filter_map(|slug| {
+                    let target = index.resolve_url(&format!("github:{slug}"))?;
+                    Some(DependencyRef::new("uses-file", "USES", &content, slug, target, 0.7))
+                })
+                .collect();
+            edges_from_refs(&repo.id, refs)
+        }
+    }
+
+    #[test]
+    fn test_registry_runs_custom_detectors_and_honours_config() {
+        let temp = TempDir::new().unwrap();
+        let app = temp.path().join("app");
+        fs::create_dir_all(&app).unwrap();
+        fs::write(app.join("USES"), "uses acme/core\n").unwrap();
+        fs::write(
+            app.join("Cargo.toml"),
+            "[dependencies]\ncore = { git = \"https://github.com/acme/core\" }\n",
+        )
+        .unwrap();
+
+        let app_repo = make_repo(Forge::Local, "local", "app", Some(&app));
+        let core_repo = make_repo(Forge::GitHub, "acme", "core", None);
+        let index = RepoIndex::from_repos([&app_repo, &core_repo]);
+
+        let mut registry = DetectorRegistry::builtin();
+        registry.register(Box::new(UsesFileDetector));
+        assert!(registry.names().contains(&"uses-file"));
+
+        let labels = |config: &ScanConfig| -> Vec<String> {
+            let mut labels: Vec<String> = registry
+                .detect(&app_repo, &app, &index, config)
+                .into_iter()
+                .filter_map(|e| e.label)
+                .collect();
+            labels.sort();
+            labels
+        };
+
+        assert_eq!(labels(&ScanConfig::default()), vec!["cargo", "uses-file"]);
+
+        let only_custom = ScanConfig {
+            detectors: Some(vec!["uses-file".into()]),
+            ..Default::default()
+        };
+        assert_eq!(labels(&only_custom), vec!["uses-file"]);
+
+        let skip_custom = ScanConfig {
+            disabled_detectors: vec!["uses-file".into()],
+            ..Default::default()
+        };
+        assert_eq!(labels(&skip_custom), vec!["cargo"]);
+
+        // A partial scan only owns the edges of its detectors
+        assert_eq!(registry.sources(&only_custom), vec!["auto-detect:uses-file"]);
+        let all = registry.sources(&ScanConfig::default());
+        assert!(all.contains(&"auto-detect:cargo".to_string()) && all.contains(&AUTO_DETECT.to_string()));
+
+        // Re-registering a name replaces rather than duplicates
+        let count = registry.names().len();
+        registry.register(Box::new(UsesFileDetector));
+        assert_eq!(registry.names().len(), count);
+    }
+}
@@ -0,0 +1,77 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! `package.json` file/link and git dependencies
+
+use super::{edges_from_refs, read_manifest, DependencyRef, EdgeDetector, RepoIndex};
+use crate::types::{Edge, Repo};
+use std::path::Path;
+
+const FILE: &str = "package.json";
+const DEP_KEYS: &[&str] = &[
+    "dependencies",
+    "devDependencies",
+    "peerDependencies",
+    "optionalDependencies",
+];
+
+/// `file:`/`link:` and git dependencies in `package.json`
+pub struct NpmDetector;
+
+impl EdgeDetector for NpmDetector {
+    fn name(&self) -> &'static str {
+        "npm"
+    }
+
+    fn detect(&self, repo: &Repo, path: &Path, index: &RepoIndex) -> Vec<Edge> {
+        edges_from_refs(&repo.id, detect(path, index))
+    }
+}
+
+fn detect(root: &Path, index: &RepoIndex) -> Vec<DependencyRef> {
+    let Some(content) = read_manifest(root, FILE) else {
+        return vec![];
+    };
+    let manifest: serde_json::Value = match serde_json::from_str(&content) {
+        Ok(v) => v,
+        Err(err) => {
+            tracing::warn!("Failed to parse {}: {}", root.join(FILE).display(), err);
+            return vec![];
+        }
+    };
+
+    let mut hits = Vec::new();
+    for key in DEP_KEYS {
+        let Some(deps) = manifest.get(*key).and_then(serde_json::Value::as_object) else {
+            continue;
+        };
+        for spec in deps.values().filter_map(serde_json::Value::as_str) {
+            if let Some(path) = ["file:", "link:", "portal:"]
+                .iter()
+                .find_map(|p| spec.strip_prefix(p))
+            {
+                if let Some(target) = index.resolve_path(root, path) {
+                    hits.push(DependencyRef::new("npm", FILE, &content, spec, target, 0.95));
+                }
+            } else if let Some(target) = resolve_git_spec(spec, index) {
+                hits.push(DependencyRef::new("npm", FILE, &content, spec, target, 0.9));
+            }
+        }
+    }
+    hits
+}
+
+/// Resolve git-style specs: URLs, `github:owner/name`, and bare `owner/name`.
+fn resolve_git_spec<'a>(spec: &str, index: &'a RepoIndex) -> Option<&'a str> {
+    if spec.starts_with("workspace:") || spec.starts_with("npm:") {
+        return None;
+    }
+    if let Some(target) = index.resolve_url(spec) {
+        return Some(target);
+    }
+    // Bare GitHub shorthand: "owner/name" or "owner/name#ref"
+    let bare = spec.split('#').next().unwrap_or(spec);
+    if !bare.contains(':') && !bare.starts_with(['@', '.', '/']) && bare.matches('/').count() == 1 {
+        return index.resolve_url(&format!("github:{bare}"));
+    }
+    None
+}
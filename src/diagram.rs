@@ -0,0 +1,1001 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Diagram exporters: Mermaid, `PlantUML`, `GraphML` and GEXF
+//!
+//! Every exporter draws the same picture as [`EcosystemGraph::to_dot`]:
+//! repos, external seams, groups as subgraphs, slots, providers and slot
+//! bindings, with relationship edges styled by [`Channel`] through
+//! [`channel_style`]. The graph is first flattened into a small diagram
+//! model so each format only has to render it.
+//!
+//! Mermaid and `PlantUML` can only nest a node in one subgraph, so a repo in
+//! several groups is drawn inside the first. `GraphML` nests it the same way;
+//! GEXF records every group in a `groups` attribute as well.
+//!
+//! DOT and Mermaid can also carry an [`AspectOverlay`]: nodes and edges
+//! filled by their net annotation weight for one aspect, weak-link badges and
+//! a legend.
+
+use crate::analysis::weak_links;
+use crate::graph::EcosystemGraph;
+use crate::types::{BindingMode, Channel, Polarity, ProviderType};
+use anyhow::{bail, Result};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+
+/// Line pattern of an edge
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineStyle {
+    /// Continuous line
+    Solid,
+    /// Long dashes
+    Dashed,
+    /// Dots
+    Dotted,
+}
+
+impl LineStyle {
+    /// Lowercase name, as used by DOT, `PlantUML` and GEXF
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Solid => "solid",
+            Self::Dashed => "dashed",
+            Self::Dotted => "dotted",
+        }
+    }
+}
+
+/// How edges of one channel are drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChannelStyle {
+    /// Stroke colour as RGB
+    pub rgb: (u8, u8, u8),
+    /// Line pattern
+    pub line: LineStyle,
+    /// Stroke width in points
+    pub width: u8,
+}
+
+impl ChannelStyle {
+    /// Colour as `#rrggbb`
+    #[must_use]
+    pub fn hex(&self) -> String {
+        let (r, g, b) = self.rgb;
+        format!("#{r:02x}{g:02x}{b:02x}")
+    }
+}
+
+/// The edge style for a channel, shared by DOT and every diagram exporter
+#[must_use]
+pub fn channel_style(channel: Channel) -> ChannelStyle {
+    let (rgb, line, width) = match channel {
+        Channel::Api => ((0x1f, 0x77, 0xb4), LineStyle::Solid, 2),
+        Channel::Artifact => ((0xff, 0x7f, 0x0e), LineStyle::Solid, 2),
+        Channel::Config => ((0x94, 0x67, 0xbd), LineStyle::Dashed, 1),
+        Channel::Runtime => ((0xd6, 0x27, 0x28), LineStyle::Solid, 3),
+        Channel::Human => ((0x7f, 0x7f, 0x7f), LineStyle::Dotted, 1),
+        Channel::Unknown => ((0xbb, 0xbb, 0xbb), LineStyle::Dashed, 1),
+    };
+    ChannelStyle { rgb, line, width }
+}
+
+/// Style of the provider → slot, provider → repo and consumer → provider edges
+const SATISFIES_STYLE: ChannelStyle = ChannelStyle { rgb: (0x80, 0x80, 0x80), line: LineStyle::Dotted, width: 1 };
+const IMPLEMENTS_STYLE: ChannelStyle = ChannelStyle { rgb: (0x40, 0x40, 0x40), line: LineStyle::Dashed, width: 1 };
+const BINDING_STYLE: ChannelStyle = ChannelStyle { rgb: (0x00, 0x64, 0x00), line: LineStyle::Solid, width: 3 };
+
+// =============================================================================
+// Aspect overlay
+// =============================================================================
+
+/// Fills for net risk 1, 2 and 3+ (red scale)
+const RISK_SCALE: [(u8, u8, u8); 3] = [(0xfc, 0xbb, 0xa1), (0xfb, 0x6a, 0x4a), (0xcb, 0x18, 0x1d)];
+/// Fills for net strength 1, 2 and 3+ (green scale)
+const STRENGTH_SCALE: [(u8, u8, u8); 3] = [(0xc7, 0xe9, 0xc0), (0x74, 0xc4, 0x76), (0x23, 0x8b, 0x45)];
+/// Fill for annotations that cancel out
+const BALANCED: (u8, u8, u8) = (0xe9, 0xec, 0xef);
+/// Legend entries: a score on each step of the scales and its description
+const LEGEND: [(i32, &str); 7] = [
+    (3, "risk 3+"),
+    (2, "risk 2"),
+    (1, "risk 1"),
+    (0, "balanced"),
+    (-1, "strength 1"),
+    (-2, "strength 2"),
+    (-3, "strength 3+"),
+];
+
+fn hex((r, g, b): (u8, u8, u8)) -> String {
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+/// Colour for a net aspect score: red for risk, green for strength, grey
+/// when they cancel out
+#[must_use]
+pub fn heat_colour(score: i32) -> String {
+    let step = |n: i32| usize::try_from(n.min(3) - 1).unwrap_or(0);
+    hex(match score {
+        0 => BALANCED,
+        s if s > 0 => RISK_SCALE[step(s)],
+        s => STRENGTH_SCALE[step(-s)],
+    })
+}
+
+/// One aspect's annotations and weak links, painted onto a diagram
+#[derive(Debug, Clone)]
+pub struct AspectOverlay {
+    /// Aspect ID (`aspect:security`)
+    pub aspect_id: String,
+    /// Display name (`Security`)
+    pub aspect_name: String,
+    /// Risk minus strength weight per annotated repo, seam or edge
+    scores: HashMap<String, i32>,
+    /// Severity of the worst weak link per repo, seam or edge
+    badges: HashMap<String, u8>,
+}
+
+impl AspectOverlay {
+    /// Sum the annotation weights for `aspect` (a name or ID) and find its
+    /// weak links.
+    ///
+    /// Weak links are those [`weak_links`] reports under this aspect, so
+    /// reliability also badges single points of failure and maintainability
+    /// badges edges without evidence.
+    ///
+    /// # Errors
+    /// Fails on an unknown aspect or if weak-link analysis fails.
+    pub fn new(graph: &EcosystemGraph, aspect: &str) -> Result<Self> {
+        let aspect_id = format!("aspect:{}", aspect.trim_start_matches("aspect:").to_lowercase());
+        let Some(found) = graph.aspects.aspects.iter().find(|a| a.id == aspect_id) else {
+            let valid: Vec<_> = graph.aspects.aspects.iter().map(|a| &a.name).collect();
+            bail!("Unknown aspect: {aspect}. Valid: {valid:?}");
+        };
+
+        let mut scores: HashMap<String, i32> = HashMap::new();
+        for ann in graph.aspects.annotations.iter().filter(|a| a.aspect_id == aspect_id) {
+            let weight = i32::from(ann.weight);
+            *scores.entry(ann.target.clone()).or_default() += match ann.polarity {
+                Polarity::Risk => weight,
+                Polarity::Strength => -weight,
+                Polarity::Neutral => 0,
+            };
+        }
+
+        let mut badges: HashMap<String, u8> = HashMap::new();
+        for link in weak_links(graph, Some(&aspect_id))? {
+            if link.aspect.eq_ignore_ascii_case(&found.name) {
+                let badge = badges.entry(link.target_id).or_default();
+                *badge = (*badge).max(link.severity);
+            }
+        }
+
+        Ok(Self { aspect_id, aspect_name: found.name.clone(), scores, badges })
+    }
+
+    /// Net weight on a repo, seam or edge (risk minus strength), if annotated
+    #[must_use]
+    pub fn score(&self, id: &str) -> Option<i32> {
+        self.scores.get(id).copied()
+    }
+
+    /// Severity (1-3) of the worst weak link on a repo, seam or edge
+    #[must_use]
+    pub fn badge(&self, id: &str) -> Option<u8> {
+        self.badges.get(id).copied()
+    }
+
+    /// Fill colour for an annotated repo, seam or edge
+    #[must_use]
+    pub fn colour(&self, id: &str) -> Option<String> {
+        self.score(id).map(heat_colour)
+    }
+
+    /// `label` with a weak-link badge (`⚠ !!`) appended after `sep`
+    #[must_use]
+    pub fn label(&self, id: &str, label: &str, sep: &str) -> String {
+        match self.badge(id) {
+            Some(severity) => format!("{label}{sep}⚠ {}", "!".repeat(usize::from(severity.max(1)))),
+            None => label.to_string(),
+        }
+    }
+
+    /// Extra DOT node attributes: a fill over `style` and a double border
+    /// for weak links
+    #[must_use]
+    pub fn dot_node_attrs(&self, id: &str, style: &str) -> String {
+        let mut attrs = String::new();
+        if let Some(colour) = self.colour(id) {
+            let _ = write!(attrs, ", style=\"{style},filled\", fillcolor=\"{colour}\"");
+        }
+        if self.badge(id).is_some() {
+            attrs.push_str(", peripheries=2");
+        }
+        attrs
+    }
+
+    /// DOT legend cluster: the heat scale and the weak-link badge
+    #[must_use]
+    pub fn dot_legend(&self) -> String {
+        let mut out = format!(
+            "\n  subgraph cluster_legend {{\n    label=\"{} (risk - strength)\";\n    style=rounded;\n    node [shape=box, style=\"rounded,filled\"];\n",
+            self.aspect_name
+        );
+        for (i, (score, text)) in LEGEND.iter().enumerate() {
+            let _ = writeln!(out, "    \"legend_{i}\" [label=\"{text}\", fillcolor=\"{}\"];", heat_colour(*score));
+        }
+        out.push_str("    \"legend_weak\" [label=\"⚠ weak link (! low .. !!! high)\", style=rounded, peripheries=2];\n");
+        // Invisible chain keeps the entries stacked in scale order
+        let ids: Vec<String> = (0..LEGEND.len()).map(|i| format!("\"legend_{i}\"")).collect();
+        let _ = writeln!(out, "    {} -> \"legend_weak\" [style=invis];", ids.join(" -> "));
+        out.push_str("  }\n");
+        out
+    }
+
+    /// Mermaid node fills, badge borders and the legend subgraph
+    fn mermaid_styles(&self, d: &Diagram, out: &mut String) {
+        for (i, n) in d.nodes.iter().enumerate() {
+            let mut style: Vec<String> = self.colour(&n.id).map(|c| format!("fill:{c}")).into_iter().collect();
+            if self.badge(&n.id).is_some() {
+                style.push("stroke:#c92a2a,stroke-width:3px".into());
+            }
+            if !style.is_empty() {
+                let _ = writeln!(out, "  style n{i} {}", style.join(","));
+            }
+        }
+
+        let _ = writeln!(out, "\n  subgraph legend[\"{} (risk - strength)\"]", self.aspect_name.replace('"', "#quot;"));
+        for (i, (_, text)) in LEGEND.iter().enumerate() {
+            let _ = writeln!(out, "    legend{i}[\"{text}\"]");
+        }
+        out.push_str("    legendweak[\"⚠ weak link (! low .. !!! high)\"]\n  end\n");
+        for (i, (score, _)) in LEGEND.iter().enumerate() {
+            let _ = writeln!(out, "  style legend{i} fill:{}", heat_colour(*score));
+        }
+        out.push_str("  style legendweak stroke:#c92a2a,stroke-width:3px\n");
+    }
+}
+
+// =============================================================================
+// Diagram model
+// =============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NodeKind {
+    Repo,
+    Seam,
+    Slot,
+    Provider { fallback: bool },
+    /// Edge endpoint that is not in the graph
+    Unknown,
+}
+
+impl NodeKind {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Repo => "repo",
+            Self::Seam => "seam",
+            Self::Slot => "slot",
+            Self::Provider { .. } => "provider",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+struct Node {
+    id: String,
+    kind: NodeKind,
+    title: String,
+    subtitle: Option<String>,
+    /// Index into `Diagram::groups` of the group this node is drawn in
+    group: Option<usize>,
+    /// Extra attributes for `GraphML` and GEXF (forge, system, …)
+    attrs: Vec<(&'static str, String)>,
+}
+
+struct Link {
+    id: String,
+    from: usize,
+    to: usize,
+    label: Option<String>,
+    /// `uses`, `satisfies`, `implements`, `binding`, …
+    kind: String,
+    channel: Option<Channel>,
+    style: ChannelStyle,
+    /// Satisfies links are undirected
+    arrow: bool,
+}
+
+struct Cluster {
+    id: String,
+    name: String,
+    members: Vec<usize>,
+}
+
+struct Diagram {
+    nodes: Vec<Node>,
+    links: Vec<Link>,
+    groups: Vec<Cluster>,
+}
+
+impl Diagram {
+    fn new(graph: &EcosystemGraph) -> Self {
+        let mut diagram = Self { nodes: Vec::new(), links: Vec::new(), groups: Vec::new() };
+        let mut index: HashMap<String, usize> = HashMap::new();
+
+        for repo in &graph.store.repos {
+            diagram.add_node(&mut index, Node {
+                id: repo.id.clone(),
+                kind: NodeKind::Repo,
+                title: repo.name.clone(),
+                subtitle: Some(repo.forge.code().to_string()),
+                group: None,
+                attrs: vec![("forge", repo.forge.code().to_string()), ("owner", repo.owner.clone())],
+            });
+        }
+        for seam in &graph.store.seams {
+            diagram.add_node(&mut index, Node {
+                id: seam.id.clone(),
+                kind: NodeKind::Seam,
+                title: seam.name.clone(),
+                subtitle: Some(format!("[{}]", seam.system)),
+                group: None,
+                attrs: vec![("system", seam.system.clone())],
+            });
+        }
+        for slot in &graph.slots.slots {
+            diagram.add_node(&mut index, Node {
+                id: slot.id.clone(),
+                kind: NodeKind::Slot,
+                title: slot.name.clone(),
+                subtitle: Some(format!("[{}]", slot.category)),
+                group: None,
+                attrs: vec![("category", slot.category.clone())],
+            });
+        }
+        for provider in &graph.slots.providers {
+            let type_str = match provider.provider_type {
+                ProviderType::Local => "local",
+                ProviderType::Ecosystem => "eco",
+                ProviderType::External => "ext",
+                ProviderType::Stub => "stub",
+            };
+            diagram.add_node(&mut index, Node {
+                id: provider.id.clone(),
+                kind: NodeKind::Provider { fallback: provider.is_fallback },
+                title: provider.name.clone(),
+                subtitle: Some(format!("({type_str})")),
+                group: None,
+                attrs: vec![("provider_type", type_str.to_string())],
+            });
+        }
+
+        for group in &graph.store.groups {
+            let g = diagram.groups.len();
+            let mut members = Vec::new();
+            for member in &group.members {
+                if let Some(&n) = index.get(member) {
+                    if diagram.nodes[n].group.is_none() {
+                        diagram.nodes[n].group = Some(g);
+                    }
+                    members.push(n);
+                }
+            }
+            diagram.groups.push(Cluster { id: group.id.clone(), name: group.name.clone(), members });
+        }
+
+        for edge in &graph.store.edges {
+            let from = diagram.node_for(&mut index, &edge.from);
+            let to = diagram.node_for(&mut index, &edge.to);
+            diagram.links.push(Link {
+                id: edge.id.clone(),
+                from,
+                to,
+                label: edge.label.clone(),
+                kind: edge.rel.code().to_string(),
+                channel: Some(edge.channel),
+                style: channel_style(edge.channel),
+                arrow: true,
+            });
+        }
+        diagram.add_slot_links(graph, &mut index);
+        diagram
+    }
+
+    /// Provider → slot, provider → repo and consumer → provider links
+    fn add_slot_links(&mut self, graph: &EcosystemGraph, index: &mut HashMap<String, usize>) {
+        for provider in &graph.slots.providers {
+            let p = index[&provider.id];
+            let slot = self.node_for(index, &provider.slot_id);
+            self.links.push(Link {
+                id: format!("{}->{}", provider.id, provider.slot_id),
+                from: p,
+                to: slot,
+                label: Some("satisfies".into()),
+                kind: "satisfies".into(),
+                channel: None,
+                style: SATISFIES_STYLE,
+                arrow: false,
+            });
+            if let Some(ref repo_id) = provider.repo_id {
+                let repo = self.node_for(index, repo_id);
+                self.links.push(Link {
+                    id: format!("{}->{}", provider.id, repo_id),
+                    from: p,
+                    to: repo,
+                    label: Some("impl".into()),
+                    kind: "implements".into(),
+                    channel: None,
+                    style: IMPLEMENTS_STYLE,
+                    arrow: true,
+                });
+            }
+        }
+        for binding in &graph.slots.bindings {
+            let mode_str = match binding.mode {
+                BindingMode::Manual => "manual",
+                BindingMode::Auto => "auto",
+                BindingMode::Scenario => "scenario",
+                BindingMode::Default => "default",
+            };
+            let from = self.node_for(index, &binding.consumer_id);
+            let to = self.node_for(index, &binding.provider_id);
+            self.links.push(Link {
+                id: binding.id.clone(),
+                from,
+                to,
+                label: Some(format!("uses ({mode_str})")),
+                kind: "binding".into(),
+                channel: None,
+                style: BINDING_STYLE,
+                arrow: true,
+            });
+        }
+    }
+
+    fn add_node(&mut self, index: &mut HashMap<String, usize>, node: Node) {
+        index.insert(node.id.clone(), self.nodes.len());
+        self.nodes.push(node);
+    }
+
+    /// Index of a node, adding a placeholder for IDs not in the graph
+    fn node_for(&mut self, index: &mut HashMap<String, usize>, id: &str) -> usize {
+        if let Some(&n) = index.get(id) {
+            return n;
+        }
+        self.add_node(index, Node {
+            id: id.to_string(),
+            kind: NodeKind::Unknown,
+            title: id.to_string(),
+            subtitle: None,
+            group: None,
+            attrs: Vec::new(),
+        });
+        self.nodes.len() - 1
+    }
+
+    /// Nodes drawn at top level (not inside any group)
+    fn ungrouped(&self) -> impl Iterator<Item = (usize, &Node)> {
+        self.nodes.iter().enumerate().filter(|(_, n)| n.group.is_none())
+    }
+
+    /// Nodes drawn inside group `g`
+    fn in_group(&self, g: usize) -> impl Iterator<Item = (usize, &Node)> {
+        self.nodes.iter().enumerate().filter(move |(_, n)| n.group == Some(g))
+    }
+
+    /// Names of every group a node belongs to
+    fn group_names(&self, n: usize) -> Vec<&str> {
+        self.groups
+            .iter()
+            .filter(|g| g.members.contains(&n))
+            .map(|g| g.name.as_str())
+            .collect()
+    }
+}
+
+/// Escape text for XML attributes and content
+pub(crate) fn xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+// =============================================================================
+// Renderers
+// =============================================================================
+
+impl EcosystemGraph {
+    /// Export to a Mermaid flowchart
+    #[must_use]
+    pub fn to_mermaid(&self) -> String {
+        self.to_mermaid_with(None)
+    }
+
+    /// Export to a Mermaid flowchart, optionally coloured by an aspect with
+    /// weak-link badges and a legend (see [`AspectOverlay`])
+    #[must_use]
+    pub fn to_mermaid_with(&self, overlay: Option<&AspectOverlay>) -> String {
+        let d = Diagram::new(self);
+        let esc = |s: &str| s.replace('"', "#quot;");
+        let label = |n: &Node| {
+            let l = match &n.subtitle {
+                Some(sub) => format!("{}<br/>{}", esc(&n.title), esc(sub)),
+                None => esc(&n.title),
+            };
+            overlay.map_or_else(|| l.clone(), |o| o.label(&n.id, &l, "<br/>"))
+        };
+        let node = |i: usize, n: &Node| -> String {
+            let l = label(n);
+            match n.kind {
+                NodeKind::Repo => format!("n{i}(\"{l}\")"),
+                NodeKind::Seam => format!("n{i}>\"{l}\"]"),
+                NodeKind::Slot => format!("n{i}{{\"{l}\"}}"),
+                NodeKind::Provider { .. } => format!("n{i}{{{{\"{l}\"}}}}"),
+                NodeKind::Unknown => format!("n{i}[\"{l}\"]"),
+            }
+        };
+
+        let mut out = String::from("flowchart LR\n");
+        for (i, n) in d.ungrouped() {
+            let _ = writeln!(out, "  {}", node(i, n));
+        }
+        for (g, group) in d.groups.iter().enumerate() {
+            let _ = writeln!(out, "  subgraph g{g}[\"{}\"]", esc(&group.name));
+            for (i, n) in d.in_group(g) {
+                let _ = writeln!(out, "    {}", node(i, n));
+            }
+            out.push_str("  end\n");
+        }
+
+        out.push('\n');
+        for link in &d.links {
+            let arrow = match (link.kind.as_str(), link.arrow) {
+                ("binding", _) => "==>",
+                (_, false) => "-.-",
+                _ if link.style.line == LineStyle::Solid => "-->",
+                _ => "-.->",
+            };
+            let badged = overlay.filter(|o| o.badge(&link.id).is_some()).map(|o| {
+                o.label(&link.id, link.label.as_deref().unwrap_or_default(), " ").trim_start().to_string()
+            });
+            match badged.as_ref().or(link.label.as_ref()) {
+                Some(l) => {
+                    let _ = writeln!(out, "  n{} {arrow}|\"{}\"| n{}", link.from, esc(l), link.to);
+                }
+                None => {
+                    let _ = writeln!(out, "  n{} {arrow} n{}", link.from, link.to);
+                }
+            }
+        }
+        for (i, link) in d.links.iter().enumerate() {
+            let dash = match link.style.line {
+                LineStyle::Solid => "",
+                LineStyle::Dashed => ",stroke-dasharray:6 4",
+                LineStyle::Dotted => ",stroke-dasharray:2 3",
+            };
+            let colour = overlay.and_then(|o| o.colour(&link.id)).unwrap_or_else(|| link.style.hex());
+            let _ = writeln!(out, "  linkStyle {i} stroke:{colour},stroke-width:{}px{dash}", link.style.width);
+        }
+
+        out.push_str("\n  classDef seam stroke-dasharray:5 5,color:#666666\n");
+        out.push_str("  classDef slot fill:lightyellow\n");
+        out.push_str("  classDef provider fill:lightblue\n");
+        out.push_str("  classDef fallback fill:lightgray\n");
+        for (i, n) in d.nodes.iter().enumerate() {
+            let class = match n.kind {
+                NodeKind::Seam => "seam",
+                NodeKind::Slot => "slot",
+                NodeKind::Provider { fallback: false } => "provider",
+                NodeKind::Provider { fallback: true } => "fallback",
+                NodeKind::Repo | NodeKind::Unknown => continue,
+            };
+            let _ = writeln!(out, "  class n{i} {class}");
+        }
+        if let Some(overlay) = overlay {
+            overlay.mermaid_styles(&d, &mut out);
+        }
+        out
+    }
+
+    /// Export to a `PlantUML` diagram
+    #[must_use]
+    pub fn to_plantuml(&self) -> String {
+        let d = Diagram::new(self);
+        let esc = |s: &str| s.replace('"', "'");
+        let node = |i: usize, n: &Node| -> String {
+            let l = match &n.subtitle {
+                Some(sub) => format!("{}\\n{}", esc(&n.title), esc(sub)),
+                None => esc(&n.title),
+            };
+            match n.kind {
+                NodeKind::Repo => format!("rectangle \"{l}\" as n{i}"),
+                NodeKind::Seam => format!("cloud \"{l}\" as n{i} #line.dashed"),
+                NodeKind::Slot => format!("interface \"{l}\" as n{i} #lightyellow"),
+                NodeKind::Provider { fallback } => {
+                    format!("hexagon \"{l}\" as n{i} #{}", if fallback { "lightgray" } else { "lightblue" })
+                }
+                NodeKind::Unknown => format!("rectangle \"{l}\" as n{i} #line.dotted"),
+            }
+        };
+
+        let mut out = String::from("@startuml ecosystem\nleft to right direction\nskinparam rectangle {\n  RoundCorner 15\n}\n\n");
+        for (g, group) in d.groups.iter().enumerate() {
+            let _ = writeln!(out, "package \"{}\" {{", esc(&group.name));
+            for (i, n) in d.in_group(g) {
+                let _ = writeln!(out, "  {}", node(i, n));
+            }
+            out.push_str("}\n");
+        }
+        for (i, n) in d.ungrouped() {
+            let _ = writeln!(out, "{}", node(i, n));
+        }
+
+        out.push('\n');
+        for link in &d.links {
+            let mut style = vec![link.style.hex()];
+            if link.style.line != LineStyle::Solid {
+                style.push(link.style.line.name().to_string());
+            }
+            style.push(format!("thickness={}", link.style.width));
+            let head = if link.arrow { ">" } else { "" };
+            let _ = write!(out, "n{} -[{}]-{head} n{}", link.from, style.join(","), link.to);
+            match &link.label {
+                Some(l) => {
+                    let _ = writeln!(out, " : {}", esc(l));
+                }
+                None => out.push('\n'),
+            }
+        }
+        out.push_str("@enduml\n");
+        out
+    }
+
+    /// Export to `GraphML` (`yEd`, Gephi, `NetworkX`); groups become nested graphs
+    #[must_use]
+    pub fn to_graphml(&self) -> String {
+        let d = Diagram::new(self);
+        let mut out = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\" \
+             xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" \
+             xsi:schemaLocation=\"http://graphml.graphdrawing.org/xmlns http://graphml.graphdrawing.org/xmlns/1.0/graphml.xsd\">\n",
+        );
+        for (id, domain, name) in [
+            ("label", "node", "label"),
+            ("kind", "node", "kind"),
+            ("forge", "node", "forge"),
+            ("owner", "node", "owner"),
+            ("system", "node", "system"),
+            ("category", "node", "category"),
+            ("provider_type", "node", "provider_type"),
+            ("groups", "node", "groups"),
+            ("elabel", "edge", "label"),
+            ("ekind", "edge", "kind"),
+            ("channel", "edge", "channel"),
+            ("color", "edge", "color"),
+            ("style", "edge", "style"),
+            ("width", "edge", "width"),
+        ] {
+            let _ = writeln!(out, "  <key id=\"{id}\" for=\"{domain}\" attr.name=\"{name}\" attr.type=\"string\"/>");
+        }
+        out.push_str("  <graph id=\"ecosystem\" edgedefault=\"directed\">\n");
+
+        let write_node = |out: &mut String, n: usize, indent: &str| {
+            let node = &d.nodes[n];
+            let _ = writeln!(out, "{indent}<node id=\"{}\">", xml(&node.id));
+            let _ = writeln!(out, "{indent}  <data key=\"label\">{}</data>", xml(&node.title));
+            let _ = writeln!(out, "{indent}  <data key=\"kind\">{}</data>", node.kind.name());
+            for (key, value) in &node.attrs {
+                let _ = writeln!(out, "{indent}  <data key=\"{key}\">{}</data>", xml(value));
+            }
+            let groups = d.group_names(n);
+            if !groups.is_empty() {
+                let _ = writeln!(out, "{indent}  <data key=\"groups\">{}</data>", xml(&groups.join(",")));
+            }
+            let _ = writeln!(out, "{indent}</node>");
+        };
+
+        for (g, group) in d.groups.iter().enumerate() {
+            let _ = writeln!(out, "    <node id=\"{}\">", xml(&group.id));
+            let _ = writeln!(out, "      <data key=\"label\">{}</data>", xml(&group.name));
+            out.push_str("      <data key=\"kind\">group</data>\n");
+            let _ = writeln!(out, "      <graph id=\"{}:\" edgedefault=\"directed\">", xml(&group.id));
+            for (i, _) in d.in_group(g) {
+                write_node(&mut out, i, "        ");
+            }
+            out.push_str("      </graph>\n    </node>\n");
+        }
+        for (i, _) in d.ungrouped() {
+            write_node(&mut out, i, "    ");
+        }
+
+        for link in &d.links {
+            let _ = writeln!(
+                out,
+                "    <edge id=\"{}\" source=\"{}\" target=\"{}\">",
+                xml(&link.id),
+                xml(&d.nodes[link.from].id),
+                xml(&d.nodes[link.to].id)
+            );
+            if let Some(ref l) = link.label {
+                let _ = writeln!(out, "      <data key=\"elabel\">{}</data>", xml(l));
+            }
+            let _ = writeln!(out, "      <data key=\"ekind\">{}</data>", xml(&link.kind));
+            if let Some(channel) = link.channel {
+                let _ = writeln!(out, "      <data key=\"channel\">{}</data>", channel.code());
+            }
+            let _ = writeln!(out, "      <data key=\"color\">{}</data>", link.style.hex());
+            let _ = writeln!(out, "      <data key=\"style\">{}</data>", link.style.line.name());
+            let _ = writeln!(out, "      <data key=\"width\">{}</data>", link.style.width);
+            out.push_str("    </edge>\n");
+        }
+
+        out.push_str("  </graph>\n</graphml>\n");
+        out
+    }
+
+    /// Export to GEXF 1.3 (Gephi), with channel colours as `viz` attributes
+    #[must_use]
+    pub fn to_gexf(&self) -> String {
+        let d = Diagram::new(self);
+        let mut out = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <gexf xmlns=\"http://gexf.net/1.3\" xmlns:viz=\"http://gexf.net/1.3/viz\" version=\"1.3\">\n\
+             \x20 <meta>\n    <creator>reposystem</creator>\n    <description>Ecosystem graph</description>\n  </meta>\n\
+             \x20 <graph defaultedgetype=\"directed\" mode=\"static\">\n\
+             \x20   <attributes class=\"node\">\n",
+        );
+        let node_attrs = ["kind", "forge", "owner", "system", "category", "provider_type", "groups"];
+        for attr in node_attrs {
+            let _ = writeln!(out, "      <attribute id=\"{attr}\" title=\"{attr}\" type=\"string\"/>");
+        }
+        out.push_str("    </attributes>\n    <attributes class=\"edge\">\n");
+        for attr in ["kind", "channel"] {
+            let _ = writeln!(out, "      <attribute id=\"{attr}\" title=\"{attr}\" type=\"string\"/>");
+        }
+        out.push_str("    </attributes>\n    <nodes>\n");
+
+        for group in &d.groups {
+            let _ = writeln!(out, "      <node id=\"{}\" label=\"{}\">", xml(&group.id), xml(&group.name));
+            out.push_str("        <attvalues>\n          <attvalue for=\"kind\" value=\"group\"/>\n        </attvalues>\n      </node>\n");
+        }
+        for (i, node) in d.nodes.iter().enumerate() {
+            let pid = node
+                .group
+                .map(|g| format!(" pid=\"{}\"", xml(&d.groups[g].id)))
+                .unwrap_or_default();
+            let _ = writeln!(out, "      <node id=\"{}\" label=\"{}\"{pid}>", xml(&node.id), xml(&node.title));
+            out.push_str("        <attvalues>\n");
+            let _ = writeln!(out, "          <attvalue for=\"kind\" value=\"{}\"/>", node.kind.name());
+            for (key, value) in &node.attrs {
+                let _ = writeln!(out, "          <attvalue for=\"{key}\" value=\"{}\"/>", xml(value));
+            }
+            let groups = d.group_names(i);
+            if !groups.is_empty() {
+                let _ = writeln!(out, "          <attvalue for=\"groups\" value=\"{}\"/>", xml(&groups.join(",")));
+            }
+            out.push_str("        </attvalues>\n      </node>\n");
+        }
+
+        out.push_str("    </nodes>\n    <edges>\n");
+        for (i, link) in d.links.iter().enumerate() {
+            let label = link
+                .label
+                .as_deref()
+                .map(|l| format!(" label=\"{}\"", xml(l)))
+                .unwrap_or_default();
+            let kind = if link.arrow { "" } else { " type=\"undirected\"" };
+            let _ = writeln!(
+                out,
+                "      <edge id=\"e{i}\" source=\"{}\" target=\"{}\"{label}{kind}>",
+                xml(&d.nodes[link.from].id),
+                xml(&d.nodes[link.to].id)
+            );
+            out.push_str("        <attvalues>\n");
+            let _ = writeln!(out, "          <attvalue for=\"kind\" value=\"{}\"/>", xml(&link.kind));
+            if let Some(channel) = link.channel {
+                let _ = writeln!(out, "          <attvalue for=\"channel\" value=\"{}\"/>", channel.code());
+            }
+            out.push_str("        </attvalues>\n");
+            let (r, g, b) = link.style.rgb;
+            let _ = writeln!(out, "        <viz:color r=\"{r}\" g=\"{g}\" b=\"{b}\"/>");
+            let _ = writeln!(out, "        <viz:thickness value=\"{}\"/>", link.style.width);
+            let _ = writeln!(out, "        <viz:shape value=\"{}\"/>", link.style.line.name());
+            out.push_str("      </edge>\n");
+        }
+        out.push_str("    </edges>\n  </graph>\n</gexf>\n");
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{
+        AnnotationSource, AspectAnnotation, Edge, EdgeMeta, ExternalSeam, Forge, Group, ImportMeta,
+        Provider, RelationType, Repo, SeamDomain, Slot, SlotBinding, Visibility,
+    };
+    use chrono::Utc;
+
+    fn repo(name: &str) -> Repo {
+        Repo {
+            kind: "Repo".into(),
+            id: Repo::forge_id(Forge::GitHub, "acme", name),
+            forge: Forge::GitHub,
+            owner: "acme".into(),
+            name: name.into(),
+            default_branch: "main".into(),
+            visibility: Visibility::Public,
+            tags: vec![],
+            estate: "estate:hyperpolymath".into(),
+            metadata: Default::default(),
+            imports: ImportMeta { source: "test".into(), path_hint: None, imported_at: Utc::now() },
+            local_path: None,
+        }
+    }
+
+    /// app -api-> core, app -config-> aerie seam; core in group Core;
+    /// app bound to podman for the runtime slot
+    fn fixture() -> EcosystemGraph {
+        let mut graph = EcosystemGraph::new();
+        let (app, core) = (repo("app"), repo("core"));
+        let edge = |to: &str, channel, label: Option<&str>| Edge {
+            kind: "Edge".into(),
+            id: Edge::generate_id(&app.id, to, RelationType::Uses, channel, label),
+            from: app.id.clone(),
+            to: to.into(),
+            rel: RelationType::Uses,
+            channel,
+            label: label.map(String::from),
+            evidence: vec![],
+            meta: EdgeMeta { created_by: "test".into(), created_at: Utc::now() },
+        };
+        graph.store.seams.push(ExternalSeam {
+            kind: "ExternalSeam".into(),
+            id: "seam:aerie:net".into(),
+            domain: SeamDomain::Network,
+            system: "aerie".into(),
+            name: "Network <edge>".into(),
+            uri: None,
+            description: None,
+            estate: "estate:hyperpolymath".into(),
+        });
+        graph.store.edges.push(edge(&core.id, Channel::Api, Some("calls \"v2\"")));
+        graph.store.edges.push(edge("seam:aerie:net", Channel::Config, None));
+        graph.store.groups.push(Group {
+            kind: "Group".into(),
+            id: "group:core".into(),
+            name: "Core".into(),
+            description: None,
+            members: vec![core.id.clone()],
+        });
+        graph.slots.slots.push(Slot {
+            kind: "Slot".into(),
+            id: "slot:container.runtime".into(),
+            name: "runtime".into(),
+            category: "container".into(),
+            description: String::new(),
+            interface_version: None,
+            required_capabilities: vec![],
+            templates: Vec::new(),
+        });
+        graph.slots.providers.push(Provider {
+            kind: "Provider".into(),
+            id: "provider:container.runtime:podman".into(),
+            name: "podman".into(),
+            slot_id: "slot:container.runtime".into(),
+            provider_type: ProviderType::External,
+            repo_id: None,
+            external_uri: None,
+            interface_version: None,
+            capabilities: vec![],
+            priority: 0,
+            is_fallback: false,
+        });
+        graph.slots.bindings.push(SlotBinding {
+            kind: "SlotBinding".into(),
+            id: SlotBinding::generate_id(&app.id, "slot:container.runtime"),
+            consumer_id: app.id.clone(),
+            slot_id: "slot:container.runtime".into(),
+            provider_id: "provider:container.runtime:podman".into(),
+            mode: BindingMode::Manual,
+            created_at: Utc::now(),
+            created_by: "test".into(),
+        });
+        graph.store.repos.extend([app, core]);
+        graph
+    }
+
+    #[test]
+    fn test_mermaid_and_plantuml() {
+        let graph = fixture();
+
+        let mermaid = graph.to_mermaid();
+        assert!(mermaid.starts_with("flowchart LR\n"));
+        assert!(mermaid.contains("subgraph g0[\"Core\"]\n    n1(\"core<br/>gh\")\n  end"));
+        assert!(mermaid.contains("n0 -->|\"calls #quot;v2#quot;\"| n1"));
+        assert!(mermaid.contains("n0 -.-> n2"));
+        assert!(mermaid.contains("n4 -.-|\"satisfies\"| n3"));
+        assert!(mermaid.contains("n0 ==>|\"uses (manual)\"| n4"));
+        assert!(mermaid.contains("linkStyle 0 stroke:#1f77b4,stroke-width:2px\n"));
+        assert!(mermaid.contains("linkStyle 1 stroke:#9467bd,stroke-width:1px,stroke-dasharray:6 4"));
+        assert!(mermaid.contains("class n2 seam"));
+
+        let puml = graph.to_plantuml();
+        assert!(puml.starts_with("@startuml"));
+        assert!(puml.trim_end().ends_with("@enduml"));
+        assert!(puml.contains("package \"Core\" {\n  rectangle \"core\\ngh\" as n1\n}"));
+        assert!(puml.contains("n0 -[#1f77b4,thickness=2]-> n1 : calls 'v2'"));
+        assert!(puml.contains("n0 -[#9467bd,dashed,thickness=1]-> n2\n"));
+        assert!(puml.contains("hexagon \"podman\\n(ext)\" as n4 #lightblue"));
+    }
+
+    #[test]
+    fn test_graphml_and_gexf() {
+        let graph = fixture();
+
+        let graphml = graph.to_graphml();
+        assert!(graphml.contains("<node id=\"group:core\">"));
+        assert!(graphml.contains("<graph id=\"group:core:\" edgedefault=\"directed\">\n        <node id=\"repo:gh:acme/core\">"));
+        assert!(graphml.contains("<data key=\"label\">Network &lt;edge&gt;</data>"));
+        assert!(graphml.contains("<data key=\"elabel\">calls &quot;v2&quot;</data>"));
+        assert!(graphml.contains("<data key=\"channel\">config</data>"));
+        assert_eq!(graphml.matches("<edge ").count(), 4);
+
+        let gexf = graph.to_gexf();
+        assert!(gexf.contains("<node id=\"repo:gh:acme/core\" label=\"core\" pid=\"group:core\">"));
+        assert!(gexf.contains("<viz:color r=\"31\" g=\"119\" b=\"180\"/>"));
+        assert!(gexf.contains("<viz:shape value=\"dashed\"/>"));
+        assert!(gexf.contains("type=\"undirected\""));
+        assert_eq!(gexf.matches("<edge ").count(), 4);
+        assert_eq!(gexf.matches("<node ").count(), 6);
+    }
+
+    #[test]
+    fn test_aspect_overlay_dot_and_mermaid() {
+        let mut graph = fixture();
+        let (app, core) = (graph.store.repos[0].id.clone(), graph.store.repos[1].id.clone());
+        let edge = graph.store.edges[0].id.clone();
+        let mut annotate = |target: &str, weight, polarity| {
+            graph.aspects.annotations.push(AspectAnnotation {
+                kind: "AspectAnnotation".into(),
+                id: AspectAnnotation::generate_id(target, "aspect:security"),
+                target: target.into(),
+                aspect_id: "aspect:security".into(),
+                weight,
+                polarity,
+                reason: "test".into(),
+                evidence: vec![],
+                source: AnnotationSource { mode: "manual".into(), who: "test".into(), when: Utc::now(), rule_id: None },
+            });
+        };
+        annotate(&core, 3, Polarity::Risk);
+        annotate(&app, 2, Polarity::Strength);
+        annotate(&edge, 1, Polarity::Risk);
+
+        assert!(AspectOverlay::new(&graph, "colour").is_err());
+        let overlay = AspectOverlay::new(&graph, "Security").unwrap();
+        assert_eq!((overlay.score(&core), overlay.score(&app), overlay.score("seam:aerie:net")), (Some(3), Some(-2), None));
+        assert_eq!((overlay.badge(&core), overlay.badge(&app), overlay.badge(&edge)), (Some(3), None, Some(1)));
+
+        let dot = graph.to_dot_with(Some(&overlay));
+        assert!(dot.contains(&format!(
+            "\"{core}\" [label=\"core\\ngh\\n⚠ !!!\", style=\"rounded,filled\", fillcolor=\"#cb181d\", peripheries=2];"
+        )));
+        assert!(dot.contains(&format!("\"{app}\" [label=\"app\\ngh\", style=\"rounded,filled\", fillcolor=\"#74c476\"];")));
+        assert!(dot.contains("\\n⚠ !\", color=\"#fcbba1\", style=solid, penwidth=2];"));
+        assert!(dot.contains("subgraph cluster_legend {\n    label=\"Security (risk - strength)\";"));
+        assert!(!graph.to_dot().contains("cluster_legend"));
+
+        let mermaid = graph.to_mermaid_with(Some(&overlay));
+        assert!(mermaid.contains("n1(\"core<br/>gh<br/>⚠ !!!\")"));
+        assert!(mermaid.contains("n0 -->|\"calls #quot;v2#quot; ⚠ !\"| n1"));
+        assert!(mermaid.contains("linkStyle 0 stroke:#fcbba1,stroke-width:2px\n"));
+        assert!(mermaid.contains("style n1 fill:#cb181d,stroke:#c92a2a,stroke-width:3px\n"));
+        assert!(mermaid.contains("style n0 fill:#74c476\n"));
+        assert!(mermaid.contains("subgraph legend[\"Security (risk - strength)\"]"));
+    }
+}
@@ -0,0 +1,582 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Semantic diff between two graph snapshots
+//!
+//! Repos, edges, groups, annotations, slots and bindings are matched by ID
+//! and reported as added, removed or changed, with a field-by-field account
+//! of each change. Edges are matched by their content hash
+//! ([`Edge::generate_id`]), recomputed rather than read, so a stale stored ID
+//! never hides a change.
+//!
+//! Timestamps rewritten by every import or re-tag (`imports.imported_at`,
+//! `meta.created_at`, `source.when`, `created_at`) are not changes. Lists of
+//! strings (tags, members, capabilities) compare as sets.
+
+use crate::graph::EcosystemGraph;
+use crate::types::{AspectAnnotation, Edge, Group, Repo, Slot, SlotBinding};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::{self, Write as _};
+
+/// One field that differs between two versions of an entity
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldChange {
+    /// Dotted path (`metadata.owner`, `evidence`)
+    pub field: String,
+    /// Old value (`null` if the field was absent)
+    pub old: Value,
+    /// New value (`null` if the field was removed)
+    pub new: Value,
+}
+
+impl fmt::Display for FieldChange {
+    /// `tags: +rust -go` for string lists, `branch: main -> trunk` otherwise
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let (Some(old), Some(new)) = (string_set(&self.old), string_set(&self.new)) {
+            let added = new.difference(&old).map(|s| format!("+{s}"));
+            let removed = old.difference(&new).map(|s| format!("-{s}"));
+            let mut parts: Vec<String> = added.chain(removed).collect();
+            parts.sort_by(|a, b| a[1..].cmp(&b[1..]));
+            return write!(f, "{}: {}", self.field, parts.join(" "));
+        }
+        let show = |v: &Value| match v {
+            Value::String(s) => s.clone(),
+            Value::Null => "(none)".into(),
+            other => other.to_string(),
+        };
+        write!(f, "{}: {} -> {}", self.field, show(&self.old), show(&self.new))
+    }
+}
+
+/// An added, removed or changed entity
+#[derive(Debug, Clone, Serialize)]
+pub struct Item {
+    /// Entity ID (for edges, the content hash)
+    pub id: String,
+    /// Human-readable name
+    pub label: String,
+    /// What changed (empty for added and removed entities)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldChange>,
+}
+
+/// Changes to one kind of entity
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Section {
+    /// Only in the new snapshot
+    pub added: Vec<Item>,
+    /// Only in the old snapshot
+    pub removed: Vec<Item>,
+    /// In both, with different content
+    pub changed: Vec<Item>,
+}
+
+impl Section {
+    /// Number of added, removed and changed entities
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.added.len() + self.removed.len() + self.changed.len()
+    }
+
+    /// True if nothing changed
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Differences between two snapshots
+///
+/// The aspect and slot sections are `None` when a snapshot only carried the
+/// graph store (a bare `graph.json`), since there is nothing to compare.
+#[derive(Debug, Clone, Serialize)]
+pub struct GraphDiff {
+    /// Repository changes
+    pub repos: Section,
+    /// Edge changes, matched by content hash
+    pub edges: Section,
+    /// Group changes
+    pub groups: Section,
+    /// Aspect annotation changes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<Section>,
+    /// Slot changes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slots: Option<Section>,
+    /// Slot binding changes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bindings: Option<Section>,
+}
+
+/// Fields that are not compared, per entity kind
+const REPO_IGNORED: &[&str] = &["imports.imported_at"];
+const EDGE_IGNORED: &[&str] = &["id", "meta.created_at"];
+const ANNOTATION_IGNORED: &[&str] = &["source.when"];
+const BINDING_IGNORED: &[&str] = &["created_at"];
+
+impl GraphDiff {
+    /// Compare every store of two snapshots
+    #[must_use]
+    pub fn new(old: &EcosystemGraph, new: &EcosystemGraph) -> Self {
+        let mut diff = Self::graph_only(old, new);
+        diff.annotations = Some(compare(
+            (old, &old.aspects.annotations),
+            (new, &new.aspects.annotations),
+            |a: &AspectAnnotation| a.id.clone(),
+            |g, a| format!("{} {}", name(g, &a.target), a.aspect_id.trim_start_matches("aspect:")),
+            ANNOTATION_IGNORED,
+        ));
+        diff.slots = Some(compare(
+            (old, &old.slots.slots),
+            (new, &new.slots.slots),
+            |s: &Slot| s.id.clone(),
+            |_, s| s.id.trim_start_matches("slot:").to_string(),
+            &[],
+        ));
+        diff.bindings = Some(compare(
+            (old, &old.slots.bindings),
+            (new, &new.slots.bindings),
+            |b: &SlotBinding| b.id.clone(),
+            |g, b| format!("{} -> {}", name(g, &b.consumer_id), b.slot_id.trim_start_matches("slot:")),
+            BINDING_IGNORED,
+        ));
+        diff
+    }
+
+    /// Compare only repos, edges and groups (the graph store)
+    #[must_use]
+    pub fn graph_only(old: &EcosystemGraph, new: &EcosystemGraph) -> Self {
+        Self {
+            repos: compare(
+                (old, &old.store.repos),
+                (new, &new.store.repos),
+                |r: &Repo| r.id.clone(),
+                |_, r| r.name.clone(),
+                REPO_IGNORED,
+            ),
+            edges: compare(
+                (old, &old.store.edges),
+                (new, &new.store.edges),
+                edge_key,
+                edge_label,
+                EDGE_IGNORED,
+            ),
+            groups: compare(
+                (old, &old.store.groups),
+                (new, &new.store.groups),
+                |g: &Group| g.id.clone(),
+                |_, g| g.name.clone(),
+                &[],
+            ),
+            annotations: None,
+            slots: None,
+            bindings: None,
+        }
+    }
+
+    /// Every section with its name; `None` marks one that was not compared
+    #[must_use]
+    pub fn sections(&self) -> Vec<(&'static str, Option<&Section>)> {
+        vec![
+            ("repos", Some(&self.repos)),
+            ("edges", Some(&self.edges)),
+            ("groups", Some(&self.groups)),
+            ("annotations", self.annotations.as_ref()),
+            ("slots", self.slots.as_ref()),
+            ("bindings", self.bindings.as_ref()),
+        ]
+    }
+
+    /// True if no compared section has changes
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.sections().iter().all(|(_, s)| s.map_or(true, Section::is_empty))
+    }
+
+    /// Render the union of both snapshots as DOT, highlighting changes:
+    /// added in green, removed in red (dashed), changed in orange
+    ///
+    /// A repo whose annotations or bindings changed counts as changed, as
+    /// does an edge whose annotations changed; their labels name what moved.
+    #[must_use]
+    pub fn to_dot(&self, old: &EcosystemGraph, new: &EcosystemGraph) -> String {
+        let status = |section: &Section, id: &str| {
+            if section.added.iter().any(|i| i.id == id) {
+                Status::Added
+            } else if section.removed.iter().any(|i| i.id == id) {
+                Status::Removed
+            } else if section.changed.iter().any(|i| i.id == id) {
+                Status::Changed
+            } else {
+                Status::Same
+            }
+        };
+        let mut notes = self.change_notes(old, new);
+        let mut note = |id: &str| -> (bool, String) {
+            notes.remove(id).map_or((false, String::new()), |mut list| {
+                list.dedup();
+                (true, format!("\\n~ {}", list.join(", ")))
+            })
+        };
+
+        let mut out = String::from("digraph diff {\n  rankdir=LR;\n  node [shape=box, style=\"rounded,filled\", fillcolor=white];\n\n");
+        let mut drawn = HashSet::new();
+        for repo in union(old, new, |g| &g.store.repos, |r| r.id.clone()) {
+            drawn.insert(repo.id.as_str());
+            let (touched, note) = note(&repo.id);
+            let st = status(&self.repos, &repo.id).or_changed(touched);
+            let attrs = st.node_attrs();
+            let _ = writeln!(out, "  \"{}\" [label=\"{}\\n{}{note}\"{attrs}];", repo.id, repo.name, repo.forge.code());
+        }
+        for seam in union(old, new, |g| &g.store.seams, |s| s.id.clone()) {
+            if drawn.insert(seam.id.as_str()) {
+                let _ = writeln!(out, "  \"{}\" [label=\"{}\", shape=note, style=dashed, color=gray40];", seam.id, seam.name);
+            }
+        }
+
+        out.push('\n');
+        for edge in union(old, new, |g| &g.store.edges, edge_key) {
+            let key = edge_key(edge);
+            let (touched, note) = note(&key);
+            let st = status(&self.edges, &key).or_changed(touched);
+            let label = edge.label.clone().unwrap_or_default() + &note;
+            let label = label.trim_start_matches("\\n");
+            let _ = writeln!(out, "  \"{}\" -> \"{}\" [label=\"{label}\"{}];", edge.from, edge.to, st.edge_attrs());
+        }
+
+        for group in union(old, new, |g| &g.store.groups, |g| g.id.clone()) {
+            let st = status(&self.groups, &group.id);
+            let _ = writeln!(out, "\n  subgraph cluster_{} {{", group.id.replace(':', "_"));
+            let _ = writeln!(out, "    label=\"{}{}\";\n    style=dashed;\n    color=\"{}\";", st.prefix(), group.name, st.colour());
+            for member in &group.members {
+                let _ = writeln!(out, "    \"{member}\";");
+            }
+            out.push_str("  }\n");
+        }
+
+        out.push_str("\n  subgraph cluster_legend {\n    label=\"Changes\";\n    style=rounded;\n");
+        for st in [Status::Added, Status::Removed, Status::Changed, Status::Same] {
+            let _ = writeln!(out, "    \"legend_{0}\" [label=\"{0}\"{1}];", st.name(), st.node_attrs());
+        }
+        out.push_str("  }\n}\n");
+        out
+    }
+
+    /// What changed on each repo (by ID) and edge (by content hash): changed
+    /// repo fields, plus annotations and bindings attached to it
+    fn change_notes(&self, old: &EcosystemGraph, new: &EcosystemGraph) -> HashMap<String, Vec<String>> {
+        let mut notes: HashMap<String, Vec<String>> = HashMap::new();
+        for item in &self.repos.changed {
+            let fields = item.fields.iter().map(|f| f.field.split('.').next().unwrap_or_default().to_string());
+            notes.entry(item.id.clone()).or_default().extend(fields);
+        }
+
+        let touched = |section: Option<&Section>| -> Vec<String> {
+            let items = section.into_iter().flat_map(|s| s.added.iter().chain(&s.removed).chain(&s.changed));
+            items.map(|i| i.id.clone()).collect()
+        };
+        for id in touched(self.annotations.as_ref()) {
+            let target = |g: &EcosystemGraph| g.aspects.annotations.iter().find(|a| a.id == id).map(|a| a.target.clone());
+            if let Some(target) = target(new).or_else(|| target(old)) {
+                notes.entry(target).or_default().push("annotations".into());
+            }
+        }
+        for id in touched(self.bindings.as_ref()) {
+            let consumer = |g: &EcosystemGraph| g.slots.bindings.iter().find(|b| b.id == id).map(|b| b.consumer_id.clone());
+            if let Some(consumer) = consumer(new).or_else(|| consumer(old)) {
+                notes.entry(consumer).or_default().push("bindings".into());
+            }
+        }
+
+        // Annotations name an edge by its stored ID; re-key those by content hash
+        for edge in old.store.edges.iter().chain(&new.store.edges) {
+            if let Some(list) = notes.remove(&edge.id) {
+                notes.entry(edge_key(edge)).or_default().extend(list);
+            }
+        }
+        notes
+    }
+}
+
+/// How an entity differs, for DOT styling
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Added,
+    Removed,
+    Changed,
+    Same,
+}
+
+impl Status {
+    /// An otherwise unchanged entity whose attachments changed
+    fn or_changed(self, touched: bool) -> Self {
+        if touched && self == Self::Same {
+            Self::Changed
+        } else {
+            self
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Added => "added",
+            Self::Removed => "removed",
+            Self::Changed => "changed",
+            Self::Same => "unchanged",
+        }
+    }
+
+    fn prefix(self) -> &'static str {
+        match self {
+            Self::Added => "+ ",
+            Self::Removed => "- ",
+            Self::Changed => "~ ",
+            Self::Same => "",
+        }
+    }
+
+    fn colour(self) -> &'static str {
+        match self {
+            Self::Added => "#2b8a3e",
+            Self::Removed => "#c92a2a",
+            Self::Changed => "#e67700",
+            Self::Same => "#868e96",
+        }
+    }
+
+    fn node_attrs(self) -> String {
+        match self {
+            Self::Added => ", fillcolor=\"#c7e9c0\", color=\"#2b8a3e\", penwidth=2".into(),
+            Self::Removed => ", style=\"rounded,filled,dashed\", fillcolor=\"#fcbba1\", color=\"#c92a2a\", fontcolor=\"#c92a2a\"".into(),
+            Self::Changed => ", fillcolor=\"#ffe8a1\", color=\"#e67700\", penwidth=2".into(),
+            Self::Same => String::new(),
+        }
+    }
+
+    fn edge_attrs(self) -> String {
+        let style = match self {
+            Self::Added | Self::Changed => "solid, penwidth=2",
+            Self::Removed => "dashed, penwidth=2",
+            Self::Same => "solid",
+        };
+        format!(", color=\"{}\", fontcolor=\"{}\", style={style}", self.colour(), self.colour())
+    }
+}
+
+/// Entities of both snapshots, new first, each key once
+fn union<'a, T>(
+    old: &'a EcosystemGraph,
+    new: &'a EcosystemGraph,
+    items: impl Fn(&'a EcosystemGraph) -> &'a Vec<T>,
+    key: impl Fn(&T) -> String,
+) -> Vec<&'a T> {
+    let mut seen = HashSet::new();
+    [new, old].into_iter().flat_map(items).filter(|item| seen.insert(key(item))).collect()
+}
+
+/// Content hash of an edge, recomputed from its identity fields
+fn edge_key(edge: &Edge) -> String {
+    Edge::generate_id(&edge.from, &edge.to, edge.rel, edge.channel, edge.label.as_deref())
+}
+
+fn edge_label(graph: &EcosystemGraph, edge: &Edge) -> String {
+    let mut label = format!(
+        "{} -> {} [{}/{}]",
+        name(graph, &edge.from),
+        name(graph, &edge.to),
+        edge.rel.code(),
+        edge.channel.code()
+    );
+    if let Some(ref text) = edge.label {
+        let _ = write!(label, " {text}");
+    }
+    label
+}
+
+/// Name of a repo or seam, or the ID itself
+fn name(graph: &EcosystemGraph, id: &str) -> String {
+    graph
+        .get_repo(id)
+        .map(|r| r.name.clone())
+        .or_else(|| graph.store.seams.iter().find(|s| s.id == id).map(|s| s.name.clone()))
+        .unwrap_or_else(|| id.to_string())
+}
+
+/// Match entities by key and compare the ones present on both sides
+fn compare<T: Serialize>(
+    (old_graph, old): (&EcosystemGraph, &[T]),
+    (new_graph, new): (&EcosystemGraph, &[T]),
+    key: impl Fn(&T) -> String,
+    label: impl Fn(&EcosystemGraph, &T) -> String,
+    ignored: &[&str],
+) -> Section {
+    let old_by_key: BTreeMap<String, &T> = old.iter().map(|t| (key(t), t)).collect();
+    let new_by_key: BTreeMap<String, &T> = new.iter().map(|t| (key(t), t)).collect();
+    let item = |id: &String, graph, t: &T, fields| Item { id: id.clone(), label: label(graph, t), fields };
+
+    let mut section = Section::default();
+    for (id, t) in &new_by_key {
+        match old_by_key.get(id) {
+            None => section.added.push(item(id, new_graph, t, Vec::new())),
+            Some(before) => {
+                let fields = field_changes(&to_value(before), &to_value(t), ignored);
+                if !fields.is_empty() {
+                    section.changed.push(item(id, new_graph, t, fields));
+                }
+            }
+        }
+    }
+    for (id, t) in &old_by_key {
+        if !new_by_key.contains_key(id) {
+            section.removed.push(item(id, old_graph, t, Vec::new()));
+        }
+    }
+    section
+}
+
+fn to_value<T: Serialize>(t: &T) -> Value {
+    serde_json::to_value(t).unwrap_or(Value::Null)
+}
+
+/// Differing leaf fields of two JSON objects, by dotted path
+fn field_changes(old: &Value, new: &Value, ignored: &[&str]) -> Vec<FieldChange> {
+    let (mut before, mut after) = (BTreeMap::new(), BTreeMap::new());
+    flatten("", old, &mut before);
+    flatten("", new, &mut after);
+
+    let fields: std::collections::BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    fields
+        .into_iter()
+        .filter(|field| !ignored.contains(&field.as_str()))
+        .filter_map(|field| {
+            let old = before.get(field).cloned().unwrap_or(Value::Null);
+            let new = after.get(field).cloned().unwrap_or(Value::Null);
+            let same = match (string_set(&old), string_set(&new)) {
+                (Some(a), Some(b)) => a == b,
+                _ => old == new,
+            };
+            (!same).then(|| FieldChange { field: field.clone(), old, new })
+        })
+        .collect()
+}
+
+fn flatten(prefix: &str, value: &Value, out: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (k, v) in map {
+                let path = if prefix.is_empty() { k.clone() } else { format!("{prefix}.{k}") };
+                flatten(&path, v, out);
+            }
+        }
+        // An empty object reads the same as an absent one
+        Value::Object(_) | Value::Null => {}
+        _ => {
+            out.insert(prefix.to_string(), value.clone());
+        }
+    }
+}
+
+/// A list of strings as a set; `null` (absent) counts as empty
+fn string_set(value: &Value) -> Option<std::collections::BTreeSet<&str>> {
+    match value {
+        Value::Array(items) => items.iter().map(Value::as_str).collect(),
+        Value::Null => Some(std::collections::BTreeSet::new()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Channel, EdgeMeta, Evidence, Forge, ImportMeta, RelationType, Visibility};
+    use chrono::Utc;
+
+    fn repo(name: &str, tags: &[&str]) -> Repo {
+        Repo {
+            kind: "Repo".into(),
+            id: Repo::forge_id(Forge::GitHub, "acme", name),
+            forge: Forge::GitHub,
+            owner: "acme".into(),
+            name: name.into(),
+            default_branch: "main".into(),
+            visibility: Visibility::Public,
+            tags: tags.iter().map(|t| (*t).to_string()).collect(),
+            estate: "estate:hyperpolymath".into(),
+            metadata: Default::default(),
+            imports: ImportMeta { source: "test".into(), path_hint: None, imported_at: Utc::now() },
+            local_path: None,
+        }
+    }
+
+    fn edge(from: &str, to: &str, evidence: &[&str]) -> Edge {
+        let (from, to) = (Repo::forge_id(Forge::GitHub, "acme", from), Repo::forge_id(Forge::GitHub, "acme", to));
+        Edge {
+            kind: "Edge".into(),
+            id: Edge::generate_id(&from, &to, RelationType::Uses, Channel::Api, None),
+            from,
+            to,
+            rel: RelationType::Uses,
+            channel: Channel::Api,
+            label: None,
+            evidence: evidence
+                .iter()
+                .map(|r| Evidence { evidence_type: "file".into(), reference: (*r).into(), excerpt: None, confidence: 1.0 })
+                .collect(),
+            meta: EdgeMeta { created_by: "test".into(), created_at: Utc::now() },
+        }
+    }
+
+    /// old: app, core, gone; app -> core, gone -> core
+    /// new: app (retagged), core, web; app -> core (with evidence), web -> core
+    fn snapshots() -> (EcosystemGraph, EcosystemGraph) {
+        let mut old = EcosystemGraph::new();
+        old.store.repos = vec![repo("app", &["rust", "cli"]), repo("core", &[]), repo("gone", &[])];
+        old.store.edges = vec![edge("app", "core", &[]), edge("gone", "core", &[])];
+
+        let mut new = EcosystemGraph::new();
+        new.store.repos = vec![repo("app", &["cli", "rust", "tui"]), repo("core", &[]), repo("web", &[])];
+        let mut stale = edge("app", "core", &["Cargo.toml"]);
+        stale.id = "edge:stale".into();
+        new.store.edges = vec![stale, edge("web", "core", &[])];
+        (old, new)
+    }
+
+    #[test]
+    fn test_diff_sections() {
+        let (old, new) = snapshots();
+        assert!(GraphDiff::new(&old, &old).is_empty());
+
+        let diff = GraphDiff::new(&old, &new);
+        let labels = |items: &[Item]| items.iter().map(|i| i.label.clone()).collect::<Vec<_>>();
+        assert_eq!(labels(&diff.repos.added), ["web"]);
+        assert_eq!(labels(&diff.repos.removed), ["gone"]);
+        // Reordered tags and a new import time are not changes; the new tag is
+        assert_eq!(labels(&diff.repos.changed), ["app"]);
+        assert_eq!(diff.repos.changed[0].fields[0].to_string(), "tags: +tui");
+
+        // The app -> core edge matches by content despite its stale stored ID
+        assert_eq!(labels(&diff.edges.added), ["web -> core [uses/api]"]);
+        assert_eq!(labels(&diff.edges.removed), ["gone -> core [uses/api]"]);
+        assert_eq!(diff.edges.changed.len(), 1);
+        assert_eq!(diff.edges.changed[0].fields[0].field, "evidence");
+        assert!(diff.annotations.as_ref().unwrap().is_empty());
+
+        let json = serde_json::to_value(GraphDiff::graph_only(&old, &new)).unwrap();
+        assert!(json.get("annotations").is_none());
+        assert_eq!(json["repos"]["changed"][0]["fields"][0]["new"], serde_json::json!(["cli", "rust", "tui"]));
+    }
+
+    #[test]
+    fn test_diff_dot() {
+        let (old, new) = snapshots();
+        let dot = GraphDiff::new(&old, &new).to_dot(&old, &new);
+        assert!(dot.starts_with("digraph diff {"));
+        assert!(dot.contains("[label=\"web\\ngh\", fillcolor=\"#c7e9c0\""));
+        assert!(dot.contains("[label=\"gone\\ngh\", style=\"rounded,filled,dashed\""));
+        assert!(dot.contains("[label=\"app\\ngh\\n~ tags\", fillcolor=\"#ffe8a1\""));
+        assert!(dot.contains("[label=\"core\\ngh\"];"));
+        assert!(dot.contains("\"repo:gh:acme/gone\" -> \"repo:gh:acme/core\" [label=\"\", color=\"#c92a2a\""));
+        assert_eq!(dot.matches(" -> \"repo:gh:acme/core\"").count(), 3);
+    }
+}
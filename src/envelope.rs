@@ -0,0 +1,217 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Text encodings of the estate-export envelope
+//!
+//! The envelope is built once as a JSON value (see
+//! [`EcosystemGraph::to_estate_export`](crate::graph::EcosystemGraph::to_estate_export))
+//! and encoded here as JSON, YAML or TOML. Decoding goes back to the same
+//! JSON value, so all three formats share one schema and one deserializer.
+//!
+//! TOML has no null: absent optional fields are simply left out, which
+//! deserializes back to `None`.
+
+use anyhow::{bail, Context, Result};
+use serde_json::{Map, Number, Value};
+use std::path::Path;
+use yaml_rust2::{yaml::Hash, Yaml, YamlEmitter, YamlLoader};
+
+/// Schema tag stamped on every envelope
+pub const SCHEMA: &str = "reposystem/estate-export@1";
+
+/// Text format of an estate envelope
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnvelopeFormat {
+    /// Pretty-printed JSON
+    Json,
+    /// YAML 1.2
+    Yaml,
+    /// TOML 1.0
+    Toml,
+}
+
+impl EnvelopeFormat {
+    /// Guess the format from a file extension (`.json`, `.yaml`/`.yml`, `.toml`)
+    #[must_use]
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "yaml" | "yml" => Some(Self::Yaml),
+            "toml" => Some(Self::Toml),
+            _ => None,
+        }
+    }
+}
+
+/// Encode an envelope value as text
+///
+/// # Errors
+/// Fails if the value cannot be represented in the target format (e.g. a
+/// null inside a TOML array).
+pub fn encode(value: &Value, format: EnvelopeFormat) -> Result<String> {
+    match format {
+        EnvelopeFormat::Json => {
+            serde_json::to_string_pretty(value).context("Failed to serialize estate export")
+        }
+        EnvelopeFormat::Yaml => {
+            let mut out = String::new();
+            YamlEmitter::new(&mut out)
+                .dump(&to_yaml(value))
+                .context("Failed to serialize estate export as YAML")?;
+            out.push('\n');
+            Ok(out)
+        }
+        EnvelopeFormat::Toml => {
+            let toml::Value::Table(table) = to_toml(value)?.unwrap_or(toml::Value::Table(toml::Table::new())) else {
+                bail!("Estate export must be a table to serialize as TOML");
+            };
+            toml::to_string_pretty(&table).context("Failed to serialize estate export as TOML")
+        }
+    }
+}
+
+/// Decode envelope text back into a JSON value
+///
+/// # Errors
+/// Fails on malformed input or YAML features the envelope never uses
+/// (aliases, non-scalar keys).
+pub fn decode(text: &str, format: EnvelopeFormat) -> Result<Value> {
+    match format {
+        EnvelopeFormat::Json => serde_json::from_str(text).context("Failed to parse estate JSON"),
+        EnvelopeFormat::Yaml => {
+            let docs = YamlLoader::load_from_str(text).context("Failed to parse estate YAML")?;
+            match docs.first() {
+                Some(doc) => from_yaml(doc),
+                None => bail!("Estate YAML is empty"),
+            }
+        }
+        EnvelopeFormat::Toml => {
+            let table: toml::Table = toml::from_str(text).context("Failed to parse estate TOML")?;
+            Ok(from_toml(toml::Value::Table(table)))
+        }
+    }
+}
+
+fn to_yaml(value: &Value) -> Yaml {
+    match value {
+        Value::Null => Yaml::Null,
+        Value::Bool(b) => Yaml::Boolean(*b),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => Yaml::Integer(i),
+            // `{:?}` keeps a decimal point so the value reads back as a float
+            None => Yaml::Real(format!("{:?}", n.as_f64().unwrap_or_default())),
+        },
+        Value::String(s) => Yaml::String(s.clone()),
+        Value::Array(items) => Yaml::Array(items.iter().map(to_yaml).collect()),
+        Value::Object(map) => {
+            let mut hash = Hash::new();
+            for (k, v) in map {
+                hash.insert(Yaml::String(k.clone()), to_yaml(v));
+            }
+            Yaml::Hash(hash)
+        }
+    }
+}
+
+fn from_yaml(yaml: &Yaml) -> Result<Value> {
+    Ok(match yaml {
+        Yaml::Null => Value::Null,
+        Yaml::Boolean(b) => Value::Bool(*b),
+        Yaml::Integer(i) => Value::from(*i),
+        Yaml::Real(_) => match yaml.as_f64().and_then(Number::from_f64) {
+            Some(n) => Value::Number(n),
+            None => bail!("Unsupported YAML number: {yaml:?}"),
+        },
+        Yaml::String(s) => Value::String(s.clone()),
+        Yaml::Array(items) => Value::Array(items.iter().map(from_yaml).collect::<Result<_>>()?),
+        Yaml::Hash(hash) => {
+            let mut map = Map::new();
+            for (k, v) in hash {
+                let key = match k {
+                    Yaml::String(s) | Yaml::Real(s) => s.clone(),
+                    Yaml::Integer(i) => i.to_string(),
+                    Yaml::Boolean(b) => b.to_string(),
+                    other => bail!("Unsupported YAML key: {other:?}"),
+                };
+                map.insert(key, from_yaml(v)?);
+            }
+            Value::Object(map)
+        }
+        Yaml::Alias(_) | Yaml::BadValue => bail!("Unsupported YAML value: {yaml:?}"),
+    })
+}
+
+/// Convert to TOML, dropping nulls (`None` for a null value)
+fn to_toml(value: &Value) -> Result<Option<toml::Value>> {
+    Ok(Some(match value {
+        Value::Null => return Ok(None),
+        Value::Bool(b) => toml::Value::Boolean(*b),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => toml::Value::Integer(i),
+            None => toml::Value::Float(n.as_f64().unwrap_or_default()),
+        },
+        Value::String(s) => toml::Value::String(s.clone()),
+        Value::Array(items) => {
+            let mut array = Vec::with_capacity(items.len());
+            for item in items {
+                match to_toml(item)? {
+                    Some(v) => array.push(v),
+                    None => bail!("TOML cannot represent a null inside an array"),
+                }
+            }
+            toml::Value::Array(array)
+        }
+        Value::Object(map) => {
+            let mut table = toml::Table::new();
+            for (k, v) in map {
+                if let Some(v) = to_toml(v)? {
+                    table.insert(k.clone(), v);
+                }
+            }
+            toml::Value::Table(table)
+        }
+    }))
+}
+
+fn from_toml(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(i) => Value::from(i),
+        toml::Value::Float(f) => Number::from_f64(f).map_or(Value::Null, Value::Number),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        // Hand-written TOML may use native datetimes; the schema stores RFC 3339 strings
+        toml::Value::Datetime(dt) => Value::String(dt.to_string()),
+        toml::Value::Array(items) => Value::Array(items.into_iter().map(from_toml).collect()),
+        toml::Value::Table(table) => {
+            Value::Object(table.into_iter().map(|(k, v)| (k, from_toml(v))).collect())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_envelope_encodings_round_trip() {
+        let value = json!({
+            "schema": SCHEMA,
+            "repos": [{"name": "alpha", "tags": ["1.0", "true", "yes", ""], "meta": {"a:b": "c # d"}}],
+            "edges": [{"confidence": 0.95, "weight": 1.0, "count": 3, "label": "multi\nline", "ok": false}],
+        });
+
+        for format in [EnvelopeFormat::Json, EnvelopeFormat::Yaml, EnvelopeFormat::Toml] {
+            let text = encode(&value, format).unwrap();
+            assert_eq!(decode(&text, format).unwrap(), value, "{format:?} round trip:\n{text}");
+        }
+    }
+
+    #[test]
+    fn test_toml_drops_nulls() {
+        let value = json!({"estate": null, "repos": [{"name": "alpha", "uri": null}]});
+        let text = encode(&value, EnvelopeFormat::Toml).unwrap();
+        assert_eq!(decode(&text, EnvelopeFormat::Toml).unwrap(), json!({"repos": [{"name": "alpha"}]}));
+
+        assert!(encode(&json!({"ids": [null]}), EnvelopeFormat::Toml).is_err());
+    }
+}
@@ -2,18 +2,190 @@
 // SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
 //! Graph data structures and algorithms for the ecosystem graph
 
+use crate::diagram::AspectOverlay;
+use crate::envelope::{self, EnvelopeFormat};
 use crate::types::{
-    AspectStore, AuditStore, Edge, ExternalSeam, GraphStore, Group, PlanStore, RelationType, Repo,
-    SlotStore,
+    Aspect, AspectAnnotation, AspectStore, AuditStore, BindingMode, ChangeOp, ChangeSet, Component, Edge,
+    Estate, ExternalSeam, Forge, GraphStore, Group, Plan, PlanStore, Provider, RelationType, Repo,
+    Scenario, Slot, SlotBinding, SlotStore,
 };
 use crate::verisimdb::VeriSimDbClient;
 use anyhow::{Context, Result};
+use chrono::Utc;
 use petgraph::graph::{DiGraph, NodeIndex};
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::Path;
 
+/// Counts of edges touched by [`EcosystemGraph::sync_edges_from`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct EdgeSync {
+    /// Newly added edges
+    pub added: usize,
+    /// Existing edges whose evidence was refreshed
+    pub updated: usize,
+    /// Stale edges that were dropped
+    pub removed: usize,
+}
+
+/// Everything touched by [`EcosystemGraph::remove_repo`],
+/// [`EcosystemGraph::rekey_repo`] or [`EcosystemGraph::merge_repo`]. IDs are as they were before the change.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct RepoCascade {
+    /// The repo that was removed or re-keyed
+    pub repo_id: String,
+    /// Its new ID (re-key only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_id: Option<String>,
+    /// Components of the repo
+    pub components: Vec<String>,
+    /// Edges to or from the repo
+    pub edges: Vec<String>,
+    /// Groups listing the repo as a member
+    pub groups: Vec<String>,
+    /// Annotations on the repo or its edges
+    pub annotations: Vec<String>,
+    /// Slot providers implemented by the repo
+    pub providers: Vec<String>,
+    /// Slot bindings consumed by the repo (or bound to a removed provider)
+    pub bindings: Vec<String>,
+    /// Scenarios whose change set referenced the repo
+    pub scenarios: Vec<String>,
+    /// Edges between the merged repos, dropped as self-loops (merge only)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub removed_edges: Vec<String>,
+    /// Annotations on those edges, dropped with them (merge only)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub removed_annotations: Vec<String>,
+}
+
+impl RepoCascade {
+    /// Total number of references touched
+    #[must_use]
+    pub fn total(&self) -> usize {
+        self.components.len()
+            + self.edges.len()
+            + self.groups.len()
+            + self.annotations.len()
+            + self.providers.len()
+            + self.bindings.len()
+            + self.scenarios.len()
+            + self.removed_edges.len()
+            + self.removed_annotations.len()
+    }
+}
+
+/// Split `repo:<forge>:<owner>/<name>` into its parts (not for local IDs).
+fn parse_forge_id(id: &str) -> Option<(Forge, String, String)> {
+    let (code, path) = id.strip_prefix("repo:")?.split_once(':')?;
+    let forge = Forge::from_code(code).filter(|f| *f != Forge::Local)?;
+    let (owner, name) = path.split_once('/')?;
+    Some((forge, owner.to_string(), name.to_string()))
+}
+
+/// Point an edge's endpoints at `new_id`, regenerating a derived edge ID.
+/// Returns the edge's previous ID if it referenced `old_id`.
+fn rekey_edge(edge: &mut Edge, old_id: &str, new_id: &str) -> Option<String> {
+    if edge.from != old_id && edge.to != old_id {
+        return None;
+    }
+    let old_edge_id = edge.id.clone();
+    let derived = Edge::generate_id(
+        &edge.from,
+        &edge.to,
+        edge.rel,
+        edge.channel,
+        edge.label.as_deref(),
+    ) == edge.id;
+    for end in [&mut edge.from, &mut edge.to] {
+        if end == old_id {
+            *end = new_id.to_string();
+        }
+    }
+    if derived {
+        edge.id = Edge::generate_id(
+            &edge.from,
+            &edge.to,
+            edge.rel,
+            edge.channel,
+            edge.label.as_deref(),
+        );
+    }
+    Some(old_edge_id)
+}
+
+/// Retarget an annotation on `old_id` (or on a re-keyed edge), regenerating a
+/// derived annotation ID. Returns the annotation's previous ID if it changed.
+fn rekey_annotation(
+    annotation: &mut AspectAnnotation,
+    old_id: &str,
+    new_id: &str,
+    edge_ids: &HashMap<String, String>,
+) -> Option<String> {
+    let target = if annotation.target == old_id {
+        new_id.to_string()
+    } else {
+        edge_ids.get(&annotation.target)?.clone()
+    };
+    let old_annotation_id = annotation.id.clone();
+    if annotation.id == AspectAnnotation::generate_id(&annotation.target, &annotation.aspect_id) {
+        annotation.id = AspectAnnotation::generate_id(&target, &annotation.aspect_id);
+    }
+    annotation.target = target;
+    Some(old_annotation_id)
+}
+
+/// Replace `id` via `map`, returning whether it changed.
+fn remap(id: &mut String, map: &HashMap<String, String>) -> bool {
+    match map.get(id.as_str()) {
+        Some(new) if new != id => {
+            *id = new.clone();
+            true
+        }
+        Some(_) | None => false,
+    }
+}
+
+/// The estate-export envelope as read back from JSON, YAML or TOML.
+/// Every collection is optional so hand-trimmed files still import.
+#[derive(Debug, Deserialize)]
+struct EstateExport {
+    schema: String,
+    #[serde(default)]
+    estate: Option<Estate>,
+    #[serde(default)]
+    estates: Vec<Estate>,
+    #[serde(default)]
+    repos: Vec<Repo>,
+    #[serde(default)]
+    components: Vec<Component>,
+    #[serde(default)]
+    seams: Vec<ExternalSeam>,
+    #[serde(default)]
+    groups: Vec<Group>,
+    #[serde(default)]
+    edges: Vec<Edge>,
+    #[serde(default)]
+    scenarios: Vec<Scenario>,
+    #[serde(default)]
+    changesets: Vec<ChangeSet>,
+    #[serde(default)]
+    aspects: Option<Vec<Aspect>>,
+    #[serde(default)]
+    annotations: Vec<AspectAnnotation>,
+    #[serde(default)]
+    slots: Vec<Slot>,
+    #[serde(default)]
+    providers: Vec<Provider>,
+    #[serde(default)]
+    bindings: Vec<SlotBinding>,
+    #[serde(default)]
+    plans: Vec<Plan>,
+}
+
 /// The ecosystem graph with petgraph backing for algorithms
+#[derive(Clone)]
 pub struct EcosystemGraph {
     /// The underlying directed graph
     graph: DiGraph<String, String>,
@@ -167,12 +339,59 @@ pub fn load(dir: &Path) -> Result<Self> {
         Ok(ecosystem)
     }
 
+    /// Build a graph from the flat JSON store files alone, without `VeriSimDB`.
+    ///
+    /// `read` maps a file name (`graph.json`, `aspects.json`, …) to its
+    /// contents, or `None` if the snapshot lacks it; a missing store is
+    /// empty. This loads snapshots: a copied data directory, or one read out
+    /// of git history.
+    ///
+    /// # Errors
+    /// Fails if `read` fails or a file does not parse.
+    pub fn from_json_files(read: impl Fn(&str) -> Result<Option<String>>) -> Result<Self> {
+        fn parse<T: serde::de::DeserializeOwned + Default>(
+            read: &impl Fn(&str) -> Result<Option<String>>,
+            name: &str,
+        ) -> Result<T> {
+            match read(name)? {
+                Some(content) => serde_json::from_str(&content).with_context(|| format!("Failed to parse {name}")),
+                None => Ok(T::default()),
+            }
+        }
+
+        let mut ecosystem = Self {
+            graph: DiGraph::new(),
+            node_indices: HashMap::new(),
+            store: parse(&read, "graph.json")?,
+            aspects: parse(&read, "aspects.json")?,
+            slots: parse(&read, "slots.json")?,
+            plans: parse(&read, "plans.json")?,
+            audit: parse(&read, "audit.json")?,
+        };
+        ecosystem.rebuild_graph();
+        Ok(ecosystem)
+    }
+
     /// Save graph to a directory and to VeriSimDB.
     ///
     /// Flat JSON files in `dir` are written first (authoritative on-disk copy).
     /// VeriSimDB is then updated with the same data.  VeriSimDB failures are
     /// logged as warnings — they do not cause `save()` to return `Err`.
     pub fn save(&self, dir: &Path) -> Result<()> {
+        self.save_json_files(dir)?;
+
+        // Mirror all five stores to VeriSimDB (warnings-only on failure).
+        let vdb = VeriSimDbClient::new();
+        vdb.save_all(&self.store, &self.aspects, &self.slots, &self.plans, &self.audit);
+
+        Ok(())
+    }
+
+    /// Write the five stores to flat JSON files in `dir`, without `VeriSimDB`.
+    ///
+    /// # Errors
+    /// Fails if `dir` cannot be created or a store cannot be written.
+    pub fn save_json_files(&self, dir: &Path) -> Result<()> {
         fs::create_dir_all(dir)
             .with_context(|| format!("Failed to create directory {}", dir.display()))?;
 
@@ -207,15 +426,11 @@ pub fn save(&self, dir: &Path) -> Result<()> {
         fs::write(&audit_path, audit_json)
             .with_context(|| format!("Failed to write {}", audit_path.display()))?;
 
-        // Mirror all five stores to VeriSimDB (warnings-only on failure).
-        let vdb = VeriSimDbClient::new();
-        vdb.save_all(&self.store, &self.aspects, &self.slots, &self.plans, &self.audit);
-
         Ok(())
     }
 
     /// Rebuild the petgraph from the store
-    fn rebuild_graph(&mut self) {
+    pub(crate) fn rebuild_graph(&mut self) {
         self.graph.clear();
         self.node_indices.clear();
 
@@ -257,6 +472,307 @@ pub fn add_repo(&mut self, repo: Repo) {
         }
     }
 
+    /// Remove a repository and everything that only makes sense with it:
+    /// its components, edges to or from it, its group memberships, annotations
+    /// on it or its edges, providers it implements, bindings it consumes (or
+    /// that point at a removed provider), and scenario change-set ops that
+    /// reference any of these.
+    ///
+    /// # Errors
+    /// Returns an error if the repo does not exist.
+    pub fn remove_repo(&mut self, id: &str) -> Result<RepoCascade> {
+        let pos = self
+            .store
+            .repos
+            .iter()
+            .position(|r| r.id == id)
+            .ok_or_else(|| anyhow::anyhow!("Repo not found: {id}"))?;
+        self.store.repos.remove(pos);
+
+        let mut cascade = RepoCascade {
+            repo_id: id.to_string(),
+            ..RepoCascade::default()
+        };
+
+        self.store.components.retain(|c| {
+            let keep = c.repo_id != id;
+            if !keep {
+                cascade.components.push(c.id.clone());
+            }
+            keep
+        });
+        self.store.edges.retain(|e| {
+            let keep = e.from != id && e.to != id;
+            if !keep {
+                cascade.edges.push(e.id.clone());
+            }
+            keep
+        });
+        for group in &mut self.store.groups {
+            if group.members.iter().any(|m| m == id) {
+                group.members.retain(|m| m != id);
+                cascade.groups.push(group.id.clone());
+            }
+        }
+        let dead_edges = &cascade.edges;
+        let mut annotations = Vec::new();
+        self.aspects.annotations.retain(|a| {
+            let keep = a.target != id && !dead_edges.contains(&a.target);
+            if !keep {
+                annotations.push(a.id.clone());
+            }
+            keep
+        });
+        cascade.annotations = annotations;
+        self.slots.providers.retain(|p| {
+            let keep = p.repo_id.as_deref() != Some(id);
+            if !keep {
+                cascade.providers.push(p.id.clone());
+            }
+            keep
+        });
+        let dead_providers = &cascade.providers;
+        let mut bindings = Vec::new();
+        self.slots.bindings.retain(|b| {
+            let keep = b.consumer_id != id && !dead_providers.contains(&b.provider_id);
+            if !keep {
+                bindings.push(b.id.clone());
+            }
+            keep
+        });
+        cascade.bindings = bindings;
+
+        for changeset in &mut self.store.changesets {
+            let before = changeset.ops.len();
+            let mut touched = false;
+            changeset.ops.retain_mut(|op| match op {
+                ChangeOp::AddEdge { edge } => edge.from != id && edge.to != id,
+                ChangeOp::RemoveEdge { edge_id } => !cascade.edges.contains(edge_id),
+                ChangeOp::AddAnnotation { annotation } => {
+                    annotation.target != id && !cascade.edges.contains(&annotation.target)
+                }
+                ChangeOp::RemoveAnnotation { annotation_id } => {
+                    !cascade.annotations.contains(annotation_id)
+                }
+                ChangeOp::SetGroupMembership { members, previous, .. } => {
+                    for list in std::iter::once(members).chain(previous) {
+                        if list.iter().any(|m| m == id) {
+                            list.retain(|m| m != id);
+                            touched = true;
+                        }
+                    }
+                    true
+                }
+            });
+            if touched || changeset.ops.len() != before {
+                cascade.scenarios.push(changeset.scenario_id.clone());
+            }
+        }
+
+        self.rebuild_graph();
+        Ok(cascade)
+    }
+
+    /// Change a repository's ID, rewriting every reference to it.
+    ///
+    /// IDs derived from the repo ID (edge, annotation and binding IDs produced
+    /// by their `generate_id`) are regenerated so later lookups stay
+    /// idempotent; hand-chosen IDs are kept. If `new_id` is a forge ID
+    /// (`repo:gh:owner/name`) the repo's forge, owner and name follow it.
+    ///
+    /// # Errors
+    /// Returns an error if `old_id` does not exist or `new_id` is already taken.
+    pub fn rekey_repo(&mut self, old_id: &str, new_id: &str) -> Result<RepoCascade> {
+        if old_id == new_id {
+            anyhow::bail!("Old and new IDs are the same: {old_id}");
+        }
+        if self.node_indices.contains_key(new_id) {
+            anyhow::bail!("ID already in use: {new_id}");
+        }
+        let repo = self
+            .store
+            .repos
+            .iter_mut()
+            .find(|r| r.id == old_id)
+            .ok_or_else(|| anyhow::anyhow!("Repo not found: {old_id}"))?;
+        repo.id = new_id.to_string();
+        if let Some((forge, owner, name)) = parse_forge_id(new_id) {
+            repo.forge = forge;
+            repo.owner = owner;
+            repo.name = name;
+        }
+
+        let mut cascade = RepoCascade {
+            repo_id: old_id.to_string(),
+            new_id: Some(new_id.to_string()),
+            ..RepoCascade::default()
+        };
+        self.retarget_references(old_id, new_id, &mut cascade);
+        self.rebuild_graph();
+        Ok(cascade)
+    }
+
+    /// Fold repository `from_id` into the existing repository `into_id`.
+    ///
+    /// Used when the same checkout turns up under two IDs, typically a
+    /// `repo:local:<hash>` node whose checkout later gained a forge remote.
+    /// Every reference is migrated as in [`Self::rekey_repo`], then anything
+    /// that became a duplicate (edges, annotations, bindings, group members)
+    /// is dropped. Edges between the two repos would become self-loops, so
+    /// they and their annotations are removed first. Metadata keys missing on `into_id` are
+    /// carried over and `from_id` is recorded as an alias.
+    ///
+    /// # Errors
+    /// Returns an error if either repo does not exist or they are the same.
+    pub fn merge_repo(&mut self, from_id: &str, into_id: &str) -> Result<RepoCascade> {
+        if from_id == into_id {
+            anyhow::bail!("Cannot merge a repo into itself: {from_id}");
+        }
+        if self.get_repo(into_id).is_none() {
+            anyhow::bail!("Repo not found: {into_id}");
+        }
+        let pos = self
+            .store
+            .repos
+            .iter()
+            .position(|r| r.id == from_id)
+            .ok_or_else(|| anyhow::anyhow!("Repo not found: {from_id}"))?;
+        let from = self.store.repos.remove(pos);
+        let aliases: Vec<String> = from.aliases().into_iter().map(String::from).collect();
+        if let Some(into) = self.store.repos.iter_mut().find(|r| r.id == into_id) {
+            for (key, value) in from.metadata {
+                if key != Repo::ALIASES_KEY {
+                    into.metadata.entry(key).or_insert(value);
+                }
+            }
+            for alias in &aliases {
+                into.add_alias(alias);
+            }
+            into.add_alias(from_id);
+        }
+
+        let mut cascade = RepoCascade {
+            repo_id: from_id.to_string(),
+            new_id: Some(into_id.to_string()),
+            ..RepoCascade::default()
+        };
+        let ends = [from_id, into_id];
+        self.store.edges.retain(|e| {
+            let keep = !(ends.contains(&e.from.as_str()) && ends.contains(&e.to.as_str()));
+            if !keep {
+                cascade.removed_edges.push(e.id.clone());
+            }
+            keep
+        });
+        let dead_edges = &cascade.removed_edges;
+        let mut annotations = Vec::new();
+        self.aspects.annotations.retain(|a| {
+            let keep = !dead_edges.contains(&a.target);
+            if !keep {
+                annotations.push(a.id.clone());
+            }
+            keep
+        });
+        cascade.removed_annotations = annotations;
+        self.retarget_references(from_id, into_id, &mut cascade);
+
+        let mut seen = HashSet::new();
+        self.store.edges.retain(|e| seen.insert(e.id.clone()));
+        let mut seen = HashSet::new();
+        self.aspects.annotations.retain(|a| seen.insert(a.id.clone()));
+        let mut seen = HashSet::new();
+        self.slots.bindings.retain(|b| seen.insert(b.id.clone()));
+        for group in &mut self.store.groups {
+            let mut seen = HashSet::new();
+            group.members.retain(|m| seen.insert(m.clone()));
+        }
+
+        self.rebuild_graph();
+        Ok(cascade)
+    }
+
+    /// Rewrite every reference to `old_id` as `new_id`, recording what was
+    /// touched. Does not touch the repo itself or rebuild the graph.
+    fn retarget_references(&mut self, old_id: &str, new_id: &str, cascade: &mut RepoCascade) {
+        let rename = |id: &mut String| {
+            if id == old_id {
+                *id = new_id.to_string();
+                true
+            } else {
+                false
+            }
+        };
+
+        for component in &mut self.store.components {
+            if rename(&mut component.repo_id) {
+                cascade.components.push(component.id.clone());
+            }
+        }
+
+        let mut edge_ids: HashMap<String, String> = HashMap::new();
+        for edge in &mut self.store.edges {
+            if let Some(old) = rekey_edge(edge, old_id, new_id) {
+                cascade.edges.push(old.clone());
+                edge_ids.insert(old, edge.id.clone());
+            }
+        }
+
+        for group in &mut self.store.groups {
+            let mut touched = false;
+            for member in &mut group.members {
+                touched |= rename(member);
+            }
+            if touched {
+                cascade.groups.push(group.id.clone());
+            }
+        }
+
+        let mut annotation_ids: HashMap<String, String> = HashMap::new();
+        for annotation in &mut self.aspects.annotations {
+            if let Some(old) = rekey_annotation(annotation, old_id, new_id, &edge_ids) {
+                cascade.annotations.push(old.clone());
+                annotation_ids.insert(old, annotation.id.clone());
+            }
+        }
+
+        for provider in &mut self.slots.providers {
+            if provider.repo_id.as_mut().is_some_and(&rename) {
+                cascade.providers.push(provider.id.clone());
+            }
+        }
+        for binding in &mut self.slots.bindings {
+            if rename(&mut binding.consumer_id) {
+                cascade.bindings.push(binding.id.clone());
+                if binding.id == SlotBinding::generate_id(old_id, &binding.slot_id) {
+                    binding.id = SlotBinding::generate_id(new_id, &binding.slot_id);
+                }
+            }
+        }
+
+        for changeset in &mut self.store.changesets {
+            let mut touched = false;
+            for op in &mut changeset.ops {
+                touched |= match op {
+                    ChangeOp::AddEdge { edge } => rekey_edge(edge, old_id, new_id).is_some(),
+                    ChangeOp::RemoveEdge { edge_id } => remap(edge_id, &edge_ids),
+                    ChangeOp::AddAnnotation { annotation } => {
+                        rekey_annotation(annotation, old_id, new_id, &edge_ids).is_some()
+                    }
+                    ChangeOp::RemoveAnnotation { annotation_id } => {
+                        remap(annotation_id, &annotation_ids)
+                    }
+                    ChangeOp::SetGroupMembership { members, previous, .. } => members
+                        .iter_mut()
+                        .chain(previous.iter_mut().flatten())
+                        .fold(false, |acc, m| rename(m) | acc),
+                };
+            }
+            if touched {
+                cascade.scenarios.push(changeset.scenario_id.clone());
+            }
+        }
+    }
+
     /// Add an edge to the graph
     pub fn add_edge(&mut self, edge: Edge) -> Result<()> {
         // Seam invariant: external seams are edge sinks, never sources, and may
@@ -292,6 +808,47 @@ pub fn add_edge(&mut self, edge: Edge) -> Result<()> {
         Ok(())
     }
 
+    /// Replace the edges created by any of `sources` that originate at `repo_id`.
+    ///
+    /// Used by re-scans: detected edges that already exist have their evidence
+    /// and `created_by` refreshed (keeping their original `created_at`), new
+    /// ones are added, and previously detected edges that were not re-detected
+    /// are dropped along with their annotations. Edges from any other source
+    /// (e.g. `manual`, or a detector the scan did not run) are never touched.
+    pub fn sync_edges_from(&mut self, repo_id: &str, sources: &[String], edges: Vec<Edge>) -> EdgeSync {
+        let mut sync = EdgeSync::default();
+        let fresh: Vec<&str> = edges.iter().map(|e| e.id.as_str()).collect();
+        let owned = |e: &Edge| sources.contains(&e.meta.created_by);
+
+        let mut stale: Vec<String> = Vec::new();
+        self.store.edges.retain(|e| {
+            let keep = !(e.from == repo_id && owned(e) && !fresh.contains(&e.id.as_str()));
+            if !keep {
+                stale.push(e.id.clone());
+            }
+            keep
+        });
+        sync.removed = stale.len();
+        if sync.removed > 0 {
+            self.aspects.annotations.retain(|a| !stale.contains(&a.target));
+            self.rebuild_graph();
+        }
+
+        for edge in edges {
+            if let Some(existing) = self.store.edges.iter_mut().find(|e| e.id == edge.id) {
+                if owned(existing) {
+                    existing.evidence = edge.evidence;
+                    existing.meta.created_by = edge.meta.created_by;
+                    sync.updated += 1;
+                }
+            } else if self.add_edge(edge).is_ok() {
+                sync.added += 1;
+            }
+        }
+
+        sync
+    }
+
     /// Add a group
     pub fn add_group(&mut self, group: Group) {
         if let Some(existing) = self.store.groups.iter_mut().find(|g| g.id == group.id) {
@@ -403,27 +960,272 @@ pub fn repos_in_group(&self, group_id: &str) -> Vec<&Repo> {
         }
     }
 
+    /// Narrow the graph to a subset of repos, edges and annotations.
+    ///
+    /// Edges survive only if both endpoints do (seams are kept when a kept
+    /// edge points at them). Groups, components and slot records follow the
+    /// repos they belong to. With `annotations` unset, annotations on any
+    /// surviving node or edge are kept.
+    pub fn retain(
+        &mut self,
+        repos: &HashSet<String>,
+        edges: &HashSet<String>,
+        annotations: Option<&HashSet<String>>,
+    ) {
+        let store = &mut self.store;
+        store.repos.retain(|r| repos.contains(&r.id));
+
+        let seam_ids: HashSet<&str> = store.seams.iter().map(|s| s.id.as_str()).collect();
+        store.edges.retain(|e| {
+            edges.contains(&e.id)
+                && repos.contains(&e.from)
+                && (repos.contains(&e.to) || seam_ids.contains(e.to.as_str()))
+        });
+        let targets: HashSet<String> = store.edges.iter().map(|e| e.to.clone()).collect();
+        store.seams.retain(|s| targets.contains(&s.id) || repos.contains(&s.id));
+
+        store.components.retain(|c| repos.contains(&c.repo_id));
+        for group in &mut store.groups {
+            group.members.retain(|m| repos.contains(m));
+        }
+        store.groups.retain(|g| !g.members.is_empty());
+
+        let kept: HashSet<String> = store
+            .repos
+            .iter()
+            .map(|r| r.id.clone())
+            .chain(store.edges.iter().map(|e| e.id.clone()))
+            .chain(store.seams.iter().map(|s| s.id.clone()))
+            .collect();
+        self.aspects.annotations.retain(|a| {
+            kept.contains(&a.target) && annotations.map_or(true, |ids| ids.contains(&a.id))
+        });
+
+        self.slots
+            .providers
+            .retain(|p| p.repo_id.as_ref().map_or(true, |id| repos.contains(id)));
+        self.slots.bindings.retain(|b| repos.contains(&b.consumer_id));
+
+        self.rebuild_graph();
+    }
+
+    /// Find a scenario by ID, by `<slug>` (for `scenario:<slug>`) or by name
+    #[must_use]
+    pub fn find_scenario(&self, name: &str) -> Option<&Scenario> {
+        self.store
+            .scenarios
+            .iter()
+            .find(|s| s.id == name || s.name == name || s.id.strip_prefix("scenario:") == Some(name))
+    }
+
+    /// A scenario followed by its bases, nearest first; empty for `baseline`
+    /// (unless a scenario has that name)
+    ///
+    /// # Errors
+    /// Fails if the scenario or one of its bases is unknown, or the base
+    /// chain loops.
+    pub fn scenario_chain(&self, name: &str) -> Result<Vec<&Scenario>> {
+        let mut chain: Vec<&Scenario> = Vec::new();
+        let mut next = match self.find_scenario(name) {
+            Some(scenario) => Some(scenario),
+            None if name == "baseline" => None,
+            None => anyhow::bail!("Scenario not found: {name}"),
+        };
+        while let Some(scenario) = next {
+            if chain.iter().any(|s| s.id == scenario.id) {
+                anyhow::bail!("Scenario base chain loops at {}", scenario.id);
+            }
+            chain.push(scenario);
+            next = match &scenario.base {
+                Some(base) => Some(
+                    self.find_scenario(base)
+                        .ok_or_else(|| anyhow::anyhow!("Base scenario not found: {base}"))?,
+                ),
+                None => None,
+            };
+        }
+        Ok(chain)
+    }
+
+    /// Materialise a scenario as a virtual "what-if" graph.
+    ///
+    /// Returns a copy of the baseline with the changesets of the scenario's
+    /// `base` chain applied oldest first, then its own; nothing is saved.
+    /// `baseline` (unless a scenario has that name) is the graph unchanged.
+    /// Removing an edge also drops the annotations on it, and removing
+    /// something already gone is a no-op, so a scenario may repeat an op of
+    /// its base. Slot bindings follow `uses` edges: adding one to a repo that
+    /// implements providers binds the consumer to them (switching any
+    /// existing binding for the same slot), removing it drops those bindings.
+    ///
+    /// # Errors
+    /// Fails if the scenario or one of its bases is unknown, the base chain
+    /// loops, an added edge has a missing endpoint, or a group does not exist.
+    pub fn with_scenario(&self, name: &str) -> Result<Self> {
+        let chain = self.scenario_chain(name)?;
+        let mut graph = self.clone();
+        for scenario in chain.iter().rev() {
+            let ops = self
+                .store
+                .changesets
+                .iter()
+                .filter(|c| c.scenario_id == scenario.id)
+                .flat_map(|c| &c.ops);
+            for op in ops {
+                graph
+                    .apply_change(op)
+                    .with_context(|| format!("Failed to apply scenario {}", scenario.name))?;
+            }
+        }
+        graph.rebuild_graph();
+        Ok(graph)
+    }
+
+    /// Record an operation in a scenario's change set instead of applying it
+    /// to the baseline
+    ///
+    /// The op is tried on the scenario's effective graph first, so one that
+    /// cannot apply there (a missing endpoint or group) is rejected rather
+    /// than stored.
+    ///
+    /// # Errors
+    /// Fails if the scenario is unknown or cannot be materialised, or the op
+    /// does not apply to it.
+    pub fn record_change(&mut self, scenario: &str, op: ChangeOp) -> Result<()> {
+        let scenario_id = self
+            .find_scenario(scenario)
+            .ok_or_else(|| anyhow::anyhow!("Scenario not found: {scenario}"))?
+            .id
+            .clone();
+        self.with_scenario(&scenario_id)?.apply_change(&op)?;
+        match self.store.changesets.iter_mut().find(|c| c.scenario_id == scenario_id) {
+            Some(changeset) => changeset.ops.push(op),
+            None => self.store.changesets.push(ChangeSet {
+                kind: "ChangeSet".into(),
+                scenario_id,
+                ops: vec![op],
+            }),
+        }
+        Ok(())
+    }
+
+    /// Apply one scenario operation in memory
+    pub(crate) fn apply_change(&mut self, op: &ChangeOp) -> Result<()> {
+        match op {
+            ChangeOp::AddEdge { edge } => {
+                self.add_edge(edge.clone())?;
+                if edge.rel == RelationType::Uses {
+                    self.bind_providers(&edge.from, &edge.to);
+                }
+            }
+            ChangeOp::RemoveEdge { edge_id } => {
+                if let Some(edge) = self.store.edges.iter().find(|e| &e.id == edge_id && e.rel == RelationType::Uses) {
+                    let (consumer, repo) = (edge.from.clone(), edge.to.clone());
+                    let provided: HashSet<&str> = self
+                        .slots
+                        .providers
+                        .iter()
+                        .filter(|p| p.repo_id.as_deref() == Some(repo.as_str()))
+                        .map(|p| p.id.as_str())
+                        .collect();
+                    self.slots
+                        .bindings
+                        .retain(|b| b.consumer_id != consumer || !provided.contains(b.provider_id.as_str()));
+                }
+                self.store.edges.retain(|e| &e.id != edge_id);
+                self.aspects.annotations.retain(|a| &a.target != edge_id);
+            }
+            ChangeOp::AddAnnotation { annotation } => {
+                self.aspects.annotations.retain(|a| a.id != annotation.id);
+                self.aspects.annotations.push(annotation.clone());
+            }
+            ChangeOp::RemoveAnnotation { annotation_id } => {
+                self.aspects.annotations.retain(|a| &a.id != annotation_id);
+            }
+            ChangeOp::SetGroupMembership { group_id, members, .. } => {
+                let group = self
+                    .store
+                    .groups
+                    .iter_mut()
+                    .find(|g| &g.id == group_id)
+                    .ok_or_else(|| anyhow::anyhow!("Group not found: {group_id}"))?;
+                group.members.clone_from(members);
+            }
+        }
+        Ok(())
+    }
+
+    /// Bind `consumer` to every slot provider implemented by `repo`
+    fn bind_providers(&mut self, consumer: &str, repo: &str) {
+        let provided: Vec<(String, String)> = self
+            .slots
+            .providers
+            .iter()
+            .filter(|p| p.repo_id.as_deref() == Some(repo))
+            .map(|p| (p.slot_id.clone(), p.id.clone()))
+            .collect();
+        for (slot_id, provider_id) in provided {
+            let existing = self
+                .slots
+                .bindings
+                .iter_mut()
+                .find(|b| b.consumer_id == consumer && b.slot_id == slot_id);
+            match existing {
+                Some(binding) if binding.provider_id == provider_id => {}
+                Some(binding) => {
+                    binding.provider_id = provider_id;
+                    binding.mode = BindingMode::Scenario;
+                }
+                None => self.slots.bindings.push(SlotBinding {
+                    kind: "SlotBinding".into(),
+                    id: SlotBinding::generate_id(consumer, &slot_id),
+                    consumer_id: consumer.to_string(),
+                    slot_id,
+                    provider_id,
+                    mode: BindingMode::Scenario,
+                    created_at: Utc::now(),
+                    created_by: "scenario".into(),
+                }),
+            }
+        }
+    }
+
     /// Export to DOT format for Graphviz
     #[must_use]
     pub fn to_dot(&self) -> String {
+        self.to_dot_with(None)
+    }
+
+    /// Export to DOT, optionally colouring nodes and edges by an aspect and
+    /// badging its weak links (see [`AspectOverlay`])
+    #[must_use]
+    pub fn to_dot_with(&self, overlay: Option<&AspectOverlay>) -> String {
+        let label_of = |id: &str, label: String| match overlay {
+            Some(o) => o.label(id, &label, "\\n"),
+            None => label,
+        };
+        let attrs_of = |id: &str, style: &str| overlay.map_or_else(String::new, |o| o.dot_node_attrs(id, style));
+
         let mut dot = String::from("digraph ecosystem {\n");
         dot.push_str("  rankdir=LR;\n");
         dot.push_str("  node [shape=box, style=rounded];\n\n");
 
         // Add nodes
         for repo in &self.store.repos {
-            let label = format!("{}\\n{}", repo.name, repo.forge.code());
-            dot.push_str(&format!("  \"{}\" [label=\"{}\"];\n", repo.id, label));
+            let label = label_of(&repo.id, format!("{}\\n{}", repo.name, repo.forge.code()));
+            dot.push_str(&format!("  \"{}\" [label=\"{}\"{}];\n", repo.id, label, attrs_of(&repo.id, "rounded")));
         }
 
         dot.push('\n');
 
-        // Add edges
+        // Add edges, styled by channel (coloured by aspect heat in an overlay)
         for edge in &self.store.edges {
-            let label = edge.label.as_deref().unwrap_or("");
+            let label = label_of(&edge.id, edge.label.clone().unwrap_or_default());
+            let style = crate::diagram::channel_style(edge.channel);
+            let colour = overlay.and_then(|o| o.colour(&edge.id)).unwrap_or_else(|| style.hex());
             dot.push_str(&format!(
-                "  \"{}\" -> \"{}\" [label=\"{}\"];\n",
-                edge.from, edge.to, label
+                "  \"{}\" -> \"{}\" [label=\"{}\", color=\"{}\", style={}, penwidth={}];\n",
+                edge.from, edge.to, label, colour, style.line.name(), style.width
             ));
         }
 
@@ -442,10 +1244,10 @@ pub fn to_dot(&self) -> String {
         if !self.store.seams.is_empty() {
             dot.push_str("\n  // External seams (sinks)\n");
             for seam in &self.store.seams {
-                let label = format!("{}\\n[{}]", seam.name, seam.system);
+                let label = label_of(&seam.id, format!("{}\\n[{}]", seam.name, seam.system));
                 dot.push_str(&format!(
-                    "  \"{}\" [label=\"{}\", shape=note, style=dashed, color=gray40, fontcolor=gray40];\n",
-                    seam.id, label
+                    "  \"{}\" [label=\"{}\", shape=note, style=dashed, color=gray40, fontcolor=gray40{}];\n",
+                    seam.id, label, attrs_of(&seam.id, "dashed")
                 ));
             }
         }
@@ -510,6 +1312,10 @@ pub fn to_dot(&self) -> String {
             }
         }
 
+        if let Some(overlay) = overlay {
+            dot.push_str(&overlay.dot_legend());
+        }
+
         dot.push_str("}\n");
         dot
     }
@@ -526,6 +1332,22 @@ pub fn to_json(&self) -> Result<String> {
     /// estates, external seams, aspects, slots/providers/bindings and plans.
     /// One serializer, many transports.
     pub fn to_estate_export(&self) -> Result<String> {
+        self.to_estate_export_as(EnvelopeFormat::Json)
+    }
+
+    /// Export the estate-export envelope as JSON, YAML or TOML.
+    ///
+    /// All three carry the same data and read back with
+    /// [`Self::from_estate_export`].
+    ///
+    /// # Errors
+    /// Fails if the envelope cannot be serialized in `format`.
+    pub fn to_estate_export_as(&self, format: EnvelopeFormat) -> Result<String> {
+        envelope::encode(&self.estate_envelope(), format)
+    }
+
+    /// Build the estate-export envelope as a JSON value
+    pub(crate) fn estate_envelope(&self) -> serde_json::Value {
         let estate_id = self
             .store
             .estate
@@ -538,8 +1360,8 @@ pub fn to_estate_export(&self) -> Result<String> {
             .find(|e| e.id == estate_id)
             .or_else(|| self.store.estates.first());
 
-        let value = serde_json::json!({
-            "schema": "reposystem/estate-export@1",
+        serde_json::json!({
+            "schema": envelope::SCHEMA,
             "estate": current,
             "estates": self.store.estates,
             "repos": self.store.repos,
@@ -548,15 +1370,59 @@ pub fn to_estate_export(&self) -> Result<String> {
             "groups": self.store.groups,
             "edges": self.store.edges,
             "scenarios": self.store.scenarios,
+            "changesets": self.store.changesets,
             "aspects": self.aspects.aspects,
             "annotations": self.aspects.annotations,
             "slots": self.slots.slots,
             "providers": self.slots.providers,
             "bindings": self.slots.bindings,
             "plans": self.plans.plans,
-        });
+        })
+    }
 
-        serde_json::to_string_pretty(&value).context("Failed to serialize estate export")
+    /// Rebuild a graph from an estate-export envelope in any format.
+    ///
+    /// The audit log and plan diffs are not part of the envelope and come
+    /// back empty; everything else round-trips.
+    ///
+    /// # Errors
+    /// Fails on malformed input or an envelope with a different schema.
+    pub fn from_estate_export(text: &str, format: EnvelopeFormat) -> Result<Self> {
+        let value = envelope::decode(text, format)?;
+        let export: EstateExport =
+            serde_json::from_value(value).context("Estate export does not match the schema")?;
+        if export.schema != envelope::SCHEMA {
+            anyhow::bail!(
+                "Unsupported estate export schema: {} (expected {})",
+                export.schema,
+                envelope::SCHEMA
+            );
+        }
+
+        let mut graph = Self::new();
+        graph.store = GraphStore {
+            estate: export.estate.map(|e| e.id),
+            estates: export.estates,
+            repos: export.repos,
+            components: export.components,
+            seams: export.seams,
+            groups: export.groups,
+            edges: export.edges,
+            scenarios: export.scenarios,
+            changesets: export.changesets,
+        };
+        if let Some(aspects) = export.aspects {
+            graph.aspects.aspects = aspects;
+        }
+        graph.aspects.annotations = export.annotations;
+        graph.slots = SlotStore {
+            slots: export.slots,
+            providers: export.providers,
+            bindings: export.bindings,
+        };
+        graph.plans.plans = export.plans;
+        graph.rebuild_graph();
+        Ok(graph)
     }
 }
 
@@ -631,6 +1497,420 @@ fn test_add_edge() {
         assert_eq!(graph.edges_to(&repo_b.id).len(), 1);
     }
 
+    #[test]
+    fn test_sync_edges_from_updates_without_duplicating() {
+        let mut graph = EcosystemGraph::new();
+        for name in ["a", "b", "c"] {
+            graph.add_repo(make_test_repo(name));
+        }
+        let detected = |to: &str, line: usize| Edge {
+            kind: "Edge".into(),
+            id: Edge::generate_id("repo:gh:test/a", to, RelationType::Uses, Channel::Artifact, Some("cargo")),
+            from: "repo:gh:test/a".into(),
+            to: to.into(),
+            rel: RelationType::Uses,
+            channel: Channel::Artifact,
+            label: Some("cargo".into()),
+            evidence: vec![crate::types::Evidence {
+                evidence_type: "file".into(),
+                reference: format!("Cargo.toml:{line}"),
+                excerpt: None,
+                confidence: 0.95,
+            }],
+            meta: EdgeMeta {
+                created_by: "auto-detect:cargo".into(),
+                created_at: Utc::now(),
+            },
+        };
+        let mut manual = detected("repo:gh:test/c", 1);
+        manual.id = "edge:manual".into();
+        manual.meta.created_by = "manual".into();
+        graph.add_edge(manual).unwrap();
+        let mut npm = detected("repo:gh:test/c", 2);
+        npm.id = "edge:npm".into();
+        npm.meta.created_by = "auto-detect:npm".into();
+        graph.add_edge(npm).unwrap();
+
+        let cargo = vec!["auto-detect:cargo".to_string()];
+        let sync = graph.sync_edges_from("repo:gh:test/a", &cargo, vec![detected("repo:gh:test/b", 5), detected("repo:gh:test/c", 6)]);
+        assert_eq!(sync, EdgeSync { added: 2, updated: 0, removed: 0 });
+        let to_c = detected("repo:gh:test/c", 6).id;
+        graph.aspects.annotations.push(AspectAnnotation {
+            kind: "AspectAnnotation".into(),
+            id: AspectAnnotation::generate_id(&to_c, "aspect:security"),
+            target: to_c,
+            aspect_id: "aspect:security".into(),
+            weight: 1,
+            polarity: crate::types::Polarity::Risk,
+            reason: "test".into(),
+            evidence: vec![],
+            source: crate::types::AnnotationSource { mode: "manual".into(), who: "test".into(), when: Utc::now(), rule_id: None },
+        });
+
+        // Re-scan: b moved lines, c dependency dropped; the npm edge belongs
+        // to a detector that did not run
+        let sync = graph.sync_edges_from("repo:gh:test/a", &cargo, vec![detected("repo:gh:test/b", 9)]);
+        assert_eq!(sync, EdgeSync { added: 0, updated: 1, removed: 1 });
+        assert_eq!(graph.edge_count(), 3);
+        assert!(graph.edges().iter().any(|e| e.id == "edge:manual"));
+        assert!(graph.edges().iter().any(|e| e.id == "edge:npm"));
+        assert!(graph.aspects.annotations.is_empty());
+        let to_b = graph.edges_to("repo:gh:test/b");
+        assert_eq!(to_b[0].evidence[0].reference, "Cargo.toml:9");
+    }
+
+    /// app --uses--> lib, with a reference to lib from every store
+    fn cascade_fixture() -> (EcosystemGraph, String) {
+        use crate::types::{
+            AnnotationSource, BindingMode, ChangeSet, Polarity, Provider, ProviderType,
+        };
+
+        let mut graph = EcosystemGraph::new();
+        let app = make_test_repo("app");
+        let lib = make_test_repo("lib");
+        graph.add_repo(app.clone());
+        graph.add_repo(lib.clone());
+
+        let edge_id = Edge::generate_id(&app.id, &lib.id, RelationType::Uses, Channel::Api, None);
+        let edge = Edge {
+            kind: "Edge".into(),
+            id: edge_id.clone(),
+            from: app.id.clone(),
+            to: lib.id.clone(),
+            rel: RelationType::Uses,
+            channel: Channel::Api,
+            label: None,
+            evidence: vec![],
+            meta: EdgeMeta {
+                created_by: "manual".into(),
+                created_at: Utc::now(),
+            },
+        };
+        graph.add_edge(edge.clone()).unwrap();
+        graph.add_group(Group {
+            kind: "Group".into(),
+            id: "group:core".into(),
+            name: "core".into(),
+            description: None,
+            members: vec![app.id.clone(), lib.id.clone()],
+        });
+
+        let annotate = |target: &str| AspectAnnotation {
+            kind: "AspectAnnotation".into(),
+            id: AspectAnnotation::generate_id(target, "aspect:security"),
+            target: target.into(),
+            aspect_id: "aspect:security".into(),
+            weight: 2,
+            polarity: Polarity::Risk,
+            reason: "test".into(),
+            evidence: vec![],
+            source: AnnotationSource {
+                mode: "manual".into(),
+                who: "test".into(),
+                when: Utc::now(),
+                rule_id: None,
+            },
+        };
+        graph.aspects.annotations.push(annotate(&lib.id));
+        graph.aspects.annotations.push(annotate(&edge_id));
+
+        graph.slots.providers.push(Provider {
+            kind: "Provider".into(),
+            id: "provider:core.auth:lib".into(),
+            name: "lib".into(),
+            slot_id: "slot:core.auth".into(),
+            provider_type: ProviderType::Local,
+            repo_id: Some(lib.id.clone()),
+            external_uri: None,
+            interface_version: None,
+            capabilities: vec![],
+            priority: 0,
+            is_fallback: false,
+        });
+        graph.slots.bindings.push(SlotBinding {
+            kind: "SlotBinding".into(),
+            id: SlotBinding::generate_id(&app.id, "slot:core.auth"),
+            consumer_id: app.id.clone(),
+            slot_id: "slot:core.auth".into(),
+            provider_id: "provider:core.auth:lib".into(),
+            mode: BindingMode::Manual,
+            created_at: Utc::now(),
+            created_by: "test".into(),
+        });
+
+        graph.store.changesets.push(ChangeSet {
+            kind: "ChangeSet".into(),
+            scenario_id: "scenario:split".into(),
+            ops: vec![
+                ChangeOp::RemoveEdge { edge_id },
+                ChangeOp::AddAnnotation {
+                    annotation: annotate(&lib.id),
+                },
+                ChangeOp::SetGroupMembership {
+                    group_id: "group:core".into(),
+                    members: vec![lib.id.clone()],
+                    previous: Some(vec![app.id.clone(), lib.id.clone()]),
+                },
+                ChangeOp::AddEdge { edge },
+            ],
+        });
+
+        (graph, lib.id)
+    }
+
+    #[test]
+    fn test_with_scenario_layers_base_chain() {
+        let (mut graph, lib_id) = cascade_fixture();
+        let scenario = |name: &str, base: Option<&str>| Scenario {
+            kind: "Scenario".into(),
+            id: format!("scenario:{name}"),
+            name: name.into(),
+            base: base.map(|b| format!("scenario:{b}")),
+            description: None,
+            created_at: Utc::now(),
+        };
+        graph.store.scenarios.push(scenario("split", None));
+        graph.store.scenarios.push(scenario("drop-lib", Some("split")));
+        let lib_annotation = AspectAnnotation::generate_id(&lib_id, "aspect:security");
+        graph.store.changesets.push(ChangeSet {
+            kind: "ChangeSet".into(),
+            scenario_id: "scenario:drop-lib".into(),
+            ops: vec![
+                ChangeOp::RemoveAnnotation { annotation_id: lib_annotation.clone() },
+                ChangeOp::SetGroupMembership { group_id: "group:core".into(), members: vec![], previous: None },
+            ],
+        });
+        let edge_id = graph.store.edges[0].id.clone();
+
+        // split: the edge is removed (with its annotation) and re-added
+        let split = graph.with_scenario("split").unwrap();
+        assert_eq!(split.edge_count(), 1);
+        assert_eq!(split.aspects.annotations.len(), 1);
+        assert_eq!(split.store.groups[0].members, vec![lib_id.clone()]);
+        assert_eq!(split.slots.bindings.len(), 1);
+        assert_eq!(split.slots.bindings[0].mode, BindingMode::Scenario);
+
+        // drop-lib builds on split by name or full ID
+        let dropped = graph.with_scenario("scenario:drop-lib").unwrap();
+        assert!(dropped.aspects.annotations.is_empty());
+        assert!(dropped.store.groups[0].members.is_empty());
+        assert_eq!(dropped.edges_to(&lib_id).len(), 1);
+        assert_eq!(dropped.store.edges[0].id, edge_id);
+
+        // Removing the uses edge alone unbinds app from lib's provider
+        graph.store.changesets.push(ChangeSet {
+            kind: "ChangeSet".into(),
+            scenario_id: "scenario:unbind".into(),
+            ops: vec![ChangeOp::RemoveEdge { edge_id: edge_id.clone() }],
+        });
+        graph.store.scenarios.push(scenario("unbind", None));
+        assert!(graph.with_scenario("unbind").unwrap().slots.bindings.is_empty());
+        assert_eq!(graph.slots.bindings.len(), 1);
+
+        // The baseline is untouched
+        assert_eq!(graph.aspects.annotations.len(), 2);
+        assert_eq!(graph.store.groups[0].members.len(), 2);
+        assert_eq!(graph.with_scenario("baseline").unwrap().aspects.annotations.len(), 2);
+
+        assert!(graph.with_scenario("nope").is_err());
+        graph.store.scenarios[0].base = Some("scenario:drop-lib".into());
+        let err = graph.with_scenario("split").err().unwrap();
+        assert!(err.to_string().contains("loops"), "{err}");
+    }
+
+    #[test]
+    fn test_record_change_validates_against_scenario() {
+        let (mut graph, lib_id) = cascade_fixture();
+        graph.store.changesets.clear();
+        graph.store.scenarios.push(Scenario {
+            kind: "Scenario".into(),
+            id: "scenario:trim".into(),
+            name: "Trim".into(),
+            base: None,
+            description: None,
+            created_at: Utc::now(),
+        });
+        let edge_id = graph.store.edges[0].id.clone();
+
+        graph.record_change("Trim", ChangeOp::RemoveEdge { edge_id }).unwrap();
+        let members = ChangeOp::SetGroupMembership { group_id: "group:core".into(), members: vec![lib_id], previous: None };
+        graph.record_change("trim", members).unwrap();
+        assert_eq!(graph.store.changesets.len(), 1);
+        assert_eq!(graph.store.changesets[0].ops.len(), 2);
+        assert_eq!(graph.edge_count(), 1, "baseline untouched");
+
+        let missing = ChangeOp::SetGroupMembership { group_id: "group:none".into(), members: vec![], previous: None };
+        assert!(graph.record_change("trim", missing).is_err());
+        let mut orphan = graph.store.edges[0].clone();
+        orphan.to = "repo:gh:test/gone".into();
+        assert!(graph.record_change("trim", ChangeOp::AddEdge { edge: orphan }).is_err());
+        assert!(graph.record_change("nope", ChangeOp::RemoveAnnotation { annotation_id: "aa:x".into() }).is_err());
+        assert_eq!(graph.store.changesets[0].ops.len(), 2);
+    }
+
+    #[test]
+    fn test_remove_repo_cascades() {
+        let (mut graph, lib) = cascade_fixture();
+        let cascade = graph.remove_repo(&lib).unwrap();
+
+        assert_eq!(cascade.edges.len(), 1);
+        assert_eq!(cascade.groups, vec!["group:core"]);
+        assert_eq!(cascade.annotations.len(), 2);
+        assert_eq!(cascade.providers, vec!["provider:core.auth:lib"]);
+        assert_eq!(cascade.bindings.len(), 1);
+        assert_eq!(cascade.scenarios, vec!["scenario:split"]);
+
+        assert!(graph.get_repo(&lib).is_none());
+        assert_eq!(graph.node_count(), 1);
+        assert_eq!(graph.edge_count(), 0);
+        assert!(graph.aspects.annotations.is_empty());
+        assert!(graph.slots.providers.is_empty() && graph.slots.bindings.is_empty());
+        assert_eq!(graph.store.groups[0].members, vec!["repo:gh:test/app"]);
+        // Only the (now empty) group-membership op survives
+        assert!(matches!(
+            graph.store.changesets[0].ops.as_slice(),
+            [ChangeOp::SetGroupMembership { members, previous: Some(previous), .. }]
+                if members.is_empty() && previous == &["repo:gh:test/app"]
+        ));
+
+        assert!(graph.remove_repo(&lib).is_err());
+    }
+
+    #[test]
+    fn test_rekey_repo_rewrites_references() {
+        let (mut graph, lib) = cascade_fixture();
+        let new_id = "repo:gl:acme/lib";
+        let cascade = graph.rekey_repo(&lib, new_id).unwrap();
+        assert!(cascade.bindings.is_empty());
+        assert_eq!(cascade.total(), 6);
+
+        let repo = graph.get_repo(new_id).unwrap();
+        assert_eq!((repo.forge, repo.owner.as_str()), (Forge::GitLab, "acme"));
+        assert!(graph.get_repo(&lib).is_none());
+
+        // Derived edge/annotation IDs follow the new endpoint
+        let new_edge = Edge::generate_id(
+            "repo:gh:test/app",
+            new_id,
+            RelationType::Uses,
+            Channel::Api,
+            None,
+        );
+        assert_eq!(graph.edges_to(new_id)[0].id, new_edge);
+        let targets: Vec<_> = graph
+            .aspects
+            .annotations
+            .iter()
+            .map(|a| a.target.as_str())
+            .collect();
+        assert_eq!(targets, vec![new_id, new_edge.as_str()]);
+        assert_eq!(
+            graph.aspects.annotations[0].id,
+            AspectAnnotation::generate_id(new_id, "aspect:security")
+        );
+
+        assert_eq!(graph.slots.providers[0].repo_id.as_deref(), Some(new_id));
+        assert!(graph.store.groups[0].members.contains(&new_id.to_string()));
+        match &graph.store.changesets[0].ops[0] {
+            ChangeOp::RemoveEdge { edge_id } => assert_eq!(edge_id, &new_edge),
+            other => panic!("unexpected op {other:?}"),
+        }
+        match &graph.store.changesets[0].ops[2] {
+            ChangeOp::SetGroupMembership { members, previous, .. } => {
+                assert_eq!(members, &[new_id]);
+                assert_eq!(previous.as_deref(), Some(&["repo:gh:test/app".to_string(), new_id.to_string()][..]));
+            }
+            other => panic!("unexpected op {other:?}"),
+        }
+
+        assert!(graph.rekey_repo(new_id, "repo:gh:test/app").is_err());
+        assert!(graph
+            .rekey_repo("repo:gh:test/missing", "repo:gh:test/x")
+            .is_err());
+    }
+
+    #[test]
+    fn test_rekey_repo_skips_ops_whose_ids_stay_put() {
+        let (mut graph, lib) = cascade_fixture();
+        // A hand-named edge keeps its ID when its endpoint is re-keyed
+        let mut custom = graph.store.edges[0].clone();
+        custom.id = "edge:custom".into();
+        custom.label = Some("custom".into());
+        graph.store.edges.push(custom);
+        graph.store.changesets.push(crate::types::ChangeSet {
+            kind: "ChangeSet".into(),
+            scenario_id: "scenario:keep".into(),
+            ops: vec![ChangeOp::RemoveEdge { edge_id: "edge:custom".into() }],
+        });
+
+        let cascade = graph.rekey_repo(&lib, "repo:gl:acme/lib").unwrap();
+        assert!(cascade.edges.contains(&"edge:custom".to_string()));
+        assert_eq!(cascade.scenarios, vec!["scenario:split"]);
+
+        let map = HashMap::from([("a".to_string(), "a".to_string()), ("b".to_string(), "c".to_string())]);
+        let (mut same, mut moved, mut other) = ("a".to_string(), "b".to_string(), "x".to_string());
+        assert!(!remap(&mut same, &map));
+        assert!(remap(&mut moved, &map));
+        assert!(!remap(&mut other, &map));
+        assert_eq!((same.as_str(), moved.as_str(), other.as_str()), ("a", "c", "x"));
+    }
+
+    #[test]
+    fn test_merge_repo_dedupes_and_records_alias() {
+        let (mut graph, lib) = cascade_fixture();
+        let mut local = make_test_repo("lib-local");
+        local.id = "repo:local:0123456789ab".into();
+        local.forge = Forge::Local;
+        local.metadata.insert("phase".into(), "beta".into());
+        graph.add_repo(local.clone());
+        graph.store.groups[0].members.push(local.id.clone());
+
+        // The same dependency recorded against both nodes, plus one between them
+        for (from, to) in [("repo:gh:test/app", local.id.as_str()), (local.id.as_str(), lib.as_str())] {
+            graph
+                .add_edge(Edge {
+                    kind: "Edge".into(),
+                    id: Edge::generate_id(from, to, RelationType::Uses, Channel::Api, None),
+                    from: from.into(),
+                    to: to.into(),
+                    rel: RelationType::Uses,
+                    channel: Channel::Api,
+                    label: None,
+                    evidence: vec![],
+                    meta: EdgeMeta {
+                        created_by: "manual".into(),
+                        created_at: Utc::now(),
+                    },
+                })
+                .unwrap();
+        }
+
+        let between = Edge::generate_id(&local.id, &lib, RelationType::Uses, Channel::Api, None);
+        let loop_note = AspectAnnotation {
+            id: AspectAnnotation::generate_id(&between, "aspect:security"),
+            target: between.clone(),
+            ..graph.aspects.annotations[0].clone()
+        };
+        graph.aspects.annotations.push(loop_note.clone());
+
+        let cascade = graph.merge_repo(&local.id, &lib).unwrap();
+        assert_eq!(cascade.edges.len(), 1);
+        assert_eq!(cascade.removed_edges, vec![between]);
+        assert_eq!(cascade.removed_annotations, vec![loop_note.id.clone()]);
+        assert_eq!(cascade.groups, vec!["group:core"]);
+        assert!(!graph.aspects.annotations.iter().any(|a| a.id == loop_note.id));
+
+        assert!(graph.get_repo(&local.id).is_none());
+        assert_eq!(graph.edge_count(), 1);
+        assert_eq!(graph.store.groups[0].members, vec!["repo:gh:test/app", lib.as_str()]);
+        let merged = graph.get_repo(&lib).unwrap();
+        assert_eq!(merged.aliases(), vec![local.id.as_str()]);
+        assert_eq!(merged.metadata.get("phase").map(String::as_str), Some("beta"));
+
+        assert!(graph.merge_repo(&lib, &lib).is_err());
+        assert!(graph.merge_repo(&local.id, &lib).is_err());
+    }
+
     #[test]
     fn test_to_dot() {
         let mut graph = EcosystemGraph::new();
@@ -12,22 +12,35 @@
 #![allow(clippy::module_name_repetitions)]
 
 #![forbid(unsafe_code)]
+pub mod analysis;
 pub mod commands;
 pub mod config;
+pub mod detect;
+pub mod diagram;
+pub mod diff;
+pub mod envelope;
 pub mod graph;
 /// Importers that populate the graph from external sources (the estate manifest).
 pub mod importers;
+pub mod patch;
+pub mod progress;
+pub mod query;
+pub mod rewrite;
+pub mod scan_cache;
 pub mod scanner;
+pub mod scenario;
+pub mod snapshot;
 pub mod tui;
 /// VeriSimDB HTTP client — replaces flat JSON file storage.
 pub mod verisimdb;
+pub mod viewer;
 
 /// Core data types matching DATA-MODEL.adoc specification
 pub mod types {
     use chrono::{DateTime, Utc};
     use serde::{Deserialize, Serialize};
     use sha2::{Digest, Sha256};
-    use std::collections::HashMap;
+    use std::collections::{BTreeMap, HashMap};
     use std::path::PathBuf;
 
     // =========================================================================
@@ -72,6 +85,20 @@ pub fn code(&self) -> &'static str {
             }
         }
 
+        /// Parse a forge from its short code (`gh`, `gl`, …)
+        #[must_use]
+        pub fn from_code(code: &str) -> Option<Self> {
+            match code {
+                "gh" => Some(Self::GitHub),
+                "gl" => Some(Self::GitLab),
+                "bb" => Some(Self::Bitbucket),
+                "cb" => Some(Self::Codeberg),
+                "sr" => Some(Self::Sourcehut),
+                "local" => Some(Self::Local),
+                _ => None,
+            }
+        }
+
         /// Parse a forge from a remote URL
         #[must_use]
         pub fn from_url(url: &str) -> Option<Self> {
@@ -277,6 +304,35 @@ pub fn local_id(path: &std::path::Path) -> String {
             let hash = hex::encode(hasher.finalize());
             format!("repo:local:{}", &hash[..12])
         }
+
+        /// Metadata key listing former IDs merged into this repo (comma-separated)
+        pub const ALIASES_KEY: &'static str = "aliases";
+
+        /// Metadata key holding the repo's root commit, used to recognise a
+        /// checkout that has moved or gained a forge remote
+        pub const ROOT_COMMIT_KEY: &'static str = "root_commit";
+
+        /// Former IDs of this repo, oldest first
+        #[must_use]
+        pub fn aliases(&self) -> Vec<&str> {
+            self.metadata
+                .get(Self::ALIASES_KEY)
+                .map(|a| a.split(',').filter(|s| !s.is_empty()).collect())
+                .unwrap_or_default()
+        }
+
+        /// Record a former ID of this repo (no-op if already recorded)
+        pub fn add_alias(&mut self, id: &str) {
+            if id == self.id || self.aliases().contains(&id) {
+                return;
+            }
+            let mut aliases = self.aliases().join(",");
+            if !aliases.is_empty() {
+                aliases.push(',');
+            }
+            aliases.push_str(id);
+            self.metadata.insert(Self::ALIASES_KEY.into(), aliases);
+        }
     }
 
     /// Repository visibility
@@ -382,6 +438,35 @@ pub enum Channel {
         Unknown,
     }
 
+    impl Channel {
+        /// Short name used on the command line and in exports
+        #[must_use]
+        pub fn code(&self) -> &'static str {
+            match self {
+                Self::Api => "api",
+                Self::Artifact => "artifact",
+                Self::Config => "config",
+                Self::Runtime => "runtime",
+                Self::Human => "human",
+                Self::Unknown => "unknown",
+            }
+        }
+
+        /// Parse a channel from its short name
+        #[must_use]
+        pub fn from_code(code: &str) -> Option<Self> {
+            match code {
+                "api" => Some(Self::Api),
+                "artifact" => Some(Self::Artifact),
+                "config" => Some(Self::Config),
+                "runtime" => Some(Self::Runtime),
+                "human" => Some(Self::Human),
+                "unknown" => Some(Self::Unknown),
+                _ => None,
+            }
+        }
+    }
+
     /// Relationship types
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
     #[serde(rename_all = "lowercase")]
@@ -401,6 +486,35 @@ pub enum RelationType {
         RefersTo,
     }
 
+    impl RelationType {
+        /// Short name used on the command line and in exports
+        #[must_use]
+        pub fn code(&self) -> &'static str {
+            match self {
+                Self::Uses => "uses",
+                Self::Provides => "provides",
+                Self::Extends => "extends",
+                Self::Mirrors => "mirrors",
+                Self::Replaces => "replaces",
+                Self::RefersTo => "refersto",
+            }
+        }
+
+        /// Parse a relation from its short name (`refers-to` is accepted too)
+        #[must_use]
+        pub fn from_code(code: &str) -> Option<Self> {
+            match code {
+                "uses" => Some(Self::Uses),
+                "provides" => Some(Self::Provides),
+                "extends" => Some(Self::Extends),
+                "mirrors" => Some(Self::Mirrors),
+                "replaces" => Some(Self::Replaces),
+                "refers-to" | "refersto" => Some(Self::RefersTo),
+                _ => None,
+            }
+        }
+    }
+
     /// Edge between repositories (or components)
     #[derive(Debug, Clone, Serialize, Deserialize)]
     pub struct Edge {
@@ -528,6 +642,18 @@ pub struct AspectAnnotation {
         pub source: AnnotationSource,
     }
 
+    impl AspectAnnotation {
+        /// Generate a deterministic annotation ID
+        #[must_use]
+        pub fn generate_id(target: &str, aspect_id: &str) -> String {
+            let mut hasher = Sha256::new();
+            hasher.update(target.as_bytes());
+            hasher.update(aspect_id.as_bytes());
+            let hash = hex::encode(hasher.finalize());
+            format!("aa:{}", &hash[..8])
+        }
+    }
+
     // =========================================================================
     // Scenarios
     // =========================================================================
@@ -579,6 +705,10 @@ pub enum ChangeOp {
             group_id: String,
             /// New member list
             members: Vec<String>,
+            /// Members the change replaced, as seen when it was recorded;
+            /// promotion refuses the change if the group has moved on since
+            #[serde(default, skip_serializing_if = "Option::is_none")]
+            previous: Option<Vec<String>>,
         },
     }
 
@@ -644,6 +774,9 @@ pub struct Slot {
         /// Required capabilities
         #[serde(default)]
         pub required_capabilities: Vec<String>,
+        /// How a binding of this slot shows up in a consumer's files
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        pub templates: Vec<BindingTemplate>,
     }
 
     impl Slot {
@@ -654,6 +787,60 @@ pub fn generate_id(category: &str, name: &str) -> String {
         }
     }
 
+    /// Where a slot binding lives in a consumer repo: the files it appears in
+    /// and how to rewrite them for a different provider.
+    ///
+    /// The rule's target (key path, pointer, pattern) may use `{id}`, `{name}`,
+    /// `{uri}`, `{repo}` and `{version}`, filled from the provider currently
+    /// bound; its value is filled from the provider being bound.
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct BindingTemplate {
+        /// Files the binding appears in, relative to the consumer's checkout
+        /// (e.g. `**/Cargo.toml`)
+        pub glob: String,
+        /// How to rewrite them
+        pub rule: RewriteRule,
+    }
+
+    /// How a binding template rewrites a file
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(tag = "kind", rename_all = "snake_case")]
+    pub enum RewriteRule {
+        /// Set the string at a dotted TOML key path (`dependencies.runtime.package`)
+        TomlKey {
+            /// Dotted key path
+            path: String,
+            /// New value
+            value: String,
+        },
+        /// Set the string at a JSON pointer (`/runtime/endpoint`)
+        JsonPointer {
+            /// RFC 6901 pointer
+            pointer: String,
+            /// New value
+            value: String,
+        },
+        /// Replace every match of a regex (`$1` refers to a capture group)
+        Regex {
+            /// Pattern
+            pattern: String,
+            /// Replacement
+            replacement: String,
+        },
+    }
+
+    impl RewriteRule {
+        /// Human-readable description
+        #[must_use]
+        pub fn description(&self) -> String {
+            match self {
+                Self::TomlKey { path, value } => format!("toml key {path} = {value:?}"),
+                Self::JsonPointer { pointer, value } => format!("json pointer {pointer} = {value:?}"),
+                Self::Regex { pattern, replacement } => format!("regex /{pattern}/ -> {replacement:?}"),
+            }
+        }
+    }
+
     /// A provider implements a slot's capability.
     /// Can be local (repo-based) or external (ecosystem service)
     #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1016,6 +1203,18 @@ pub enum FileChangeType {
         Delete,
     }
 
+    impl FileChangeType {
+        /// The change that undoes this one
+        #[must_use]
+        pub fn inverse(self) -> Self {
+            match self {
+                FileChangeType::Create => FileChangeType::Delete,
+                FileChangeType::Modify => FileChangeType::Modify,
+                FileChangeType::Delete => FileChangeType::Create,
+            }
+        }
+    }
+
     /// Plan status
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
     #[serde(rename_all = "lowercase")]
@@ -1026,6 +1225,8 @@ pub enum PlanStatus {
         Ready,
         /// Plan has been applied
         Applied,
+        /// Some stages of a staged apply are done; the rest can be resumed
+        PartiallyApplied,
         /// Plan was rolled back
         RolledBack,
         /// Plan was cancelled
@@ -1065,6 +1266,15 @@ pub struct Plan {
         pub applied_at: Option<DateTime<Utc>>,
         /// Rollback plan ID (if this plan was applied)
         pub rollback_plan_id: Option<String>,
+        /// Indexes of the operations a staged apply has done so far (while
+        /// `PartiallyApplied`)
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        pub applied_ops: Vec<usize>,
+        /// Ref each checkout was on before its file changes were applied on
+        /// the plan branch (repo ID -> ref name, or commit ID if detached),
+        /// for undo to switch back to
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        pub base_refs: BTreeMap<String, String>,
     }
 
     impl Plan {
@@ -1179,10 +1389,10 @@ pub fn get_diff(&self, plan_id: &str) -> Option<&PlanDiff> {
         /// Generate a rollback plan from an existing plan
         #[must_use]
         pub fn generate_rollback(plan: &Plan) -> Plan {
-            let rollback_ops: Vec<PlanOp> = plan.operations.iter().rev().filter_map(|op| {
+            let rollback_ops: Vec<PlanOp> = plan.operations.iter().rev().map(|op| {
                 match op {
                     PlanOp::SwitchBinding { binding_id, consumer_id, slot_id, from_provider_id, to_provider_id, risk, .. } => {
-                        Some(PlanOp::SwitchBinding {
+                        PlanOp::SwitchBinding {
                             binding_id: binding_id.clone(),
                             consumer_id: consumer_id.clone(),
                             slot_id: slot_id.clone(),
@@ -1190,31 +1400,35 @@ pub fn generate_rollback(plan: &Plan) -> Plan {
                             to_provider_id: from_provider_id.clone(),
                             risk: *risk,
                             reason: format!("Rollback of plan {}", plan.id),
-                        })
+                        }
                     }
                     PlanOp::CreateBinding { consumer_id, slot_id, provider_id, risk, .. } => {
-                        Some(PlanOp::RemoveBinding {
+                        PlanOp::RemoveBinding {
                             binding_id: SlotBinding::generate_id(consumer_id, slot_id),
                             consumer_id: consumer_id.clone(),
                             slot_id: slot_id.clone(),
                             provider_id: provider_id.clone(),
                             risk: *risk,
                             reason: format!("Rollback of plan {}", plan.id),
-                        })
+                        }
                     }
                     PlanOp::RemoveBinding { consumer_id, slot_id, provider_id, risk, .. } => {
-                        Some(PlanOp::CreateBinding {
+                        PlanOp::CreateBinding {
                             consumer_id: consumer_id.clone(),
                             slot_id: slot_id.clone(),
                             provider_id: provider_id.clone(),
                             risk: *risk,
                             reason: format!("Rollback of plan {}", plan.id),
-                        })
+                        }
                     }
-                    PlanOp::FileChange { .. } => {
-                        // File changes need more complex rollback logic
-                        // For now, we skip them - f4 will handle this
-                        None
+                    PlanOp::FileChange { repo_id, file_path, change_type, diff, risk } => {
+                        PlanOp::FileChange {
+                            repo_id: repo_id.clone(),
+                            file_path: file_path.clone(),
+                            change_type: change_type.inverse(),
+                            diff: diff.as_deref().and_then(crate::patch::reverse_diff),
+                            risk: *risk,
+                        }
                     }
                 }
             }).collect();
@@ -1231,6 +1445,8 @@ pub fn generate_rollback(plan: &Plan) -> Plan {
                 created_at: Utc::now(),
                 created_by: "system".into(),
                 applied_at: None,
+                applied_ops: Vec::new(),
+                base_refs: BTreeMap::new(),
                 rollback_plan_id: None,
             }
         }
@@ -1265,6 +1481,37 @@ pub enum ApplyResult {
         Failure,
         /// Plan was rolled back (manually or auto)
         RolledBack,
+        /// A staged apply stopped with stages left to do
+        Incomplete,
+    }
+
+    /// What happened to one stage (one repo's operations) of a staged apply
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    pub enum StageOutcome {
+        /// Every operation succeeded
+        Applied,
+        /// An operation failed; the stage's later operations were not run
+        Failed,
+        /// A failed stage whose operations were reversed (auto-rollback)
+        RolledBack,
+        /// Declined; left for a later resume
+        Skipped,
+    }
+
+    /// Result of one stage of a staged apply
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct StageResult {
+        /// Repository the stage's operations touch
+        pub repo_id: String,
+        /// Plan operation indexes in the stage
+        pub op_indexes: Vec<usize>,
+        /// Highest risk among them
+        pub risk: RiskLevel,
+        /// What happened
+        pub outcome: StageOutcome,
+        /// When the stage was decided
+        pub decided_at: DateTime<Utc>,
     }
 
     /// Audit log entry for a plan application
@@ -1294,6 +1541,9 @@ pub struct AuditEntry {
         pub health_check_passed: Option<bool>,
         /// Any notes or warnings
         pub notes: Vec<String>,
+        /// Per-stage results of a staged apply
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        pub stages: Vec<StageResult>,
     }
 
     impl AuditEntry {
@@ -5,6 +5,7 @@
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
+use std::io::IsTerminal;
 
 // Use the library modules
 use reposystem::commands;
@@ -65,11 +66,37 @@ enum Commands {
         /// Detect workspaces (Cargo, npm, etc.)
         #[arg(long)]
         detect_workspaces: bool,
+
+        /// Only run these edge detectors on deep scans (comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        detectors: Option<Vec<String>>,
+
+        /// Skip these edge detectors on deep scans (comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        skip_detectors: Vec<String>,
+
+        /// Rescan every repo, ignoring the scan cache
+        #[arg(long)]
+        full: bool,
+
+        /// Remove repos whose checkout no longer exists from the graph
+        #[arg(long)]
+        prune: bool,
+
+        /// Merge local-only repos into the forge repo they became (same
+        /// checkout path or root commit), migrating edges and annotations
+        #[arg(long)]
+        merge_transfers: bool,
+
+        /// Worker threads for scanning (0 = one per CPU)
+        #[arg(short, long, default_value_t = 0)]
+        jobs: usize,
     },
 
-    /// Import the estate from a manifest (repos.toml) into the graph
+    /// Import the estate from a manifest (repos.toml) or an estate export
+    /// (JSON, YAML or TOML) into the graph
     Import {
-        /// Import source (currently: manifest)
+        /// Import source: manifest, or estate (with --file)
         #[arg(default_value = "manifest")]
         source: String,
 
@@ -88,14 +115,28 @@ enum Commands {
         /// Estate display name
         #[arg(long)]
         estate_name: Option<String>,
+
+        /// Estate export to read for the `estate` source (.json, .yaml or .toml)
+        #[arg(long)]
+        file: Option<std::path::PathBuf>,
     },
 
     /// Launch interactive TUI
-    View,
+    View {
+        /// Start with a filter applied (e.g. "repos where tag = rust");
+        /// press / in the viewer to change it
+        #[arg(long)]
+        query: Option<String>,
+
+        /// View a scenario's what-if graph instead of the baseline
+        #[arg(long)]
+        scenario: Option<String>,
+    },
 
     /// Export graph to various formats
     Export {
-        /// Output format (dot, json, yaml, toml)
+        /// Output format (dot, mermaid, plantuml, graphml, gexf, json,
+        /// estate-json, yaml, toml, html)
         #[arg(short, long, default_value = "dot")]
         format: String,
 
@@ -106,6 +147,19 @@ enum Commands {
         /// Filter by aspect
         #[arg(long)]
         aspect: Option<String>,
+
+        /// Colour nodes by an aspect's weights, with weak-link badges and a
+        /// legend (dot and mermaid)
+        #[arg(long)]
+        overlay: Option<String>,
+
+        /// Only export what a query selects (e.g. "repos where tag = rust")
+        #[arg(long)]
+        query: Option<String>,
+
+        /// Export a scenario's what-if graph instead of the baseline
+        #[arg(long)]
+        scenario: Option<String>,
     },
 
     /// Manage edges (relationships) between repositories
@@ -136,18 +190,39 @@ enum Commands {
         /// Evidence reference (file path, URL, etc.)
         #[arg(long)]
         evidence: Option<String>,
+
+        /// Record the change in this scenario instead of the baseline
+        #[arg(long)]
+        in_scenario: Option<String>,
+    },
+
+    /// Remove, re-key or merge a repository, cleaning up every reference to it
+    Repo {
+        /// Action: rm, mv, merge
+        action: String,
+
+        /// Repository (name or ID)
+        repo: Option<String>,
+
+        /// New repository ID (for mv, e.g. repo:gl:owner/name) or the
+        /// repository to merge into (for merge)
+        new_id: Option<String>,
     },
 
     /// Manage repository groups
     Group {
-        /// Action: create, add, remove, delete, list, show
+        /// Action: create, add, remove, set, delete, list, show
         action: String,
 
         /// Group name
         name: Option<String>,
 
-        /// Repositories to add/remove
+        /// Repositories to add/remove (or the full member list, for set)
         repos: Vec<String>,
+
+        /// Record add/remove/set in this scenario instead of the baseline
+        #[arg(long)]
+        in_scenario: Option<String>,
     },
 
     /// Manage aspect annotations
@@ -178,24 +253,36 @@ enum Commands {
         /// Evidence reference
         #[arg(long)]
         evidence: Option<String>,
+
+        /// Record tag/remove in this scenario instead of the baseline
+        #[arg(long)]
+        in_scenario: Option<String>,
     },
 
     /// Manage scenarios
     Scenario {
-        /// Action: create, delete, list, show, compare
+        /// Action: create, delete, list, show, compare, edit, promote, merge
         action: String,
 
-        /// Scenario name (required for create/delete/show/compare)
+        /// Scenario name (required for all actions but list); for merge, the
+        /// scenario to merge into
         name: Option<String>,
 
-        /// Base scenario for comparison
+        /// For edit: the change and its flags, one of add-edge, remove-edge,
+        /// annotate, unannotate, set-group (e.g. add-edge --from a --to b).
+        /// For merge: the scenario whose changes are merged in
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+
+        /// Base scenario for create; for compare, the scenario to compare
+        /// against (default: the baseline)
         #[arg(long)]
         base: Option<String>,
     },
 
     /// Manage slots (swappable capabilities)
     Slot {
-        /// Action: create, delete, list, show
+        /// Action: create, delete, list, show, add-template, remove-template
         action: String,
 
         /// Slot name
@@ -216,6 +303,27 @@ enum Commands {
         /// Required capabilities (comma-separated)
         #[arg(long)]
         capabilities: Option<String>,
+
+        /// Binding template: files the binding appears in (e.g. "**/Cargo.toml")
+        #[arg(long)]
+        glob: Option<String>,
+
+        /// Binding template: dotted TOML key path to rewrite
+        #[arg(long, conflicts_with_all = ["json_pointer", "regex"])]
+        toml_key: Option<String>,
+
+        /// Binding template: JSON pointer to rewrite
+        #[arg(long, conflicts_with = "regex")]
+        json_pointer: Option<String>,
+
+        /// Binding template: regex to rewrite
+        #[arg(long)]
+        regex: Option<String>,
+
+        /// Binding template: new value or regex replacement, with {id}, {name},
+        /// {uri}, {repo} or {version} from the provider being bound
+        #[arg(long)]
+        value: Option<String>,
     },
 
     /// Manage providers (slot implementations)
@@ -292,6 +400,15 @@ enum Commands {
         /// Plan description
         #[arg(long)]
         description: Option<String>,
+
+        /// Also propose bindings for repos that have none for a slot with a
+        /// compatible provider
+        #[arg(long)]
+        include_suggestions: bool,
+
+        /// Write the plan's file diffs to this file as a patch bundle (diff)
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
     },
 
     /// Apply plans and manage execution
@@ -313,6 +430,112 @@ enum Commands {
         /// Skip health check after apply
         #[arg(long)]
         skip_health_check: bool,
+
+        /// Apply one repo at a time, showing each stage's diff and risk and
+        /// asking for confirmation; rerun to resume a partially applied plan
+        #[arg(long)]
+        staged: bool,
+
+        /// Read stage answers (y, n or q, one per line) from this file
+        /// instead of prompting
+        #[arg(long, requires = "staged")]
+        answers: Option<std::path::PathBuf>,
+    },
+
+    /// Manage the store snapshots taken before each apply and undo
+    Snapshot {
+        /// Action: list, restore, prune, retention
+        action: String,
+
+        /// Snapshot ID, or "latest" (restore)
+        id: Option<String>,
+
+        /// Keep at most this many snapshots (prune, retention)
+        #[arg(long)]
+        keep: Option<usize>,
+
+        /// Remove snapshots older than this many days (prune, retention)
+        #[arg(long)]
+        max_age_days: Option<u32>,
+    },
+
+    /// Analyze dependency structure: cycles, release layers, longest chain,
+    /// fan-in/fan-out (over uses/extends edges)
+    Analyze {
+        /// Number of repos to show in the fan-in/fan-out rankings
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+    },
+
+    /// Show everything that transitively depends on a repo, seam or edge
+    Impact {
+        /// Repo (name or ID), seam ID or edge ID assumed to break
+        target: String,
+
+        /// Only follow these relations (comma-separated, e.g. uses,extends)
+        #[arg(long, value_delimiter = ',')]
+        rel: Vec<String>,
+
+        /// Only follow these channels (comma-separated, e.g. api,runtime)
+        #[arg(long, value_delimiter = ',')]
+        channel: Vec<String>,
+
+        /// Stop after this many hops (0 = unlimited)
+        #[arg(long, default_value_t = 0)]
+        depth: usize,
+
+        /// Leave out repos reached with a lower path confidence (0.0-1.0)
+        #[arg(long, default_value_t = 0.0)]
+        min_confidence: f64,
+    },
+
+    /// Show how two repos (or seams) are connected
+    Path {
+        /// Start repo (name or ID) or seam ID
+        from: String,
+
+        /// End repo (name or ID) or seam ID
+        to: String,
+
+        /// Longest path to list, in hops
+        #[arg(long, default_value_t = 6)]
+        max_hops: usize,
+
+        /// Stop listing after this many paths
+        #[arg(long, default_value_t = 100)]
+        limit: usize,
+
+        /// Also walk edges backwards (finds coupling through shared
+        /// dependencies and seams)
+        #[arg(long)]
+        undirected: bool,
+    },
+
+    /// Select repos, edges or annotations with a filter expression, e.g.
+    /// `repos where tag = rust and has_edge(rel = uses, to = group:core)`
+    Query {
+        /// Query text: `<repos|edges|annotations> [where <predicate>]`
+        query: String,
+
+        /// Print matching IDs only, one per line
+        #[arg(long)]
+        ids: bool,
+    },
+
+    /// Show semantic changes between two graph snapshots: added, removed and
+    /// changed repos, edges, groups, annotations, slots and bindings
+    Diff {
+        /// Old snapshot: a git revision (`HEAD~1`), `<rev>:<path>`, a data
+        /// directory, an estate export or a graph.json
+        old: String,
+
+        /// New snapshot, in the same forms (default: the current graph)
+        #[arg(default_value = "current")]
+        new: String,
+
+        /// Output format (text, json, dot)
+        #[arg(short, long, default_value = "text")]
+        format: String,
     },
 
     /// Identify weak links in ecosystem
@@ -324,6 +547,10 @@ enum Commands {
         /// Minimum severity
         #[arg(long)]
         severity: Option<String>,
+
+        /// Analyze a scenario's what-if graph instead of the baseline
+        #[arg(long)]
+        scenario: Option<String>,
     },
 
     /// Get or set configuration
@@ -363,48 +590,90 @@ fn main() -> Result<()> {
 
     // Execute command
     match cli.command {
-        Commands::Scan { path, deep, shallow, metadata, detect_workspaces } => {
-            commands::scan::run(path, deep, shallow, metadata, detect_workspaces, cli.json)
+        Commands::Scan {
+            path,
+            deep,
+            shallow,
+            metadata,
+            detect_workspaces,
+            detectors,
+            skip_detectors,
+            full,
+            prune,
+            merge_transfers,
+            jobs,
+        } => {
+            let args = commands::scan::ScanArgs {
+                path,
+                deep,
+                shallow,
+                metadata,
+                detect_workspaces,
+                detectors,
+                skip_detectors,
+                full,
+                prune,
+                merge_transfers,
+                jobs,
+                quiet: cli.quiet,
+            };
+            commands::scan::run(args, cli.json)
         }
-        Commands::Import { source, manifest, groups, estate, estate_name } => {
+        Commands::Import { source, manifest, groups, estate, estate_name, file } => {
             let args = commands::import::ImportArgs {
                 manifest,
                 groups,
                 estate,
                 estate_name,
+                file,
             };
             commands::import::run(&source, args)
         }
-        Commands::View => {
-            commands::view::run()
+        Commands::View { query, scenario } => {
+            commands::view::run(query.as_deref(), scenario.as_deref())
         }
-        Commands::Export { format, output, aspect } => {
-            commands::export::run(&format, output, aspect)
+        Commands::Export { format, output, aspect, overlay, query, scenario } => {
+            commands::export::run(&format, output, aspect, overlay.as_deref(), query.as_deref(), scenario.as_deref())
         }
-        Commands::Edge { action, from, to, rel, channel, label, evidence } => {
-            commands::edge::run(&action, from, to, rel, channel, label, evidence)
+        Commands::Edge { action, from, to, rel, channel, label, evidence, in_scenario } => {
+            let args = commands::edge::EdgeArgs { rel, channel, label, evidence, in_scenario };
+            commands::edge::run(&action, from, to, args)
         }
-        Commands::Group { action, name, repos } => {
-            commands::group::run(&action, name, repos)
+        Commands::Group { action, name, repos, in_scenario } => {
+            commands::group::run(&action, name, repos, in_scenario.as_deref())
         }
-        Commands::Aspect { action, target, aspect, weight, polarity, reason, evidence } => {
+        Commands::Aspect { action, target, aspect, weight, polarity, reason, evidence, in_scenario } => {
             let args = commands::aspect::AspectArgs {
                 weight,
                 polarity,
                 reason,
                 evidence,
+                in_scenario,
             };
             commands::aspect::run(&action, target, aspect, args)
         }
-        Commands::Scenario { action, name, base } => {
-            commands::scenario::run(&action, name, base)
+        Commands::Repo {
+            action,
+            repo,
+            new_id,
+        } => commands::repo::run(&action, repo, new_id, cli.json),
+        Commands::Scenario { action, name, base, args } => {
+            commands::scenario::run(&action, name, base, &args, cli.json)
         }
-        Commands::Slot { action, name, category, iface_version, description, capabilities } => {
+        Commands::Slot {
+            action, name, category, iface_version, description, capabilities,
+            glob, toml_key, json_pointer, regex, value,
+        } => {
             let args = commands::slot::SlotArgs {
                 category,
                 version: iface_version,
                 description,
                 capabilities,
+                glob,
+                toml_key,
+                json_pointer,
+                regex,
+                value,
             };
             commands::slot::run_slot(&action, name, args)
         }
@@ -429,24 +698,67 @@ fn main() -> Result<()> {
             };
             commands::slot::run_binding(&action, args)
         }
-        Commands::Plan { action, name, scenario, description } => {
+        Commands::Plan { action, name, scenario, description, include_suggestions, output } => {
             let args = commands::plan::PlanArgs {
                 scenario,
                 name: name.clone(),
                 description,
+                include_suggestions,
+                output,
+                color: !cli.no_color && std::io::stdout().is_terminal(),
             };
             commands::plan::run(&action, name, args)
         }
-        Commands::Apply { action, name, dry_run, auto_rollback, skip_health_check } => {
+        Commands::Apply { action, name, dry_run, auto_rollback, skip_health_check, staged, answers } => {
             let args = commands::apply::ApplyArgs {
                 dry_run,
                 auto_rollback,
                 skip_health_check,
+                staged,
+                answers,
+                color: !cli.no_color && std::io::stdout().is_terminal(),
             };
             commands::apply::run(&action, name, args)
         }
-        Commands::WeakLinks { aspect, severity } => {
-            commands::weak_links::run(aspect, severity)
+        Commands::Snapshot { action, id, keep, max_age_days } => {
+            let args = commands::snapshot::SnapshotArgs { keep, max_age_days, json: cli.json };
+            commands::snapshot::run(&action, id, &args)
+        }
+        Commands::Analyze { top } => commands::analyze::run(top, cli.json),
+        Commands::Impact {
+            target,
+            rel,
+            channel,
+            depth,
+            min_confidence,
+        } => {
+            let args = commands::impact::ImpactArgs {
+                target,
+                rels: rel,
+                channels: channel,
+                depth,
+                min_confidence,
+            };
+            commands::impact::run(args, cli.json)
+        }
+        Commands::Path {
+            from,
+            to,
+            max_hops,
+            limit,
+            undirected,
+        } => {
+            let query = reposystem::analysis::PathQuery {
+                max_hops,
+                limit,
+                undirected,
+            };
+            commands::path::run(&from, &to, &query, cli.json)
+        }
+        Commands::Query { query, ids } => commands::query::run(&query, ids, cli.json),
+        Commands::Diff { old, new, format } => commands::diff::run(&old, &new, &format, cli.json),
+        Commands::WeakLinks { aspect, severity, scenario } => {
+            commands::weak_links::run(aspect, severity, scenario.as_deref())
         }
         Commands::Config { key, value } => {
             commands::config::run(&key, value)
@@ -0,0 +1,553 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Unified diffs for plan file changes
+//!
+//! A [`Patch`] is a single-file unified diff, as carried by
+//! `PlanOp::FileChange`. [`Patch::apply`] works like `git apply`: every hunk's
+//! context and removed lines must be found in the file (a hunk may have moved
+//! by a few lines, but never overlaps the previous one), and nothing is
+//! written unless the whole patch applies, so it doubles as the
+//! `git apply --check` step. [`Patch::reverse`] gives the patch that undoes
+//! it, for rollback. [`Patch::between`] makes one from two versions of a
+//! file.
+
+use anyhow::{bail, Context, Result};
+use std::fmt;
+
+/// A unified diff for one file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Patch {
+    /// Path before the change; `None` when the patch creates the file
+    pub old_path: Option<String>,
+    /// Path after the change; `None` when the patch deletes the file
+    pub new_path: Option<String>,
+    /// Hunks in file order
+    pub hunks: Vec<Hunk>,
+}
+
+/// One `@@ -a,b +c,d @@` section
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    /// First line of the hunk in the old file (1-based; 0 for an empty file)
+    pub old_start: usize,
+    /// First line of the hunk in the new file
+    pub new_start: usize,
+    /// Lines of the hunk
+    pub lines: Vec<Line>,
+    /// The old side's last line has no trailing newline
+    pub old_no_newline: bool,
+    /// The new side's last line has no trailing newline
+    pub new_no_newline: bool,
+}
+
+/// A line of a hunk, without its line terminator
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Line {
+    /// Unchanged
+    Context(String),
+    /// Only in the old file
+    Remove(String),
+    /// Only in the new file
+    Add(String),
+}
+
+impl Hunk {
+    fn old_lines(&self) -> impl Iterator<Item = &str> {
+        self.lines.iter().filter_map(|l| match l {
+            Line::Context(s) | Line::Remove(s) => Some(s.as_str()),
+            Line::Add(_) => None,
+        })
+    }
+
+    fn new_lines(&self) -> impl Iterator<Item = &str> {
+        self.lines.iter().filter_map(|l| match l {
+            Line::Context(s) | Line::Add(s) => Some(s.as_str()),
+            Line::Remove(_) => None,
+        })
+    }
+}
+
+impl Patch {
+    /// Parse a unified diff; anything before the `---` header (such as a
+    /// `diff --git` line) is skipped
+    ///
+    /// # Errors
+    /// Fails on a missing header, a malformed hunk header, or a hunk whose
+    /// line counts do not match its header.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut lines = text.lines().skip_while(|l| !l.starts_with("--- ")).peekable();
+        let old = lines.next().context("Patch has no '--- ' header")?;
+        let new = lines
+            .next()
+            .filter(|l| l.starts_with("+++ "))
+            .context("Patch has no '+++ ' header")?;
+        let mut patch = Self { old_path: header_path(old, "a/"), new_path: header_path(new, "b/"), hunks: Vec::new() };
+
+        while let Some(header) = lines.next() {
+            let (old_start, mut old_left, new_start, mut new_left) = parse_hunk_header(header)?;
+            let mut hunk =
+                Hunk { old_start, new_start, lines: Vec::new(), old_no_newline: false, new_no_newline: false };
+            while old_left > 0 || new_left > 0 {
+                let line = lines.next().with_context(|| format!("Hunk '{header}' is truncated"))?;
+                let (mark, rest) = line.split_at(line.chars().next().map_or(0, char::len_utf8));
+                let rest = rest.to_string();
+                match mark {
+                    " " | "" => {
+                        old_left = old_left.checked_sub(1).context("Hunk has more lines than its header says")?;
+                        new_left = new_left.checked_sub(1).context("Hunk has more lines than its header says")?;
+                        hunk.lines.push(Line::Context(rest));
+                    }
+                    "-" => {
+                        old_left = old_left.checked_sub(1).context("Hunk removes more lines than its header says")?;
+                        hunk.lines.push(Line::Remove(rest));
+                    }
+                    "+" => {
+                        new_left = new_left.checked_sub(1).context("Hunk adds more lines than its header says")?;
+                        hunk.lines.push(Line::Add(rest));
+                    }
+                    _ => bail!("Unexpected line in hunk '{header}': {line}"),
+                }
+                mark_no_newline(&mut lines, &mut hunk);
+            }
+            patch.hunks.push(hunk);
+        }
+        Ok(patch)
+    }
+
+    /// The patch from `old` to `new` contents of `path` (`None` for a file
+    /// that does not exist on that side), with three lines of context
+    #[must_use]
+    pub fn between(path: &str, old: Option<&str>, new: Option<&str>) -> Self {
+        let (a, b) = (split_lines(old.unwrap_or_default()), split_lines(new.unwrap_or_default()));
+        let mut diff = Self {
+            old_path: old.map(|_| path.to_string()),
+            new_path: new.map(|_| path.to_string()),
+            hunks: Vec::new(),
+        };
+
+        let edits = edit_script(&a, &b);
+        // Group changes that are within two contexts' reach of each other
+        let changed: Vec<usize> = edits.iter().enumerate().filter(|(_, e)| !matches!(e, Edit::Same(..))).map(|(i, _)| i).collect();
+        let mut groups: Vec<(usize, usize)> = Vec::new();
+        for i in changed {
+            match groups.last_mut() {
+                Some((_, end)) if i <= *end + 2 * CONTEXT + 1 => *end = i,
+                _ => groups.push((i, i)),
+            }
+        }
+
+        for (first, last) in groups {
+            let start = first.saturating_sub(CONTEXT);
+            let end = (last + CONTEXT).min(edits.len() - 1);
+            // Lines of each side before the hunk
+            let (mut old_before, mut new_before) = (0, 0);
+            for edit in &edits[..start] {
+                match edit {
+                    Edit::Same(..) => (old_before, new_before) = (old_before + 1, new_before + 1),
+                    Edit::Remove(_) => old_before += 1,
+                    Edit::Add(_) => new_before += 1,
+                }
+            }
+            let mut hunk = Hunk { old_start: 0, new_start: 0, lines: Vec::new(), old_no_newline: false, new_no_newline: false };
+            for edit in &edits[start..=end] {
+                let line = match *edit {
+                    Edit::Same(i) => {
+                        hunk.old_no_newline |= !a[i].1;
+                        hunk.new_no_newline |= !a[i].1;
+                        Line::Context(a[i].0.to_string())
+                    }
+                    Edit::Remove(i) => {
+                        hunk.old_no_newline |= !a[i].1;
+                        Line::Remove(a[i].0.to_string())
+                    }
+                    Edit::Add(j) => {
+                        hunk.new_no_newline |= !b[j].1;
+                        Line::Add(b[j].0.to_string())
+                    }
+                };
+                hunk.lines.push(line);
+            }
+            let (old_len, new_len) = (hunk.old_lines().count(), hunk.new_lines().count());
+            hunk.old_start = if old_len == 0 { old_before } else { old_before + 1 };
+            hunk.new_start = if new_len == 0 { new_before } else { new_before + 1 };
+            diff.hunks.push(hunk);
+        }
+        diff
+    }
+
+    /// True if the patch changes nothing
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.hunks.is_empty() && self.old_path.is_some() == self.new_path.is_some()
+    }
+
+    /// Apply the patch to a file's contents (`None` if the file does not
+    /// exist), returning the new contents (`None` if the patch deletes it)
+    ///
+    /// # Errors
+    /// Fails if the file's existence does not match the patch, or a hunk's
+    /// context or removed lines are not found.
+    pub fn apply(&self, original: Option<&str>) -> Result<Option<String>> {
+        let original = match (&self.old_path, original) {
+            (None, Some(_)) => bail!("{} already exists", self.path()),
+            (Some(_), None) => bail!("{} does not exist", self.path()),
+            (None, None) => "",
+            (Some(_), Some(text)) => text,
+        };
+        // Each line keeps its own terminator; added lines take the file's
+        let raw: Vec<&str> = original.split_inclusive('\n').collect();
+        let old: Vec<&str> = raw.iter().map(|l| l.trim_end_matches(['\n', '\r'])).collect();
+        let eol = raw.iter().find_map(|l| l.strip_suffix("\r\n").map(|_| "\r\n")).unwrap_or("\n");
+        let mut newline_at_end = original.is_empty() || original.ends_with('\n');
+        let mut out: Vec<(&str, &str)> = Vec::new();
+        let mut cursor = 0;
+        let keep = |i: usize| (old[i], &raw[i][old[i].len()..]);
+
+        for (i, hunk) in self.hunks.iter().enumerate() {
+            let expected: Vec<&str> = hunk.old_lines().collect();
+            let at = find_hunk(&old, &expected, cursor, hunk.old_start)
+                .with_context(|| format!("Hunk {} (line {}) of {} does not apply", i + 1, hunk.old_start, self.path()))?;
+            out.extend((cursor..at).map(keep));
+            let mut next = at;
+            for line in &hunk.lines {
+                match line {
+                    Line::Context(_) => {
+                        out.push(keep(next));
+                        next += 1;
+                    }
+                    Line::Remove(_) => next += 1,
+                    Line::Add(s) => out.push((s, eol)),
+                }
+            }
+            cursor = next;
+            if cursor == old.len() {
+                newline_at_end = !hunk.new_no_newline;
+            }
+        }
+        out.extend((cursor..old.len()).map(keep));
+
+        if self.new_path.is_none() {
+            if !out.is_empty() {
+                bail!("{} has lines the deletion does not remove", self.path());
+            }
+            return Ok(None);
+        }
+        let last = out.len().saturating_sub(1);
+        let mut text = String::with_capacity(original.len());
+        for (i, (line, end)) in out.into_iter().enumerate() {
+            text.push_str(line);
+            if i < last || newline_at_end {
+                text.push_str(if end.is_empty() { eol } else { end });
+            }
+        }
+        Ok(Some(text))
+    }
+
+    /// The patch that undoes this one
+    #[must_use]
+    pub fn reverse(&self) -> Self {
+        Self {
+            old_path: self.new_path.clone(),
+            new_path: self.old_path.clone(),
+            hunks: self
+                .hunks
+                .iter()
+                .map(|h| {
+                    // Swap each run of changes, keeping removals before additions
+                    let mut lines = Vec::with_capacity(h.lines.len());
+                    let (mut removed, mut added) = (Vec::new(), Vec::new());
+                    for line in &h.lines {
+                        match line {
+                            Line::Add(s) => removed.push(Line::Remove(s.clone())),
+                            Line::Remove(s) => added.push(Line::Add(s.clone())),
+                            Line::Context(s) => {
+                                lines.append(&mut removed);
+                                lines.append(&mut added);
+                                lines.push(Line::Context(s.clone()));
+                            }
+                        }
+                    }
+                    lines.append(&mut removed);
+                    lines.append(&mut added);
+                    Hunk {
+                        old_start: h.new_start,
+                        new_start: h.old_start,
+                        lines,
+                        old_no_newline: h.new_no_newline,
+                        new_no_newline: h.old_no_newline,
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    /// Lines added and removed
+    #[must_use]
+    pub fn line_counts(&self) -> (usize, usize) {
+        let lines = self.hunks.iter().flat_map(|h| &h.lines);
+        lines.fold((0, 0), |(added, removed), l| match l {
+            Line::Add(_) => (added + 1, removed),
+            Line::Remove(_) => (added, removed + 1),
+            Line::Context(_) => (added, removed),
+        })
+    }
+
+    /// The file the patch is about (the new path, or the old one for a
+    /// deletion)
+    #[must_use]
+    pub fn path(&self) -> &str {
+        self.new_path.as_deref().or(self.old_path.as_deref()).unwrap_or("<unnamed>")
+    }
+}
+
+impl fmt::Display for Patch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let side = |path: &Option<String>, prefix: &str| {
+            path.as_ref().map_or_else(|| "/dev/null".to_string(), |p| format!("{prefix}{p}"))
+        };
+        writeln!(f, "--- {}", side(&self.old_path, "a/"))?;
+        writeln!(f, "+++ {}", side(&self.new_path, "b/"))?;
+        for hunk in &self.hunks {
+            let (old_len, new_len) = (hunk.old_lines().count(), hunk.new_lines().count());
+            writeln!(f, "@@ -{},{old_len} +{},{new_len} @@", hunk.old_start, hunk.new_start)?;
+            let last = |pred: fn(&Line) -> bool| hunk.lines.iter().rposition(pred);
+            let old_last = last(|l| !matches!(l, Line::Add(_)));
+            let new_last = last(|l| !matches!(l, Line::Remove(_)));
+            for (i, line) in hunk.lines.iter().enumerate() {
+                let (mark, text) = match line {
+                    Line::Context(s) => (' ', s),
+                    Line::Remove(s) => ('-', s),
+                    Line::Add(s) => ('+', s),
+                };
+                writeln!(f, "{mark}{text}")?;
+                let no_newline = (hunk.old_no_newline && old_last == Some(i) && mark != '+')
+                    || (hunk.new_no_newline && new_last == Some(i) && mark != '-');
+                if no_newline {
+                    writeln!(f, "\\ No newline at end of file")?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Lines of context around each change
+const CONTEXT: usize = 3;
+
+/// Largest number of line pairs compared when diffing; past that, the
+/// differing middle of the files is replaced as a whole
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// A line and whether it ends with a newline
+type TextLine<'a> = (&'a str, bool);
+
+fn split_lines(text: &str) -> Vec<TextLine<'_>> {
+    text.split_inclusive('\n')
+        .map(|l| match l.strip_suffix('\n') {
+            Some(line) => (line.strip_suffix('\r').unwrap_or(line), true),
+            None => (l, false),
+        })
+        .collect()
+}
+
+/// One step of an edit script: an index into the old or new lines
+#[derive(Debug, Clone, Copy)]
+enum Edit {
+    Same(usize),
+    Remove(usize),
+    Add(usize),
+}
+
+/// Shortest edit script from `a` to `b` by longest common subsequence, after
+/// trimming the common prefix and suffix
+fn edit_script(a: &[TextLine<'_>], b: &[TextLine<'_>]) -> Vec<Edit> {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..].iter().rev().zip(b[prefix..].iter().rev()).take_while(|(x, y)| x == y).count();
+    let (a_mid, b_mid) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+    let mut edits: Vec<Edit> = (0..prefix).map(Edit::Same).collect();
+    if a_mid.len() * b_mid.len() > MAX_DIFF_CELLS {
+        edits.extend((0..a_mid.len()).map(|i| Edit::Remove(prefix + i)));
+        edits.extend((0..b_mid.len()).map(|j| Edit::Add(prefix + j)));
+    } else {
+        // lcs[i][j]: common subsequence length of a_mid[i..] and b_mid[j..]
+        let (old_len, new_len) = (a_mid.len(), b_mid.len());
+        let mut lcs = vec![vec![0u32; new_len + 1]; old_len + 1];
+        for i in (0..old_len).rev() {
+            for j in (0..new_len).rev() {
+                lcs[i][j] = if a_mid[i] == b_mid[j] { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < old_len || j < new_len {
+            if i < old_len && j < new_len && a_mid[i] == b_mid[j] {
+                edits.push(Edit::Same(prefix + i));
+                (i, j) = (i + 1, j + 1);
+            } else if j == new_len || (i < old_len && lcs[i + 1][j] >= lcs[i][j + 1]) {
+                edits.push(Edit::Remove(prefix + i));
+                i += 1;
+            } else {
+                edits.push(Edit::Add(prefix + j));
+                j += 1;
+            }
+        }
+    }
+    edits.extend((a.len() - suffix..a.len()).map(Edit::Same));
+    edits
+}
+
+/// The reverse of a unified diff, or `None` if it does not parse
+#[must_use]
+pub fn reverse_diff(diff: &str) -> Option<String> {
+    Patch::parse(diff).ok().map(|p| p.reverse().to_string())
+}
+
+/// The path in a `---`/`+++` header, without its `a/`/`b/` prefix or a
+/// trailing timestamp; `None` for `/dev/null`
+fn header_path(line: &str, prefix: &str) -> Option<String> {
+    let path = line[4..].split('\t').next().unwrap_or_default().trim_end();
+    (path != "/dev/null").then(|| path.strip_prefix(prefix).unwrap_or(path).to_string())
+}
+
+/// `@@ -a,b +c,d @@` → (a, b, c, d); a missing count is 1
+fn parse_hunk_header(line: &str) -> Result<(usize, usize, usize, usize)> {
+    let malformed = || format!("Malformed hunk header: {line}");
+    let ranges = line
+        .strip_prefix("@@ -")
+        .and_then(|rest| rest.split(" @@").next())
+        .with_context(malformed)?;
+    let (old, new) = ranges.split_once(" +").with_context(malformed)?;
+    let range = |r: &str| -> Result<(usize, usize)> {
+        let (start, len) = r.split_once(',').unwrap_or((r, "1"));
+        Ok((start.parse().with_context(malformed)?, len.parse().with_context(malformed)?))
+    };
+    let ((old_start, old_len), (new_start, new_len)) = (range(old)?, range(new)?);
+    Ok((old_start, old_len, new_start, new_len))
+}
+
+/// Consume a `\ No newline at end of file` marker after the hunk's last line
+fn mark_no_newline<'a>(lines: &mut std::iter::Peekable<impl Iterator<Item = &'a str>>, hunk: &mut Hunk) {
+    if !lines.peek().is_some_and(|l| l.starts_with('\\')) {
+        return;
+    }
+    lines.next();
+    match hunk.lines.last() {
+        Some(Line::Remove(_)) => hunk.old_no_newline = true,
+        Some(Line::Add(_)) => hunk.new_no_newline = true,
+        Some(Line::Context(_)) => {
+            hunk.old_no_newline = true;
+            hunk.new_no_newline = true;
+        }
+        None => {}
+    }
+}
+
+/// Where `expected` occurs in `old` at or after `cursor`, nearest to the
+/// hunk's stated line
+fn find_hunk(old: &[&str], expected: &[&str], cursor: usize, old_start: usize) -> Option<usize> {
+    // An empty old side inserts after line `old_start`
+    let stated = if expected.is_empty() { old_start } else { old_start.saturating_sub(1) };
+    let stated = stated.clamp(cursor, old.len());
+    let fits = |at: usize| at + expected.len() <= old.len() && old[at..at + expected.len()] == *expected;
+    (0..=old.len()).find_map(|offset| {
+        let later = stated + offset;
+        let earlier = stated.checked_sub(offset).filter(|&at| at >= cursor);
+        [earlier, Some(later)].into_iter().flatten().find(|&at| fits(at))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PATCH: &str = "diff --git a/config.toml b/config.toml
+--- a/config.toml
++++ b/config.toml
+@@ -1,3 +1,3 @@
+ [runtime]
+-engine = \"podman\"
++engine = \"cerro-torre\"
+ rootless = true
+@@ -6,2 +6,3 @@
+ [build]
+ cache = true
++push = false
+";
+
+    #[test]
+    fn test_apply_and_reverse() {
+        let original = "[runtime]\nengine = \"podman\"\nrootless = true\n\n# extra\n[build]\ncache = true\n";
+        let patch = Patch::parse(PATCH).unwrap();
+        assert_eq!(patch.path(), "config.toml");
+        assert_eq!(patch.line_counts(), (2, 1));
+
+        let patched = patch.apply(Some(original)).unwrap().unwrap();
+        assert_eq!(
+            patched,
+            "[runtime]\nengine = \"cerro-torre\"\nrootless = true\n\n# extra\n[build]\ncache = true\npush = false\n"
+        );
+        // Already applied: the removed line is gone
+        assert!(patch.apply(Some(&patched)).is_err());
+
+        let reverse = Patch::parse(&patch.reverse().to_string()).unwrap();
+        assert_eq!(reverse.apply(Some(&patched)).unwrap().as_deref(), Some(original));
+
+        // A hunk that moved down a line still applies
+        let shifted = format!("# header\n{original}");
+        assert!(patch.apply(Some(&shifted)).unwrap().unwrap().contains("push = false"));
+    }
+
+    #[test]
+    fn test_apply_keeps_line_endings() {
+        let original = "[runtime]\r\nengine = \"podman\"\r\nrootless = true\r\n\r\n# extra\n[build]\r\ncache = true\r\n";
+        let patched = Patch::parse(PATCH).unwrap().apply(Some(original)).unwrap().unwrap();
+        assert_eq!(
+            patched,
+            "[runtime]\r\nengine = \"cerro-torre\"\r\nrootless = true\r\n\r\n# extra\n[build]\r\ncache = true\r\npush = false\r\n"
+        );
+    }
+
+    #[test]
+    fn test_create_and_delete() {
+        let create = Patch::parse("--- /dev/null\n+++ b/new.txt\n@@ -0,0 +1,2 @@\n+one\n+two\n\\ No newline at end of file\n")
+            .unwrap();
+        assert_eq!(create.old_path, None);
+        assert_eq!(create.apply(None).unwrap().as_deref(), Some("one\ntwo"));
+        assert!(create.apply(Some("")).unwrap_err().to_string().contains("already exists"));
+
+        let delete = create.reverse();
+        assert!(delete.to_string().contains("\\ No newline at end of file"));
+        assert_eq!(delete.apply(Some("one\ntwo")).unwrap(), None);
+        assert!(delete.apply(Some("one\ntwo\nthree")).is_err());
+        assert!(delete.apply(None).is_err());
+    }
+
+    #[test]
+    fn test_between_round_trips() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\n";
+        let new = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\nm";
+        let patch = Patch::between("x.txt", Some(old), Some(new));
+        assert_eq!(patch.hunks.len(), 2);
+        assert_eq!(patch.line_counts(), (2, 1));
+        let text = patch.to_string();
+        assert!(text.starts_with("--- a/x.txt\n+++ b/x.txt\n@@ -1,5 +1,5 @@\n a\n-b\n+B\n"), "{text}");
+        assert!(text.ends_with("@@ -10,3 +10,4 @@\n j\n k\n l\n+m\n\\ No newline at end of file\n"), "{text}");
+
+        let parsed = Patch::parse(&text).unwrap();
+        assert_eq!(parsed.apply(Some(old)).unwrap().as_deref(), Some(new));
+        assert_eq!(parsed.reverse().apply(Some(new)).unwrap().as_deref(), Some(old));
+
+        assert!(Patch::between("x.txt", Some(old), Some(old)).is_empty());
+        let created = Patch::between("x.txt", None, Some("one\n"));
+        assert_eq!(created.to_string(), "--- /dev/null\n+++ b/x.txt\n@@ -0,0 +1,1 @@\n+one\n");
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_counts() {
+        assert!(Patch::parse("--- a/x\n+++ b/x\n@@ -1,2 +1,2 @@\n-a\n+b\n").is_err());
+        assert!(Patch::parse("--- a/x\n+++ b/x\n@@ -1 +1 @@\n a\n-b\n").is_err());
+        assert!(Patch::parse("+just a line").is_err());
+        let err = Patch::parse("--- a/x\n+++ b/x\n@@ -1 +1 @@\né\n").unwrap_err();
+        assert!(err.to_string().contains("Unexpected line"), "{err}");
+    }
+}
@@ -0,0 +1,77 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Minimal stderr progress bar, safe to tick from worker threads
+
+use std::io::{IsTerminal, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+const WIDTH: usize = 30;
+
+/// A single-line `label [####    ] n/total` bar drawn on stderr
+///
+/// Drawing is disabled when quiet or when stderr is not a terminal, so piped
+/// output and test logs stay clean.
+pub struct Progress {
+    label: &'static str,
+    total: usize,
+    done: AtomicUsize,
+    enabled: bool,
+    out: Mutex<()>,
+}
+
+impl Progress {
+    /// Create a bar for `total` steps.
+    #[must_use]
+    pub fn new(label: &'static str, total: usize, quiet: bool) -> Self {
+        Self {
+            label,
+            total,
+            done: AtomicUsize::new(0),
+            enabled: !quiet && total > 0 && std::io::stderr().is_terminal(),
+            out: Mutex::new(()),
+        }
+    }
+
+    /// A bar that never draws.
+    #[must_use]
+    pub fn hidden() -> Self {
+        Self::new("", 0, true)
+    }
+
+    /// Record one completed step and redraw.
+    pub fn tick(&self) {
+        let done = self.done.fetch_add(1, Ordering::Relaxed) + 1;
+        if !self.enabled {
+            return;
+        }
+        let filled = WIDTH * done.min(self.total) / self.total;
+        let _guard = self.out.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+        let mut stderr = std::io::stderr().lock();
+        let _ = write!(
+            stderr,
+            "\r{} [{}{}] {}/{}",
+            self.label,
+            "#".repeat(filled),
+            " ".repeat(WIDTH - filled),
+            done,
+            self.total
+        );
+        let _ = stderr.flush();
+    }
+
+    /// Number of completed steps.
+    #[must_use]
+    pub fn done(&self) -> usize {
+        self.done.load(Ordering::Relaxed)
+    }
+
+    /// Clear the bar so following output starts on a clean line.
+    pub fn finish(&self) {
+        if self.enabled {
+            let _guard = self.out.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+            let blank = " ".repeat(self.label.len() + WIDTH + 24);
+            let _ = write!(std::io::stderr().lock(), "\r{blank}\r");
+        }
+    }
+}
@@ -0,0 +1,939 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! A small query language over the ecosystem graph
+//!
+//! One expression language replaces the ad-hoc filters scattered across
+//! commands. A query names what to select and an optional predicate:
+//!
+//! ```text
+//! repos where tag = "rust" and forge = gh and has_edge(rel = uses, to = group:core)
+//! edges where channel = api and confidence < 0.5
+//! annotations where aspect = security and polarity = risk and weight >= 2
+//! ```
+//!
+//! Predicates combine comparisons (`=`, `!=`, `~` contains, `<`, `<=`, `>`,
+//! `>=`) with `and`, `or`, `not` and parentheses. Functions descend into
+//! related records, evaluating their arguments against them: `has_edge(...)`,
+//! `has_incoming(...)`, `has_annotation(...)`, `binds(...)`, `provides(...)`
+//! and `in_group(<group>)`. Commas between function arguments mean `and`.
+//!
+//! Fields that reference nodes (`from`, `to`, `target`, `consumer`, `repo`)
+//! match a node's ID, its repo name, or any group it belongs to, so
+//! `to = group:core`, `to = core` and `to = reposystem` all work.
+
+use crate::graph::EcosystemGraph;
+use crate::types::{AspectAnnotation, Edge, Provider, Repo, SlotBinding};
+use anyhow::{bail, Result};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt;
+
+/// What a query selects
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Target {
+    /// Repository nodes
+    Repos,
+    /// Edges between nodes
+    Edges,
+    /// Aspect annotations
+    Annotations,
+}
+
+impl Target {
+    /// Parse a target keyword (`repos`, `edges`, `annotations`, singular or plural)
+    #[must_use]
+    pub fn from_keyword(word: &str) -> Option<Self> {
+        match word.to_lowercase().as_str() {
+            "repos" | "repo" => Some(Self::Repos),
+            "edges" | "edge" => Some(Self::Edges),
+            "annotations" | "annotation" => Some(Self::Annotations),
+            _ => None,
+        }
+    }
+
+    fn kind(self) -> Kind {
+        match self {
+            Self::Repos => Kind::Repo,
+            Self::Edges => Kind::Edge,
+            Self::Annotations => Kind::Annotation,
+        }
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Repos => "repos",
+            Self::Edges => "edges",
+            Self::Annotations => "annotations",
+        })
+    }
+}
+
+/// Record kinds a predicate can be evaluated against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Repo,
+    Edge,
+    Annotation,
+    Binding,
+    Provider,
+}
+
+impl Kind {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Repo => "repos",
+            Self::Edge => "edges",
+            Self::Annotation => "annotations",
+            Self::Binding => "bindings",
+            Self::Provider => "providers",
+        }
+    }
+
+    fn fields(self) -> &'static [&'static str] {
+        match self {
+            Self::Repo => &[
+                "id", "name", "owner", "forge", "visibility", "tag", "estate", "branch", "group",
+                "alias",
+            ],
+            Self::Edge => &[
+                "id", "from", "to", "rel", "channel", "label", "created_by", "confidence",
+                "evidence",
+            ],
+            Self::Annotation => &[
+                "id", "target", "aspect", "weight", "polarity", "reason", "mode", "who",
+            ],
+            Self::Binding => &["id", "consumer", "slot", "provider", "mode"],
+            Self::Provider => &["id", "name", "slot", "type", "repo", "priority", "fallback"],
+        }
+    }
+
+    /// The kind a function's arguments are evaluated against
+    fn function(self, name: &str) -> Option<Kind> {
+        match (self, name) {
+            (Self::Repo, "has_edge" | "has_incoming") => Some(Self::Edge),
+            (Self::Repo | Self::Edge, "has_annotation") => Some(Self::Annotation),
+            (Self::Repo, "binds") => Some(Self::Binding),
+            (Self::Repo, "provides") => Some(Self::Provider),
+            _ => None,
+        }
+    }
+
+    fn functions(self) -> &'static [&'static str] {
+        match self {
+            Self::Repo => &[
+                "has_edge", "has_incoming", "has_annotation", "binds", "provides", "in_group",
+            ],
+            Self::Edge => &["has_annotation"],
+            _ => &[],
+        }
+    }
+}
+
+/// Comparison operators
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Contains,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// Parsed predicate
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    True,
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Cmp { field: String, op: Op, value: String },
+    InGroup(String),
+    Call { func: String, arg: Box<Expr> },
+}
+
+/// A parsed query
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    /// What the query selects
+    pub target: Target,
+    filter: Expr,
+}
+
+/// Records selected by a query
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum QueryResult<'a> {
+    /// Matching repositories
+    Repos(Vec<&'a Repo>),
+    /// Matching edges
+    Edges(Vec<&'a Edge>),
+    /// Matching annotations
+    Annotations(Vec<&'a AspectAnnotation>),
+}
+
+impl QueryResult<'_> {
+    /// Number of matching records
+    #[must_use]
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Repos(r) => r.len(),
+            Self::Edges(e) => e.len(),
+            Self::Annotations(a) => a.len(),
+        }
+    }
+
+    /// Whether nothing matched
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// IDs of the matching records
+    #[must_use]
+    pub fn ids(&self) -> Vec<&str> {
+        match self {
+            Self::Repos(r) => r.iter().map(|r| r.id.as_str()).collect(),
+            Self::Edges(e) => e.iter().map(|e| e.id.as_str()).collect(),
+            Self::Annotations(a) => a.iter().map(|a| a.id.as_str()).collect(),
+        }
+    }
+}
+
+impl Query {
+    /// Parse a query such as `repos where tag = rust`.
+    ///
+    /// The target keyword may be omitted, in which case the whole input is
+    /// a predicate over repos.
+    ///
+    /// # Errors
+    /// Fails on syntax errors and on fields or functions the selected
+    /// record kind does not have.
+    pub fn parse(input: &str) -> Result<Self> {
+        let tokens = lex(input)?;
+        let mut parser = Parser { tokens, pos: 0 };
+
+        let target = match parser.peek() {
+            Some(Token::Word(w)) => match Target::from_keyword(w) {
+                Some(t) => {
+                    parser.pos += 1;
+                    t
+                }
+                None => Target::Repos,
+            },
+            _ => Target::Repos,
+        };
+
+        // Without a target keyword the whole input is the predicate
+        let filter = if parser.pos == 0 || parser.eat_keyword("where") {
+            parser.expr()?
+        } else {
+            Expr::True
+        };
+
+        if let Some(tok) = parser.peek() {
+            bail!("Unexpected {tok} in query");
+        }
+
+        check(&filter, target.kind())?;
+        Ok(Self { target, filter })
+    }
+
+    /// A query selecting every record of `target`
+    #[must_use]
+    pub fn all(target: Target) -> Self {
+        Self { target, filter: Expr::True }
+    }
+
+    /// Whether a repo satisfies this query's predicate
+    #[must_use]
+    pub fn matches_repo(&self, graph: &EcosystemGraph, repo: &Repo) -> bool {
+        self.target == Target::Repos && Eval { graph }.eval(&self.filter, Subject::Repo(repo))
+    }
+
+    /// Whether an edge satisfies this query's predicate
+    #[must_use]
+    pub fn matches_edge(&self, graph: &EcosystemGraph, edge: &Edge) -> bool {
+        self.target == Target::Edges && Eval { graph }.eval(&self.filter, Subject::Edge(edge))
+    }
+
+    /// Whether an annotation satisfies this query's predicate
+    #[must_use]
+    pub fn matches_annotation(&self, graph: &EcosystemGraph, ann: &AspectAnnotation) -> bool {
+        self.target == Target::Annotations
+            && Eval { graph }.eval(&self.filter, Subject::Annotation(ann))
+    }
+
+    /// Run the query against the graph, aspect and slot stores
+    #[must_use]
+    pub fn select<'a>(&self, graph: &'a EcosystemGraph) -> QueryResult<'a> {
+        match self.target {
+            Target::Repos => QueryResult::Repos(
+                graph.store.repos.iter().filter(|r| self.matches_repo(graph, r)).collect(),
+            ),
+            Target::Edges => QueryResult::Edges(
+                graph.store.edges.iter().filter(|e| self.matches_edge(graph, e)).collect(),
+            ),
+            Target::Annotations => QueryResult::Annotations(
+                graph
+                    .aspects
+                    .annotations
+                    .iter()
+                    .filter(|a| self.matches_annotation(graph, a))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Narrow a copy of the graph to what this query selects.
+    ///
+    /// Selected edges bring their endpoints along and selected annotations
+    /// bring their targets, so the result is always a consistent graph.
+    #[must_use]
+    pub fn subgraph(&self, graph: &EcosystemGraph) -> EcosystemGraph {
+        let mut repos: HashSet<String> = HashSet::new();
+        let mut edges: HashSet<String> = HashSet::new();
+        let mut annotations = None;
+
+        match self.select(graph) {
+            QueryResult::Repos(selected) => {
+                repos.extend(selected.iter().map(|r| r.id.clone()));
+                edges.extend(graph.store.edges.iter().map(|e| e.id.clone()));
+            }
+            QueryResult::Edges(selected) => {
+                for edge in selected {
+                    repos.insert(edge.from.clone());
+                    repos.insert(edge.to.clone());
+                    edges.insert(edge.id.clone());
+                }
+            }
+            QueryResult::Annotations(selected) => {
+                for ann in &selected {
+                    repos.insert(ann.target.clone());
+                    if let Some(edge) = graph.store.edges.iter().find(|e| e.id == ann.target) {
+                        repos.insert(edge.from.clone());
+                        repos.insert(edge.to.clone());
+                        edges.insert(edge.id.clone());
+                    }
+                }
+                annotations = Some(selected.iter().map(|a| a.id.clone()).collect());
+            }
+        }
+
+        let mut sub = graph.clone();
+        sub.retain(&repos, &edges, annotations.as_ref());
+        sub
+    }
+}
+
+// =============================================================================
+// Lexer
+// =============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Str(String),
+    Op(Op),
+    LParen,
+    RParen,
+    Comma,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Word(w) => write!(f, "'{w}'"),
+            Self::Str(s) => write!(f, "\"{s}\""),
+            Self::Op(op) => write!(f, "operator {op:?}"),
+            Self::LParen => f.write_str("'('"),
+            Self::RParen => f.write_str("')'"),
+            Self::Comma => f.write_str("','"),
+        }
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | ':' | '/' | '@' | '#' | '*')
+}
+
+fn lex(input: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::LParen);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::RParen);
+            }
+            ',' => {
+                chars.next();
+                tokens.push(Token::Comma);
+            }
+            '"' | '\'' => {
+                chars.next();
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        Some(ch) if ch == c => break,
+                        Some('\\') => s.extend(chars.next()),
+                        Some(ch) => s.push(ch),
+                        None => bail!("Unterminated string in query"),
+                    }
+                }
+                tokens.push(Token::Str(s));
+            }
+            '=' | '~' => {
+                chars.next();
+                // Accept `==` as a synonym for `=`
+                if c == '=' && chars.peek() == Some(&'=') {
+                    chars.next();
+                }
+                tokens.push(Token::Op(if c == '=' { Op::Eq } else { Op::Contains }));
+            }
+            '!' | '<' | '>' => {
+                chars.next();
+                let eq = chars.peek() == Some(&'=');
+                if eq {
+                    chars.next();
+                }
+                let op = match (c, eq) {
+                    ('!', true) => Op::Ne,
+                    ('<', false) => Op::Lt,
+                    ('<', true) => Op::Le,
+                    ('>', false) => Op::Gt,
+                    ('>', true) => Op::Ge,
+                    _ => bail!("Expected '!=' in query"),
+                };
+                tokens.push(Token::Op(op));
+            }
+            c if is_word_char(c) => {
+                let mut w = String::new();
+                while let Some(&ch) = chars.peek() {
+                    if !is_word_char(ch) {
+                        break;
+                    }
+                    w.push(ch);
+                    chars.next();
+                }
+                tokens.push(Token::Word(w));
+            }
+            other => bail!("Unexpected character '{other}' in query"),
+        }
+    }
+
+    Ok(tokens)
+}
+
+// =============================================================================
+// Parser
+// =============================================================================
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let tok = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        tok
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        match self.peek() {
+            Some(Token::Word(w)) if w.eq_ignore_ascii_case(keyword) => {
+                self.pos += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn expect(&mut self, expected: &Token) -> Result<()> {
+        match self.next() {
+            Some(ref tok) if tok == expected => Ok(()),
+            Some(tok) => bail!("Expected {expected} but found {tok} in query"),
+            None => bail!("Expected {expected} at end of query"),
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr> {
+        let mut lhs = self.and()?;
+        while self.eat_keyword("or") {
+            lhs = Expr::Or(Box::new(lhs), Box::new(self.and()?));
+        }
+        Ok(lhs)
+    }
+
+    fn and(&mut self) -> Result<Expr> {
+        let mut lhs = self.unary()?;
+        while self.eat_keyword("and") {
+            lhs = Expr::And(Box::new(lhs), Box::new(self.unary()?));
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        if self.eat_keyword("not") {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        self.atom()
+    }
+
+    fn atom(&mut self) -> Result<Expr> {
+        match self.next() {
+            Some(Token::LParen) => {
+                let inner = self.expr()?;
+                self.expect(&Token::RParen)?;
+                Ok(inner)
+            }
+            Some(Token::Word(word)) => match self.peek() {
+                Some(Token::LParen) => {
+                    self.pos += 1;
+                    self.call(word.to_lowercase())
+                }
+                Some(Token::Op(op)) => {
+                    let op = *op;
+                    self.pos += 1;
+                    let value = self.value()?;
+                    Ok(Expr::Cmp { field: word.to_lowercase(), op, value })
+                }
+                _ => bail!("Expected an operator or '(' after '{word}' in query"),
+            },
+            Some(tok) => bail!("Unexpected {tok} in query"),
+            None => bail!("Unexpected end of query"),
+        }
+    }
+
+    fn call(&mut self, func: String) -> Result<Expr> {
+        if func == "in_group" {
+            let group = self.value()?;
+            self.expect(&Token::RParen)?;
+            return Ok(Expr::InGroup(group));
+        }
+
+        if self.peek() == Some(&Token::RParen) {
+            self.pos += 1;
+            return Ok(Expr::Call { func, arg: Box::new(Expr::True) });
+        }
+
+        let mut arg = self.expr()?;
+        while self.peek() == Some(&Token::Comma) {
+            self.pos += 1;
+            arg = Expr::And(Box::new(arg), Box::new(self.expr()?));
+        }
+        self.expect(&Token::RParen)?;
+        Ok(Expr::Call { func, arg: Box::new(arg) })
+    }
+
+    fn value(&mut self) -> Result<String> {
+        match self.next() {
+            Some(Token::Word(w) | Token::Str(w)) => Ok(w),
+            Some(tok) => bail!("Expected a value but found {tok} in query"),
+            None => bail!("Expected a value at end of query"),
+        }
+    }
+}
+
+/// Reject fields and functions the record kind does not have
+fn check(expr: &Expr, kind: Kind) -> Result<()> {
+    match expr {
+        Expr::True => Ok(()),
+        Expr::And(a, b) | Expr::Or(a, b) => {
+            check(a, kind)?;
+            check(b, kind)
+        }
+        Expr::Not(inner) => check(inner, kind),
+        Expr::Cmp { field, .. } => {
+            if kind.fields().contains(&field.as_str())
+                || (kind == Kind::Repo && field.starts_with("meta."))
+            {
+                Ok(())
+            } else {
+                bail!(
+                    "Unknown field '{field}' for {}. Valid: {}",
+                    kind.name(),
+                    kind.fields().join(", ")
+                )
+            }
+        }
+        Expr::InGroup(_) if kind == Kind::Repo => Ok(()),
+        Expr::Call { func, arg } => match kind.function(func) {
+            Some(inner) => check(arg, inner),
+            None => bail!(
+                "Unknown function '{func}' for {}. Valid: {}",
+                kind.name(),
+                if kind.functions().is_empty() { "(none)".to_string() } else { kind.functions().join(", ") }
+            ),
+        },
+        Expr::InGroup(_) => bail!("Function 'in_group' only applies to repos"),
+    }
+}
+
+// =============================================================================
+// Evaluator
+// =============================================================================
+
+#[derive(Clone, Copy)]
+enum Subject<'a> {
+    Repo(&'a Repo),
+    Edge(&'a Edge),
+    Annotation(&'a AspectAnnotation),
+    Binding(&'a SlotBinding),
+    Provider(&'a Provider),
+}
+
+struct Eval<'g> {
+    graph: &'g EcosystemGraph,
+}
+
+/// Lowercase serde code of a unit enum (`Visibility::Public` → `public`)
+fn code<T: fmt::Debug>(value: &T) -> String {
+    format!("{value:?}").to_lowercase()
+}
+
+/// An ID plus its form without the type prefix (`aspect:security` → `security`)
+fn id_forms(id: &str) -> Vec<String> {
+    let mut forms = vec![id.to_string()];
+    for prefix in ["aspect:", "slot:", "group:", "provider:", "seam:"] {
+        if let Some(short) = id.strip_prefix(prefix) {
+            forms.push(short.to_string());
+        }
+    }
+    forms
+}
+
+impl Eval<'_> {
+    fn eval(&self, expr: &Expr, subject: Subject<'_>) -> bool {
+        match expr {
+            Expr::True => true,
+            Expr::And(a, b) => self.eval(a, subject) && self.eval(b, subject),
+            Expr::Or(a, b) => self.eval(a, subject) || self.eval(b, subject),
+            Expr::Not(inner) => !self.eval(inner, subject),
+            Expr::Cmp { field, op, value } => {
+                let values = self.field(subject, field);
+                match op {
+                    Op::Ne => !values.iter().any(|v| compare(v, Op::Eq, value)),
+                    _ => values.iter().any(|v| compare(v, *op, value)),
+                }
+            }
+            Expr::InGroup(group) => match subject {
+                Subject::Repo(repo) => self.groups_of(&repo.id).iter().any(|g| g.eq_ignore_ascii_case(group)),
+                _ => false,
+            },
+            Expr::Call { func, arg } => self.call(func, arg, subject),
+        }
+    }
+
+    fn call(&self, func: &str, arg: &Expr, subject: Subject<'_>) -> bool {
+        let store = &self.graph.store;
+        match (subject, func) {
+            (Subject::Repo(repo), "has_edge") => store
+                .edges
+                .iter()
+                .filter(|e| e.from == repo.id)
+                .any(|e| self.eval(arg, Subject::Edge(e))),
+            (Subject::Repo(repo), "has_incoming") => store
+                .edges
+                .iter()
+                .filter(|e| e.to == repo.id)
+                .any(|e| self.eval(arg, Subject::Edge(e))),
+            (Subject::Repo(Repo { id, .. }) | Subject::Edge(Edge { id, .. }), "has_annotation") => self
+                .graph
+                .aspects
+                .annotations
+                .iter()
+                .filter(|a| &a.target == id)
+                .any(|a| self.eval(arg, Subject::Annotation(a))),
+            (Subject::Repo(repo), "binds") => self
+                .graph
+                .slots
+                .bindings
+                .iter()
+                .filter(|b| b.consumer_id == repo.id)
+                .any(|b| self.eval(arg, Subject::Binding(b))),
+            (Subject::Repo(repo), "provides") => self
+                .graph
+                .slots
+                .providers
+                .iter()
+                .filter(|p| p.repo_id.as_deref() == Some(repo.id.as_str()))
+                .any(|p| self.eval(arg, Subject::Provider(p))),
+            _ => false,
+        }
+    }
+
+    /// IDs and names of every group a node belongs to
+    fn groups_of(&self, node: &str) -> Vec<String> {
+        self.graph
+            .store
+            .groups
+            .iter()
+            .filter(|g| g.members.iter().any(|m| m == node))
+            .flat_map(|g| {
+                let mut forms = id_forms(&g.id);
+                forms.push(g.name.clone());
+                forms
+            })
+            .collect()
+    }
+
+    /// Everything a node reference can be matched by: its ID, repo or seam
+    /// name, and the groups it belongs to
+    fn node_forms(&self, node: &str) -> Vec<String> {
+        let mut forms = id_forms(node);
+        if let Some(repo) = self.graph.get_repo(node) {
+            forms.push(repo.name.clone());
+        } else if let Some(seam) = self.graph.store.seams.iter().find(|s| s.id == node) {
+            forms.push(seam.name.clone());
+        }
+        forms.extend(self.groups_of(node));
+        forms
+    }
+
+    fn field(&self, subject: Subject<'_>, field: &str) -> Vec<String> {
+        match subject {
+            Subject::Repo(r) => match field {
+                "id" => vec![r.id.clone()],
+                "name" => vec![r.name.clone()],
+                "owner" => vec![r.owner.clone()],
+                "forge" => vec![r.forge.code().to_string(), code(&r.forge)],
+                "visibility" => vec![code(&r.visibility)],
+                "tag" => r.tags.clone(),
+                "estate" => id_forms(&r.estate),
+                "branch" => vec![r.default_branch.clone()],
+                "group" => self.groups_of(&r.id),
+                "alias" => r.aliases().into_iter().map(String::from).collect(),
+                meta => meta
+                    .strip_prefix("meta.")
+                    .and_then(|key| r.metadata.get(key))
+                    .cloned()
+                    .into_iter()
+                    .collect(),
+            },
+            Subject::Edge(e) => match field {
+                "id" => vec![e.id.clone()],
+                "from" => self.node_forms(&e.from),
+                "to" => self.node_forms(&e.to),
+                "rel" => vec![e.rel.code().to_string(), code(&e.rel)],
+                "channel" => vec![e.channel.code().to_string()],
+                "label" => e.label.clone().into_iter().collect(),
+                "created_by" => vec![e.meta.created_by.clone()],
+                "confidence" => vec![crate::analysis::edge_confidence(e).to_string()],
+                "evidence" => e.evidence.iter().map(|ev| ev.evidence_type.clone()).collect(),
+                _ => Vec::new(),
+            },
+            Subject::Annotation(a) => match field {
+                "id" => vec![a.id.clone()],
+                "target" => self.node_forms(&a.target),
+                "aspect" => id_forms(&a.aspect_id),
+                "weight" => vec![a.weight.to_string()],
+                "polarity" => vec![code(&a.polarity)],
+                "reason" => vec![a.reason.clone()],
+                "mode" => vec![a.source.mode.clone()],
+                "who" => vec![a.source.who.clone()],
+                _ => Vec::new(),
+            },
+            Subject::Binding(b) => match field {
+                "id" => vec![b.id.clone()],
+                "consumer" => self.node_forms(&b.consumer_id),
+                "slot" => id_forms(&b.slot_id),
+                "provider" => id_forms(&b.provider_id),
+                "mode" => vec![code(&b.mode)],
+                _ => Vec::new(),
+            },
+            Subject::Provider(p) => match field {
+                "id" => id_forms(&p.id),
+                "name" => vec![p.name.clone()],
+                "slot" => id_forms(&p.slot_id),
+                "type" => vec![code(&p.provider_type)],
+                "repo" => p.repo_id.as_deref().map(|id| self.node_forms(id)).unwrap_or_default(),
+                "priority" => vec![p.priority.to_string()],
+                "fallback" => vec![p.is_fallback.to_string()],
+                _ => Vec::new(),
+            },
+        }
+    }
+}
+
+/// Compare one field value against a literal. Ordering operators compare
+/// numerically; equality and containment are case-insensitive.
+fn compare(actual: &str, op: Op, expected: &str) -> bool {
+    match op {
+        Op::Eq | Op::Ne => {
+            let eq = actual.eq_ignore_ascii_case(expected)
+                || matches!(
+                    (actual.parse::<f64>(), expected.parse::<f64>()),
+                    (Ok(a), Ok(b)) if (a - b).abs() < f64::EPSILON
+                );
+            eq == (op == Op::Eq)
+        }
+        Op::Contains => actual.to_lowercase().contains(&expected.to_lowercase()),
+        Op::Lt | Op::Le | Op::Gt | Op::Ge => {
+            let (Ok(a), Ok(b)) = (actual.parse::<f64>(), expected.parse::<f64>()) else {
+                return false;
+            };
+            match op {
+                Op::Lt => a < b,
+                Op::Le => a <= b,
+                Op::Gt => a > b,
+                _ => a >= b,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::EcosystemGraph;
+    use crate::types::{
+        AnnotationSource, Channel, EdgeMeta, Forge, Group, ImportMeta, Polarity, RelationType,
+        Visibility,
+    };
+    use chrono::Utc;
+    use std::collections::HashMap;
+
+    fn repo(name: &str, forge: Forge, tags: &[&str]) -> Repo {
+        Repo {
+            kind: "Repo".into(),
+            id: Repo::forge_id(forge, "acme", name),
+            forge,
+            owner: "acme".into(),
+            name: name.into(),
+            default_branch: "main".into(),
+            visibility: Visibility::Public,
+            tags: tags.iter().map(|t| (*t).to_string()).collect(),
+            estate: "estate:hyperpolymath".into(),
+            metadata: HashMap::new(),
+            imports: ImportMeta { source: "test".into(), path_hint: None, imported_at: Utc::now() },
+            local_path: None,
+        }
+    }
+
+    fn edge(from: &str, to: &str, rel: RelationType, channel: Channel) -> Edge {
+        Edge {
+            kind: "Edge".into(),
+            id: Edge::generate_id(from, to, rel, channel, None),
+            from: from.into(),
+            to: to.into(),
+            rel,
+            channel,
+            label: None,
+            evidence: vec![],
+            meta: EdgeMeta { created_by: "manual".into(), created_at: Utc::now() },
+        }
+    }
+
+    /// app (rust, gh) uses core (rust, gh, group core); web (js, gl) uses app;
+    /// core carries a security risk annotation
+    fn fixture() -> EcosystemGraph {
+        let mut graph = EcosystemGraph::new();
+        let app = repo("app", Forge::GitHub, &["rust"]);
+        let core = repo("core", Forge::GitHub, &["rust", "lib"]);
+        let web = repo("web", Forge::GitLab, &["js"]);
+        let (app_id, core_id, web_id) = (app.id.clone(), core.id.clone(), web.id.clone());
+        for r in [app, core, web] {
+            graph.add_repo(r);
+        }
+        graph.add_edge(edge(&app_id, &core_id, RelationType::Uses, Channel::Artifact)).unwrap();
+        graph.add_edge(edge(&web_id, &app_id, RelationType::Uses, Channel::Api)).unwrap();
+        graph.store.groups.push(Group {
+            kind: "Group".into(),
+            id: "group:core".into(),
+            name: "Core".into(),
+            description: None,
+            members: vec![core_id.clone()],
+        });
+        graph.aspects.annotations.push(AspectAnnotation {
+            kind: "AspectAnnotation".into(),
+            id: AspectAnnotation::generate_id(&core_id, "aspect:security"),
+            target: core_id,
+            aspect_id: "aspect:security".into(),
+            weight: 2,
+            polarity: Polarity::Risk,
+            reason: "unaudited crypto".into(),
+            evidence: vec![],
+            source: AnnotationSource {
+                mode: "manual".into(),
+                who: "test".into(),
+                when: Utc::now(),
+                rule_id: None,
+            },
+        });
+        graph
+    }
+
+    fn names(graph: &EcosystemGraph, q: &str) -> Vec<String> {
+        match Query::parse(q).unwrap().select(graph) {
+            QueryResult::Repos(r) => r.iter().map(|r| r.name.clone()).collect(),
+            other => panic!("expected repos, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_query_repos() {
+        let graph = fixture();
+        assert_eq!(
+            names(&graph, r#"repos where tag = "rust" and forge = gh and has_edge(rel = uses, to = group:core)"#),
+            vec!["app"]
+        );
+        assert_eq!(names(&graph, "tag = rust"), vec!["app", "core"]);
+        assert_eq!(names(&graph, "repos where not tag = rust or in_group(core)"), vec!["core", "web"]);
+        assert_eq!(names(&graph, "repos where has_annotation(aspect = security, weight >= 2)"), vec!["core"]);
+        assert_eq!(names(&graph, "repos where has_incoming(channel = api)"), vec!["app"]);
+        assert_eq!(names(&graph, "repos where name ~ O"), vec!["core"]);
+        assert_eq!(names(&graph, "repos").len(), 3);
+    }
+
+    #[test]
+    fn test_query_edges_and_annotations() {
+        let graph = fixture();
+        let edges = Query::parse("edges where to = app").unwrap().select(&graph);
+        assert_eq!(edges.len(), 1);
+        assert!(matches!(edges, QueryResult::Edges(ref e) if e[0].channel == Channel::Api));
+
+        let anns = Query::parse("annotations where polarity = risk and target = core").unwrap();
+        assert_eq!(anns.select(&graph).len(), 1);
+        assert!(Query::parse("annotations where weight > 2").unwrap().select(&graph).is_empty());
+
+        // The subgraph of an annotation query carries its target repo
+        let sub = anns.subgraph(&graph);
+        assert_eq!(sub.repos().len(), 1);
+        assert_eq!(sub.aspects.annotations.len(), 1);
+        assert!(sub.edges().is_empty());
+    }
+
+    #[test]
+    fn test_query_errors() {
+        for bad in [
+            "repos where colour = red",
+            "edges where in_group(core)",
+            "repos where has_edge(weight = 2)",
+            "repos where (tag = rust",
+            "repos where tag = \"rust",
+            "repos where tag rust",
+            "repos tag = rust",
+        ] {
+            assert!(Query::parse(bad).is_err(), "should reject: {bad}");
+        }
+    }
+}
@@ -0,0 +1,689 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! File patches implied by a plan
+//!
+//! A plan's `FileChange` ops carry their own diffs. A binding switch is
+//! translated into patches on the consumer's checkout (found through its
+//! `path_hint`):
+//! - a dependency on the old provider in `Cargo.toml` or `package.json` is
+//!   renamed to the new one
+//! - a config value naming the old provider's `external_uri` is pointed at the
+//!   new provider's
+//!
+//! Several switches touching one file are folded into a single patch.
+//!
+//! A slot's binding templates say exactly where its bindings live; `plan
+//! create` turns them into `FileChange` ops (see [`template_file_changes`]).
+
+use crate::graph::EcosystemGraph;
+use crate::patch::Patch;
+use crate::types::{
+    BindingTemplate, FileChangeType, FileDiff, Plan, PlanOp, Provider, RewriteRule, SlotBinding,
+};
+use anyhow::{Context, Result};
+use globset::{GlobBuilder, GlobMatcher};
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// How far below the checkout root files are considered
+const MAX_DEPTH: usize = 3;
+
+/// Directories never searched
+const SKIP_DIRS: &[&str] = &["node_modules", "target", "vendor", "dist", "build"];
+
+/// Extensions of config files searched for provider URIs
+const CONFIG_EXTENSIONS: &[&str] = &["toml", "yaml", "yml", "json", "ini", "conf", "cfg", "env"];
+
+/// `package.json` sections holding dependencies
+const NPM_SECTIONS: &[&str] = &["dependencies", "devDependencies", "peerDependencies", "optionalDependencies"];
+
+/// How a provider can be named in a consumer's files
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProviderRefs {
+    /// Dependency names: the implementing repo's name (if local), then the
+    /// provider's name
+    pub names: Vec<String>,
+    /// The provider's external URI
+    pub uri: Option<String>,
+}
+
+impl ProviderRefs {
+    /// The names and URI of a provider
+    #[must_use]
+    pub fn of(graph: &EcosystemGraph, provider: &Provider) -> Self {
+        let mut names: Vec<String> = provider
+            .repo_id
+            .as_deref()
+            .and_then(|id| graph.get_repo(id))
+            .map(|repo| repo.name.clone())
+            .into_iter()
+            .collect();
+        if !names.contains(&provider.name) {
+            names.push(provider.name.clone());
+        }
+        Self { names, uri: provider.external_uri.clone() }
+    }
+}
+
+/// The file diffs of a plan: its `FileChange` ops, then the patches its
+/// binding switches imply (except for files a `FileChange` already covers)
+#[must_use]
+pub fn plan_file_diffs(graph: &EcosystemGraph, plan: &Plan) -> Vec<FileDiff> {
+    let mut diffs = Vec::new();
+    for op in &plan.operations {
+        if let PlanOp::FileChange { repo_id, file_path, change_type, diff: Some(diff), .. } = op {
+            let (lines_added, lines_removed) = Patch::parse(diff).map_or((0, 0), |p| p.line_counts());
+            diffs.push(FileDiff {
+                repo_id: repo_id.clone(),
+                file_path: file_path.clone(),
+                change_type: *change_type,
+                diff: diff.clone(),
+                lines_added,
+                lines_removed,
+            });
+        }
+    }
+
+    // (repo, file) -> (original, rewritten) across all switches
+    let mut files: BTreeMap<(String, String), (String, String)> = BTreeMap::new();
+    for op in &plan.operations {
+        let PlanOp::SwitchBinding { consumer_id, from_provider_id, to_provider_id, .. } = op else {
+            continue;
+        };
+        let provider = |id: &str| graph.slots.providers.iter().find(|p| p.id == id);
+        let (Some(from), Some(to), Some(root)) =
+            (provider(from_provider_id), provider(to_provider_id), checkout(graph, consumer_id))
+        else {
+            continue;
+        };
+        let (from, to) = (ProviderRefs::of(graph, from), ProviderRefs::of(graph, to));
+        for (rel, path) in candidate_files(&root) {
+            if diffs.iter().any(|d| &d.repo_id == consumer_id && d.file_path == rel) {
+                continue;
+            }
+            let key = (consumer_id.clone(), rel);
+            let current = match files.get(&key) {
+                Some((_, text)) => text.clone(),
+                None => match std::fs::read_to_string(&path) {
+                    Ok(text) => text,
+                    Err(_) => continue,
+                },
+            };
+            if let Some(text) = rewrite_file(&key.1, &current, &from, &to) {
+                files.entry(key).or_insert_with(|| (current, String::new())).1 = text;
+            }
+        }
+    }
+
+    for ((repo_id, file_path), (original, rewritten)) in files {
+        let patch = Patch::between(&file_path, Some(&original), Some(&rewritten));
+        if patch.is_empty() {
+            continue;
+        }
+        let (lines_added, lines_removed) = patch.line_counts();
+        diffs.push(FileDiff {
+            repo_id,
+            file_path,
+            change_type: FileChangeType::Modify,
+            diff: patch.to_string(),
+            lines_added,
+            lines_removed,
+        });
+    }
+    diffs
+}
+
+/// A repo's local checkout, if it has one
+fn checkout(graph: &EcosystemGraph, repo_id: &str) -> Option<PathBuf> {
+    graph.get_repo(repo_id)?.imports.path_hint.clone().filter(|p| p.is_dir())
+}
+
+/// Manifests and config files in a checkout, as (relative path, full path),
+/// skipping hidden and build directories and lock files
+fn candidate_files(root: &Path) -> Vec<(String, PathBuf)> {
+    let mut files: Vec<(String, PathBuf)> = WalkDir::new(root)
+        .max_depth(MAX_DEPTH)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !e.file_type().is_dir() || !skipped_dir(&e.file_name().to_string_lossy()))
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_file())
+        .filter(|e| {
+            let name = e.file_name().to_string_lossy();
+            let extension = Path::new(name.as_ref()).extension().map(|x| x.to_string_lossy().to_lowercase());
+            !name.ends_with("lock.json")
+                && (name.starts_with(".env") || extension.is_some_and(|x| CONFIG_EXTENSIONS.contains(&x.as_str())))
+        })
+        .filter_map(|e| Some((relative(root, e.path())?, e.into_path())))
+        .collect();
+    files.sort();
+    files
+}
+
+/// `text` with references to `from` pointed at `to`, or `None` if nothing
+/// changed
+#[must_use]
+pub fn rewrite_file(path: &str, text: &str, from: &ProviderRefs, to: &ProviderRefs) -> Option<String> {
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    let mut out = match (file_name, to.names.first()) {
+        ("Cargo.toml", Some(name)) => rename_cargo_dependency(text, &from.names, name),
+        ("package.json", Some(name)) => rename_npm_dependency(text, &from.names, name),
+        _ => text.to_string(),
+    };
+    if let (Some(old), Some(new)) = (&from.uri, &to.uri) {
+        if !old.is_empty() && old != new {
+            out = out.replace(old.as_str(), new);
+        }
+    }
+    (out != text).then_some(out)
+}
+
+/// Rename `name = ...` keys in `*dependencies` tables, and
+/// `[dependencies.name]` headers
+fn rename_cargo_dependency(text: &str, names: &[String], new_name: &str) -> String {
+    let mut in_dependencies = false;
+    map_lines(text, |line| {
+        let trimmed = line.trim_start();
+        if let Some(header) = trimmed.strip_prefix('[') {
+            let header = header.trim_end().trim_end_matches(']');
+            in_dependencies = header.ends_with("dependencies");
+            for name in names {
+                for quoted in [name.clone(), format!("\"{name}\"")] {
+                    let dotted = format!("dependencies.{quoted}");
+                    if header.ends_with(&dotted) {
+                        let start = line.len() - trimmed.len() + 1 + header.len() - quoted.len();
+                        return Some(format!("{}{new_name}{}", &line[..start], &line[start + quoted.len()..]));
+                    }
+                }
+            }
+            return None;
+        }
+        if !in_dependencies {
+            return None;
+        }
+        names.iter().find_map(|name| {
+            let key = [name.clone(), format!("\"{name}\"")]
+                .into_iter()
+                .find(|key| trimmed.strip_prefix(key.as_str()).is_some_and(|rest| rest.trim_start().starts_with('=')))?;
+            let indent = &line[..line.len() - trimmed.len()];
+            Some(format!("{indent}{new_name}{}", &trimmed[key.len()..]))
+        })
+    })
+}
+
+/// Rename `"name": ...` keys in the dependency sections of a `package.json`
+fn rename_npm_dependency(text: &str, names: &[String], new_name: &str) -> String {
+    let mut in_dependencies = false;
+    map_lines(text, |line| {
+        let trimmed = line.trim_start();
+        if NPM_SECTIONS.iter().any(|s| trimmed.starts_with(&format!("\"{s}\""))) {
+            in_dependencies = !trimmed.contains('}');
+            return None;
+        }
+        if in_dependencies && trimmed.starts_with('}') {
+            in_dependencies = false;
+            return None;
+        }
+        if !in_dependencies {
+            return None;
+        }
+        names.iter().find_map(|name| {
+            let rest = trimmed.strip_prefix(&format!("\"{name}\""))?;
+            rest.trim_start().starts_with(':').then(|| {
+                let indent = &line[..line.len() - trimmed.len()];
+                format!("{indent}\"{new_name}\"{rest}")
+            })
+        })
+    })
+}
+
+/// Replace each line `f` returns a new version of, keeping line endings
+fn map_lines(text: &str, mut f: impl FnMut(&str) -> Option<String>) -> String {
+    text.split_inclusive('\n')
+        .map(|raw| {
+            let line = raw.trim_end_matches(['\n', '\r']);
+            match f(line) {
+                Some(new) => format!("{new}{}", &raw[line.len()..]),
+                None => raw.to_string(),
+            }
+        })
+        .collect()
+}
+
+/// `FileChange` ops for the binding creates and switches in `ops`, from their
+/// slots' binding templates. Each file gets one op, with every template
+/// applied in order, at the highest risk of the binding ops touching it.
+///
+/// # Errors
+/// Fails if a template's glob or regex is invalid.
+pub fn template_file_changes(graph: &EcosystemGraph, ops: &[PlanOp]) -> Result<Vec<PlanOp>> {
+    // (repo, file) -> (original, rewritten, binding ops)
+    let mut files: BTreeMap<(String, String), (String, String, Vec<PlanOp>)> = BTreeMap::new();
+    for op in ops {
+        let (consumer_id, slot_id, from_id, to_id) = match op {
+            PlanOp::SwitchBinding { consumer_id, slot_id, from_provider_id, to_provider_id, .. } => {
+                (consumer_id, slot_id, Some(from_provider_id), to_provider_id)
+            }
+            PlanOp::CreateBinding { consumer_id, slot_id, provider_id, .. } => (consumer_id, slot_id, None, provider_id),
+            _ => continue,
+        };
+        let provider = |id: &str| graph.slots.providers.iter().find(|p| p.id == id);
+        let (Some(slot), Some(next), Some(root)) = (
+            graph.slots.slots.iter().find(|s| &s.id == slot_id),
+            provider(to_id),
+            checkout(graph, consumer_id),
+        ) else {
+            continue;
+        };
+        let current = from_id.and_then(|id| provider(id)).unwrap_or(next);
+
+        for template in &slot.templates {
+            for (rel, path) in template_files(&root, &template.glob)? {
+                let key = (consumer_id.clone(), rel);
+                let text = match files.get(&key) {
+                    Some((_, text, _)) => text.clone(),
+                    None => match std::fs::read_to_string(&path) {
+                        Ok(text) => text,
+                        Err(_) => continue,
+                    },
+                };
+                let Some(new) = apply_template(graph, &template.rule, &text, current, next)? else {
+                    continue;
+                };
+                let entry = files.entry(key).or_insert_with(|| (text, String::new(), Vec::new()));
+                entry.1 = new;
+                entry.2.push(op.clone());
+            }
+        }
+    }
+
+    Ok(files
+        .into_iter()
+        .map(|((repo_id, file_path), (original, rewritten, causes))| {
+            let diff = Patch::between(&file_path, Some(&original), Some(&rewritten)).to_string();
+            PlanOp::FileChange {
+                repo_id,
+                file_path,
+                change_type: FileChangeType::Modify,
+                diff: Some(diff),
+                risk: Plan::calculate_overall_risk(&causes),
+            }
+        })
+        .collect())
+}
+
+/// Files in the consumer's checkout where a binding's templates find their
+/// target, each with the template that found it
+///
+/// # Errors
+/// Fails if a template's glob or regex is invalid.
+pub fn binding_files<'a>(
+    graph: &EcosystemGraph,
+    templates: &'a [BindingTemplate],
+    binding: &SlotBinding,
+) -> Result<Vec<(String, &'a BindingTemplate)>> {
+    let (Some(provider), Some(root)) = (
+        graph.slots.providers.iter().find(|p| p.id == binding.provider_id),
+        checkout(graph, &binding.consumer_id),
+    ) else {
+        return Ok(Vec::new());
+    };
+    let mut found = Vec::new();
+    for template in templates {
+        for (rel, path) in template_files(&root, &template.glob)? {
+            let Ok(text) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+            if template_target(graph, &template.rule, &text, provider)?.is_some() {
+                found.push((rel, template));
+            }
+        }
+    }
+    Ok(found)
+}
+
+/// Check that a template's glob and pattern are well formed
+///
+/// # Errors
+/// Fails on an invalid glob or regex, or a JSON pointer not starting with `/`.
+pub fn validate_template(template: &BindingTemplate) -> Result<()> {
+    glob_matcher(&template.glob)?;
+    match &template.rule {
+        RewriteRule::Regex { pattern, .. } => {
+            let sample = PLACEHOLDERS.iter().fold(pattern.clone(), |p, placeholder| p.replace(placeholder, "x"));
+            Regex::new(&sample).with_context(|| format!("Invalid regex: {pattern}"))?;
+        }
+        RewriteRule::JsonPointer { pointer, .. } if !pointer.is_empty() && !pointer.starts_with('/') => {
+            anyhow::bail!("A JSON pointer starts with '/': {pointer}");
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// `text` rewritten by a template rule, locating its target with `current`
+/// and filling its value from `next`; `None` if the target is not there, a
+/// field the rule names is unset, or nothing changes. Files that do not
+/// parse are left alone.
+///
+/// # Errors
+/// Fails if the rule's regex is invalid.
+pub fn apply_template(
+    graph: &EcosystemGraph,
+    rule: &RewriteRule,
+    text: &str,
+    current: &Provider,
+    next: &Provider,
+) -> Result<Option<String>> {
+    let Some(target) = template_target(graph, rule, text, current)? else {
+        return Ok(None);
+    };
+    let new = match (rule, target) {
+        (RewriteRule::TomlKey { value, .. }, Target::Toml(mut doc, path)) => {
+            let Some(value) = fill(graph, value, next, str::to_string) else {
+                return Ok(None);
+            };
+            let Some(item) = toml_item(&mut doc, &path).and_then(toml_edit::Item::as_value_mut) else {
+                return Ok(None);
+            };
+            if item.as_str() == Some(value.as_str()) {
+                return Ok(None);
+            }
+            let decor = item.decor().clone();
+            *item = value.into();
+            *item.decor_mut() = decor;
+            doc.to_string()
+        }
+        (RewriteRule::JsonPointer { value, .. }, Target::Json(span)) => {
+            let Some(value) = fill(graph, value, next, str::to_string) else {
+                return Ok(None);
+            };
+            let value = serde_json::Value::String(value);
+            if serde_json::from_str::<serde_json::Value>(&text[span.clone()]).ok().as_ref() == Some(&value) {
+                return Ok(None);
+            }
+            format!("{}{value}{}", &text[..span.start], &text[span.end..])
+        }
+        (RewriteRule::Regex { replacement, .. }, Target::Regex(re)) => {
+            let Some(replacement) = fill(graph, replacement, next, |v| v.replace('$', "$$")) else {
+                return Ok(None);
+            };
+            re.replace_all(text, replacement.as_str()).into_owned()
+        }
+        _ => return Ok(None),
+    };
+    Ok((new != text).then_some(new))
+}
+
+/// Where a rule's target was found in a file
+enum Target {
+    Toml(toml_edit::DocumentMut, String),
+    Json(Range<usize>),
+    Regex(Regex),
+}
+
+/// Locate a rule's target in `text`, filled from `provider`
+fn template_target(graph: &EcosystemGraph, rule: &RewriteRule, text: &str, provider: &Provider) -> Result<Option<Target>> {
+    Ok(match rule {
+        RewriteRule::TomlKey { path, .. } => fill(graph, path, provider, str::to_string).and_then(|path| {
+            let mut doc: toml_edit::DocumentMut = text.parse().ok()?;
+            toml_item(&mut doc, &path)?.as_value()?;
+            Some(Target::Toml(doc, path))
+        }),
+        RewriteRule::JsonPointer { pointer, .. } => {
+            let escape = |v: &str| v.replace('~', "~0").replace('/', "~1");
+            fill(graph, pointer, provider, escape).and_then(|pointer| json_span(text, &pointer)).map(Target::Json)
+        }
+        RewriteRule::Regex { pattern, .. } => match fill(graph, pattern, provider, regex::escape) {
+            Some(pattern) => {
+                let re = Regex::new(&pattern).with_context(|| format!("Invalid regex: {pattern}"))?;
+                re.is_match(text).then_some(Target::Regex(re))
+            }
+            None => None,
+        },
+    })
+}
+
+/// Provider fields a template can use
+const PLACEHOLDERS: [&str; 5] = ["{id}", "{name}", "{uri}", "{repo}", "{version}"];
+
+/// Fill `{id}`, `{name}`, `{uri}`, `{repo}` and `{version}` from a provider,
+/// escaping each value; `None` if a field used is unset
+fn fill(graph: &EcosystemGraph, text: &str, provider: &Provider, escape: impl Fn(&str) -> String) -> Option<String> {
+    let repo = provider.repo_id.as_deref().and_then(|id| graph.get_repo(id)).map(|r| r.name.as_str());
+    let values = [
+        Some(provider.id.as_str()),
+        Some(provider.name.as_str()),
+        provider.external_uri.as_deref(),
+        repo,
+        provider.interface_version.as_deref(),
+    ];
+    let mut out = text.to_string();
+    for (placeholder, value) in PLACEHOLDERS.into_iter().zip(values) {
+        if out.contains(placeholder) {
+            out = out.replace(placeholder, &escape(value?));
+        }
+    }
+    Some(out)
+}
+
+fn glob_matcher(glob: &str) -> Result<GlobMatcher> {
+    Ok(GlobBuilder::new(glob)
+        .literal_separator(true)
+        .build()
+        .with_context(|| format!("Invalid glob: {glob}"))?
+        .compile_matcher())
+}
+
+/// Files in a checkout matching a template glob, as (relative path, full
+/// path), skipping hidden and build directories
+fn template_files(root: &Path, glob: &str) -> Result<Vec<(String, PathBuf)>> {
+    let matcher = glob_matcher(glob)?;
+    let mut files: Vec<(String, PathBuf)> = WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !e.file_type().is_dir() || !skipped_dir(&e.file_name().to_string_lossy()))
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| {
+            let rel = relative(root, e.path())?;
+            matcher.is_match(&rel).then(|| (rel, e.into_path()))
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+fn skipped_dir(name: &str) -> bool {
+    name.starts_with('.') || SKIP_DIRS.contains(&name)
+}
+
+/// `path` relative to `root`, with `/` separators
+fn relative(root: &Path, path: &Path) -> Option<String> {
+    let rel = path.strip_prefix(root).ok()?;
+    Some(rel.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/"))
+}
+
+/// The item at a dotted key path
+fn toml_item<'a>(doc: &'a mut toml_edit::DocumentMut, path: &str) -> Option<&'a mut toml_edit::Item> {
+    path.split('.').try_fold(doc.as_item_mut(), |item, key| item.get_mut(key))
+}
+
+/// Byte range of the value at a JSON pointer
+fn json_span(text: &str, pointer: &str) -> Option<Range<usize>> {
+    // Only well-formed documents, so the scan below can trust the structure
+    serde_json::from_str::<serde_json::Value>(text).ok()?;
+    let tokens: Vec<String> = if pointer.is_empty() {
+        Vec::new()
+    } else {
+        pointer.strip_prefix('/')?.split('/').map(|t| t.replace("~1", "/").replace("~0", "~")).collect()
+    };
+    let bytes = text.as_bytes();
+    let skip_ws = |pos: usize| pos + bytes[pos..].iter().take_while(|b| b.is_ascii_whitespace()).count();
+
+    let mut pos = skip_ws(0);
+    for token in &tokens {
+        match bytes[pos] {
+            b'{' => loop {
+                pos = skip_ws(pos + 1);
+                if bytes[pos] == b'}' {
+                    return None;
+                }
+                let key_end = json_end(bytes, pos)?;
+                let key: String = serde_json::from_str(&text[pos..key_end]).ok()?;
+                pos = skip_ws(skip_ws(key_end) + 1);
+                if key == *token {
+                    break;
+                }
+                pos = skip_ws(json_end(bytes, pos)?);
+                if bytes[pos] != b',' {
+                    return None;
+                }
+            },
+            b'[' => {
+                let index: usize = token.parse().ok()?;
+                pos = skip_ws(pos + 1);
+                for _ in 0..index {
+                    if bytes[pos] == b']' {
+                        return None;
+                    }
+                    pos = skip_ws(json_end(bytes, pos)?);
+                    if bytes[pos] != b',' {
+                        return None;
+                    }
+                    pos = skip_ws(pos + 1);
+                }
+                if bytes[pos] == b']' {
+                    return None;
+                }
+            }
+            _ => return None,
+        }
+    }
+    Some(pos..json_end(bytes, pos)?)
+}
+
+/// End of the JSON value starting at `start`
+fn json_end(bytes: &[u8], start: usize) -> Option<usize> {
+    match bytes.get(start)? {
+        b'"' => {
+            let mut i = start + 1;
+            loop {
+                match bytes.get(i)? {
+                    b'\\' => i += 2,
+                    b'"' => return Some(i + 1),
+                    _ => i += 1,
+                }
+            }
+        }
+        b'{' | b'[' => {
+            let (mut depth, mut i) = (0usize, start);
+            loop {
+                match bytes.get(i)? {
+                    b'"' => {
+                        i = json_end(bytes, i)?;
+                        continue;
+                    }
+                    b'{' | b'[' => depth += 1,
+                    b'}' | b']' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            return Some(i + 1);
+                        }
+                    }
+                    _ => {}
+                }
+                i += 1;
+            }
+        }
+        _ => {
+            let len = bytes[start..]
+                .iter()
+                .position(|b| matches!(b, b',' | b'}' | b']') || b.is_ascii_whitespace())
+                .unwrap_or(bytes.len() - start);
+            (len > 0).then_some(start + len)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ProviderType;
+
+    fn provider(name: &str, uri: Option<&str>) -> Provider {
+        Provider {
+            kind: "Provider".into(),
+            id: format!("provider:container.runtime:{name}"),
+            name: name.into(),
+            slot_id: "slot:container.runtime".into(),
+            provider_type: ProviderType::External,
+            repo_id: None,
+            external_uri: uri.map(Into::into),
+            interface_version: Some("1.0".into()),
+            capabilities: vec![],
+            priority: 0,
+            is_fallback: false,
+        }
+    }
+
+    fn refs(names: &[&str], uri: Option<&str>) -> ProviderRefs {
+        ProviderRefs { names: names.iter().map(ToString::to_string).collect(), uri: uri.map(Into::into) }
+    }
+
+    #[test]
+    fn test_rewrite_manifests_and_config() {
+        let from = refs(&["podman-rs", "podman"], Some("https://podman.io/api"));
+        let to = refs(&["cerro-torre"], Some("https://cerro.example/api"));
+
+        let cargo = "[package]\nname = \"podman-rs\"\n\n[dependencies]\nserde = \"1\"\npodman-rs = { version = \"4\" }\n\n[dev-dependencies.podman]\nversion = \"4\"\n";
+        assert_eq!(
+            rewrite_file("Cargo.toml", cargo, &from, &to).unwrap(),
+            "[package]\nname = \"podman-rs\"\n\n[dependencies]\nserde = \"1\"\ncerro-torre = { version = \"4\" }\n\n[dev-dependencies.cerro-torre]\nversion = \"4\"\n"
+        );
+
+        let npm = "{\n  \"name\": \"podman\",\n  \"dependencies\": {\n    \"podman\": \"^4.0.0\",\n    \"left-pad\": \"1\"\n  }\n}\n";
+        let rewritten = rewrite_file("web/package.json", npm, &from, &to).unwrap();
+        assert!(rewritten.contains("  \"name\": \"podman\",\n"));
+        assert!(rewritten.contains("    \"cerro-torre\": \"^4.0.0\",\n"));
+
+        let config = "runtime:\n  endpoint: https://podman.io/api\r\n";
+        assert_eq!(
+            rewrite_file("deploy/app.yaml", config, &from, &to).unwrap(),
+            "runtime:\n  endpoint: https://cerro.example/api\r\n"
+        );
+        assert_eq!(rewrite_file("README.md.toml", "nothing here\n", &from, &to), None);
+    }
+
+    #[test]
+    fn test_template_rules() {
+        let graph = EcosystemGraph::new();
+        let (podman, cerro) = (provider("podman", Some("unix:///run/podman.sock")), provider("cerro", None));
+        let apply = |rule: &RewriteRule, text: &str, next: &Provider| apply_template(&graph, rule, text, &podman, next).unwrap();
+
+        // TOML keeps comments and layout
+        let rule = RewriteRule::TomlKey { path: "runtime.{name}.engine".into(), value: "{name}@{version}".into() };
+        let toml = "# runtime\n[runtime.podman]\nengine = \"podman@1.0\"  # pinned\n";
+        assert_eq!(apply(&rule, toml, &cerro).unwrap(), "# runtime\n[runtime.podman]\nengine = \"cerro@1.0\"  # pinned\n");
+        assert_eq!(apply(&rule, toml, &podman), None);
+        assert_eq!(apply(&rule, "[runtime.other]\nengine = \"x\"\n", &cerro), None);
+
+        let rule = RewriteRule::JsonPointer { pointer: "/services/0/runtime".into(), value: "{name}".into() };
+        let json = "{\n  \"name\": \"app\",\n  \"services\": [\n    {\"runtime\": \"podman\", \"port\": 80}\n  ]\n}\n";
+        assert_eq!(
+            apply(&rule, json, &cerro).unwrap(),
+            "{\n  \"name\": \"app\",\n  \"services\": [\n    {\"runtime\": \"cerro\", \"port\": 80}\n  ]\n}\n"
+        );
+        assert_eq!(apply(&rule, "{\"services\": []}", &cerro), None);
+
+        // The pattern finds the current provider, escaped; unset fields skip
+        let rule = RewriteRule::Regex { pattern: r"RUNTIME=(\w+:)?{uri}".into(), replacement: "RUNTIME=${1}{name}".into() };
+        assert_eq!(apply(&rule, "RUNTIME=unix:///run/podman.sock\n", &cerro).unwrap(), "RUNTIME=cerro\n");
+        let rule = RewriteRule::Regex { pattern: "{name}".into(), replacement: "{uri}".into() };
+        assert_eq!(apply(&rule, "podman\n", &cerro), None);
+
+        assert_eq!(json_span("[1, {\"a\": \"}\"}, 3]", "/1/a"), Some(10..13));
+        assert_eq!(json_span("{\"a/b\": {\"~\": true}}", "/a~1b/~0"), Some(14..18));
+    }
+}
@@ -0,0 +1,275 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Scan cache - lets repeat scans skip repos that have not changed
+//!
+//! Each scanned checkout is fingerprinted by its HEAD commit, the mtime of
+//! `.git/config` (remote changes) and the mtimes of the manifests the edge
+//! detectors read. The fingerprint is computed straight from the filesystem,
+//! without opening the repo through gix, so an unchanged repo costs a handful
+//! of `stat` calls. The cache persists to `scan-cache.json` in the data dir.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File name of the cache inside the data dir
+pub const CACHE_FILE: &str = "scan-cache.json";
+
+/// Files whose modification invalidates a cached scan (relative to the repo root).
+const WATCHED_FILES: &[&str] = &[
+    ".git/config",
+    ".gitmodules",
+    "Cargo.toml",
+    "package.json",
+    "deno.json",
+    "deno.jsonc",
+    "go.mod",
+    "mix.exs",
+    "gleam.toml",
+];
+
+/// Cheap identity of a checkout's state
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RepoFingerprint {
+    /// HEAD commit OID (`None` for an unborn branch)
+    pub head: Option<String>,
+    /// Modification times of watched files that exist
+    #[serde(default)]
+    pub mtimes: BTreeMap<String, DateTime<Utc>>,
+}
+
+impl RepoFingerprint {
+    /// Fingerprint the checkout at `path`.
+    #[must_use]
+    pub fn of(path: &Path) -> Self {
+        let mtimes = WATCHED_FILES
+            .iter()
+            .filter_map(|file| {
+                let modified = fs::metadata(path.join(file)).ok()?.modified().ok()?;
+                Some(((*file).to_string(), DateTime::<Utc>::from(modified)))
+            })
+            .collect();
+        Self {
+            head: git_dir(path).and_then(|dir| read_head_oid(&dir)),
+            mtimes,
+        }
+    }
+}
+
+/// What was recorded the last time a checkout was scanned
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheEntry {
+    /// Repo ID the checkout resolved to
+    pub repo_id: String,
+    /// Fingerprint at scan time
+    pub fingerprint: RepoFingerprint,
+    /// Whether that scan was deep (tags + edge detection)
+    pub deep: bool,
+    /// Edge detectors that ran (deep scans only)
+    #[serde(default)]
+    pub detectors: Vec<String>,
+    /// When the checkout was last scanned
+    pub scanned_at: DateTime<Utc>,
+}
+
+/// Persistent scan cache, keyed by canonical checkout path
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScanCache {
+    /// Entries by checkout path
+    #[serde(default)]
+    pub entries: BTreeMap<PathBuf, CacheEntry>,
+}
+
+impl ScanCache {
+    /// Load the cache from `dir`, starting empty if it is missing or unreadable.
+    #[must_use]
+    pub fn load(dir: &Path) -> Self {
+        let path = dir.join(CACHE_FILE);
+        let Ok(content) = fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        serde_json::from_str(&content).unwrap_or_else(|err| {
+            tracing::warn!("Ignoring unreadable scan cache {}: {}", path.display(), err);
+            Self::default()
+        })
+    }
+
+    /// Save the cache to `dir`.
+    ///
+    /// # Errors
+    /// Returns an error if the directory cannot be created or the file written.
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create data directory: {}", dir.display()))?;
+        let path = dir.join(CACHE_FILE);
+        let json = serde_json::to_string_pretty(self).context("Failed to serialize scan cache")?;
+        fs::write(&path, json).with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Return the cached entry for `path` if it still matches `fingerprint`
+    /// and was scanned at least as thoroughly as requested now.
+    #[must_use]
+    pub fn fresh(
+        &self,
+        path: &Path,
+        fingerprint: &RepoFingerprint,
+        deep: bool,
+        detectors: &[String],
+    ) -> Option<&CacheEntry> {
+        let entry = self.entries.get(path)?;
+        let thorough = !deep || (entry.deep && entry.detectors == detectors);
+        (entry.fingerprint == *fingerprint && thorough).then_some(entry)
+    }
+
+    /// Record a completed scan of `path`.
+    pub fn record(&mut self, path: PathBuf, entry: CacheEntry) {
+        self.entries.insert(path, entry);
+    }
+
+    /// Forget every entry for a repo ID, e.g. after it was pruned.
+    pub fn forget_repo(&mut self, repo_id: &str) {
+        self.entries.retain(|_, e| e.repo_id != repo_id);
+    }
+}
+
+/// The git dir of the checkout at `path`: `.git` itself, or where a `.git`
+/// file's `gitdir:` line points (worktrees, submodules).
+fn git_dir(path: &Path) -> Option<PathBuf> {
+    let dot_git = path.join(".git");
+    if dot_git.is_dir() {
+        return Some(dot_git);
+    }
+    let pointer = fs::read_to_string(&dot_git).ok()?;
+    let target = pointer.lines().find_map(|l| l.strip_prefix("gitdir:"))?.trim();
+    Some(path.join(target))
+}
+
+/// Resolve HEAD to a commit OID by reading the git dir directly.
+///
+/// Handles a detached HEAD, loose refs and `packed-refs`, looking refs up in
+/// the common dir too when `git_dir` belongs to a linked worktree.
+fn read_head_oid(git_dir: &Path) -> Option<String> {
+    let head = fs::read_to_string(git_dir.join("HEAD")).ok()?;
+    let head = head.trim();
+    let Some(reference) = head.strip_prefix("ref:") else {
+        return Some(head.to_string());
+    };
+    let reference = reference.trim();
+
+    let common_dir = fs::read_to_string(git_dir.join("commondir")).ok().map(|c| git_dir.join(c.trim()));
+    let dirs = std::iter::once(git_dir).chain(common_dir.as_deref());
+    for dir in dirs.clone() {
+        if let Ok(oid) = fs::read_to_string(dir.join(reference)) {
+            return Some(oid.trim().to_string());
+        }
+    }
+    dirs.filter_map(|dir| fs::read_to_string(dir.join("packed-refs")).ok()).find_map(|packed| {
+        packed.lines().find_map(|line| {
+            let (oid, name) = line.split_once(' ')?;
+            (name.trim() == reference).then(|| oid.to_string())
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn fake_checkout(root: &Path) {
+        fs::create_dir_all(root.join(".git/refs/heads")).unwrap();
+        fs::write(root.join(".git/HEAD"), "ref: refs/heads/main\n").unwrap();
+        fs::write(root.join(".git/config"), "[core]\n").unwrap();
+    }
+
+    #[test]
+    fn test_head_oid_from_loose_and_packed_refs() {
+        let temp = TempDir::new().unwrap();
+        fake_checkout(temp.path());
+        let git = temp.path().join(".git");
+
+        assert_eq!(read_head_oid(&git), None);
+
+        fs::write(git.join("packed-refs"), "# pack-refs with: peeled\nabc123 refs/heads/main\n").unwrap();
+        assert_eq!(read_head_oid(&git).as_deref(), Some("abc123"));
+
+        fs::write(git.join("refs/heads/main"), "def456\n").unwrap();
+        assert_eq!(read_head_oid(&git).as_deref(), Some("def456"));
+
+        fs::write(git.join("HEAD"), "0123abcd\n").unwrap();
+        assert_eq!(read_head_oid(&git).as_deref(), Some("0123abcd"));
+    }
+
+    #[test]
+    fn test_head_oid_through_gitdir_file() {
+        let temp = TempDir::new().unwrap();
+        let main = temp.path().join("main");
+        fake_checkout(&main);
+        fs::write(main.join(".git/refs/heads/main"), "aaa\n").unwrap();
+
+        // A linked worktree: HEAD in its own git dir, refs in the common dir
+        let linked_dir = main.join(".git/worktrees/feature");
+        fs::create_dir_all(&linked_dir).unwrap();
+        fs::write(linked_dir.join("HEAD"), "ref: refs/heads/feature\n").unwrap();
+        fs::write(linked_dir.join("commondir"), "../..\n").unwrap();
+        fs::write(main.join(".git/refs/heads/feature"), "bbb\n").unwrap();
+        let linked = temp.path().join("feature");
+        fs::create_dir_all(&linked).unwrap();
+        fs::write(linked.join(".git"), format!("gitdir: {}\n", linked_dir.display())).unwrap();
+
+        assert_eq!(RepoFingerprint::of(&main).head.as_deref(), Some("aaa"));
+        assert_eq!(RepoFingerprint::of(&linked).head.as_deref(), Some("bbb"));
+
+        // A relative pointer, as submodules use
+        let sub = main.join("sub");
+        fs::create_dir_all(main.join(".git/modules/sub")).unwrap();
+        fs::write(main.join(".git/modules/sub/HEAD"), "ccc\n").unwrap();
+        fs::create_dir_all(&sub).unwrap();
+        fs::write(sub.join(".git"), "gitdir: ../.git/modules/sub\n").unwrap();
+        assert_eq!(RepoFingerprint::of(&sub).head.as_deref(), Some("ccc"));
+    }
+
+    #[test]
+    fn test_fresh_requires_matching_fingerprint_and_depth() {
+        let temp = TempDir::new().unwrap();
+        fake_checkout(temp.path());
+        fs::write(temp.path().join(".git/refs/heads/main"), "aaa\n").unwrap();
+
+        let path = temp.path().to_path_buf();
+        let fingerprint = RepoFingerprint::of(&path);
+        assert!(fingerprint.mtimes.contains_key(".git/config"));
+
+        let mut cache = ScanCache::default();
+        cache.record(
+            path.clone(),
+            CacheEntry {
+                repo_id: "repo:local:x".into(),
+                fingerprint: fingerprint.clone(),
+                deep: false,
+                detectors: vec![],
+                scanned_at: Utc::now(),
+            },
+        );
+
+        assert!(cache.fresh(&path, &fingerprint, false, &[]).is_some());
+        // A shallow cache entry cannot satisfy a deep scan
+        assert!(cache.fresh(&path, &fingerprint, true, &[]).is_none());
+
+        // A new commit invalidates the entry
+        fs::write(temp.path().join(".git/refs/heads/main"), "bbb\n").unwrap();
+        let moved = RepoFingerprint::of(&path);
+        assert_ne!(moved, fingerprint);
+        assert!(cache.fresh(&path, &moved, false, &[]).is_none());
+
+        // Round-trips through the data dir
+        cache.save(temp.path()).unwrap();
+        let loaded = ScanCache::load(temp.path());
+        assert_eq!(loaded.entries[&path].fingerprint, fingerprint);
+
+        cache.forget_repo("repo:local:x");
+        assert!(cache.entries.is_empty());
+    }
+}
@@ -2,13 +2,19 @@
 // SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
 //! Repository scanning - discovers git repositories in a directory tree
 
+use crate::graph::EcosystemGraph;
+use crate::progress::Progress;
 use crate::types::{Forge, ImportMeta, Repo, Visibility};
 use anyhow::{Context, Result};
 use chrono::Utc;
-use std::path::Path;
+use rayon::prelude::*;
+use std::path::{Path, PathBuf};
 use tracing::{debug, info, warn};
 use walkdir::WalkDir;
 
+/// `ImportMeta.source` stamped on repos discovered by a local scan
+pub const SCAN_SOURCE: &str = "local-scan";
+
 /// Configuration for scanning
 #[derive(Debug, Clone)]
 pub struct ScanConfig {
@@ -20,6 +26,24 @@ pub struct ScanConfig {
     pub deep: bool,
     /// Directories to skip
     pub skip_dirs: Vec<String>,
+    /// Edge detectors to run on deep scans (`None` = all registered)
+    pub detectors: Option<Vec<String>>,
+    /// Edge detectors to skip, applied after `detectors`
+    pub disabled_detectors: Vec<String>,
+    /// Worker threads for scanning (0 = one per CPU)
+    pub jobs: usize,
+}
+
+impl ScanConfig {
+    /// Whether the edge detector called `name` should run.
+    #[must_use]
+    pub fn detector_enabled(&self, name: &str) -> bool {
+        let selected = self
+            .detectors
+            .as_ref()
+            .map_or(true, |names| names.iter().any(|n| n == name));
+        selected && !self.disabled_detectors.iter().any(|n| n == name)
+    }
 }
 
 impl Default for ScanConfig {
@@ -38,6 +62,9 @@ fn default() -> Self {
                 "dist".into(),
                 "build".into(),
             ],
+            detectors: None,
+            disabled_detectors: Vec::new(),
+            jobs: 0,
         }
     }
 }
@@ -53,13 +80,75 @@ pub struct ScanResult {
 
 /// Scan a path for git repositories
 pub fn scan_path(path: &Path, config: &ScanConfig) -> Result<Vec<ScanResult>> {
+    let repo_paths = discover_repos(path, config)?;
+    let pool = worker_pool(config.jobs)?;
+
+    let mut results = Vec::new();
+    for (repo_path, result) in scan_repos(&pool, repo_paths, config, &Progress::hidden()) {
+        match result {
+            Ok(result) => results.push(result),
+            Err(err) => {
+                warn!(
+                    "Failed to scan repository {}: {}",
+                    repo_path.display(),
+                    err
+                );
+            }
+        }
+    }
+
+    info!("Found {} repositories", results.len());
+    Ok(results)
+}
+
+/// Build the bounded worker pool used for scanning
+///
+/// # Errors
+/// Returns an error if the pool's threads cannot be spawned.
+pub fn worker_pool(jobs: usize) -> Result<rayon::ThreadPool> {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(jobs)
+        .thread_name(|i| format!("reposystem-scan-{i}"))
+        .build()
+        .context("Failed to start scan worker pool")
+}
+
+/// Scan checkouts on `pool`, ticking `progress` as each one finishes
+///
+/// Results come back in the order of `repo_paths`, however the work was
+/// scheduled, so callers see the same output on every run.
+pub fn scan_repos(
+    pool: &rayon::ThreadPool,
+    repo_paths: Vec<PathBuf>,
+    config: &ScanConfig,
+    progress: &Progress,
+) -> Vec<(PathBuf, Result<ScanResult>)> {
+    pool.install(|| {
+        repo_paths
+            .into_par_iter()
+            .map(|repo_path| {
+                let result = scan_repo(&repo_path, config);
+                progress.tick();
+                (repo_path, result)
+            })
+            .collect()
+    })
+}
+
+/// Find git checkouts under a path without opening them
+///
+/// Returned paths are canonical and sorted, independent of directory order.
+///
+/// # Errors
+/// Returns an error if `path` cannot be canonicalized.
+pub fn discover_repos(path: &Path, config: &ScanConfig) -> Result<Vec<PathBuf>> {
     let path = path
         .canonicalize()
         .with_context(|| format!("Failed to canonicalize path: {}", path.display()))?;
 
     info!("Scanning {} for git repositories", path.display());
 
-    let mut results = Vec::new();
+    let mut repos = Vec::new();
     let mut walker = WalkDir::new(&path).follow_links(config.follow_symlinks);
 
     if config.max_depth > 0 {
@@ -82,27 +171,18 @@ pub fn scan_path(path: &Path, config: &ScanConfig) -> Result<Vec<ScanResult>> {
         let git_dir = entry.path().join(".git");
         if git_dir.exists() && git_dir.is_dir() {
             debug!("Found git repository: {}", entry.path().display());
-
-            match scan_repo(entry.path(), config) {
-                Ok(result) => results.push(result),
-                Err(err) => {
-                    warn!(
-                        "Failed to scan repository {}: {}",
-                        entry.path().display(),
-                        err
-                    );
-                }
-            }
+            repos.push(entry.path().to_path_buf());
         }
     }
 
-    info!("Found {} repositories", results.len());
-    Ok(results)
+    repos.sort();
+    Ok(repos)
 }
 
 /// Check if a directory entry should be skipped
 fn should_skip(entry: &walkdir::DirEntry, skip_dirs: &[String]) -> bool {
-    if !entry.file_type().is_dir() {
+    // Never skip the root the user asked to scan
+    if entry.depth() == 0 || !entry.file_type().is_dir() {
         return false;
     }
 
@@ -117,7 +197,10 @@ fn should_skip(entry: &walkdir::DirEntry, skip_dirs: &[String]) -> bool {
 }
 
 /// Scan a single git repository
-fn scan_repo(path: &Path, config: &ScanConfig) -> Result<ScanResult> {
+///
+/// # Errors
+/// Returns an error if the repository cannot be opened.
+pub fn scan_repo(path: &Path, config: &ScanConfig) -> Result<ScanResult> {
     let mut warnings = Vec::new();
 
     // Try to open the repository with gix
@@ -136,8 +219,13 @@ fn scan_repo(path: &Path, config: &ScanConfig) -> Result<ScanResult> {
         vec![]
     };
 
-    // Generate ID
+    // Generate ID. Local-only repos remember their root commit so they can
+    // be recognised if the checkout later moves or gains a forge remote.
+    let mut metadata = std::collections::HashMap::new();
     let id = if forge == Forge::Local {
+        if let Some(root) = root_commit_of(&repo) {
+            metadata.insert(Repo::ROOT_COMMIT_KEY.to_string(), root);
+        }
         Repo::local_id(path)
     } else {
         Repo::forge_id(forge, &owner, &name)
@@ -153,9 +241,9 @@ fn scan_repo(path: &Path, config: &ScanConfig) -> Result<ScanResult> {
         visibility,
         tags,
         estate: crate::types::default_estate(),
-        metadata: Default::default(),
+        metadata,
         imports: ImportMeta {
-            source: "local-scan".into(),
+            source: SCAN_SOURCE.into(),
             path_hint: Some(path.to_path_buf()),
             imported_at: Utc::now(),
         },
@@ -266,6 +354,98 @@ fn get_default_branch(repo: &gix::Repository) -> Option<String> {
         .map(|r| r.name().shorten().to_string())
 }
 
+/// The root commit of a checkout's history
+///
+/// With several roots (merged histories) the smallest ID is returned so the
+/// answer is stable.
+#[must_use]
+pub fn root_commit(path: &Path) -> Option<String> {
+    root_commit_of(&gix::open(path).ok()?)
+}
+
+fn root_commit_of(repo: &gix::Repository) -> Option<String> {
+    repo.head_id()
+        .ok()?
+        .ancestors()
+        .all()
+        .ok()?
+        .filter_map(Result::ok)
+        .filter(|info| info.parent_ids.is_empty())
+        .map(|info| info.id.to_string())
+        .min()
+}
+
+/// A local-only repo node that has reappeared under a forge ID
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transfer {
+    /// The `repo:local:<hash>` node
+    pub local_id: String,
+    /// The forge node it should be merged into
+    pub forge_id: String,
+    /// How the two were matched: `"path"` or `"root-commit"`
+    pub matched_by: &'static str,
+}
+
+/// Match local-only repos in `graph` against freshly seen forge `repos`
+///
+/// A local node matches a forge repo checked out at the same path. A local
+/// node whose checkout is gone matches a forge repo with the same root commit,
+/// provided exactly one does (forks share root commits).
+#[must_use]
+pub fn find_transfers(graph: &EcosystemGraph, repos: &[&Repo]) -> Vec<Transfer> {
+    let forge_repos: Vec<&Repo> = repos
+        .iter()
+        .copied()
+        .filter(|r| r.forge != Forge::Local)
+        .collect();
+    let mut transfers = Vec::new();
+    let mut orphans = Vec::new();
+
+    for local in graph.repos().iter().filter(|r| r.forge == Forge::Local) {
+        let Some(hint) = local.imports.path_hint.as_deref() else {
+            continue;
+        };
+        if let Some(repo) = forge_repos.iter().find(|r| r.imports.path_hint.as_deref() == Some(hint)) {
+            transfers.push(Transfer {
+                local_id: local.id.clone(),
+                forge_id: repo.id.clone(),
+                matched_by: "path",
+            });
+        } else if !hint.join(".git").is_dir() {
+            if let Some(root) = local.metadata.get(Repo::ROOT_COMMIT_KEY) {
+                orphans.push((local, root));
+            }
+        }
+    }
+    if orphans.is_empty() {
+        return transfers;
+    }
+
+    // A stored root commit (e.g. kept from a merged local node) saves a
+    // history walk
+    let roots: Vec<(&Repo, String)> = forge_repos
+        .iter()
+        .filter_map(|r| {
+            if let Some(root) = r.metadata.get(Repo::ROOT_COMMIT_KEY) {
+                return Some((*r, root.clone()));
+            }
+            let path = r.local_path.as_deref().or(r.imports.path_hint.as_deref())?;
+            Some((*r, root_commit(path)?))
+        })
+        .collect();
+    for (local, root) in orphans {
+        let mut matches = roots.iter().filter(|(_, r)| r == root);
+        if let (Some((repo, _)), None) = (matches.next(), matches.next()) {
+            transfers.push(Transfer {
+                local_id: local.id.clone(),
+                forge_id: repo.id.clone(),
+                matched_by: "root-commit",
+            });
+        }
+    }
+    transfers
+}
+
 /// Extract tags from repository (e.g., from topics, languages, etc.)
 fn extract_tags(path: &Path, warnings: &mut Vec<String>) -> Vec<String> {
     let mut tags = Vec::new();
@@ -390,4 +570,59 @@ fn test_scan_empty_dir() {
         let results = scan_path(temp.path(), &config).unwrap();
         assert!(results.is_empty());
     }
+
+    #[test]
+    fn test_transfer_by_stored_root_commit() {
+        let temp = TempDir::new().unwrap();
+        let repo = |forge: Forge, id: &str, path: Option<PathBuf>, root: &str| Repo {
+            kind: "Repo".into(),
+            id: id.into(),
+            forge,
+            owner: "test".into(),
+            name: "app".into(),
+            default_branch: "main".into(),
+            visibility: crate::types::Visibility::Public,
+            tags: vec![],
+            estate: "estate:test".into(),
+            metadata: [(Repo::ROOT_COMMIT_KEY.to_string(), root.to_string())].into(),
+            imports: crate::types::ImportMeta {
+                source: "scan".into(),
+                path_hint: path,
+                imported_at: chrono::Utc::now(),
+            },
+            local_path: None,
+        };
+
+        // The local checkout is gone; the forge repo has no checkout to walk
+        let mut graph = EcosystemGraph::new();
+        graph.add_repo(repo(Forge::Local, "repo:local:abc", Some(temp.path().join("gone")), "r00t"));
+        let forge = repo(Forge::GitHub, "repo:gh:test/app", None, "r00t");
+        let transfers = find_transfers(&graph, &[&forge]);
+        assert_eq!(transfers.len(), 1);
+        assert_eq!(transfers[0].forge_id, "repo:gh:test/app");
+        assert_eq!(transfers[0].matched_by, "root-commit");
+    }
+
+    #[test]
+    fn test_parallel_scan_order_is_deterministic() {
+        let temp = TempDir::new().unwrap();
+        for name in ["zeta", "alpha", "mid/beta", "mid/aardvark", "omega"] {
+            gix::init(temp.path().join(name)).unwrap();
+        }
+
+        let ids = |jobs: usize| -> Vec<String> {
+            let config = ScanConfig { jobs, ..Default::default() };
+            scan_path(temp.path(), &config)
+                .unwrap()
+                .into_iter()
+                .map(|r| r.repo.name)
+                .collect()
+        };
+
+        let sequential = ids(1);
+        assert_eq!(sequential, ["alpha", "aardvark", "beta", "omega", "zeta"]);
+        for _ in 0..3 {
+            assert_eq!(ids(4), sequential);
+        }
+    }
 }
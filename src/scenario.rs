@@ -0,0 +1,568 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Scenario comparison, promotion and merging
+//!
+//! Both scenarios are materialised with [`EcosystemGraph::with_scenario`] and
+//! compared as graphs: a [`GraphDiff`] from B to A (edges, annotations, group
+//! memberships and the slot bindings the scenarios imply), the weak links
+//! each one has, and the risk and strength weight per aspect.
+//!
+//! [`promote`] replays a scenario's change set onto the baseline and [`merge`]
+//! replays one scenario's onto another. Both check every op first and change
+//! nothing if any conflicts: an edge or annotation that is already gone, an
+//! annotation id that was written concurrently with different content, or a
+//! group whose members moved on since the op was recorded.
+
+use crate::analysis::{weak_links, WeakLink};
+use crate::diff::GraphDiff;
+use crate::graph::EcosystemGraph;
+use crate::types::{
+    ApplyResult, AspectAnnotation, AuditEntry, ChangeOp, ChangeSet, OpResult, Polarity, Scenario,
+};
+use anyhow::Result;
+use chrono::Utc;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+/// The effective graphs of two scenarios, compared
+#[derive(Debug, Clone, Serialize)]
+pub struct ScenarioComparison {
+    /// Scenario A (the one being evaluated)
+    pub a: String,
+    /// Scenario B (the reference; `baseline` by default)
+    pub b: String,
+    /// Changes going from B to A: "added" is only in A, "removed" only in B
+    pub diff: GraphDiff,
+    /// Weak links that appear, disappear or change severity
+    pub weak_links: WeakLinkChanges,
+    /// Annotation weights per aspect, for aspects whose weights differ
+    pub risk: Vec<AspectScore>,
+}
+
+/// Weak links of B and A, matched by target and aspect
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct WeakLinkChanges {
+    /// Weak links in B
+    pub before: usize,
+    /// Weak links in A
+    pub after: usize,
+    /// Only in A
+    pub added: Vec<WeakLink>,
+    /// Only in B
+    pub removed: Vec<WeakLink>,
+    /// In both, with a different severity or reason: (B, A)
+    pub changed: Vec<(WeakLink, WeakLink)>,
+}
+
+/// Summed annotation weights for one aspect in B and A
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AspectScore {
+    /// Aspect ID
+    pub aspect_id: String,
+    /// Total risk weight in B
+    pub risk_before: u32,
+    /// Total risk weight in A
+    pub risk_after: u32,
+    /// Total strength weight in B
+    pub strength_before: u32,
+    /// Total strength weight in A
+    pub strength_after: u32,
+}
+
+impl ScenarioComparison {
+    /// Compare scenario `a` against scenario `b` (`baseline` for the
+    /// unmodified graph)
+    ///
+    /// # Errors
+    /// Fails if either scenario cannot be materialised.
+    pub fn new(graph: &EcosystemGraph, a: &str, b: &str) -> Result<Self> {
+        let after = graph.with_scenario(a)?;
+        let before = graph.with_scenario(b)?;
+        Ok(Self {
+            a: a.to_string(),
+            b: b.to_string(),
+            diff: GraphDiff::new(&before, &after),
+            weak_links: WeakLinkChanges::new(weak_links(&before, None)?, weak_links(&after, None)?),
+            risk: aspect_scores(&before, &after),
+        })
+    }
+
+    /// True if the scenarios have the same effective graph
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.diff.is_empty() && self.weak_links.is_empty() && self.risk.is_empty()
+    }
+}
+
+impl WeakLinkChanges {
+    fn new(before: Vec<WeakLink>, after: Vec<WeakLink>) -> Self {
+        let mut changes = Self { before: before.len(), after: after.len(), ..Self::default() };
+        let key = |w: &WeakLink| (w.target_id.clone(), w.aspect.clone(), w.reason.clone());
+        let mut remaining: BTreeMap<_, WeakLink> = before.into_iter().map(|w| (key(&w), w)).collect();
+        let mut unmatched = Vec::new();
+        for link in after {
+            match remaining.remove(&key(&link)) {
+                Some(old) if old.severity != link.severity => changes.changed.push((old, link)),
+                Some(_) => {}
+                None => unmatched.push(link),
+            }
+        }
+        // A reason that changed wording (e.g. a dependent count) on the same
+        // target and aspect is a change rather than a swap
+        for link in unmatched {
+            let same = remaining
+                .iter()
+                .find(|(_, w)| w.target_id == link.target_id && w.aspect == link.aspect)
+                .map(|(k, _)| k.clone());
+            match same.and_then(|k| remaining.remove(&k)) {
+                Some(old) => changes.changed.push((old, link)),
+                None => changes.added.push(link),
+            }
+        }
+        changes.removed = remaining.into_values().collect();
+        changes
+    }
+
+    /// True if no weak link appears, disappears or changes
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Risk and strength weight per aspect, keeping the aspects that differ
+fn aspect_scores(before: &EcosystemGraph, after: &EcosystemGraph) -> Vec<AspectScore> {
+    let mut scores: BTreeMap<&str, AspectScore> = BTreeMap::new();
+    for (graph, is_after) in [(before, false), (after, true)] {
+        for annotation in &graph.aspects.annotations {
+            let score = scores.entry(&annotation.aspect_id).or_insert_with(|| AspectScore {
+                aspect_id: annotation.aspect_id.clone(),
+                risk_before: 0,
+                risk_after: 0,
+                strength_before: 0,
+                strength_after: 0,
+            });
+            let weight = u32::from(annotation.weight);
+            match (annotation.polarity, is_after) {
+                (Polarity::Risk, false) => score.risk_before += weight,
+                (Polarity::Risk, true) => score.risk_after += weight,
+                (Polarity::Strength, false) => score.strength_before += weight,
+                (Polarity::Strength, true) => score.strength_after += weight,
+                (Polarity::Neutral, _) => {}
+            }
+        }
+    }
+    scores
+        .into_values()
+        .filter(|s| s.risk_before != s.risk_after || s.strength_before != s.strength_after)
+        .collect()
+}
+
+/// An op that cannot be replayed onto the target graph
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Conflict {
+    /// Scenario the op belongs to
+    pub scenario: String,
+    /// Index of the op in the scenario's change set
+    pub index: usize,
+    /// What the op does
+    pub op: String,
+    /// Why it conflicts
+    pub reason: String,
+}
+
+/// Result of a promotion or merge
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Outcome {
+    /// Ops replayed (zero if there were conflicts)
+    pub applied: usize,
+    /// Ops that conflict; when non-empty nothing was changed
+    pub conflicts: Vec<Conflict>,
+    /// Audit entry recorded by a promotion
+    pub audit_id: Option<String>,
+}
+
+/// Apply a scenario's change set to the baseline
+///
+/// The scenario and its change set are removed, and scenarios based on it are
+/// rebased onto the baseline. An [`AuditEntry`] records the promotion, with
+/// the scenario ID as its plan.
+///
+/// # Errors
+/// Fails if the scenario is unknown or has a base (promote or merge that
+/// first).
+pub fn promote(graph: &mut EcosystemGraph, name: &str, applied_by: &str) -> Result<Outcome> {
+    let scenario = graph
+        .find_scenario(name)
+        .ok_or_else(|| anyhow::anyhow!("Scenario not found: {name}"))?
+        .clone();
+    if let Some(base) = &scenario.base {
+        anyhow::bail!(
+            "Scenario {} is based on {base}; promote or merge {base} first",
+            scenario.name
+        );
+    }
+
+    let started_at = Utc::now();
+    let ops = own_ops(graph, &scenario.id);
+    // Annotations written after the scenario was created were not seen by it
+    let concurrent = |a: &AspectAnnotation| a.source.when > scenario.created_at;
+    let mut working = graph.clone();
+    let conflicts = replay(&mut working, &scenario, &ops, concurrent);
+    if !conflicts.is_empty() {
+        return Ok(Outcome { applied: 0, conflicts, audit_id: None });
+    }
+
+    working.store.scenarios.retain(|s| s.id != scenario.id);
+    working.store.changesets.retain(|c| c.scenario_id != scenario.id);
+    for dependent in &mut working.store.scenarios {
+        if dependent.base.as_deref() == Some(scenario.id.as_str()) {
+            dependent.base = None;
+        }
+    }
+
+    let finished_at = Utc::now();
+    let entry = AuditEntry {
+        kind: "AuditEntry".into(),
+        id: AuditEntry::generate_id(&scenario.id),
+        plan_id: scenario.id.clone(),
+        result: ApplyResult::Success,
+        op_results: (0..ops.len())
+            .map(|op_index| OpResult { op_index, success: true, error: None, executed_at: finished_at })
+            .collect(),
+        started_at,
+        finished_at,
+        applied_by: applied_by.to_string(),
+        auto_rollback_triggered: false,
+        rollback_plan_id: None,
+        health_check_passed: None,
+        notes: vec![format!("Promoted scenario {} to the baseline ({} ops)", scenario.name, ops.len())],
+        stages: Vec::new(),
+    };
+    let audit_id = entry.id.clone();
+    working.audit.entries.push(entry);
+    *graph = working;
+    Ok(Outcome { applied: ops.len(), conflicts: Vec::new(), audit_id: Some(audit_id) })
+}
+
+/// Append scenario `from`'s change set to scenario `into`
+///
+/// `from` must be based on `into` or on one of `into`'s bases (the baseline
+/// included), so that its ops were recorded against a graph `into` builds on.
+/// `from` itself is left as it was.
+///
+/// # Errors
+/// Fails if either scenario is unknown, they are the same, or `from` is not
+/// based on `into`'s chain.
+pub fn merge(graph: &mut EcosystemGraph, into: &str, from: &str) -> Result<Outcome> {
+    let chain_into: Vec<Scenario> = graph.scenario_chain(into)?.into_iter().cloned().collect();
+    let chain_from: Vec<Scenario> = graph.scenario_chain(from)?.into_iter().cloned().collect();
+    let (Some(target), Some(source)) = (chain_into.first(), chain_from.first()) else {
+        anyhow::bail!("Both sides of a merge must be scenarios");
+    };
+    if target.id == source.id {
+        anyhow::bail!("Cannot merge scenario {} into itself", target.name);
+    }
+    if let Some(base) = &source.base {
+        if !chain_into.iter().any(|s| &s.id == base) {
+            anyhow::bail!(
+                "Scenario {} is based on {base}, which {} does not build on",
+                source.name,
+                target.name
+            );
+        }
+    }
+
+    // Annotations the target side wrote that the source never saw
+    let shared: HashSet<&str> = chain_from.iter().map(|s| s.id.as_str()).collect();
+    let written: HashSet<String> = chain_into
+        .iter()
+        .filter(|s| !shared.contains(s.id.as_str()))
+        .flat_map(|s| own_ops(graph, &s.id))
+        .filter_map(|op| match op {
+            ChangeOp::AddAnnotation { annotation } => Some(annotation.id),
+            _ => None,
+        })
+        .collect();
+    let concurrent = |a: &AspectAnnotation| written.contains(&a.id);
+
+    let ops = own_ops(graph, &source.id);
+    let mut working = graph.with_scenario(&target.id)?;
+    let conflicts = replay(&mut working, source, &ops, concurrent);
+    if !conflicts.is_empty() {
+        return Ok(Outcome { applied: 0, conflicts, audit_id: None });
+    }
+
+    let applied = ops.len();
+    match graph.store.changesets.iter_mut().find(|c| c.scenario_id == target.id) {
+        Some(changeset) => changeset.ops.extend(ops),
+        None => graph.store.changesets.push(ChangeSet {
+            kind: "ChangeSet".into(),
+            scenario_id: target.id.clone(),
+            ops,
+        }),
+    }
+    Ok(Outcome { applied, conflicts: Vec::new(), audit_id: None })
+}
+
+/// The ops recorded directly in a scenario's change set
+fn own_ops(graph: &EcosystemGraph, scenario_id: &str) -> Vec<ChangeOp> {
+    graph
+        .store
+        .changesets
+        .iter()
+        .filter(|c| c.scenario_id == scenario_id)
+        .flat_map(|c| c.ops.iter().cloned())
+        .collect()
+}
+
+/// Apply `ops` to `graph` in order, returning the ones that conflict
+///
+/// `concurrent` tells whether an existing annotation was written without the
+/// scenario seeing it, in which case a differing annotation with the same id
+/// is a collision rather than a deliberate replacement.
+fn replay(
+    graph: &mut EcosystemGraph,
+    scenario: &Scenario,
+    ops: &[ChangeOp],
+    concurrent: impl Fn(&AspectAnnotation) -> bool,
+) -> Vec<Conflict> {
+    let mut conflicts = Vec::new();
+    // Annotations this replay wrote itself, which later ops may overwrite
+    let mut replayed: HashSet<String> = HashSet::new();
+    for (index, op) in ops.iter().enumerate() {
+        let reason = match op {
+            ChangeOp::RemoveEdge { edge_id } if !graph.store.edges.iter().any(|e| &e.id == edge_id) => {
+                Some("edge already removed".to_string())
+            }
+            ChangeOp::RemoveAnnotation { annotation_id }
+                if !graph.aspects.annotations.iter().any(|a| &a.id == annotation_id) =>
+            {
+                Some("annotation already removed".to_string())
+            }
+            ChangeOp::AddAnnotation { annotation } => graph
+                .aspects
+                .annotations
+                .iter()
+                .find(|a| a.id == annotation.id)
+                .filter(|a| !replayed.contains(&a.id) && concurrent(a) && !same_annotation(a, annotation))
+                .map(|a| {
+                    format!(
+                        "annotation id collision: {} was changed to weight {}, {:?}: {}",
+                        a.id, a.weight, a.polarity, a.reason
+                    )
+                }),
+            ChangeOp::SetGroupMembership { group_id, previous: Some(previous), .. } => graph
+                .store
+                .groups
+                .iter()
+                .find(|g| &g.id == group_id)
+                .filter(|g| as_set(&g.members) != as_set(previous))
+                .map(|g| format!("group changed since the scenario was created: now {}", g.members.join(", "))),
+            _ => None,
+        };
+        let reason = match reason {
+            Some(reason) => Some(reason),
+            None => graph.apply_change(op).err().map(|e| e.to_string()),
+        };
+        match (reason, op) {
+            (Some(reason), _) => {
+                conflicts.push(Conflict { scenario: scenario.id.clone(), index, op: describe(op), reason });
+            }
+            (None, ChangeOp::AddAnnotation { annotation }) => {
+                replayed.insert(annotation.id.clone());
+            }
+            (None, _) => {}
+        }
+    }
+    graph.rebuild_graph();
+    conflicts
+}
+
+fn same_annotation(a: &AspectAnnotation, b: &AspectAnnotation) -> bool {
+    a.weight == b.weight && a.polarity == b.polarity && a.reason == b.reason
+}
+
+fn as_set(members: &[String]) -> BTreeSet<&str> {
+    members.iter().map(String::as_str).collect()
+}
+
+/// One-line description of an op, as in `scenario show`
+fn describe(op: &ChangeOp) -> String {
+    match op {
+        ChangeOp::AddEdge { edge } => format!("add edge {} -> {}", edge.from, edge.to),
+        ChangeOp::RemoveEdge { edge_id } => format!("remove edge {edge_id}"),
+        ChangeOp::AddAnnotation { annotation } => {
+            format!("annotate {} with {}", annotation.target, annotation.aspect_id)
+        }
+        ChangeOp::RemoveAnnotation { annotation_id } => format!("remove annotation {annotation_id}"),
+        ChangeOp::SetGroupMembership { group_id, members, .. } => {
+            format!("set {group_id} to {} member(s)", members.len())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{AnnotationSource, Group};
+    use chrono::{DateTime, Duration};
+
+    fn link(target: &str, aspect: &str, reason: &str, severity: u8) -> WeakLink {
+        WeakLink {
+            target_id: target.into(),
+            target_name: target.into(),
+            reason: reason.into(),
+            severity,
+            aspect: aspect.into(),
+        }
+    }
+
+    #[test]
+    fn test_weak_link_changes_match_by_target_and_aspect() {
+        let before = vec![
+            link("core", "Reliability", "Single point of failure: 3 repos depend on this (3 transitively)", 2),
+            link("web", "Security", "old deps", 2),
+            link("app", "Security", "unpinned", 1),
+        ];
+        let after = vec![
+            link("core", "Reliability", "Single point of failure: 5 repos depend on this (6 transitively)", 3),
+            link("web", "Security", "old deps", 2),
+            link("cli", "Maintainability", "No evidence", 1),
+        ];
+        let changes = WeakLinkChanges::new(before, after);
+        assert_eq!((changes.before, changes.after), (3, 3));
+        assert_eq!(changes.added.len(), 1);
+        assert_eq!(changes.added[0].target_id, "cli");
+        assert_eq!(changes.removed.len(), 1);
+        assert_eq!(changes.removed[0].target_id, "app");
+        assert_eq!(changes.changed.len(), 1);
+        assert_eq!((changes.changed[0].0.severity, changes.changed[0].1.severity), (2, 3));
+    }
+
+    fn annotation(target: &str, weight: u8, when: DateTime<Utc>) -> AspectAnnotation {
+        AspectAnnotation {
+            kind: "AspectAnnotation".into(),
+            id: AspectAnnotation::generate_id(target, "aspect:security"),
+            target: target.into(),
+            aspect_id: "aspect:security".into(),
+            weight,
+            polarity: Polarity::Risk,
+            reason: "test".into(),
+            evidence: vec![],
+            source: AnnotationSource { mode: "manual".into(), who: "test".into(), when, rule_id: None },
+        }
+    }
+
+    fn scenario(graph: &mut EcosystemGraph, name: &str, base: Option<&str>, ops: Vec<ChangeOp>) {
+        graph.store.scenarios.push(Scenario {
+            kind: "Scenario".into(),
+            id: format!("scenario:{name}"),
+            name: name.into(),
+            base: base.map(|b| format!("scenario:{b}")),
+            description: None,
+            created_at: Utc::now(),
+        });
+        graph.store.changesets.push(ChangeSet { kind: "ChangeSet".into(), scenario_id: format!("scenario:{name}"), ops });
+    }
+
+    fn fixture() -> EcosystemGraph {
+        let mut graph = EcosystemGraph::new();
+        graph.add_group(Group {
+            kind: "Group".into(),
+            id: "group:core".into(),
+            name: "core".into(),
+            description: None,
+            members: vec!["a".into(), "b".into()],
+        });
+        graph.aspects.annotations.push(annotation("a", 1, Utc::now() - Duration::days(1)));
+        graph
+    }
+
+    #[test]
+    fn test_promote_applies_and_audits_or_reports_conflicts() {
+        let mut graph = fixture();
+        let trim = ChangeOp::SetGroupMembership {
+            group_id: "group:core".into(),
+            members: vec!["a".into()],
+            previous: Some(vec!["b".into(), "a".into()]),
+        };
+        let unannotate = ChangeOp::RemoveAnnotation { annotation_id: AspectAnnotation::generate_id("a", "aspect:security") };
+        scenario(&mut graph, "trim", None, vec![trim.clone(), unannotate.clone()]);
+        scenario(&mut graph, "again", None, vec![trim, unannotate]);
+        scenario(&mut graph, "child", Some("trim"), vec![]);
+
+        assert!(promote(&mut graph, "child", "me").is_err());
+        let outcome = promote(&mut graph, "trim", "me").unwrap();
+        assert_eq!(outcome.applied, 2);
+        assert!(outcome.conflicts.is_empty());
+        assert_eq!(graph.store.groups[0].members, vec!["a".to_string()]);
+        assert!(graph.aspects.annotations.is_empty());
+        assert!(graph.find_scenario("trim").is_none());
+        assert_eq!(graph.find_scenario("child").unwrap().base, None);
+        let entry = &graph.audit.entries[0];
+        assert_eq!((entry.plan_id.as_str(), entry.applied_by.as_str()), ("scenario:trim", "me"));
+        assert_eq!(outcome.audit_id.as_deref(), Some(entry.id.as_str()));
+        assert_eq!(entry.success_count(), 2);
+
+        // The same ops again: the group moved on and the annotation is gone
+        let outcome = promote(&mut graph, "again", "me").unwrap();
+        let reasons: Vec<_> = outcome.conflicts.iter().map(|c| (c.index, c.reason.as_str())).collect();
+        assert_eq!(outcome.applied, 0);
+        assert_eq!(reasons[1], (1, "annotation already removed"));
+        assert!(reasons[0].1.starts_with("group changed since"), "{reasons:?}");
+        assert!(graph.find_scenario("again").is_some());
+        assert_eq!(graph.audit.entries.len(), 1);
+    }
+
+    #[test]
+    fn test_promote_allows_retagging_within_the_scenario() {
+        let mut graph = fixture();
+        let later = Utc::now() + Duration::seconds(1);
+        let tag = |target: &str, weight| ChangeOp::AddAnnotation { annotation: annotation(target, weight, later) };
+        scenario(&mut graph, "retag", None, vec![tag("b", 2), tag("b", 3)]);
+        scenario(&mut graph, "late", None, vec![tag("c", 2)]);
+
+        let outcome = promote(&mut graph, "retag", "me").unwrap();
+        assert!(outcome.conflicts.is_empty(), "{:?}", outcome.conflicts);
+        assert_eq!(outcome.applied, 2);
+        let id = AspectAnnotation::generate_id("b", "aspect:security");
+        assert_eq!(graph.aspects.annotations.iter().find(|a| a.id == id).unwrap().weight, 3);
+
+        // A baseline annotation written after the scenario still collides
+        graph.aspects.annotations.push(annotation("c", 1, later));
+        let outcome = promote(&mut graph, "late", "me").unwrap();
+        assert_eq!(outcome.conflicts.len(), 1);
+        assert!(outcome.conflicts[0].reason.starts_with("annotation id collision"));
+    }
+
+    #[test]
+    fn test_merge_reports_annotation_collisions() {
+        let mut graph = fixture();
+        let tag = |weight| ChangeOp::AddAnnotation { annotation: annotation("b", weight, Utc::now()) };
+        scenario(&mut graph, "a", None, vec![tag(3)]);
+        scenario(&mut graph, "b", None, vec![tag(1)]);
+        scenario(&mut graph, "c", None, vec![tag(3), ChangeOp::RemoveEdge { edge_id: "edge:gone".into() }]);
+        scenario(&mut graph, "d", Some("b"), vec![]);
+
+        assert!(merge(&mut graph, "a", "a").is_err());
+        assert!(merge(&mut graph, "a", "d").is_err());
+
+        let outcome = merge(&mut graph, "a", "b").unwrap();
+        assert_eq!(outcome.conflicts.len(), 1);
+        assert!(outcome.conflicts[0].reason.starts_with("annotation id collision"));
+
+        // Same content is no collision, but the edge was never there
+        let outcome = merge(&mut graph, "a", "c").unwrap();
+        assert_eq!(outcome.conflicts.len(), 1);
+        assert_eq!(outcome.conflicts[0].reason, "edge already removed");
+
+        let outcome = merge(&mut graph, "b", "d").unwrap();
+        assert_eq!(outcome.applied, 0);
+        graph.store.changesets[3].ops.push(tag(2));
+        assert_eq!(merge(&mut graph, "b", "d").unwrap().applied, 1);
+        assert_eq!(graph.store.changesets[1].ops.len(), 2);
+        assert_eq!(graph.with_scenario("b").unwrap().aspects.annotations[1].weight, 2);
+        assert_eq!(graph.store.changesets[3].ops.len(), 1);
+    }
+}
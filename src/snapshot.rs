@@ -0,0 +1,306 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Store snapshots - point-in-time copies of the five stores
+//!
+//! `apply` and `undo` snapshot the stores into `snapshots/<id>/` in the data
+//! dir before they change anything, so a bad run can be undone even when
+//! its generated rollback plan is incomplete. Each snapshot holds the flat
+//! JSON files (`graph.json`, `aspects.json`, `slots.json`, `plans.json`,
+//! `audit.json`) and a `snapshot.json` describing it. Only the stores are
+//! captured; file changes in checkouts are reversed by `undo`.
+//!
+//! After each automatic snapshot, old ones are pruned by the retention
+//! policy in `snapshots/retention.json`.
+
+use crate::graph::EcosystemGraph;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directory of snapshots inside the data dir
+pub const SNAPSHOT_DIR: &str = "snapshots";
+
+/// File describing a snapshot, inside its directory
+const INFO_FILE: &str = "snapshot.json";
+
+/// File holding the retention policy, inside the snapshot directory
+const RETENTION_FILE: &str = "retention.json";
+
+/// Store files captured by a snapshot
+pub const STORE_FILES: [&str; 5] = ["graph.json", "aspects.json", "slots.json", "plans.json", "audit.json"];
+
+/// Snapshots kept when no retention policy has been set
+pub const DEFAULT_KEEP: usize = 20;
+
+/// A point-in-time copy of the stores
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotInfo {
+    /// Snapshot ID (its directory name), sortable by time
+    pub id: String,
+    /// When the snapshot was taken
+    pub created_at: DateTime<Utc>,
+    /// What the snapshot was taken before (e.g. "apply plan:move:1")
+    pub reason: String,
+}
+
+/// How many snapshots to keep, and for how long
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Retention {
+    /// Keep at most this many snapshots, newest first
+    #[serde(default)]
+    pub keep: Option<usize>,
+    /// Remove snapshots older than this many days
+    #[serde(default)]
+    pub max_age_days: Option<u32>,
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        Self {
+            keep: Some(DEFAULT_KEEP),
+            max_age_days: None,
+        }
+    }
+}
+
+impl Retention {
+    /// Load the retention policy from `data_dir`, or the default if none is set.
+    ///
+    /// # Errors
+    /// Fails if the policy file exists but cannot be read or parsed.
+    pub fn load(data_dir: &Path) -> Result<Self> {
+        let path = snapshot_root(data_dir).join(RETENTION_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    /// Save the retention policy to `data_dir`.
+    ///
+    /// # Errors
+    /// Fails if `keep` is zero or the policy file cannot be written.
+    pub fn save(&self, data_dir: &Path) -> Result<()> {
+        if self.keep == Some(0) {
+            bail!("Retention must keep at least one snapshot");
+        }
+        let root = snapshot_root(data_dir);
+        fs::create_dir_all(&root).with_context(|| format!("Failed to create directory {}", root.display()))?;
+        let path = root.join(RETENTION_FILE);
+        let json = serde_json::to_string_pretty(self).context("Failed to serialize retention policy")?;
+        fs::write(&path, json).with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Whether the snapshot at `rank` (0 = newest) taken at `created_at` is kept
+    fn keeps(&self, rank: usize, created_at: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+        let within_count = self.keep.map_or(true, |keep| rank < keep);
+        let within_age = self
+            .max_age_days
+            .map_or(true, |days| now - created_at <= Duration::days(i64::from(days)));
+        within_count && within_age
+    }
+}
+
+/// The snapshot directory inside `data_dir`
+#[must_use]
+pub fn snapshot_root(data_dir: &Path) -> PathBuf {
+    data_dir.join(SNAPSHOT_DIR)
+}
+
+/// Snapshot the stores of `graph` into `data_dir`.
+///
+/// # Errors
+/// Fails if the snapshot directory or any of its files cannot be written.
+pub fn create(data_dir: &Path, graph: &EcosystemGraph, reason: &str) -> Result<SnapshotInfo> {
+    let root = snapshot_root(data_dir);
+    let created_at = Utc::now();
+    let stamp = created_at.format("%Y%m%dT%H%M%S%.3fZ").to_string();
+
+    // Two snapshots within the same millisecond get a numbered suffix
+    let mut id = stamp.clone();
+    let mut n = 1;
+    while root.join(&id).exists() {
+        n += 1;
+        id = format!("{stamp}-{n}");
+    }
+
+    let dir = root.join(&id);
+    graph.save_json_files(&dir)?;
+    let info = SnapshotInfo {
+        id,
+        created_at,
+        reason: reason.to_string(),
+    };
+    let json = serde_json::to_string_pretty(&info).context("Failed to serialize snapshot info")?;
+    fs::write(dir.join(INFO_FILE), json).with_context(|| format!("Failed to write snapshot {}", dir.display()))?;
+    Ok(info)
+}
+
+/// Snapshot the stores before a run changes them, then prune by the
+/// retention policy. A failed prune is only logged.
+///
+/// # Errors
+/// Fails if the snapshot cannot be taken.
+pub fn take(data_dir: &Path, graph: &EcosystemGraph, reason: &str) -> Result<SnapshotInfo> {
+    let info = create(data_dir, graph, reason)?;
+    let pruned = Retention::load(data_dir).and_then(|retention| prune(data_dir, &retention, Utc::now()));
+    if let Err(err) = pruned {
+        tracing::warn!("Failed to prune snapshots: {:#}", err);
+    }
+    Ok(info)
+}
+
+/// List the snapshots in `data_dir`, newest first.
+///
+/// Directories without a readable `snapshot.json` are skipped.
+///
+/// # Errors
+/// Fails if the snapshot directory exists but cannot be read.
+pub fn list(data_dir: &Path) -> Result<Vec<SnapshotInfo>> {
+    let root = snapshot_root(data_dir);
+    if !root.exists() {
+        return Ok(Vec::new());
+    }
+    let mut snapshots: Vec<SnapshotInfo> = fs::read_dir(&root)
+        .with_context(|| format!("Failed to read {}", root.display()))?
+        .filter_map(|entry| {
+            let content = fs::read_to_string(entry.ok()?.path().join(INFO_FILE)).ok()?;
+            serde_json::from_str(&content).ok()
+        })
+        .collect();
+    snapshots.sort_by(|a, b| b.created_at.cmp(&a.created_at).then_with(|| b.id.cmp(&a.id)));
+    Ok(snapshots)
+}
+
+/// Find a snapshot by ID, or the newest one for `latest`.
+///
+/// # Errors
+/// Fails if there is no such snapshot.
+pub fn find(data_dir: &Path, id: &str) -> Result<SnapshotInfo> {
+    let snapshots = list(data_dir)?;
+    let found = if id == "latest" {
+        snapshots.into_iter().next()
+    } else {
+        snapshots.into_iter().find(|s| s.id == id)
+    };
+    found.with_context(|| format!("Snapshot not found: {id}"))
+}
+
+/// Load the stores saved in a snapshot.
+///
+/// A store file the snapshot lacks loads as an empty store.
+///
+/// # Errors
+/// Fails if a store file cannot be read or parsed.
+pub fn load(data_dir: &Path, snapshot: &SnapshotInfo) -> Result<EcosystemGraph> {
+    let dir = snapshot_root(data_dir).join(&snapshot.id);
+    EcosystemGraph::from_json_files(|name| {
+        let path = dir.join(name);
+        if path.exists() {
+            fs::read_to_string(&path)
+                .map(Some)
+                .with_context(|| format!("Failed to read {}", path.display()))
+        } else {
+            Ok(None)
+        }
+    })
+}
+
+/// Remove the snapshots `retention` does not keep, returning them.
+///
+/// # Errors
+/// Fails if the snapshots cannot be listed or one cannot be removed.
+pub fn prune(data_dir: &Path, retention: &Retention, now: DateTime<Utc>) -> Result<Vec<SnapshotInfo>> {
+    let root = snapshot_root(data_dir);
+    let mut removed = Vec::new();
+    for (rank, snapshot) in list(data_dir)?.into_iter().enumerate() {
+        if retention.keeps(rank, snapshot.created_at, now) {
+            continue;
+        }
+        let dir = root.join(&snapshot.id);
+        fs::remove_dir_all(&dir).with_context(|| format!("Failed to remove {}", dir.display()))?;
+        removed.push(snapshot);
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Forge, ImportMeta, Repo, Visibility};
+    use tempfile::TempDir;
+
+    fn repo(name: &str) -> Repo {
+        Repo {
+            kind: "Repo".into(),
+            id: format!("repo:gh:test/{name}"),
+            forge: Forge::GitHub,
+            owner: "test".into(),
+            name: name.into(),
+            default_branch: "main".into(),
+            visibility: Visibility::Public,
+            tags: vec![],
+            estate: "estate:test".into(),
+            metadata: Default::default(),
+            imports: ImportMeta {
+                source: "test".into(),
+                path_hint: None,
+                imported_at: Utc::now(),
+            },
+            local_path: None,
+        }
+    }
+
+    fn backdate(data_dir: &Path, id: &str, days: i64) {
+        let path = snapshot_root(data_dir).join(id).join(INFO_FILE);
+        let mut info: SnapshotInfo = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        info.created_at -= Duration::days(days);
+        fs::write(&path, serde_json::to_string(&info).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_snapshot_round_trip_and_prune() {
+        let temp = TempDir::new().unwrap();
+        let data_dir = temp.path();
+        assert!(list(data_dir).unwrap().is_empty());
+
+        let mut graph = EcosystemGraph::default();
+        let first = create(data_dir, &graph, "apply plan:a").unwrap();
+        graph.add_repo(repo("app"));
+        let second = create(data_dir, &graph, "apply plan:b").unwrap();
+        let third = create(data_dir, &graph, "undo plan:b").unwrap();
+        assert_ne!(first.id, second.id);
+        for file in STORE_FILES {
+            assert!(snapshot_root(data_dir).join(&third.id).join(file).exists(), "{file}");
+        }
+
+        let ids: Vec<String> = list(data_dir).unwrap().into_iter().map(|s| s.id).collect();
+        assert_eq!(ids, [third.id.clone(), second.id.clone(), first.id.clone()]);
+        assert_eq!(find(data_dir, "latest").unwrap().reason, "undo plan:b");
+        assert!(find(data_dir, "nope").is_err());
+        assert!(load(data_dir, &first).unwrap().store.repos.is_empty());
+        assert_eq!(load(data_dir, &second).unwrap().store.repos[0].name, "app");
+
+        // Age and count limits both apply
+        backdate(data_dir, &first.id, 40);
+        let by_age = Retention { keep: None, max_age_days: Some(30) };
+        let removed = prune(data_dir, &by_age, Utc::now()).unwrap();
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].id, first.id);
+
+        let by_count = Retention { keep: Some(1), max_age_days: None };
+        let removed = prune(data_dir, &by_count, Utc::now()).unwrap();
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].id, second.id);
+        assert_eq!(list(data_dir).unwrap().len(), 1);
+
+        // The policy round-trips and defaults when unset
+        assert_eq!(Retention::load(data_dir).unwrap(), Retention::default());
+        by_age.save(data_dir).unwrap();
+        assert_eq!(Retention::load(data_dir).unwrap(), by_age);
+        assert!(Retention { keep: Some(0), max_age_days: None }.save(data_dir).is_err());
+    }
+}
@@ -0,0 +1,115 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Self-contained HTML viewer export
+//!
+//! [`EcosystemGraph::to_html`] writes one HTML file that opens from disk with
+//! no network access: the estate-export envelope is embedded as a JSON data
+//! block and read by a small force-directed viewer (search, group colouring,
+//! aspect heat overlay and a metadata panel). The page template lives in
+//! `viewer.html` next to this module and is compiled into the binary.
+
+use crate::diagram::{channel_style, xml};
+use crate::graph::EcosystemGraph;
+use crate::types::Channel;
+use anyhow::Result;
+use serde_json::{json, Map, Value};
+
+const TEMPLATE: &str = include_str!("viewer.html");
+
+impl EcosystemGraph {
+    /// Export to a single offline HTML page with an interactive viewer
+    ///
+    /// # Errors
+    /// Fails if the estate envelope cannot be serialized.
+    pub fn to_html(&self) -> Result<String> {
+        let envelope = self.estate_envelope();
+        let title = envelope["estate"]["name"]
+            .as_str()
+            .map_or_else(|| "Reposystem estate".to_string(), |name| format!("{name} estate"));
+
+        let styles: Map<String, Value> = [
+            Channel::Api,
+            Channel::Artifact,
+            Channel::Config,
+            Channel::Runtime,
+            Channel::Human,
+            Channel::Unknown,
+        ]
+        .into_iter()
+        .map(|channel| {
+            let style = channel_style(channel);
+            let value = json!({"color": style.hex(), "line": style.line.name(), "width": style.width});
+            (channel.code().to_string(), value)
+        })
+        .collect();
+
+        // The data is substituted last so placeholder-like text inside the
+        // estate is never expanded
+        Ok(TEMPLATE
+            .replace("{{TITLE}}", &xml(&title))
+            .replace("{{CHANNEL_STYLES}}", &script_json(&Value::Object(styles))?)
+            .replace("{{ESTATE_JSON}}", &script_json(&envelope)?))
+    }
+}
+
+/// Serialize JSON for a `<script type="application/json">` block
+///
+/// `<`, `>` and `&` only occur inside JSON strings, where their `\u` escapes
+/// are equivalent, so `</script>` or `<!--` in the data cannot close the block.
+fn script_json(value: &Value) -> Result<String> {
+    Ok(serde_json::to_string(value)?
+        .replace('<', "\\u003c")
+        .replace('>', "\\u003e")
+        .replace('&', "\\u0026"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Forge, ImportMeta, Repo, Visibility};
+    use chrono::Utc;
+
+    fn embedded<'a>(html: &'a str, id: &str) -> &'a str {
+        let open = format!("<script type=\"application/json\" id=\"{id}\">");
+        let start = html.find(&open).unwrap() + open.len();
+        &html[start..start + html[start..].find("</script>").unwrap()]
+    }
+
+    #[test]
+    fn test_html_embeds_estate_and_works_offline() {
+        let mut graph = EcosystemGraph::new();
+        let mut metadata = std::collections::HashMap::new();
+        metadata.insert("note".to_string(), "</script><!-- {{TITLE}} & more".to_string());
+        graph.store.repos.push(Repo {
+            kind: "Repo".into(),
+            id: Repo::forge_id(Forge::GitHub, "acme", "app"),
+            forge: Forge::GitHub,
+            owner: "acme".into(),
+            name: "app".into(),
+            default_branch: "main".into(),
+            visibility: Visibility::Public,
+            tags: vec![],
+            estate: "estate:hyperpolymath".into(),
+            metadata,
+            imports: ImportMeta { source: "test".into(), path_hint: None, imported_at: Utc::now() },
+            local_path: None,
+        });
+
+        let html = graph.to_html().unwrap();
+        let estate: Value = serde_json::from_str(embedded(&html, "estate")).unwrap();
+        assert_eq!(estate, graph.estate_envelope());
+        assert_eq!(estate["repos"][0]["metadata"]["note"], "</script><!-- {{TITLE}} & more");
+
+        let styles: Value = serde_json::from_str(embedded(&html, "channel-styles")).unwrap();
+        assert_eq!(styles["runtime"]["color"], "#d62728");
+        assert_eq!(styles["human"]["line"], "dotted");
+
+        // Nothing is fetched: no external scripts, stylesheets or requests
+        for needle in ["src=\"http", "href=\"http", "<link", "@import", "fetch(", "XMLHttpRequest"] {
+            assert!(!html.contains(needle), "page references {needle}");
+        }
+        for placeholder in ["{{ESTATE_JSON}}", "{{CHANNEL_STYLES}}", "<title>{{TITLE}}"] {
+            assert!(!html.contains(placeholder), "unfilled {placeholder}");
+        }
+    }
+}
@@ -331,4 +331,755 @@ fn test_export_formats() {
     let json = stdout_str(&output);
     assert!(json.contains("\"repos\""));
     assert!(json.contains("alpha"));
+
+    // Diagram formats
+    for (format, marker) in [
+        ("mermaid", "flowchart LR"),
+        ("plantuml", "@startuml"),
+        ("graphml", "<graphml"),
+        ("gexf", "<gexf"),
+        ("html", "<!DOCTYPE html>"),
+    ] {
+        let output = run_reposystem(&data_dir, &["export", "--format", format]);
+        assert!(output.status.success(), "{format} export failed: {}", stderr_str(&output));
+        let text = stdout_str(&output);
+        assert!(text.contains(marker) && text.contains("alpha"), "{format}: {text}");
+    }
+
+    // YAML and TOML estate exports import back into a fresh data dir
+    for ext in ["yaml", "toml"] {
+        let file = data_dir.path().join(format!("estate.{ext}"));
+        let output = run_reposystem(&data_dir, &["export", "--format", ext, "-o", file.to_str().unwrap()]);
+        assert!(output.status.success(), "{ext} export failed: {}", stderr_str(&output));
+        let text = std::fs::read_to_string(&file).unwrap();
+        assert!(text.contains("reposystem/estate-export@1"));
+
+        let fresh = TempDir::new().unwrap();
+        let output = run_reposystem(&fresh, &["import", "estate", "--file", file.to_str().unwrap()]);
+        assert!(output.status.success(), "{ext} import failed: {}", stderr_str(&output));
+        assert!(stdout_str(&output).contains("Imported 1 repos"));
+        let output = run_reposystem(&fresh, &["export", "--format", "json"]);
+        assert!(stdout_str(&output).contains("repo:gh:test/alpha"));
+    }
+}
+
+#[test]
+fn test_repo_rekey_and_remove() {
+    let data_dir = TempDir::new().unwrap();
+
+    // Create a mock graph with two repos
+    let graph_json = r#"{
+        "repos": [
+            {
+                "kind": "Repo",
+                "id": "repo:gh:test/alpha",
+                "forge": "gh",
+                "owner": "test",
+                "name": "alpha",
+                "default_branch": "main",
+                "visibility": "public",
+                "tags": [],
+                "imports": {
+                    "source": "test",
+                    "path_hint": null,
+                    "imported_at": "2025-01-01T00:00:00Z"
+                },
+                "local_path": null
+            },
+            {
+                "kind": "Repo",
+                "id": "repo:gh:test/beta",
+                "forge": "gh",
+                "owner": "test",
+                "name": "beta",
+                "default_branch": "main",
+                "visibility": "public",
+                "tags": [],
+                "imports": {
+                    "source": "test",
+                    "path_hint": null,
+                    "imported_at": "2025-01-01T00:00:00Z"
+                },
+                "local_path": null
+            }
+        ],
+        "components": [],
+        "groups": [],
+        "edges": []
+    }"#;
+
+    std::fs::write(data_dir.path().join("graph.json"), graph_json).unwrap();
+
+    // Connect the repos and group them
+    let output = run_reposystem(&data_dir, &["edge", "add", "--from", "alpha", "--to", "beta", "--rel", "uses"]);
+    assert!(output.status.success(), "Failed to add edge: {}", stderr_str(&output));
+    let output = run_reposystem(&data_dir, &["group", "create", "Core", "alpha", "beta"]);
+    assert!(output.status.success(), "Failed to create group: {}", stderr_str(&output));
+
+    // Move beta to another forge
+    let output = run_reposystem(&data_dir, &["repo", "mv", "beta", "repo:gl:other/beta"]);
+    assert!(output.status.success(), "Failed to move repo: {}", stderr_str(&output));
+    assert!(stdout_str(&output).contains("Moved repo:gh:test/beta -> repo:gl:other/beta"));
+
+    let output = run_reposystem(&data_dir, &["group", "show", "Core"]);
+    assert!(stdout_str(&output).contains("repo:gl:other/beta"));
+    let output = run_reposystem(&data_dir, &["export", "--format", "json"]);
+    assert!(stdout_str(&output).contains("\"to\": \"repo:gl:other/beta\""));
+
+    // Remove it again
+    let output = run_reposystem(&data_dir, &["--json", "repo", "rm", "beta"]);
+    assert!(output.status.success(), "Failed to remove repo: {}", stderr_str(&output));
+    let cascade: serde_json::Value = serde_json::from_str(&stdout_str(&output)).unwrap();
+    assert_eq!(cascade["edges"].as_array().unwrap().len(), 1);
+    assert_eq!(cascade["groups"].as_array().unwrap().len(), 1);
+
+    let output = run_reposystem(&data_dir, &["edge", "list"]);
+    assert!(stdout_str(&output).contains("No edges defined"));
+
+    // Unknown repos are rejected
+    let output = run_reposystem(&data_dir, &["repo", "rm", "beta"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_impact_and_single_point_of_failure() {
+    let data_dir = TempDir::new().unwrap();
+
+    // core is used by three repos, one of which is used by a fourth
+    let repo = |name: &str| {
+        format!(
+            r#"{{"kind": "Repo", "id": "repo:gh:test/{name}", "forge": "gh", "owner": "test", "name": "{name}",
+                "default_branch": "main", "visibility": "public", "tags": [],
+                "imports": {{"source": "test", "path_hint": null, "imported_at": "2025-01-01T00:00:00Z"}}}}"#
+        )
+    };
+    let names = ["core", "web", "cli", "api", "app"];
+    let graph_json = format!(
+        r#"{{"repos": [{}], "components": [], "groups": [], "edges": []}}"#,
+        names.map(repo).join(",")
+    );
+    std::fs::write(data_dir.path().join("graph.json"), graph_json).unwrap();
+
+    for (from, to) in [("web", "core"), ("cli", "core"), ("api", "core"), ("app", "web")] {
+        let output = run_reposystem(&data_dir, &["edge", "add", "--from", from, "--to", to, "--rel", "uses"]);
+        assert!(output.status.success(), "Failed to add edge: {}", stderr_str(&output));
+    }
+
+    let output = run_reposystem(&data_dir, &["impact", "core"]);
+    assert!(output.status.success(), "impact failed: {}", stderr_str(&output));
+    assert!(stdout_str(&output).contains("4 affected, 3 direct"));
+
+    let output = run_reposystem(&data_dir, &["--json", "impact", "core", "--depth", "1"]);
+    assert!(output.status.success());
+    let blast: serde_json::Value = serde_json::from_str(&stdout_str(&output)).unwrap();
+    assert_eq!(blast["affected"].as_array().unwrap().len(), 3);
+
+    let output = run_reposystem(&data_dir, &["weak-links"]);
+    assert!(output.status.success());
+    assert!(stdout_str(&output).contains("Single point of failure: 3 repos depend on this (4 transitively)"));
+}
+
+#[test]
+fn test_query_and_filtered_export() {
+    let data_dir = TempDir::new().unwrap();
+
+    let repo = |(name, tags): (&str, &str)| {
+        format!(
+            r#"{{"kind": "Repo", "id": "repo:gh:test/{name}", "forge": "gh", "owner": "test", "name": "{name}",
+                "default_branch": "main", "visibility": "public", "tags": [{tags}],
+                "imports": {{"source": "test", "path_hint": null, "imported_at": "2025-01-01T00:00:00Z"}}}}"#
+        )
+    };
+    let repos = [("core", r#""rust""#), ("app", r#""rust""#), ("web", r#""js""#)];
+    let graph_json = format!(
+        r#"{{"repos": [{}], "components": [], "groups": [], "edges": []}}"#,
+        repos.map(repo).join(",")
+    );
+    std::fs::write(data_dir.path().join("graph.json"), graph_json).unwrap();
+
+    for args in [
+        &["group", "create", "Core", "core"][..],
+        &["edge", "add", "--from", "app", "--to", "core", "--rel", "uses"],
+        &["edge", "add", "--from", "web", "--to", "core", "--rel", "uses"],
+        &["aspect", "tag", "--target", "web", "--aspect", "security", "--weight", "2", "--polarity", "risk", "--reason", "old deps"],
+    ] {
+        let output = run_reposystem(&data_dir, args);
+        assert!(output.status.success(), "{args:?} failed: {}", stderr_str(&output));
+    }
+
+    let output = run_reposystem(
+        &data_dir,
+        &["query", "--ids", "repos where tag = rust and has_edge(rel = uses, to = group:core)"],
+    );
+    assert!(output.status.success(), "query failed: {}", stderr_str(&output));
+    assert_eq!(stdout_str(&output).trim(), "repo:gh:test/app");
+
+    let output = run_reposystem(&data_dir, &["--json", "query", "edges where to = core"]);
+    assert!(output.status.success());
+    let edges: serde_json::Value = serde_json::from_str(&stdout_str(&output)).unwrap();
+    assert_eq!(edges.as_array().unwrap().len(), 2);
+
+    let output = run_reposystem(&data_dir, &["query", "repos where colour = red"]);
+    assert!(!output.status.success());
+    assert!(stderr_str(&output).contains("Unknown field 'colour'"));
+
+    // Export keeps only what the query selects, plus edges among it
+    let output = run_reposystem(&data_dir, &["export", "-f", "json", "--query", "tag = rust"]);
+    assert!(output.status.success(), "export failed: {}", stderr_str(&output));
+    let store: serde_json::Value = serde_json::from_str(&stdout_str(&output)).unwrap();
+    assert_eq!(store["repos"].as_array().unwrap().len(), 2);
+    assert_eq!(store["edges"].as_array().unwrap().len(), 1);
+
+    let output = run_reposystem(&data_dir, &["export", "-f", "json", "--aspect", "security"]);
+    assert!(output.status.success());
+    let store: serde_json::Value = serde_json::from_str(&stdout_str(&output)).unwrap();
+    let names: Vec<_> = store["repos"].as_array().unwrap().iter().map(|r| r["name"].as_str().unwrap()).collect();
+    assert_eq!(names, ["web"]);
+
+    // An annotated edge brings in both of its endpoints
+    let output = run_reposystem(&data_dir, &["--json", "query", "edges where from = app"]);
+    let edges: serde_json::Value = serde_json::from_str(&stdout_str(&output)).unwrap();
+    let edge_id = edges[0]["id"].as_str().unwrap();
+    let output = run_reposystem(
+        &data_dir,
+        &["aspect", "tag", "--target", edge_id, "--aspect", "reliability", "--weight", "1", "--polarity", "risk", "--reason", "no retry"],
+    );
+    assert!(output.status.success(), "tag failed: {}", stderr_str(&output));
+    let output = run_reposystem(&data_dir, &["export", "-f", "json", "--aspect", "reliability"]);
+    assert!(output.status.success(), "export failed: {}", stderr_str(&output));
+    let store: serde_json::Value = serde_json::from_str(&stdout_str(&output)).unwrap();
+    let mut names: Vec<_> = store["repos"].as_array().unwrap().iter().map(|r| r["name"].as_str().unwrap()).collect();
+    names.sort_unstable();
+    assert_eq!(names, ["app", "core"]);
+    assert_eq!(store["edges"].as_array().unwrap().len(), 1);
+
+    // --aspect filters DOT like every other format
+    let output = run_reposystem(&data_dir, &["export", "-f", "dot", "--aspect", "security"]);
+    assert!(output.status.success(), "export failed: {}", stderr_str(&output));
+    let dot = stdout_str(&output);
+    assert!(dot.contains("\"repo:gh:test/web\"") && !dot.contains("\"repo:gh:test/core\""), "{dot}");
+    assert!(!dot.contains("cluster_legend"));
+
+    // --overlay colours the whole graph by the aspect
+    let output = run_reposystem(&data_dir, &["export", "-f", "dot", "--overlay", "security"]);
+    assert!(output.status.success(), "export failed: {}", stderr_str(&output));
+    let dot = stdout_str(&output);
+    assert!(dot.contains("\"repo:gh:test/core\" [label=\"core\\ngh\"];"), "{dot}");
+    assert!(dot.contains("[label=\"web\\ngh\\n⚠ !!\", style=\"rounded,filled\", fillcolor=\"#fb6a4a\", peripheries=2]"), "{dot}");
+    assert!(dot.contains("cluster_legend"));
+
+    let output = run_reposystem(&data_dir, &["export", "-f", "mermaid", "--overlay", "security", "--query", "tag = js"]);
+    assert!(output.status.success());
+    let mermaid = stdout_str(&output);
+    assert!(mermaid.contains("n0(\"web<br/>gh<br/>⚠ !!\")") && !mermaid.contains("core<br/>"), "{mermaid}");
+    assert!(mermaid.contains("style n0 fill:#fb6a4a"));
+
+    let output = run_reposystem(&data_dir, &["export", "-f", "dot", "--overlay", "colour"]);
+    assert!(!output.status.success());
+    assert!(stderr_str(&output).contains("Unknown aspect: colour"));
+
+    let output = run_reposystem(&data_dir, &["export", "-f", "json", "--overlay", "security"]);
+    assert!(!output.status.success());
+    assert!(stderr_str(&output).contains("--overlay is only supported for dot and mermaid"));
+}
+
+#[test]
+fn test_scenario_overlay_graph() {
+    let data_dir = TempDir::new().unwrap();
+
+    let repo = |name: &str| {
+        format!(
+            r#"{{"kind": "Repo", "id": "repo:gh:test/{name}", "forge": "gh", "owner": "test", "name": "{name}",
+                "default_branch": "main", "visibility": "public", "tags": [],
+                "imports": {{"source": "test", "path_hint": null, "imported_at": "2025-01-01T00:00:00Z"}}}}"#
+        )
+    };
+    let graph_json = format!(
+        r#"{{"repos": [{}], "components": [], "groups": [], "edges": []}}"#,
+        ["core", "app", "web"].map(repo).join(",")
+    );
+    std::fs::write(data_dir.path().join("graph.json"), graph_json).unwrap();
+
+    for args in [
+        &["edge", "add", "--from", "app", "--to", "core", "--rel", "uses"][..],
+        &["scenario", "create", "unplug"],
+        &["scenario", "create", "harden", "--base", "unplug"],
+    ] {
+        let output = run_reposystem(&data_dir, args);
+        assert!(output.status.success(), "{args:?} failed: {}", stderr_str(&output));
+    }
+
+    // unplug swaps app -> core for web -> core; harden (on top) flags core
+    let path = data_dir.path().join("graph.json");
+    let mut store: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    let mut web_edge = store["edges"][0].clone();
+    web_edge["id"] = "edge:web-core".into();
+    web_edge["from"] = "repo:gh:test/web".into();
+    store["changesets"][0]["ops"] = serde_json::json!([
+        {"op": "remove_edge", "edge_id": store["edges"][0]["id"]},
+        {"op": "add_edge", "edge": web_edge},
+    ]);
+    store["changesets"][1]["ops"] = serde_json::json!([{"op": "add_annotation", "annotation": {
+        "kind": "AspectAnnotation", "id": "aa:core", "target": "repo:gh:test/core",
+        "aspect_id": "aspect:security", "weight": 3, "polarity": "risk", "reason": "unpatched",
+        "evidence": [], "source": {"mode": "manual", "who": "test", "when": "2025-01-01T00:00:00Z"}
+    }}]);
+    std::fs::write(&path, store.to_string()).unwrap();
+
+    let output = run_reposystem(&data_dir, &["export", "-f", "json", "--scenario", "harden"]);
+    assert!(output.status.success(), "export failed: {}", stderr_str(&output));
+    let what_if: serde_json::Value = serde_json::from_str(&stdout_str(&output)).unwrap();
+    let edges = what_if["edges"].as_array().unwrap();
+    assert_eq!(edges.len(), 1);
+    assert_eq!(edges[0]["from"], "repo:gh:test/web");
+
+    let output = run_reposystem(&data_dir, &["weak-links", "--scenario", "harden"]);
+    assert!(output.status.success(), "weak-links failed: {}", stderr_str(&output));
+    let stdout = stdout_str(&output);
+    assert!(stdout.contains("Scenario: harden") && stdout.contains("unpatched"), "{stdout}");
+
+    // The stored baseline is unchanged
+    let output = run_reposystem(&data_dir, &["weak-links"]);
+    assert!(!stdout_str(&output).contains("unpatched"));
+    let output = run_reposystem(&data_dir, &["export", "-f", "dot", "--scenario", "unplug"]);
+    let dot = stdout_str(&output);
+    assert!(dot.contains("\"repo:gh:test/web\" -> \"repo:gh:test/core\"") && !dot.contains("\"repo:gh:test/app\" ->"), "{dot}");
+    let store: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(store["edges"][0]["from"], "repo:gh:test/app");
+
+    let output = run_reposystem(&data_dir, &["--json", "scenario", "compare", "harden"]);
+    assert!(output.status.success(), "compare failed: {}", stderr_str(&output));
+    let comparison: serde_json::Value = serde_json::from_str(&stdout_str(&output)).unwrap();
+    assert_eq!(comparison["b"], "baseline");
+    assert_eq!(comparison["diff"]["edges"]["added"][0]["label"], "web -> core [uses/unknown]");
+    assert_eq!(comparison["diff"]["edges"]["removed"][0]["label"], "app -> core [uses/unknown]");
+    assert_eq!(comparison["diff"]["annotations"]["added"][0]["label"], "core security");
+    assert_eq!(comparison["weak_links"]["added"][0]["reason"], "unpatched");
+    assert_eq!(comparison["risk"][0]["aspect_id"], "aspect:security");
+    assert_eq!(comparison["risk"][0]["risk_after"], 3);
+
+    // Against its base only the annotation differs
+    let output = run_reposystem(&data_dir, &["scenario", "compare", "harden", "--base", "unplug"]);
+    assert!(output.status.success());
+    let stdout = stdout_str(&output);
+    assert!(stdout.contains("Comparing scenario harden with unplug"), "{stdout}");
+    assert!(stdout.contains("annotations: 1 added, 0 removed, 0 changed"), "{stdout}");
+    assert!(stdout.contains("  + [Security] core !!!: unpatched"), "{stdout}");
+    assert!(stdout.contains("  security: risk 0 -> 3, strength 0 -> 0"), "{stdout}");
+    assert!(!stdout.contains("edges:"), "{stdout}");
+
+    let output = run_reposystem(&data_dir, &["scenario", "compare", "unplug", "--base", "unplug"]);
+    assert!(stdout_str(&output).contains("No differences"));
+
+    let output = run_reposystem(&data_dir, &["export", "--scenario", "nope"]);
+    assert!(!output.status.success());
+    assert!(stderr_str(&output).contains("Scenario not found: nope"));
+}
+
+#[test]
+fn test_scenario_edit_records_changes() {
+    let data_dir = TempDir::new().unwrap();
+
+    let repo = |name: &str| {
+        format!(
+            r#"{{"kind": "Repo", "id": "repo:gh:test/{name}", "forge": "gh", "owner": "test", "name": "{name}",
+                "default_branch": "main", "visibility": "public", "tags": [],
+                "imports": {{"source": "test", "path_hint": null, "imported_at": "2025-01-01T00:00:00Z"}}}}"#
+        )
+    };
+    let graph_json = format!(
+        r#"{{"repos": [{}], "components": [], "groups": [], "edges": []}}"#,
+        ["core", "app", "web"].map(repo).join(",")
+    );
+    std::fs::write(data_dir.path().join("graph.json"), graph_json).unwrap();
+
+    for args in [
+        &["edge", "add", "--from", "app", "--to", "core"][..],
+        &["group", "create", "Core", "core"],
+        &["scenario", "create", "Web Split"],
+        &["scenario", "edit", "web-split", "add-edge", "--from", "web", "--to", "core", "--channel", "api"],
+        &["scenario", "edit", "Web Split", "remove-edge", "--from", "app", "--to", "core"],
+        &["scenario", "edit", "web-split", "annotate", "--target", "web", "--aspect", "security", "--weight", "2", "--polarity", "risk"],
+        &["scenario", "edit", "web-split", "set-group", "Core", "core", "app"],
+        &["group", "add", "Core", "web", "--in-scenario", "web-split"],
+        &["aspect", "tag", "--target", "core", "--aspect", "docs", "--in-scenario", "web-split"],
+        &["scenario", "edit", "web-split", "unannotate", "--target", "core", "--aspect", "docs"],
+        &["edge", "add", "--from", "app", "--to", "web", "--in-scenario", "web-split"],
+    ] {
+        let output = run_reposystem(&data_dir, args);
+        assert!(output.status.success(), "{args:?} failed: {}", stderr_str(&output));
+    }
+
+    let output = run_reposystem(&data_dir, &["scenario", "show", "web-split"]);
+    assert!(stdout_str(&output).contains("operations (8):"), "{}", stdout_str(&output));
+
+    // Nothing reached the baseline
+    let output = run_reposystem(&data_dir, &["--json", "export", "-f", "estate-json"]);
+    let baseline: serde_json::Value = serde_json::from_str(&stdout_str(&output)).unwrap();
+    assert_eq!(baseline["edges"].as_array().unwrap().len(), 1);
+    assert_eq!(baseline["groups"][0]["members"].as_array().unwrap().len(), 1);
+    assert!(baseline["annotations"].as_array().unwrap().is_empty());
+
+    let output = run_reposystem(&data_dir, &["--json", "scenario", "compare", "web-split"]);
+    let comparison: serde_json::Value = serde_json::from_str(&stdout_str(&output)).unwrap();
+    let diff = &comparison["diff"];
+    let labels = |section: &str, kind: &str| -> Vec<String> {
+        diff[section][kind].as_array().unwrap().iter().map(|i| i["label"].as_str().unwrap().to_string()).collect()
+    };
+    assert_eq!(labels("edges", "added"), ["web -> core [uses/api]", "app -> web [uses/unknown]"]);
+    assert_eq!(labels("edges", "removed"), ["app -> core [uses/unknown]"]);
+    assert_eq!(labels("annotations", "added"), ["web security"]);
+    assert_eq!(diff["groups"]["changed"][0]["fields"][0]["new"].as_array().unwrap().len(), 3);
+
+    // Ops that cannot apply to the scenario are refused
+    let output = run_reposystem(&data_dir, &["scenario", "edit", "web-split", "remove-edge", "--from", "app", "--to", "core"]);
+    assert!(stdout_str(&output).contains("No edges found"));
+    let output = run_reposystem(&data_dir, &["group", "add", "Nope", "web", "--in-scenario", "web-split"]);
+    assert!(!output.status.success());
+    let output = run_reposystem(&data_dir, &["edge", "add", "--from", "app", "--to", "web", "--in-scenario", "nope"]);
+    assert!(stderr_str(&output).contains("Scenario not found: nope"));
+    let output = run_reposystem(&data_dir, &["scenario", "edit", "web-split", "frobnicate"]);
+    assert!(stderr_str(&output).contains("unrecognized subcommand 'frobnicate'"));
+}
+
+#[test]
+fn test_scenario_merge_and_promote() {
+    let data_dir = TempDir::new().unwrap();
+
+    let repo = |name: &str| {
+        format!(
+            r#"{{"kind": "Repo", "id": "repo:gh:test/{name}", "forge": "gh", "owner": "test", "name": "{name}",
+                "default_branch": "main", "visibility": "public", "tags": [],
+                "imports": {{"source": "test", "path_hint": null, "imported_at": "2025-01-01T00:00:00Z"}}}}"#
+        )
+    };
+    let graph_json = format!(
+        r#"{{"repos": [{}], "components": [], "groups": [], "edges": []}}"#,
+        ["core", "app", "web"].map(repo).join(",")
+    );
+    std::fs::write(data_dir.path().join("graph.json"), graph_json).unwrap();
+
+    for args in [
+        &["edge", "add", "--from", "app", "--to", "core"][..],
+        &["group", "create", "Core", "core"],
+        &["scenario", "create", "drop"],
+        &["scenario", "create", "again"],
+        &["scenario", "create", "grow"],
+        &["scenario", "edit", "drop", "remove-edge", "--from", "app", "--to", "core"],
+        &["scenario", "edit", "again", "remove-edge", "--from", "app", "--to", "core"],
+        &["scenario", "edit", "grow", "set-group", "Core", "core", "web"],
+        &["scenario", "edit", "grow", "add-edge", "--from", "web", "--to", "core"],
+    ] {
+        let output = run_reposystem(&data_dir, args);
+        assert!(output.status.success(), "{args:?} failed: {}", stderr_str(&output));
+    }
+
+    let output = run_reposystem(&data_dir, &["scenario", "merge", "drop", "grow"]);
+    assert!(output.status.success(), "{}", stderr_str(&output));
+    assert!(stdout_str(&output).contains("Merged scenario grow into drop (2 ops)"));
+
+    let output = run_reposystem(&data_dir, &["scenario", "promote", "drop"]);
+    assert!(output.status.success(), "{}", stderr_str(&output));
+    assert!(stdout_str(&output).contains("audit: audit:scenario:drop:"));
+
+    let output = run_reposystem(&data_dir, &["--json", "export", "-f", "estate-json"]);
+    let baseline: serde_json::Value = serde_json::from_str(&stdout_str(&output)).unwrap();
+    let edges = baseline["edges"].as_array().unwrap();
+    assert_eq!(edges.len(), 1);
+    assert_eq!(edges[0]["from"], "repo:gh:test/web");
+    assert_eq!(baseline["groups"][0]["members"].as_array().unwrap().len(), 2);
+    let audit = std::fs::read_to_string(data_dir.path().join("audit.json")).unwrap();
+    assert!(audit.contains("Promoted scenario drop to the baseline (3 ops)"));
+
+    // The other scenario removed the same edge, which the baseline no longer has
+    let output = run_reposystem(&data_dir, &["scenario", "promote", "again"]);
+    assert!(!output.status.success());
+    assert!(stdout_str(&output).contains("#0 remove edge"));
+    assert!(stdout_str(&output).contains("edge already removed"));
+    assert!(stderr_str(&output).contains("the baseline is unchanged"));
+
+    // grow's group change was recorded against a membership regroup has replaced
+    let output = run_reposystem(&data_dir, &["scenario", "create", "regroup"]);
+    assert!(output.status.success());
+    let output = run_reposystem(&data_dir, &["scenario", "edit", "regroup", "set-group", "Core", "core", "app"]);
+    assert!(output.status.success());
+    let output = run_reposystem(&data_dir, &["--json", "scenario", "merge", "regroup", "grow"]);
+    let outcome: serde_json::Value = serde_json::from_str(&stdout_str(&output)).unwrap();
+    assert!(outcome["conflicts"][0]["reason"].as_str().unwrap().starts_with("group changed"));
+}
+
+#[test]
+fn test_plan_from_scenario_changes() {
+    let data_dir = TempDir::new().unwrap();
+
+    let repo = |name: &str| {
+        format!(
+            r#"{{"kind": "Repo", "id": "repo:gh:test/{name}", "forge": "gh", "owner": "test", "name": "{name}",
+                "default_branch": "main", "visibility": "public", "tags": [],
+                "imports": {{"source": "test", "path_hint": null, "imported_at": "2025-01-01T00:00:00Z"}}}}"#
+        )
+    };
+    let graph_json = format!(
+        r#"{{"repos": [{}], "components": [], "groups": [], "edges": []}}"#,
+        ["podman", "cerro", "app", "web"].map(repo).join(",")
+    );
+    std::fs::write(data_dir.path().join("graph.json"), graph_json).unwrap();
+
+    for args in [
+        &["slot", "create", "runtime", "--category", "container", "--iface-version", "1.0"][..],
+        &["provider", "create", "podman", "--slot", "container.runtime", "--repo", "podman", "--iface-version", "1.0"],
+        &["provider", "create", "cerro", "--slot", "container.runtime", "--repo", "cerro", "--iface-version", "1.0"],
+        &["edge", "add", "--from", "app", "--to", "podman"],
+        &["binding", "bind", "--consumer", "app", "--slot", "container.runtime", "--provider", "podman"],
+        &["scenario", "create", "move"],
+        &["scenario", "edit", "move", "remove-edge", "--from", "app", "--to", "podman"],
+        &["scenario", "edit", "move", "add-edge", "--from", "app", "--to", "cerro"],
+        &["scenario", "edit", "move", "add-edge", "--from", "web", "--to", "cerro"],
+        &["scenario", "create", "drop"],
+        &["scenario", "edit", "drop", "remove-edge", "--from", "app", "--to", "podman"],
+        &["plan", "create", "--scenario", "move"],
+        &["plan", "create", "--scenario", "drop"],
+        &["plan", "create", "--scenario", "drop", "--include-suggestions", "with suggestions"],
+    ] {
+        let output = run_reposystem(&data_dir, args);
+        assert!(output.status.success(), "{args:?} failed: {}", stderr_str(&output));
+    }
+
+    let plans: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(data_dir.path().join("plans.json")).unwrap()).unwrap();
+    let ops = |i: usize| -> Vec<(String, String)> {
+        plans["plans"][i]["operations"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|op| (op["op"].as_str().unwrap().to_string(), op["consumer_id"].as_str().unwrap().to_string()))
+            .collect()
+    };
+    let op = |kind: &str, consumer: &str| (kind.to_string(), format!("repo:gh:test/{consumer}"));
+
+    assert_eq!(ops(0), [op("switch_binding", "app"), op("create_binding", "web")]);
+    let switch = &plans["plans"][0]["operations"][0];
+    assert_eq!(switch["to_provider_id"], "provider:container.runtime:cerro");
+    assert!(switch["reason"].as_str().unwrap().contains("Scenario adds uses edge"));
+
+    // Dropping the edge unbinds app; suggestions are opt-in and skip the
+    // providers' own repos
+    assert_eq!(ops(1), [op("remove_binding", "app")]);
+    assert_eq!(ops(2), [op("remove_binding", "app"), op("create_binding", "app"), op("create_binding", "web")]);
+    assert!(plans["plans"][2]["operations"][2]["reason"].as_str().unwrap().starts_with("Suggestion:"));
+}
+
+#[test]
+fn test_plan_diff_patches_checkout() {
+    let data_dir = TempDir::new().unwrap();
+    let checkouts = TempDir::new().unwrap();
+    let app = checkouts.path().join("app");
+    std::fs::create_dir_all(app.join("config")).unwrap();
+    std::fs::write(app.join("Cargo.toml"), "[package]\nname = \"app\"\n\n[dependencies]\npodman = \"4\"\nserde = \"1\"\n").unwrap();
+    std::fs::write(app.join("config/runtime.yaml"), "socket: unix:///run/podman.sock\nretries: 3\n").unwrap();
+
+    let repo = |name: &str| {
+        let hint = if name == "app" { format!("{:?}", app.display().to_string()) } else { "null".into() };
+        format!(
+            r#"{{"kind": "Repo", "id": "repo:gh:test/{name}", "forge": "gh", "owner": "test", "name": "{name}",
+                "default_branch": "main", "visibility": "public", "tags": [],
+                "imports": {{"source": "test", "path_hint": {hint}, "imported_at": "2025-01-01T00:00:00Z"}}}}"#
+        )
+    };
+    let graph_json = format!(
+        r#"{{"repos": [{}], "components": [], "groups": [], "edges": []}}"#,
+        ["podman", "cerro", "app"].map(repo).join(",")
+    );
+    std::fs::write(data_dir.path().join("graph.json"), graph_json).unwrap();
+
+    for args in [
+        &["slot", "create", "runtime", "--category", "container", "--iface-version", "1.0"][..],
+        &["provider", "create", "podman", "--slot", "container.runtime", "--repo", "podman",
+            "--uri", "unix:///run/podman.sock", "--iface-version", "1.0"],
+        &["provider", "create", "cerro", "--slot", "container.runtime", "--repo", "cerro",
+            "--uri", "unix:///run/cerro.sock", "--iface-version", "1.0"],
+        &["edge", "add", "--from", "app", "--to", "podman"],
+        &["binding", "bind", "--consumer", "app", "--slot", "container.runtime", "--provider", "podman"],
+        &["scenario", "create", "move"],
+        &["scenario", "edit", "move", "remove-edge", "--from", "app", "--to", "podman"],
+        &["scenario", "edit", "move", "add-edge", "--from", "app", "--to", "cerro"],
+        &["plan", "create", "--scenario", "move", "switch"],
+    ] {
+        let output = run_reposystem(&data_dir, args);
+        assert!(output.status.success(), "{args:?} failed: {}", stderr_str(&output));
+    }
+
+    let plans: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(data_dir.path().join("plans.json")).unwrap()).unwrap();
+    let diff = &plans["diffs"][0];
+    assert_eq!(diff["files_affected"], 2);
+    let files: Vec<_> = diff["file_diffs"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|fd| (fd["file_path"].as_str().unwrap(), fd["lines_added"].as_u64().unwrap(), fd["lines_removed"].as_u64().unwrap()))
+        .collect();
+    assert_eq!(files, [("Cargo.toml", 1, 1), ("config/runtime.yaml", 1, 1)]);
+
+    // Not a terminal, so no colour codes
+    let output = run_reposystem(&data_dir, &["plan", "diff", "switch"]);
+    assert!(output.status.success(), "{}", stderr_str(&output));
+    let stdout = stdout_str(&output);
+    assert!(stdout.contains("Files affected: 2"));
+    assert!(stdout.contains("    -podman = \"4\"\n    +cerro = \"4\"\n"));
+    assert!(stdout.contains("    +socket: unix:///run/cerro.sock\n"));
+    assert!(!stdout.contains('\u{1b}'));
+
+    // The bundle applies from the directory holding the checkouts
+    let bundle = checkouts.path().join("switch.patch");
+    let output = run_reposystem(&data_dir, &["plan", "diff", "switch", "-o", bundle.to_str().unwrap()]);
+    assert!(output.status.success(), "{}", stderr_str(&output));
+    assert!(stdout_str(&output).contains("Wrote 2 file patch(es)"));
+    let text = std::fs::read_to_string(&bundle).unwrap();
+    assert!(text.contains("diff --git a/app/Cargo.toml b/app/Cargo.toml\n--- a/app/Cargo.toml\n+++ b/app/Cargo.toml\n"));
+
+    let status = std::process::Command::new("git")
+        .args(["apply", "switch.patch"])
+        .current_dir(checkouts.path())
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert!(std::fs::read_to_string(app.join("Cargo.toml")).unwrap().contains("\ncerro = \"4\"\n"));
+    assert_eq!(std::fs::read_to_string(app.join("config/runtime.yaml")).unwrap(), "socket: unix:///run/cerro.sock\nretries: 3\n");
+}
+
+#[test]
+fn test_slot_binding_templates() {
+    let data_dir = TempDir::new().unwrap();
+    let checkout = TempDir::new().unwrap();
+    std::fs::write(checkout.path().join("runtime.toml"), "[runtime]\nengine = \"podman\" # default\n").unwrap();
+    std::fs::create_dir(checkout.path().join("deploy")).unwrap();
+    std::fs::write(checkout.path().join("deploy/app.json"), "{\n  \"runtime\": {\"socket\": \"unix:///run/podman.sock\"}\n}\n").unwrap();
+    std::fs::write(checkout.path().join("deploy/other.json"), "{\"runtime\": {}}\n").unwrap();
+
+    let repo = |name: &str| {
+        let hint = if name == "app" { format!("{:?}", checkout.path().display().to_string()) } else { "null".into() };
+        format!(
+            r#"{{"kind": "Repo", "id": "repo:gh:test/{name}", "forge": "gh", "owner": "test", "name": "{name}",
+                "default_branch": "main", "visibility": "public", "tags": [],
+                "imports": {{"source": "test", "path_hint": {hint}, "imported_at": "2025-01-01T00:00:00Z"}}}}"#
+        )
+    };
+    let graph_json = format!(
+        r#"{{"repos": [{}], "components": [], "groups": [], "edges": []}}"#,
+        ["podman", "cerro", "app"].map(repo).join(",")
+    );
+    std::fs::write(data_dir.path().join("graph.json"), graph_json).unwrap();
+
+    for args in [
+        &["slot", "create", "runtime", "--category", "container", "--iface-version", "1.0"][..],
+        &["provider", "create", "podman", "--slot", "container.runtime", "--repo", "podman",
+            "--uri", "unix:///run/podman.sock", "--iface-version", "1.0"],
+        &["provider", "create", "cerro", "--slot", "container.runtime", "--repo", "cerro",
+            "--uri", "unix:///run/cerro.sock", "--iface-version", "1.0"],
+        &["slot", "add-template", "runtime", "--glob", "*.toml", "--toml-key", "runtime.engine", "--value", "{name}"],
+        &["slot", "add-template", "runtime", "--glob", "deploy/*.json",
+            "--json-pointer", "/runtime/socket", "--value", "{uri}"],
+        &["slot", "add-template", "runtime", "--glob", "**/*.md", "--regex", "{name}", "--value", "{name}"],
+        &["slot", "remove-template", "runtime", "--glob", "**/*.md"],
+        &["edge", "add", "--from", "app", "--to", "podman"],
+        &["binding", "bind", "--consumer", "app", "--slot", "container.runtime", "--provider", "podman"],
+        &["scenario", "create", "move"],
+        &["scenario", "edit", "move", "remove-edge", "--from", "app", "--to", "podman"],
+        &["scenario", "edit", "move", "add-edge", "--from", "app", "--to", "cerro"],
+        &["plan", "create", "--scenario", "move", "switch"],
+    ] {
+        let output = run_reposystem(&data_dir, args);
+        assert!(output.status.success(), "{args:?} failed: {}", stderr_str(&output));
+    }
+
+    let output = run_reposystem(&data_dir, &["slot", "add-template", "runtime", "--glob", "*.toml", "--regex", "(", "--value", "x"]);
+    assert!(!output.status.success());
+    assert!(stderr_str(&output).contains("Invalid regex"));
+
+    // Only files where the template finds its target are listed
+    let output = run_reposystem(&data_dir, &["slot", "show", "runtime"]);
+    let stdout = stdout_str(&output);
+    assert!(stdout.contains("  binding templates (2):\n    *.toml: toml key runtime.engine = \"{name}\"\n"));
+    assert!(stdout.contains("    app -> podman\n      runtime.toml (toml key runtime.engine"));
+    assert!(stdout.contains("      deploy/app.json (json pointer /runtime/socket"));
+    assert!(!stdout.contains("other.json"));
+
+    let plans: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(data_dir.path().join("plans.json")).unwrap()).unwrap();
+    let ops = plans["plans"][0]["operations"].as_array().unwrap();
+    let changes: Vec<_> = ops
+        .iter()
+        .filter(|op| op["op"] == "file_change")
+        .map(|op| (op["file_path"].as_str().unwrap(), op["diff"].as_str().unwrap(), op["risk"].as_str().unwrap()))
+        .collect();
+    assert_eq!(changes.len(), 2, "{ops:?}");
+    assert_eq!(changes[0].0, "deploy/app.json");
+    assert!(changes[0].1.contains("+  \"runtime\": {\"socket\": \"unix:///run/cerro.sock\"}\n"));
+    assert_eq!(changes[1].0, "runtime.toml");
+    assert!(changes[1].1.contains("-engine = \"podman\" # default\n+engine = \"cerro\" # default\n"));
+    assert_eq!(changes[1].2, ops[0]["risk"].as_str().unwrap());
+}
+
+#[test]
+fn test_snapshot_before_apply_and_restore() {
+    let data_dir = TempDir::new().unwrap();
+    let plans = serde_json::json!({
+        "plans": [{
+            "kind": "Plan", "id": "plan:noop:1", "name": "noop", "scenario_id": "scenario:noop",
+            "description": null, "operations": [], "overall_risk": "low", "status": "ready",
+            "created_at": "2025-01-01T00:00:00Z", "created_by": "test", "applied_at": null,
+            "rollback_plan_id": null
+        }]
+    });
+    std::fs::write(data_dir.path().join("plans.json"), plans.to_string()).unwrap();
+    let plan_status = || -> serde_json::Value {
+        serde_json::from_str::<serde_json::Value>(&std::fs::read_to_string(data_dir.path().join("plans.json")).unwrap())
+            .unwrap()["plans"][0]["status"]
+            .clone()
+    };
+
+    // Dry runs change nothing, so they take no snapshot
+    let output = run_reposystem(&data_dir, &["apply", "apply", "noop", "--dry-run"]);
+    assert!(output.status.success(), "{}", stderr_str(&output));
+    assert!(!data_dir.path().join("snapshots").exists());
+
+    let output = run_reposystem(&data_dir, &["apply", "apply", "noop", "--skip-health-check"]);
+    assert!(output.status.success(), "{}", stderr_str(&output));
+    assert!(stdout_str(&output).contains("(restore with: reposystem snapshot restore "));
+    assert_eq!(plan_status(), "applied");
+
+    let output = run_reposystem(&data_dir, &["--json", "snapshot", "list"]);
+    let snapshots: serde_json::Value = serde_json::from_str(&stdout_str(&output)).unwrap();
+    assert_eq!(snapshots[0]["reason"], "apply plan:noop:1");
+    let applied_id = snapshots[0]["id"].as_str().unwrap().to_string();
+
+    // Restoring brings the plan back and keeps the replaced stores
+    let output = run_reposystem(&data_dir, &["snapshot", "restore", "latest"]);
+    assert!(output.status.success(), "{}", stderr_str(&output));
+    assert!(stdout_str(&output).contains(&format!("Restored snapshot {applied_id} (taken before: apply plan:noop:1)")));
+    assert_eq!(plan_status(), "ready");
+
+    let output = run_reposystem(&data_dir, &["snapshot", "list"]);
+    let stdout = stdout_str(&output);
+    assert!(stdout.contains(&format!("restore {applied_id}")), "{stdout}");
+    assert!(stdout.contains("Total: 2 snapshot(s)"), "{stdout}");
+
+    let output = run_reposystem(&data_dir, &["snapshot", "restore", "nope"]);
+    assert!(!output.status.success());
+    assert!(stderr_str(&output).contains("Snapshot not found: nope"));
+
+    // Retention applies after every automatic snapshot
+    let output = run_reposystem(&data_dir, &["snapshot", "retention", "--keep", "2"]);
+    assert!(stdout_str(&output).contains("  Keep: newest 2 snapshot(s)"));
+    let output = run_reposystem(&data_dir, &["apply", "apply", "noop", "--skip-health-check"]);
+    assert!(output.status.success(), "{}", stderr_str(&output));
+    let output = run_reposystem(&data_dir, &["snapshot", "list"]);
+    assert!(stdout_str(&output).contains("Total: 2 snapshot(s)"));
+    assert!(!stdout_str(&output).contains(&format!("{applied_id} ")));
+
+    let output = run_reposystem(&data_dir, &["snapshot", "prune", "--keep", "1"]);
+    assert!(stdout_str(&output).contains("Pruned 1 snapshot(s)"));
+    let output = run_reposystem(&data_dir, &["snapshot", "prune", "--keep", "0"]);
+    assert!(!output.status.success());
 }
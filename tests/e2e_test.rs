@@ -210,6 +210,160 @@ fn test_scan_json_output() {
     }
 }
 
+/// Create a real (empty) git repository with `git init`
+fn init_real_git(path: &std::path::Path) {
+    fs::create_dir_all(path).unwrap();
+    let status = std::process::Command::new("git")
+        .args(["init", "-q"])
+        .arg(path)
+        .status()
+        .expect("git must be installed");
+    assert!(status.success());
+}
+
+#[test]
+fn test_incremental_rescan_and_prune() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let data_dir = TempDir::new().expect("Failed to create data dir");
+    init_real_git(&temp_dir.path().join("alpha"));
+    init_real_git(&temp_dir.path().join("beta"));
+
+    let scan = |extra: &[&str]| {
+        let output = std::process::Command::new(reposystem_bin())
+            .env("REPOSYSTEM_DATA_DIR", data_dir.path())
+            .arg("scan")
+            .arg(temp_dir.path())
+            .args(extra)
+            .output()
+            .expect("Failed to run scan");
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+    let graph = || fs::read_to_string(data_dir.path().join("graph.json")).unwrap();
+
+    assert!(scan(&[]).contains("(2 scanned, 0 unchanged)"));
+    assert!(data_dir.path().join("scan-cache.json").exists());
+    let first = graph();
+
+    // Nothing changed: both repos come from the cache
+    assert!(scan(&[]).contains("(0 scanned, 2 unchanged)"));
+
+    // A new commit moves HEAD; only that repo is rescanned
+    let commit = std::process::Command::new("git")
+        .arg("-C")
+        .arg(temp_dir.path().join("alpha"))
+        .args(["-c", "user.name=t", "-c", "user.email=t@example.com"])
+        .args(["commit", "-q", "--allow-empty", "-m", "init"])
+        .status()
+        .unwrap();
+    assert!(commit.success());
+    assert!(scan(&[]).contains("(1 scanned, 1 unchanged)"));
+
+    // imported_at survives the rescan
+    let stamps = |json: &str| -> Vec<String> {
+        let v: serde_json::Value = serde_json::from_str(json).unwrap();
+        let mut out: Vec<String> = v["repos"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["imports"]["imported_at"].as_str().unwrap().to_string())
+            .collect();
+        out.sort();
+        out
+    };
+    assert_eq!(stamps(&first), stamps(&graph()));
+
+    // --full ignores the cache
+    assert!(scan(&["--full"]).contains("(2 scanned, 0 unchanged)"));
+
+    // A deleted checkout is reported, then pruned on request
+    fs::remove_dir_all(temp_dir.path().join("beta")).unwrap();
+    let out = scan(&[]);
+    assert!(out.contains("Vanished 1 repositories"), "{out}");
+    assert!(graph().contains("beta"));
+
+    let out = scan(&["--prune"]);
+    assert!(out.contains("Pruned 1 repositories"), "{out}");
+    assert!(!graph().contains("beta"));
+    assert!(!scan(&[]).contains("Vanished"));
+}
+
+/// Run a git command in `path`
+fn git(path: &std::path::Path, args: &[&str]) {
+    let status = std::process::Command::new("git")
+        .arg("-C")
+        .arg(path)
+        .args(["-c", "user.name=t", "-c", "user.email=t@example.com"])
+        .args(args)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_local_repo_transfer_is_detected_and_merged() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let data_dir = TempDir::new().expect("Failed to create data dir");
+    for name in ["app", "lib"] {
+        let path = temp_dir.path().join(name);
+        init_real_git(&path);
+        git(&path, &["commit", "-q", "--allow-empty", "-m", name]);
+    }
+
+    let run = |args: &[&str]| {
+        let output = std::process::Command::new(reposystem_bin())
+            .env("REPOSYSTEM_DATA_DIR", data_dir.path())
+            .args(args)
+            .output()
+            .expect("Failed to run reposystem");
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+    let root = temp_dir.path().to_str().unwrap();
+    let graph = || -> serde_json::Value {
+        serde_json::from_str(&fs::read_to_string(data_dir.path().join("graph.json")).unwrap()).unwrap()
+    };
+    let repo_ids = || -> Vec<String> {
+        let mut ids: Vec<String> = graph()["repos"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["id"].as_str().unwrap().to_string())
+            .collect();
+        ids.sort();
+        ids
+    };
+
+    run(&["scan", root]);
+    run(&["edge", "add", "--from", "app", "--to", "lib", "--rel", "uses"]);
+    let local_lib = repo_ids().into_iter().find(|id| graph()["edges"][0]["to"] == *id).unwrap();
+
+    // lib gains a GitHub remote: reported, not merged
+    git(&temp_dir.path().join("lib"), &["remote", "add", "origin", "https://github.com/acme/lib.git"]);
+    let out = run(&["scan", root]);
+    assert!(out.contains("Transferred 1 local repositories"), "{out}");
+    assert!(out.contains(&format!("{local_lib} -> repo:gh:acme/lib (same path)")), "{out}");
+    assert_eq!(repo_ids().len(), 3);
+
+    let out = run(&["scan", root, "--merge-transfers"]);
+    assert!(out.contains("Merged 1 local repositories"), "{out}");
+    assert_eq!(repo_ids().len(), 2);
+    let g = graph();
+    assert_eq!(g["edges"].as_array().unwrap().len(), 1);
+    assert_eq!(g["edges"][0]["to"], "repo:gh:acme/lib");
+    let lib = g["repos"].as_array().unwrap().iter().find(|r| r["id"] == "repo:gh:acme/lib").unwrap();
+    assert_eq!(lib["metadata"]["aliases"], local_lib.as_str());
+
+    // app moves and gains a remote: matched by its root commit, not vanished
+    fs::rename(temp_dir.path().join("app"), temp_dir.path().join("app2")).unwrap();
+    git(&temp_dir.path().join("app2"), &["remote", "add", "origin", "git@github.com:acme/app.git"]);
+    let out = run(&["scan", root, "--merge-transfers", "--prune"]);
+    assert!(out.contains("-> repo:gh:acme/app (same root commit)"), "{out}");
+    assert!(!out.contains("Pruned"), "{out}");
+    assert_eq!(repo_ids(), vec!["repo:gh:acme/app", "repo:gh:acme/lib"]);
+    assert_eq!(graph()["edges"][0]["from"], "repo:gh:acme/app");
+}
+
 #[test]
 fn test_scan_nonexistent_path() {
     let output = std::process::Command::new(reposystem_bin())
@@ -226,6 +380,80 @@ fn test_scan_nonexistent_path() {
     );
 }
 
+// =============================================================================
+// Diff Tests
+// =============================================================================
+
+#[test]
+fn test_diff_against_git_history() {
+    let data_dir = TempDir::new().expect("Failed to create data dir");
+    let run = |args: &[&str]| {
+        let output = std::process::Command::new(reposystem_bin())
+            .env("REPOSYSTEM_DATA_DIR", data_dir.path())
+            .current_dir(data_dir.path())
+            .args(args)
+            .output()
+            .expect("Failed to run reposystem");
+        assert!(output.status.success(), "{args:?}: {}", String::from_utf8_lossy(&output.stderr));
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    // The data directory is itself the graph repo
+    init_real_git(data_dir.path());
+    let repo = |name: &str| {
+        format!(
+            r#"{{"kind": "Repo", "id": "repo:gh:test/{name}", "forge": "gh", "owner": "test", "name": "{name}",
+                "default_branch": "main", "visibility": "public", "tags": [],
+                "imports": {{"source": "test", "path_hint": null, "imported_at": "2025-01-01T00:00:00Z"}}}}"#
+        )
+    };
+    let repos = ["core", "app", "web"].map(repo).join(",");
+    fs::write(data_dir.path().join("graph.json"), format!(r#"{{"repos": [{repos}], "edges": []}}"#)).unwrap();
+    run(&["edge", "add", "--from", "app", "--to", "core", "--rel", "uses"]);
+    git(data_dir.path(), &["add", "-A"]);
+    git(data_dir.path(), &["commit", "-q", "-m", "baseline"]);
+
+    assert!(run(&["diff", "HEAD"]).contains("No changes."));
+
+    run(&["edge", "add", "--from", "web", "--to", "core", "--rel", "uses", "--channel", "api"]);
+    run(&["group", "create", "Core", "core"]);
+    run(&["aspect", "tag", "--target", "web", "--aspect", "security", "--weight", "2", "--polarity", "risk", "--reason", "old deps"]);
+
+    let text = run(&["diff", "HEAD"]);
+    assert!(text.contains("Comparing HEAD -> current"), "{text}");
+    assert!(text.contains("edges: 1 added, 0 removed, 0 changed"), "{text}");
+    assert!(text.contains("  + web -> core [uses/api]"), "{text}");
+    assert!(text.contains("groups: 1 added"), "{text}");
+    assert!(text.contains("  + web security"), "{text}");
+    assert!(!text.contains("repos:"), "{text}");
+
+    let json: serde_json::Value = serde_json::from_str(&run(&["--json", "diff", "HEAD"])).unwrap();
+    assert_eq!(json["edges"]["added"].as_array().unwrap().len(), 1);
+    assert_eq!(json["annotations"]["added"][0]["label"], "web security");
+
+    // `<rev>:<path>` resolves against the repository containing the cwd;
+    // a single committed file carries no aspect data, so annotations are skipped
+    let text = run(&["diff", "HEAD:graph.json"]);
+    assert!(text.contains("annotations: not compared"), "{text}");
+
+    let dot = run(&["diff", "HEAD", "current", "--format", "dot"]);
+    assert!(dot.starts_with("digraph diff {"));
+    assert!(dot.contains("\"repo:gh:test/web\" -> \"repo:gh:test/core\" [label=\"\", color=\"#2b8a3e\""), "{dot}");
+    assert!(dot.contains("[label=\"web\\ngh\\n~ annotations\", fillcolor=\"#ffe8a1\""), "{dot}");
+
+    // Reversed: the new edge shows as removed
+    assert!(run(&["diff", "current", "HEAD"]).contains("  - web -> core [uses/api]"));
+
+    let output = std::process::Command::new(reposystem_bin())
+        .env("REPOSYSTEM_DATA_DIR", data_dir.path())
+            .current_dir(data_dir.path())
+        .args(["diff", "no-such-rev"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("'no-such-rev' is not a file, directory or git revision"));
+}
+
 // =============================================================================
 // Config File Tests
 // =============================================================================
@@ -439,3 +667,428 @@ fn test_multiple_flags_combined() {
 
     assert!(output.status.success(), "Should handle multiple flags");
 }
+
+#[test]
+fn test_apply_file_changes_on_branch_and_undo() {
+    let checkout = TempDir::new().expect("Failed to create checkout");
+    let data_dir = TempDir::new().expect("Failed to create data dir");
+    let app = checkout.path().join("app");
+    init_real_git(&app);
+    fs::write(app.join("runtime.toml"), "[runtime]\nengine = \"podman\"\nrootless = true\n").unwrap();
+    git(&app, &["add", "-A"]);
+    git(&app, &["commit", "-q", "-m", "initial"]);
+
+    let graph_json = serde_json::json!({
+        "repos": [{
+            "kind": "Repo", "id": "repo:gh:test/app", "forge": "gh", "owner": "test", "name": "app",
+            "default_branch": "main", "visibility": "public", "tags": [],
+            "imports": {"source": "test", "path_hint": app, "imported_at": "2025-01-01T00:00:00Z"}
+        }],
+        "edges": []
+    });
+    fs::write(data_dir.path().join("graph.json"), graph_json.to_string()).unwrap();
+
+    let file_change = |file: &str, change_type: &str, diff: &str| {
+        serde_json::json!({
+            "op": "file_change", "repo_id": "repo:gh:test/app", "file_path": file,
+            "change_type": change_type, "diff": diff, "risk": "low"
+        })
+    };
+    let plan = |id: &str, operations: Vec<serde_json::Value>| {
+        serde_json::json!({
+            "kind": "Plan", "id": id, "name": id, "scenario_id": "scenario:move",
+            "description": null, "operations": operations, "overall_risk": "low", "status": "ready",
+            "created_at": "2025-01-01T00:00:00Z", "created_by": "test", "applied_at": null,
+            "rollback_plan_id": null
+        })
+    };
+    let modify = "--- a/runtime.toml\n+++ b/runtime.toml\n@@ -1,3 +1,3 @@\n [runtime]\n-engine = \"podman\"\n+engine = \"cerro-torre\"\n rootless = true\n";
+    let create = "--- /dev/null\n+++ b/deploy/README\n@@ -0,0 +1 @@\n+Deployed with cerro-torre\n";
+    let stale = "--- a/runtime.toml\n+++ b/runtime.toml\n@@ -1,2 +1,2 @@\n [runtime]\n-engine = \"docker\"\n+engine = \"podman\"\n";
+    let plans = serde_json::json!({
+        "plans": [
+            plan("plan:move:1", vec![file_change("runtime.toml", "modify", modify), file_change("deploy/README", "create", create)]),
+            plan("plan:stale:1", vec![file_change("runtime.toml", "modify", stale)]),
+        ]
+    });
+    fs::write(data_dir.path().join("plans.json"), plans.to_string()).unwrap();
+
+    let run = |args: &[&str]| {
+        std::process::Command::new(reposystem_bin())
+            .env("REPOSYSTEM_DATA_DIR", data_dir.path())
+            .args(args)
+            .output()
+            .expect("Failed to run reposystem")
+    };
+    let head = || fs::read_to_string(app.join(".git/HEAD")).unwrap();
+    let base = head();
+    let git_out = |args: &[&str]| {
+        let output = std::process::Command::new("git").arg("-C").arg(&app).args(args).output().unwrap();
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    // A dry run checks the patches without touching the checkout
+    let output = run(&["apply", "apply", "plan:move:1", "--dry-run"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("applied on reposystem/move-1"), "{stdout}");
+    assert_eq!(stdout.matches("applies cleanly").count(), 2, "{stdout}");
+    assert!(!app.join("deploy").exists());
+
+    // A patch that does not match the file stops the plan before any change
+    let output = run(&["apply", "apply", "plan:stale:1"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("FAILED: Hunk 1 (line 1) of runtime.toml does not apply"));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("nothing was changed"));
+    assert!(!head().contains("reposystem/"));
+
+    // Uncommitted changes would be carried onto the branch, so they block it
+    fs::write(app.join("runtime.toml"), "[runtime]\nengine = \"podman\"\nrootless = false\n").unwrap();
+    let output = run(&["apply", "apply", "plan:move:1"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("(runtime.toml is modified); commit or stash them first"));
+    git(&app, &["checkout", "--", "runtime.toml"]);
+    fs::write(app.join("notes.txt"), "untracked\n").unwrap();
+    git(&app, &["add", "notes.txt"]);
+    let output = run(&["apply", "apply", "plan:move:1", "--dry-run"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("(notes.txt is staged)"), "{output:?}");
+    git(&app, &["rm", "-q", "--cached", "notes.txt"]);
+
+    // Each change is committed on the plan branch, leaving the checkout clean
+    let output = run(&["apply", "apply", "plan:move:1", "--skip-health-check"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Plan applied successfully!"), "{output:?}");
+    assert_eq!(head().trim(), "ref: refs/heads/reposystem/move-1");
+    assert!(fs::read_to_string(app.join("runtime.toml")).unwrap().contains("cerro-torre"));
+    assert_eq!(fs::read_to_string(app.join("deploy/README")).unwrap(), "Deployed with cerro-torre\n");
+    assert_eq!(git_out(&["log", "--format=%s", "-3"]), "Create deploy/README\nModify runtime.toml\ninitial\n");
+    assert_eq!(git_out(&["status", "--porcelain"]), "?? notes.txt\n");
+
+    // Undo reverses both changes, newest first
+    let output = run(&["apply", "undo", "plan:move:1"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Plan undone successfully!"));
+    assert_eq!(fs::read_to_string(app.join("runtime.toml")).unwrap(), "[runtime]\nengine = \"podman\"\nrootless = true\n");
+    assert!(!app.join("deploy/README").exists());
+
+    // ...and switches back to the original branch, dropping the plan branch
+    assert_eq!(head(), base);
+    assert_eq!(git_out(&["branch", "--list", "reposystem/*"]), "");
+    assert_eq!(git_out(&["status", "--porcelain"]), "?? notes.txt\n");
+}
+
+#[test]
+fn test_auto_rollback_reverses_only_succeeded_ops() {
+    let checkout = TempDir::new().expect("Failed to create checkout");
+    let data_dir = TempDir::new().expect("Failed to create data dir");
+    let app = checkout.path().join("app");
+    init_real_git(&app);
+    fs::write(app.join("runtime.toml"), "engine = \"podman\"\n").unwrap();
+    git(&app, &["add", "-A"]);
+    git(&app, &["commit", "-q", "-m", "initial"]);
+    let plain = checkout.path().join("plain");
+    fs::create_dir_all(&plain).unwrap();
+    fs::write(plain.join("runtime.toml"), "engine = \"podman\"\n").unwrap();
+
+    let repo = |name: &str, path: &std::path::Path| {
+        serde_json::json!({
+            "kind": "Repo", "id": format!("repo:gh:test/{name}"), "forge": "gh", "owner": "test", "name": name,
+            "default_branch": "main", "visibility": "public", "tags": [],
+            "imports": {"source": "test", "path_hint": path, "imported_at": "2025-01-01T00:00:00Z"}
+        })
+    };
+    let graph_json = serde_json::json!({"repos": [repo("app", &app), repo("plain", &plain)], "edges": []});
+    fs::write(data_dir.path().join("graph.json"), graph_json.to_string()).unwrap();
+
+    let modify = "--- a/runtime.toml\n+++ b/runtime.toml\n@@ -1 +1 @@\n-engine = \"podman\"\n+engine = \"cerro\"\n";
+    let file_change = |repo: &str| {
+        serde_json::json!({
+            "op": "file_change", "repo_id": format!("repo:gh:test/{repo}"), "file_path": "runtime.toml",
+            "change_type": "modify", "diff": modify, "risk": "low"
+        })
+    };
+    // The switch fails at apply time: its provider does not exist
+    let switch = serde_json::json!({
+        "op": "switch_binding", "binding_id": "binding:runtime:app:podman", "consumer_id": "repo:gh:test/app",
+        "slot_id": "slot:runtime", "from_provider_id": "provider:runtime:podman",
+        "to_provider_id": "provider:runtime:missing", "risk": "low", "reason": "test"
+    });
+    let plan = |id: &str, operations: Vec<serde_json::Value>| {
+        serde_json::json!({
+            "kind": "Plan", "id": id, "name": id, "scenario_id": "scenario:x",
+            "description": null, "operations": operations, "overall_risk": "low", "status": "ready",
+            "created_at": "2025-01-01T00:00:00Z", "created_by": "test", "applied_at": null,
+            "rollback_plan_id": null
+        })
+    };
+    let plans = serde_json::json!({
+        "plans": [
+            plan("plan:x:1", vec![file_change("app"), switch]),
+            plan("plan:plain:1", vec![file_change("app"), file_change("plain")]),
+        ]
+    });
+    fs::write(data_dir.path().join("plans.json"), plans.to_string()).unwrap();
+
+    let run = |args: &[&str]| {
+        std::process::Command::new(reposystem_bin())
+            .env("REPOSYSTEM_DATA_DIR", data_dir.path())
+            .args(args)
+            .output()
+            .expect("Failed to run reposystem")
+    };
+
+    // A checkout that is not a git repository fails the pre-check
+    let output = run(&["apply", "apply", "plan:plain:1", "--dry-run"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("  1. applies cleanly"), "{stdout}");
+    assert!(stdout.contains("  2. FAILED: No git repository at"), "{stdout}");
+    let output = run(&["apply", "apply", "plan:plain:1", "--auto-rollback"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("nothing was changed"));
+    assert_eq!(fs::read_to_string(app.join("runtime.toml")).unwrap(), "engine = \"podman\"\n");
+
+    // Only the file change that succeeded is reversed
+    let output = run(&["apply", "apply", "plan:x:1", "--auto-rollback", "--skip-health-check"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("FAILED: Provider not found: provider:runtime:missing"), "{stdout}");
+    assert!(stdout.contains("[rollback 1/1] Modify runtime.toml in repo:gh:test/app"), "{stdout}");
+    assert!(!stdout.contains("ROLLBACK FAILED"), "{stdout}");
+    assert!(stdout.contains("Plan was rolled back due to failure"), "{stdout}");
+    assert_eq!(fs::read_to_string(app.join("runtime.toml")).unwrap(), "engine = \"podman\"\n");
+    assert!(!fs::read_to_string(app.join(".git/HEAD")).unwrap().contains("reposystem/"), "{stdout}");
+
+    let audit: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(data_dir.path().join("audit.json")).unwrap()).unwrap();
+    assert_eq!(audit["entries"][0]["result"], "rolledback");
+}
+
+#[test]
+fn test_staged_apply_with_answers_and_resume() {
+    let checkout = TempDir::new().expect("Failed to create checkout");
+    let data_dir = TempDir::new().expect("Failed to create data dir");
+    let mut repos = Vec::new();
+    for name in ["app", "web"] {
+        let path = checkout.path().join(name);
+        init_real_git(&path);
+        fs::write(path.join("runtime.toml"), "engine = \"podman\"\n").unwrap();
+        git(&path, &["add", "-A"]);
+        git(&path, &["commit", "-q", "-m", "initial"]);
+        repos.push(serde_json::json!({
+            "kind": "Repo", "id": format!("repo:gh:test/{name}"), "forge": "gh", "owner": "test", "name": name,
+            "default_branch": "main", "visibility": "public", "tags": [],
+            "imports": {"source": "test", "path_hint": path, "imported_at": "2025-01-01T00:00:00Z"}
+        }));
+    }
+    fs::write(data_dir.path().join("graph.json"), serde_json::json!({"repos": repos, "edges": []}).to_string()).unwrap();
+
+    let modify = "--- a/runtime.toml\n+++ b/runtime.toml\n@@ -1 +1 @@\n-engine = \"podman\"\n+engine = \"cerro\"\n";
+    let file_change = |repo: &str, risk: &str| {
+        serde_json::json!({
+            "op": "file_change", "repo_id": format!("repo:gh:test/{repo}"), "file_path": "runtime.toml",
+            "change_type": "modify", "diff": modify, "risk": risk
+        })
+    };
+    let create_binding = serde_json::json!({
+        "op": "create_binding", "consumer_id": "repo:gh:test/app", "slot_id": "slot:container.runtime",
+        "provider_id": "provider:container.runtime:cerro", "risk": "high", "reason": "test"
+    });
+    let plans = serde_json::json!({
+        "plans": [{
+            "kind": "Plan", "id": "plan:move:1", "name": "move", "scenario_id": "scenario:move",
+            "description": null, "overall_risk": "high", "status": "ready",
+            "operations": [create_binding, file_change("web", "low"), file_change("app", "medium")],
+            "created_at": "2025-01-01T00:00:00Z", "created_by": "test", "applied_at": null, "rollback_plan_id": null
+        }]
+    });
+    fs::write(data_dir.path().join("plans.json"), plans.to_string()).unwrap();
+
+    let run = |args: &[&str], stdin: &str| {
+        let mut child = std::process::Command::new(reposystem_bin())
+            .env("REPOSYSTEM_DATA_DIR", data_dir.path())
+            .args(args)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .expect("Failed to run reposystem");
+        std::io::Write::write_all(&mut child.stdin.take().unwrap(), stdin.as_bytes()).unwrap();
+        child.wait_with_output().unwrap()
+    };
+    let engine = |repo: &str| fs::read_to_string(checkout.path().join(repo).join("runtime.toml")).unwrap();
+    let plan = || -> serde_json::Value {
+        serde_json::from_str::<serde_json::Value>(&fs::read_to_string(data_dir.path().join("plans.json")).unwrap())
+            .unwrap()["plans"][0]
+            .clone()
+    };
+
+    // Ops are grouped per repo in plan order: app (binding + file), then web
+    let answers = data_dir.path().join("answers");
+    fs::write(&answers, "# CI answers\nn\ny\n").unwrap();
+    let output = run(&["apply", "apply", "move", "--staged", "--answers", answers.to_str().unwrap(), "--skip-health-check"], "");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(stdout.contains("Stage 1/2: app (2 op(s), risk High)"), "{stdout}");
+    assert!(stdout.contains("       +engine = \"cerro\""), "{stdout}");
+    assert!(stdout.contains("Apply stage 1/2 (app)? [y/n/q] n\n  Skipped"), "{stdout}");
+    assert!(stdout.contains("Stage 2/2: web (1 op(s), risk Low)"), "{stdout}");
+    assert!(stdout.contains("1 of 3 operations applied\nResume with: reposystem apply apply plan:move:1 --staged"), "{stdout}");
+    assert_eq!(engine("app"), "engine = \"podman\"\n");
+    assert_eq!(engine("web"), "engine = \"cerro\"\n");
+    assert_eq!(plan()["status"], "partiallyapplied");
+    assert_eq!(plan()["applied_ops"], serde_json::json!([1]));
+
+    // Only a staged apply resumes it
+    let output = run(&["apply", "apply", "move"], "");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("resume it with 'apply --staged'"));
+
+    // Resume from stdin: web is done, app is applied now
+    let output = run(&["apply", "apply", "move", "--staged", "--skip-health-check"], "maybe\ny\n");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(stdout.contains("Please answer y (apply), n (skip for now) or q (stop)"), "{stdout}");
+    assert!(stdout.contains("Stage 2/2: web - already applied"), "{stdout}");
+    assert!(stdout.contains("Plan applied successfully!"), "{stdout}");
+    assert_eq!(engine("app"), "engine = \"cerro\"\n");
+    assert_eq!(plan()["status"], "applied");
+    assert!(plan().get("applied_ops").is_none());
+
+    let audit: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(data_dir.path().join("audit.json")).unwrap()).unwrap();
+    let stages = |i: usize| -> Vec<(String, String)> {
+        audit["entries"][i]["stages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|s| (s["repo_id"].as_str().unwrap().to_string(), s["outcome"].as_str().unwrap().to_string()))
+            .collect()
+    };
+    let stage = |repo: &str, outcome: &str| (format!("repo:gh:test/{repo}"), outcome.to_string());
+    assert_eq!(audit["entries"][0]["result"], "incomplete");
+    assert_eq!(stages(0), [stage("app", "skipped"), stage("web", "applied")]);
+    assert_eq!(audit["entries"][1]["result"], "success");
+    assert_eq!(stages(1), [stage("app", "applied")]);
+    assert_eq!(audit["entries"][1]["op_results"][1]["op_index"], 2);
+}
+
+#[test]
+fn test_staged_rollback_failure_keeps_unreversed_ops() {
+    let checkout = TempDir::new().expect("Failed to create checkout");
+    let data_dir = TempDir::new().expect("Failed to create data dir");
+    let app = checkout.path().join("app");
+    init_real_git(&app);
+    fs::write(app.join("runtime.toml"), "engine = \"podman\"\n").unwrap();
+    git(&app, &["add", "-A"]);
+    git(&app, &["commit", "-q", "-m", "initial"]);
+    let graph_json = serde_json::json!({"repos": [{
+        "kind": "Repo", "id": "repo:gh:test/app", "forge": "gh", "owner": "test", "name": "app",
+        "default_branch": "main", "visibility": "public", "tags": [],
+        "imports": {"source": "test", "path_hint": app, "imported_at": "2025-01-01T00:00:00Z"}
+    }], "edges": []});
+    fs::write(data_dir.path().join("graph.json"), graph_json.to_string()).unwrap();
+    let slots = serde_json::json!({
+        "slots": [{"kind": "Slot", "id": "slot:runtime", "name": "runtime", "category": "container", "description": "test"}],
+        "providers": [{
+            "kind": "Provider", "id": "provider:runtime:cerro", "name": "cerro", "slot_id": "slot:runtime",
+            "provider_type": "local", "repo_id": null, "external_uri": null
+        }]
+    });
+    fs::write(data_dir.path().join("slots.json"), slots.to_string()).unwrap();
+
+    // Reversing the first switch fails (its old provider is gone), so the
+    // rollback stops after reversing the file change
+    let switch = |from: &str, to: &str| {
+        serde_json::json!({
+            "op": "switch_binding", "binding_id": "binding:runtime:app:podman", "consumer_id": "repo:gh:test/app",
+            "slot_id": "slot:runtime", "from_provider_id": from, "to_provider_id": to, "risk": "low", "reason": "test"
+        })
+    };
+    let file_change = serde_json::json!({
+        "op": "file_change", "repo_id": "repo:gh:test/app", "file_path": "runtime.toml", "change_type": "modify",
+        "diff": "--- a/runtime.toml\n+++ b/runtime.toml\n@@ -1 +1 @@\n-engine = \"podman\"\n+engine = \"cerro\"\n",
+        "risk": "low"
+    });
+    let plans = serde_json::json!({
+        "plans": [{
+            "kind": "Plan", "id": "plan:x:1", "name": "x", "scenario_id": "scenario:x",
+            "description": null, "overall_risk": "low", "status": "ready",
+            "operations": [
+                switch("provider:runtime:podman", "provider:runtime:cerro"),
+                file_change,
+                switch("provider:runtime:cerro", "provider:runtime:missing"),
+            ],
+            "created_at": "2025-01-01T00:00:00Z", "created_by": "test", "applied_at": null, "rollback_plan_id": null
+        }]
+    });
+    fs::write(data_dir.path().join("plans.json"), plans.to_string()).unwrap();
+
+    let answers = data_dir.path().join("answers");
+    fs::write(&answers, "y\n").unwrap();
+    let output = std::process::Command::new(reposystem_bin())
+        .env("REPOSYSTEM_DATA_DIR", data_dir.path())
+        .args(["apply", "apply", "plan:x:1", "--staged", "--auto-rollback", "--skip-health-check"])
+        .args(["--answers", answers.to_str().unwrap()])
+        .output()
+        .expect("Failed to run reposystem");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("[rollback 1/2] Modify runtime.toml in repo:gh:test/app"), "{stdout}");
+    assert!(stdout.contains("ROLLBACK FAILED: Provider not found: provider:runtime:podman"), "{stdout}");
+    assert!(stdout.contains("1 of 3 operations applied"), "{stdout}");
+    assert_eq!(fs::read_to_string(app.join("runtime.toml")).unwrap(), "engine = \"podman\"\n");
+
+    // The reversed file change is not recorded as applied
+    let plans: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(data_dir.path().join("plans.json")).unwrap()).unwrap();
+    assert_eq!(plans["plans"][0]["status"], "partiallyapplied");
+    assert_eq!(plans["plans"][0]["applied_ops"], serde_json::json!([0]));
+}
+
+#[test]
+fn test_rescan_with_fewer_detectors_keeps_other_edges() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let data_dir = TempDir::new().expect("Failed to create data dir");
+    let app = temp_dir.path().join("app");
+    init_real_git(&app);
+    init_real_git(&temp_dir.path().join("lib"));
+    fs::write(app.join("Cargo.toml"), "[dependencies]\nlib = { path = \"../lib\" }\n").unwrap();
+    fs::write(app.join("package.json"), "{\"dependencies\": {\"lib\": \"file:../lib\"}}\n").unwrap();
+
+    let run = |args: &[&str]| {
+        let output = std::process::Command::new(reposystem_bin())
+            .env("REPOSYSTEM_DATA_DIR", data_dir.path())
+            .args(args)
+            .output()
+            .expect("Failed to run reposystem");
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+    let edges = || -> Vec<(String, String)> {
+        let graph: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(data_dir.path().join("graph.json")).unwrap()).unwrap();
+        let mut edges: Vec<(String, String)> = graph["edges"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| (e["label"].as_str().unwrap().to_string(), e["meta"]["created_by"].as_str().unwrap().to_string()))
+            .collect();
+        edges.sort();
+        edges
+    };
+    let root = temp_dir.path().to_str().unwrap();
+    let both = vec![
+        ("cargo".to_string(), "auto-detect:cargo".to_string()),
+        ("npm".to_string(), "auto-detect:npm".to_string()),
+    ];
+
+    run(&["scan", root, "--deep"]);
+    assert_eq!(edges(), both);
+
+    // Only the cargo detector runs: its edge is refreshed, the npm edge stays
+    fs::write(app.join("Cargo.toml"), "[dependencies]\n\nlib = { path = \"../lib\" }\n").unwrap();
+    let out = run(&["scan", root, "--deep", "--detectors", "cargo"]);
+    assert!(out.contains("0 removed"), "{out}");
+    assert_eq!(edges(), both);
+
+    // The cargo dependency goes away: only its edge is removed
+    fs::write(app.join("Cargo.toml"), "[dependencies]\n").unwrap();
+    run(&["scan", root, "--deep", "--detectors", "cargo"]);
+    assert_eq!(edges(), vec![("npm".to_string(), "auto-detect:npm".to_string())]);
+}
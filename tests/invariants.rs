@@ -131,6 +131,17 @@ fn test_edge_id_uniqueness() {
     assert_eq!(ids.len(), 5, "All edge IDs should be unique");
 }
 
+#[test]
+fn test_relation_code_matches_serde() {
+    use RelationType::{Extends, Mirrors, Provides, RefersTo, Replaces, Uses};
+    // Exports and stored data must spell each relation one way
+    for rel in [Uses, Provides, Extends, Mirrors, Replaces, RefersTo] {
+        assert_eq!(serde_json::to_value(rel).unwrap(), rel.code());
+        assert_eq!(RelationType::from_code(rel.code()), Some(rel));
+    }
+    assert_eq!(RelationType::from_code("refers-to"), Some(RefersTo));
+}
+
 #[test]
 fn test_local_repo_id_determinism() {
     use std::path::PathBuf;
@@ -379,6 +390,127 @@ fn test_json_export_import_fidelity() {
     assert_eq!(loaded.edge_count(), 2);
 }
 
+#[test]
+fn test_yaml_and_toml_estate_export_import_fidelity() {
+    use reposystem::envelope::EnvelopeFormat;
+    use reposystem::types::{ChangeOp, ChangeSet, Scenario};
+
+    let mut graph = EcosystemGraph::new();
+
+    // Repos, including awkward strings that YAML/TOML must quote
+    let mut repo_a = make_repo("alpha", Forge::GitHub, "test");
+    repo_a.tags = vec!["true".into(), "1.0".into(), "needs: quoting # really".into()];
+    repo_a.metadata.insert("phase".into(), "beta".into());
+    repo_a.metadata.insert("notes".into(), "line one\nline two".into());
+    let repo_b = make_repo("beta", Forge::GitLab, "test");
+    let repo_c = make_repo("gamma", Forge::Codeberg, "other");
+    graph.add_repo(repo_a.clone());
+    graph.add_repo(repo_b.clone());
+    graph.add_repo(repo_c.clone());
+
+    // Edges with and without evidence/labels (None must survive TOML)
+    let mut edge1 = make_edge(&repo_a.id, &repo_b.id, RelationType::Uses, Some("api client"));
+    edge1.evidence.push(Evidence {
+        evidence_type: "file".into(),
+        reference: "Cargo.toml".into(),
+        excerpt: Some("beta = \"1.0\"".into()),
+        confidence: 0.95,
+    });
+    graph.add_edge(edge1).unwrap();
+    graph
+        .add_edge(make_edge(&repo_b.id, &repo_c.id, RelationType::Provides, None))
+        .unwrap();
+
+    graph.add_group(Group {
+        kind: "Group".into(),
+        id: "group:test-cluster".into(),
+        name: "Test Cluster".into(),
+        description: None,
+        members: vec![repo_a.id.clone(), repo_b.id.clone()],
+    });
+    graph.aspects.annotations.push(make_annotation(&repo_a.id, "security", 2, Polarity::Risk));
+
+    // Slot registry
+    let slot = Slot {
+        kind: "Slot".into(),
+        id: "slot:container.runtime".into(),
+        name: "runtime".into(),
+        category: "container".into(),
+        description: "Container runtime slot".into(),
+        interface_version: None,
+        required_capabilities: vec!["run".into()],
+        templates: Vec::new(),
+    };
+    let provider = Provider {
+        kind: "Provider".into(),
+        id: "provider:container.runtime:podman".into(),
+        name: "podman".into(),
+        slot_id: slot.id.clone(),
+        provider_type: ProviderType::Local,
+        repo_id: Some(repo_c.id.clone()),
+        external_uri: None,
+        interface_version: Some("v1".into()),
+        capabilities: vec!["run".into()],
+        priority: 10,
+        is_fallback: false,
+    };
+    graph.slots.bindings.push(SlotBinding {
+        kind: "SlotBinding".into(),
+        id: SlotBinding::generate_id(&repo_a.id, &slot.id),
+        consumer_id: repo_a.id.clone(),
+        slot_id: slot.id.clone(),
+        provider_id: provider.id.clone(),
+        mode: BindingMode::Manual,
+        created_at: Utc::now(),
+        created_by: "test".into(),
+    });
+    graph.slots.slots.push(slot);
+    graph.slots.providers.push(provider);
+
+    // Scenario with a pending change
+    graph.store.scenarios.push(Scenario {
+        kind: "Scenario".into(),
+        id: "scenario:split".into(),
+        name: "Split".into(),
+        base: None,
+        description: None,
+        created_at: Utc::now(),
+    });
+    graph.store.changesets.push(ChangeSet {
+        kind: "ChangeSet".into(),
+        scenario_id: "scenario:split".into(),
+        ops: vec![ChangeOp::AddEdge {
+            edge: make_edge(&repo_c.id, &repo_a.id, RelationType::Extends, None),
+        }],
+    });
+
+    let canonical = |g: &EcosystemGraph| -> serde_json::Value {
+        serde_json::from_str(&g.to_estate_export().unwrap()).unwrap()
+    };
+    let original = canonical(&graph);
+
+    for format in [EnvelopeFormat::Yaml, EnvelopeFormat::Toml] {
+        let text = graph.to_estate_export_as(format).unwrap();
+        let loaded = EcosystemGraph::from_estate_export(&text, format)
+            .unwrap_or_else(|e| panic!("{format:?} import failed: {e:#}\n{text}"));
+
+        // Everything in the envelope survives, field for field
+        assert_eq!(canonical(&loaded), original, "{format:?} round trip differs");
+
+        let loaded_a = loaded.get_repo(&repo_a.id).unwrap();
+        assert_eq!(loaded_a.tags, repo_a.tags);
+        assert_eq!(loaded_a.metadata, repo_a.metadata);
+        let loaded_edge = loaded.store.edges.iter().find(|e| e.from == repo_a.id).unwrap();
+        assert_eq!(loaded_edge.evidence[0].confidence, 0.95);
+        assert!(loaded.store.groups[0].description.is_none());
+        assert_eq!(loaded.store.changesets[0].ops.len(), 1);
+
+        // Petgraph rebuilt from the imported stores
+        assert_eq!(loaded.node_count(), 3);
+        assert_eq!(loaded.edge_count(), 2);
+    }
+}
+
 #[test]
 fn test_dot_export_contains_all_elements() {
     let mut graph = EcosystemGraph::new();
@@ -588,6 +720,7 @@ fn make_slot(category: &str, name: &str) -> Slot {
         description: format!("Test {} slot", name),
         interface_version: Some("v1".into()),
         required_capabilities: vec!["basic".into()],
+        templates: Vec::new(),
     }
 }
 
@@ -704,6 +837,7 @@ fn test_slot_store_compatibility_check() {
         description: "Container runtime".into(),
         interface_version: Some("v1".into()),
         required_capabilities: vec!["run".into(), "build".into()],
+        templates: Vec::new(),
     };
     graph.slots.slots.push(slot);
 
@@ -807,6 +941,7 @@ fn test_slots_round_trip() {
         description: "Container runtime slot".into(),
         interface_version: Some("v1".into()),
         required_capabilities: vec!["run".into(), "build".into()],
+        templates: Vec::new(),
     };
     graph.slots.slots.push(slot.clone());
 
@@ -1045,6 +1180,8 @@ fn make_plan(scenario_id: &str, operations: Vec<PlanOp>) -> Plan {
         created_at: Utc::now(),
         created_by: "test".into(),
         applied_at: None,
+        applied_ops: Vec::new(),
+        base_refs: Default::default(),
         rollback_plan_id: None,
     }
 }
@@ -1146,6 +1283,8 @@ fn test_rollback_plan_reverses_operations() {
         created_at: Utc::now(),
         created_by: "test".into(),
         applied_at: None,
+        applied_ops: Vec::new(),
+        base_refs: Default::default(),
         rollback_plan_id: None,
     };
 
@@ -1180,6 +1319,53 @@ fn test_rollback_plan_reverses_operations() {
     }
 }
 
+#[test]
+fn test_rollback_plan_reverses_file_changes() {
+    let file_op = |change_type, diff: &str| PlanOp::FileChange {
+        repo_id: "repo:test".into(),
+        file_path: "config.toml".into(),
+        change_type,
+        diff: Some(diff.into()),
+        risk: RiskLevel::Low,
+    };
+    let plan = Plan {
+        kind: "Plan".into(),
+        id: "plan:test:files".into(),
+        name: "Files".into(),
+        scenario_id: "scenario:test".into(),
+        description: None,
+        operations: vec![
+            file_op(FileChangeType::Create, "--- /dev/null\n+++ b/config.toml\n@@ -0,0 +1 @@\n+a = 1\n"),
+            file_op(FileChangeType::Modify, "--- a/config.toml\n+++ b/config.toml\n@@ -1 +1 @@\n-a = 1\n+a = 2\n"),
+        ],
+        overall_risk: RiskLevel::Low,
+        status: PlanStatus::Applied,
+        created_at: Utc::now(),
+        created_by: "test".into(),
+        applied_at: None,
+        applied_ops: Vec::new(),
+        base_refs: Default::default(),
+        rollback_plan_id: None,
+    };
+
+    let rollback = PlanStore::generate_rollback(&plan);
+    assert_eq!(rollback.operations.len(), 2);
+    match &rollback.operations[0] {
+        PlanOp::FileChange { change_type, diff, .. } => {
+            assert_eq!(*change_type, FileChangeType::Modify);
+            assert!(diff.as_deref().unwrap().contains("-a = 2\n+a = 1"));
+        }
+        _ => panic!("First rollback op should undo the modification"),
+    }
+    match &rollback.operations[1] {
+        PlanOp::FileChange { change_type, diff, .. } => {
+            assert_eq!(*change_type, FileChangeType::Delete);
+            assert!(diff.as_deref().unwrap().contains("+++ /dev/null"));
+        }
+        _ => panic!("Second rollback op should delete the created file"),
+    }
+}
+
 #[test]
 fn test_plan_op_descriptions() {
     let switch_op = PlanOp::SwitchBinding {
@@ -1234,6 +1420,8 @@ fn test_plan_store_queries() {
         created_at: Utc::now() - chrono::Duration::hours(2),
         created_by: "test".into(),
         applied_at: None,
+        applied_ops: Vec::new(),
+        base_refs: Default::default(),
         rollback_plan_id: None,
     };
     let plan2 = Plan {
@@ -1248,6 +1436,8 @@ fn test_plan_store_queries() {
         created_at: Utc::now(),
         created_by: "test".into(),
         applied_at: None,
+        applied_ops: Vec::new(),
+        base_refs: Default::default(),
         rollback_plan_id: None,
     };
     let plan3 = Plan {
@@ -1262,6 +1452,8 @@ fn test_plan_store_queries() {
         created_at: Utc::now(),
         created_by: "test".into(),
         applied_at: None,
+        applied_ops: Vec::new(),
+        base_refs: Default::default(),
         rollback_plan_id: None,
     };
 
@@ -1326,6 +1518,8 @@ fn test_plan_diff_summary() {
         created_at: Utc::now(),
         created_by: "test".into(),
         applied_at: None,
+        applied_ops: Vec::new(),
+        base_refs: Default::default(),
         rollback_plan_id: None,
     };
 
@@ -1361,6 +1555,8 @@ fn test_plans_round_trip() {
         created_at: Utc::now(),
         created_by: "test".into(),
         applied_at: None,
+        applied_ops: Vec::new(),
+        base_refs: Default::default(),
         rollback_plan_id: None,
     };
 
@@ -1446,6 +1642,7 @@ fn test_audit_entry_structure() {
         rollback_plan_id: None,
         health_check_passed: Some(true),
         notes: vec!["Test note".into()],
+        stages: Vec::new(),
     };
 
     assert_eq!(entry.kind, "AuditEntry");
@@ -1522,6 +1719,7 @@ fn test_audit_store_round_trip() {
         rollback_plan_id: None,
         health_check_passed: Some(true),
         notes: vec![],
+        stages: Vec::new(),
     };
 
     let entry2 = AuditEntry {
@@ -1550,6 +1748,7 @@ fn test_audit_store_round_trip() {
         rollback_plan_id: Some("rollback:plan:test:002".into()),
         health_check_passed: None,
         notes: vec!["Auto-rollback triggered".into()],
+        stages: Vec::new(),
     };
 
     graph.audit.entries.push(entry1);
@@ -1614,6 +1813,7 @@ fn test_audit_entry_timing_integrity() {
         rollback_plan_id: None,
         health_check_passed: None,
         notes: vec![],
+        stages: Vec::new(),
     };
 
     assert!(entry.finished_at > entry.started_at);
@@ -1639,6 +1839,7 @@ fn test_audit_store_filter_by_plan() {
         rollback_plan_id: None,
         health_check_passed: None,
         notes: vec![],
+        stages: Vec::new(),
     });
     store.entries.push(AuditEntry {
         kind: "AuditEntry".into(),
@@ -1653,6 +1854,7 @@ fn test_audit_store_filter_by_plan() {
         rollback_plan_id: None,
         health_check_passed: None,
         notes: vec![],
+        stages: Vec::new(),
     });
     store.entries.push(AuditEntry {
         kind: "AuditEntry".into(),
@@ -1667,6 +1869,7 @@ fn test_audit_store_filter_by_plan() {
         rollback_plan_id: None,
         health_check_passed: None,
         notes: vec![],
+        stages: Vec::new(),
     });
 
     // Filter by plan ID
@@ -1711,6 +1914,7 @@ fn test_auto_rollback_state_consistency() {
         rollback_plan_id: Some("rollback:plan:test".into()),
         health_check_passed: None, // Health check not run after rollback
         notes: vec!["Auto-rollback triggered after operation 2 failed".into()],
+        stages: Vec::new(),
     };
 
     assert!(entry.auto_rollback_triggered);